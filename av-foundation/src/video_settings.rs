@@ -1,3 +1,4 @@
+use core_video::color_code_point::{ColorPrimaries, MatrixCoefficients, TransferCharacteristics};
 use objc2_foundation::NSString;
 
 pub type AVVideoCodecKey = NSString;
@@ -99,3 +100,56 @@ extern "C" {
     pub static AVVideoApertureModeProductionAperture: &'static AVVideoApertureMode;
     pub static AVVideoApertureModeEncodedPixels: &'static AVVideoApertureMode;
 }
+
+pub fn av_video_color_primaries(color_primaries: ColorPrimaries) -> Option<&'static NSString> {
+    unsafe {
+        match color_primaries {
+            ColorPrimaries::BT709 => Some(AVVideoColorPrimaries_ITU_R_709_2),
+            ColorPrimaries::BT470BG => Some(AVVideoColorPrimaries_EBU_3213),
+            ColorPrimaries::SMPTE170M => Some(AVVideoColorPrimaries_SMPTE_C),
+            ColorPrimaries::SMPTE432 => Some(AVVideoColorPrimaries_P3_D65),
+            ColorPrimaries::BT2020 => Some(AVVideoColorPrimaries_ITU_R_2020),
+            _ => None,
+        }
+    }
+}
+
+pub fn av_video_transfer_function(transfer_characteristics: TransferCharacteristics) -> Option<&'static NSString> {
+    unsafe {
+        match transfer_characteristics {
+            TransferCharacteristics::BT709 | TransferCharacteristics::SMPTE170M => Some(AVVideoTransferFunction_ITU_R_709_2),
+            TransferCharacteristics::SMPTE240M => Some(AVVideoTransferFunction_SMPTE_240M_1995),
+            TransferCharacteristics::SMPTE2084 => Some(AVVideoTransferFunction_SMPTE_ST_2084_PQ),
+            TransferCharacteristics::ARIB_STD_B67 => Some(AVVideoTransferFunction_ITU_R_2100_HLG),
+            TransferCharacteristics::Linear => Some(AVVideoTransferFunction_Linear),
+            _ => None,
+        }
+    }
+}
+
+pub fn av_video_ycbcr_matrix(matrix_coefficients: MatrixCoefficients) -> Option<&'static NSString> {
+    unsafe {
+        match matrix_coefficients {
+            MatrixCoefficients::BT709 => Some(AVVideoYCbCrMatrix_ITU_R_709_2),
+            MatrixCoefficients::BT470BG | MatrixCoefficients::SMPTE170M => Some(AVVideoYCbCrMatrix_ITU_R_601_4),
+            MatrixCoefficients::SMPTE240M => Some(AVVideoYCbCrMatrix_SMPTE_240M_1995),
+            MatrixCoefficients::BT2020_NCL => Some(AVVideoYCbCrMatrix_ITU_R_2020),
+            _ => None,
+        }
+    }
+}
+
+// Several code points share one AVVideo string, which reads back as the
+// canonical code point from `from_string_value`
+pub fn color_primaries_from_av_video(value: &NSString) -> Option<ColorPrimaries> {
+    ColorPrimaries::from_string_value(&value.to_string()).filter(|color_primaries| color_primaries.av_video_string_value().is_some())
+}
+
+pub fn transfer_characteristics_from_av_video(value: &NSString) -> Option<TransferCharacteristics> {
+    TransferCharacteristics::from_string_value(&value.to_string())
+        .filter(|transfer_characteristics| transfer_characteristics.av_video_string_value().is_some())
+}
+
+pub fn matrix_coefficients_from_av_video(value: &NSString) -> Option<MatrixCoefficients> {
+    MatrixCoefficients::from_string_value(&value.to_string()).filter(|matrix_coefficients| matrix_coefficients.av_video_string_value().is_some())
+}
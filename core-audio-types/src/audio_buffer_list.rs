@@ -0,0 +1,331 @@
+use std::{
+    alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout},
+    mem::{align_of, size_of},
+    ptr::{addr_of_mut, copy_nonoverlapping, NonNull},
+    slice::{from_raw_parts, from_raw_parts_mut},
+};
+
+use core_foundation_sys::base::OSStatus;
+use libc::c_void;
+
+use crate::base_types::*;
+
+mod private {
+    pub trait Sealed {}
+}
+
+pub trait AudioSample: private::Sealed + Copy + Default + 'static {}
+
+macro_rules! impl_audio_sample {
+    ($($sample:ty),*) => {
+        $(
+            impl private::Sealed for $sample {}
+            impl AudioSample for $sample {}
+        )*
+    };
+}
+
+impl_audio_sample!(u8, i8, i16, i32, f32, f64);
+
+pub struct AudioBufferListBuf {
+    list: NonNull<AudioBufferList>,
+    layout: Layout,
+    // Buffer memory is kept as u64 words so every sample type is suitably aligned
+    storage: Vec<Vec<u64>>,
+}
+
+unsafe impl Send for AudioBufferListBuf {}
+unsafe impl Sync for AudioBufferListBuf {}
+
+impl AudioBufferListBuf {
+    pub fn new(buffer_count: usize, channels_per_buffer: u32, bytes_per_buffer: usize) -> Result<Self, OSStatus> {
+        if buffer_count == 0 || bytes_per_buffer > u32::MAX as usize {
+            return Err(kAudio_ParamError);
+        }
+        let layout = Self::layout(buffer_count)?;
+        let list = unsafe { alloc_zeroed(layout) } as *mut AudioBufferList;
+        let list = NonNull::new(list).unwrap_or_else(|| handle_alloc_error(layout));
+        let mut storage = (0..buffer_count).map(|_| vec![0u64; bytes_per_buffer.div_ceil(8)]).collect::<Vec<_>>();
+        unsafe {
+            let list_ptr = list.as_ptr();
+            (*list_ptr).mNumberBuffers = buffer_count as u32;
+            let buffers = addr_of_mut!((*list_ptr).mBuffers) as *mut AudioBuffer;
+            for (i, data) in storage.iter_mut().enumerate() {
+                buffers.add(i).write(AudioBuffer {
+                    mNumberChannels: channels_per_buffer,
+                    mDataByteSize: bytes_per_buffer as u32,
+                    mData: data.as_mut_ptr() as *mut c_void,
+                });
+            }
+        }
+        Ok(Self {
+            list,
+            layout,
+            storage,
+        })
+    }
+
+    pub fn from_stream_basic_description(asbd: &AudioStreamBasicDescription, frame_count: usize) -> Result<Self, OSStatus> {
+        if asbd.mBytesPerFrame == 0 || asbd.mChannelsPerFrame == 0 {
+            return Err(kAudio_ParamError);
+        }
+        let bytes_per_buffer = (asbd.mBytesPerFrame as usize).checked_mul(frame_count).ok_or(kAudio_ParamError)?;
+        if asbd.mFormatFlags & kAudioFormatFlagIsNonInterleaved != 0 {
+            Self::new(asbd.mChannelsPerFrame as usize, 1, bytes_per_buffer)
+        } else {
+            Self::new(1, asbd.mChannelsPerFrame, bytes_per_buffer)
+        }
+    }
+
+    fn layout(buffer_count: usize) -> Result<Layout, OSStatus> {
+        let size = size_of::<AudioBufferList>() + (buffer_count - 1) * size_of::<AudioBuffer>();
+        Layout::from_size_align(size, align_of::<AudioBufferList>()).map_err(|_| kAudio_ParamError)
+    }
+
+    #[inline]
+    pub fn buffer_count(&self) -> usize {
+        self.storage.len()
+    }
+
+    #[inline]
+    pub fn as_ptr(&self) -> *const AudioBufferList {
+        self.list.as_ptr()
+    }
+
+    /// Foreign code may point `mData` at its own memory and set `mDataByteSize`
+    /// through the returned pointer.
+    ///
+    /// # Safety
+    ///
+    /// Dereferencing the returned pointer is unsafe. Any `mData` written
+    /// through it must be null, point back at this list's own storage, or
+    /// point at memory that stays valid, suitably aligned and unaliased for
+    /// `mDataByteSize` bytes until it is replaced or the list is dropped.
+    /// The safe accessors read and write that many bytes, and
+    /// [`set_data_byte_size`](Self::set_data_byte_size) never grows a
+    /// foreign buffer past the size it was installed with.
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut AudioBufferList {
+        self.list.as_ptr()
+    }
+
+    #[inline]
+    pub fn as_audio_buffer_list(&self) -> &AudioBufferList {
+        unsafe { self.list.as_ref() }
+    }
+
+    #[inline]
+    pub fn audio_buffers(&self) -> &[AudioBuffer] {
+        unsafe { from_raw_parts(addr_of_mut!((*self.list.as_ptr()).mBuffers) as *const AudioBuffer, self.storage.len()) }
+    }
+
+    #[inline]
+    fn audio_buffers_mut(&mut self) -> &mut [AudioBuffer] {
+        unsafe { from_raw_parts_mut(addr_of_mut!((*self.list.as_ptr()).mBuffers) as *mut AudioBuffer, self.storage.len()) }
+    }
+
+    // Buffers swapped in by foreign code have no known size beyond the one they
+    // currently report, so they can only shrink
+    pub fn capacity(&self, index: usize) -> Option<usize> {
+        let buffer = self.audio_buffers().get(index)?;
+        let storage = &self.storage[index];
+        if buffer.mData as *const u64 == storage.as_ptr() {
+            Some(storage.len() * 8)
+        } else if buffer.mData.is_null() {
+            Some(0)
+        } else {
+            Some(buffer.mDataByteSize as usize)
+        }
+    }
+
+    pub fn set_data_byte_size(&mut self, index: usize, data_byte_size: usize) -> Result<(), OSStatus> {
+        let capacity = self.capacity(index).ok_or(kAudio_ParamError)?;
+        if data_byte_size > capacity || data_byte_size > u32::MAX as usize {
+            return Err(kAudio_ParamError);
+        }
+        self.audio_buffers_mut()[index].mDataByteSize = data_byte_size as u32;
+        Ok(())
+    }
+
+    // Reads through the current mData so that buffers swapped in by foreign code
+    // are seen
+    fn data(&self, index: usize) -> Option<(*mut u8, usize)> {
+        let buffer = self.audio_buffers().get(index)?;
+        if buffer.mData.is_null() {
+            return None;
+        }
+        let storage = &self.storage[index];
+        let length = if buffer.mData as *const u64 == storage.as_ptr() {
+            // Our own allocation is never read past, even if mDataByteSize grew
+            (buffer.mDataByteSize as usize).min(storage.len() * 8)
+        } else {
+            buffer.mDataByteSize as usize
+        };
+        Some((buffer.mData as *mut u8, length))
+    }
+
+    pub fn bytes(&self, index: usize) -> Option<&[u8]> {
+        let (data, length) = self.data(index)?;
+        Some(unsafe { from_raw_parts(data, length) })
+    }
+
+    pub fn bytes_mut(&mut self, index: usize) -> Option<&mut [u8]> {
+        let (data, length) = self.data(index)?;
+        Some(unsafe { from_raw_parts_mut(data, length) })
+    }
+
+    pub fn buffer<T: AudioSample>(&self, index: usize) -> Option<&[T]> {
+        let (data, length) = self.data(index)?;
+        if data as usize & (align_of::<T>() - 1) != 0 {
+            return None;
+        }
+        Some(unsafe { from_raw_parts(data as *const T, length / size_of::<T>()) })
+    }
+
+    pub fn buffer_mut<T: AudioSample>(&mut self, index: usize) -> Option<&mut [T]> {
+        let (data, length) = self.data(index)?;
+        if data as usize & (align_of::<T>() - 1) != 0 {
+            return None;
+        }
+        Some(unsafe { from_raw_parts_mut(data as *mut T, length / size_of::<T>()) })
+    }
+
+    pub fn buffers<T: AudioSample>(&self) -> Vec<&[T]> {
+        (0..self.buffer_count()).filter_map(|index| self.buffer(index)).collect()
+    }
+
+    pub fn buffers_mut<T: AudioSample>(&mut self) -> Vec<&mut [T]> {
+        (0..self.buffer_count())
+            .filter_map(|index| self.data(index))
+            .filter(|(data, _)| *data as usize & (align_of::<T>() - 1) == 0)
+            .map(|(data, length)| unsafe { from_raw_parts_mut(data as *mut T, length / size_of::<T>()) })
+            .collect()
+    }
+}
+
+impl Clone for AudioBufferListBuf {
+    fn clone(&self) -> Self {
+        let buffers = self.audio_buffers();
+        let capacity =
+            (0..self.buffer_count()).map(|i| self.bytes(i).map_or(0, |bytes| bytes.len()).max(self.storage[i].len() * 8)).max().unwrap_or(0);
+        let mut clone = Self::new(self.buffer_count(), buffers[0].mNumberChannels, capacity).unwrap_or_else(|_| handle_alloc_error(self.layout));
+        for (i, buffer) in buffers.iter().enumerate() {
+            let bytes = self.bytes(i).unwrap_or_default();
+            let cloned = &mut clone.audio_buffers_mut()[i];
+            cloned.mNumberChannels = buffer.mNumberChannels;
+            cloned.mDataByteSize = bytes.len() as u32;
+            unsafe { copy_nonoverlapping(bytes.as_ptr(), clone.storage[i].as_mut_ptr() as *mut u8, bytes.len()) };
+        }
+        clone
+    }
+}
+
+impl Drop for AudioBufferListBuf {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.list.as_ptr() as *mut u8, self.layout) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn float_format(is_non_interleaved: bool) -> AudioStreamBasicDescription {
+        let mut asbd = AudioStreamBasicDescription::default();
+        FillOutASBDForLPCM(&mut asbd, 48000.0, 2, 32, 32, true, false, is_non_interleaved);
+        asbd
+    }
+
+    #[test]
+    fn interleaved_round_trip() {
+        let mut list = AudioBufferListBuf::from_stream_basic_description(&float_format(false), 4).unwrap();
+        assert_eq!(list.buffer_count(), 1);
+        assert_eq!(list.as_audio_buffer_list().mNumberBuffers, 1);
+        assert_eq!(list.audio_buffers()[0].mNumberChannels, 2);
+        assert_eq!(list.audio_buffers()[0].mDataByteSize, 32);
+        list.buffer_mut::<f32>(0).unwrap().iter_mut().enumerate().for_each(|(i, sample)| *sample = i as f32);
+        assert_eq!(list.buffer::<f32>(0).unwrap(), [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+        assert_eq!(&list.bytes(0).unwrap()[4..8], &1.0f32.to_ne_bytes());
+        assert_eq!(list.buffer::<i16>(0).unwrap().len(), 16);
+        assert!(list.bytes(1).is_none());
+
+        // Shrinking the byte size shortens every view
+        list.set_data_byte_size(0, 8).unwrap();
+        assert_eq!(list.buffer::<f32>(0).unwrap(), [0.0, 1.0]);
+        assert!(list.set_data_byte_size(0, list.capacity(0).unwrap() + 1).is_err());
+    }
+
+    #[test]
+    fn non_interleaved_round_trip() {
+        let mut list = AudioBufferListBuf::from_stream_basic_description(&float_format(true), 3).unwrap();
+        assert_eq!(list.buffer_count(), 2);
+        assert!(list.audio_buffers().iter().all(|buffer| buffer.mNumberChannels == 1 && buffer.mDataByteSize == 12));
+        for (channel, buffer) in list.buffers_mut::<f32>().into_iter().enumerate() {
+            buffer.iter_mut().for_each(|sample| *sample = channel as f32 + 0.5);
+        }
+        let clone = list.clone();
+        drop(list);
+        assert_eq!(clone.buffers::<f32>(), [&[0.5, 0.5, 0.5][..], &[1.5, 1.5, 1.5][..]]);
+        assert!(AudioBufferListBuf::new(0, 1, 16).is_err());
+    }
+
+    #[test]
+    fn accessors_follow_swapped_buffers() {
+        let mut list = AudioBufferListBuf::from_stream_basic_description(&float_format(true), 2).unwrap();
+        let mut external = [1.0f32, 2.0, 3.0, 4.0];
+        unsafe {
+            let buffers = addr_of_mut!((*list.as_mut_ptr()).mBuffers) as *mut AudioBuffer;
+            (*buffers.add(1)).mData = external.as_mut_ptr() as *mut c_void;
+            (*buffers.add(1)).mDataByteSize = 16;
+        }
+        assert_eq!(list.buffer::<f32>(1).unwrap(), [1.0, 2.0, 3.0, 4.0]);
+        list.buffer_mut::<f32>(1).unwrap()[0] = 5.0;
+        assert_eq!(list.bytes(1).unwrap(), [5.0f32, 2.0, 3.0, 4.0].iter().flat_map(|sample| sample.to_ne_bytes()).collect::<Vec<_>>());
+
+        // Clones copy the swapped in samples into their own storage
+        let clone = list.clone();
+        assert_eq!(clone.buffer::<f32>(1).unwrap(), [5.0, 2.0, 3.0, 4.0]);
+        assert_ne!(clone.audio_buffers()[1].mData, list.audio_buffers()[1].mData);
+
+        // Misaligned and missing buffers are not reinterpreted
+        unsafe {
+            let buffers = addr_of_mut!((*list.as_mut_ptr()).mBuffers) as *mut AudioBuffer;
+            (*buffers.add(1)).mData = (external.as_mut_ptr() as *mut u8).add(1) as *mut c_void;
+            (*buffers.add(1)).mDataByteSize = 8;
+            (*buffers).mData = std::ptr::null_mut();
+        }
+        assert!(list.buffer::<f32>(1).is_none());
+        assert_eq!(list.bytes(1).unwrap().len(), 8);
+        assert!(list.bytes(0).is_none());
+        assert_eq!(list.buffers_mut::<f32>().len(), 0);
+    }
+
+    #[test]
+    fn swapped_buffers_only_shrink() {
+        let mut list = AudioBufferListBuf::from_stream_basic_description(&float_format(true), 16).unwrap();
+        let mut external = [1.0f32, 2.0];
+        unsafe {
+            let buffers = addr_of_mut!((*list.as_mut_ptr()).mBuffers) as *mut AudioBuffer;
+            (*buffers.add(1)).mData = external.as_mut_ptr() as *mut c_void;
+            (*buffers.add(1)).mDataByteSize = 8;
+        }
+        assert_eq!(list.capacity(0), Some(64));
+        assert_eq!(list.capacity(1), Some(8));
+        assert_eq!(list.set_data_byte_size(1, 64), Err(kAudio_ParamError));
+        assert_eq!(list.buffer_mut::<f32>(1).unwrap().len(), 2);
+        list.set_data_byte_size(1, 4).unwrap();
+        assert_eq!(list.buffer::<f32>(1).unwrap(), [1.0]);
+        assert_eq!(list.set_data_byte_size(1, 8), Err(kAudio_ParamError));
+
+        // Our own storage can still grow back to its full size
+        list.set_data_byte_size(0, 0).unwrap();
+        list.set_data_byte_size(0, 64).unwrap();
+        assert_eq!(list.buffer::<f32>(0).unwrap().len(), 16);
+
+        unsafe {
+            let buffers = addr_of_mut!((*list.as_mut_ptr()).mBuffers) as *mut AudioBuffer;
+            (*buffers).mData = std::ptr::null_mut();
+        }
+        assert_eq!(list.set_data_byte_size(0, 4), Err(kAudio_ParamError));
+    }
+}
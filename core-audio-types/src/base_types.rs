@@ -1,7 +1,7 @@
 use core_foundation_sys::base::OSStatus;
 use libc::{c_long, c_void};
 
-use crate::OSType;
+use crate::{four_cc::FourCC, OSType};
 
 cfg_if! {
     if #[cfg(all(target_os = "ios", not(target_os = "macos")))] {
@@ -12,17 +12,12 @@ cfg_if! {
     }
 }
 
-#[inline]
-const fn fourcc(code: &[u8; 4]) -> u32 {
-    (((code[0] as u32) << 24) | ((code[1] as u32) << 16) | ((code[2] as u32) << 8) | ((code[3] as u32) << 0)) as u32
-}
-
 pub const kAudio_NoError: OSStatus = 0;
 pub const kAudio_UnimplementedError: OSStatus = -4;
 pub const kAudio_FileNotFoundError: OSStatus = -43;
 pub const kAudio_FilePermissionError: OSStatus = -54;
 pub const kAudio_TooManyFilesOpenError: OSStatus = -42;
-pub const kAudio_BadFilePathError: OSStatus = fourcc(b"!pth") as OSStatus;
+pub const kAudio_BadFilePathError: OSStatus = FourCC::new(b"!pth").as_u32() as OSStatus;
 pub const kAudio_ParamError: OSStatus = -50;
 pub const kAudio_MemFullError: OSStatus = -108;
 
@@ -88,46 +83,46 @@ pub struct AudioStreamBasicDescription {
 
 pub static kAudioStreamAnyRate: f64 = 0.0;
 
-pub const kAudioFormatLinearPCM: AudioFormatID = fourcc(b"lpcm");
-pub const kAudioFormatAC3: AudioFormatID = fourcc(b"ac-3");
-pub const kAudioFormat60958AC3: AudioFormatID = fourcc(b"cac3");
-pub const kAudioFormatAppleIMA4: AudioFormatID = fourcc(b"ima4");
-pub const kAudioFormatMPEG4AAC: AudioFormatID = fourcc(b"aac ");
-pub const kAudioFormatMPEG4CELP: AudioFormatID = fourcc(b"celp");
-pub const kAudioFormatMPEG4HVXC: AudioFormatID = fourcc(b"hvxc");
-pub const kAudioFormatMPEG4TwinVQ: AudioFormatID = fourcc(b"twvq");
-pub const kAudioFormatMACE3: AudioFormatID = fourcc(b"MAC3");
-pub const kAudioFormatMACE6: AudioFormatID = fourcc(b"MAC6");
-pub const kAudioFormatULaw: AudioFormatID = fourcc(b"ulaw");
-pub const kAudioFormatALaw: AudioFormatID = fourcc(b"alaw");
-pub const kAudioFormatQDesign: AudioFormatID = fourcc(b"QDMC");
-pub const kAudioFormatQDesign2: AudioFormatID = fourcc(b"QDM2");
-pub const kAudioFormatQUALCOMM: AudioFormatID = fourcc(b"Qclp");
-pub const kAudioFormatMPEGLayer1: AudioFormatID = fourcc(b".mp1");
-pub const kAudioFormatMPEGLayer2: AudioFormatID = fourcc(b".mp2");
-pub const kAudioFormatMPEGLayer3: AudioFormatID = fourcc(b".mp3");
-pub const kAudioFormatTimeCode: AudioFormatID = fourcc(b"time");
-pub const kAudioFormatMIDIStream: AudioFormatID = fourcc(b"midi");
-pub const kAudioFormatParameterValueStream: AudioFormatID = fourcc(b"apvs");
-pub const kAudioFormatAppleLossless: AudioFormatID = fourcc(b"alac");
-pub const kAudioFormatMPEG4AAC_HE: AudioFormatID = fourcc(b"aach");
-pub const kAudioFormatMPEG4AAC_LD: AudioFormatID = fourcc(b"aacl");
-pub const kAudioFormatMPEG4AAC_ELD: AudioFormatID = fourcc(b"aace");
-pub const kAudioFormatMPEG4AAC_ELD_SBR: AudioFormatID = fourcc(b"aacf");
-pub const kAudioFormatMPEG4AAC_ELD_V2: AudioFormatID = fourcc(b"aacg");
-pub const kAudioFormatMPEG4AAC_HE_V2: AudioFormatID = fourcc(b"aacp");
-pub const kAudioFormatMPEG4AAC_Spatial: AudioFormatID = fourcc(b"aacs");
-pub const kAudioFormatMPEGD_USAC: AudioFormatID = fourcc(b"usac");
-pub const kAudioFormatAMR: AudioFormatID = fourcc(b"samr");
-pub const kAudioFormatAMR_WB: AudioFormatID = fourcc(b"sawb");
-pub const kAudioFormatAudible: AudioFormatID = fourcc(b"AUDB");
-pub const kAudioFormatiLBC: AudioFormatID = fourcc(b"ilbc");
+pub const kAudioFormatLinearPCM: AudioFormatID = FourCC::new(b"lpcm").as_u32();
+pub const kAudioFormatAC3: AudioFormatID = FourCC::new(b"ac-3").as_u32();
+pub const kAudioFormat60958AC3: AudioFormatID = FourCC::new(b"cac3").as_u32();
+pub const kAudioFormatAppleIMA4: AudioFormatID = FourCC::new(b"ima4").as_u32();
+pub const kAudioFormatMPEG4AAC: AudioFormatID = FourCC::new(b"aac ").as_u32();
+pub const kAudioFormatMPEG4CELP: AudioFormatID = FourCC::new(b"celp").as_u32();
+pub const kAudioFormatMPEG4HVXC: AudioFormatID = FourCC::new(b"hvxc").as_u32();
+pub const kAudioFormatMPEG4TwinVQ: AudioFormatID = FourCC::new(b"twvq").as_u32();
+pub const kAudioFormatMACE3: AudioFormatID = FourCC::new(b"MAC3").as_u32();
+pub const kAudioFormatMACE6: AudioFormatID = FourCC::new(b"MAC6").as_u32();
+pub const kAudioFormatULaw: AudioFormatID = FourCC::new(b"ulaw").as_u32();
+pub const kAudioFormatALaw: AudioFormatID = FourCC::new(b"alaw").as_u32();
+pub const kAudioFormatQDesign: AudioFormatID = FourCC::new(b"QDMC").as_u32();
+pub const kAudioFormatQDesign2: AudioFormatID = FourCC::new(b"QDM2").as_u32();
+pub const kAudioFormatQUALCOMM: AudioFormatID = FourCC::new(b"Qclp").as_u32();
+pub const kAudioFormatMPEGLayer1: AudioFormatID = FourCC::new(b".mp1").as_u32();
+pub const kAudioFormatMPEGLayer2: AudioFormatID = FourCC::new(b".mp2").as_u32();
+pub const kAudioFormatMPEGLayer3: AudioFormatID = FourCC::new(b".mp3").as_u32();
+pub const kAudioFormatTimeCode: AudioFormatID = FourCC::new(b"time").as_u32();
+pub const kAudioFormatMIDIStream: AudioFormatID = FourCC::new(b"midi").as_u32();
+pub const kAudioFormatParameterValueStream: AudioFormatID = FourCC::new(b"apvs").as_u32();
+pub const kAudioFormatAppleLossless: AudioFormatID = FourCC::new(b"alac").as_u32();
+pub const kAudioFormatMPEG4AAC_HE: AudioFormatID = FourCC::new(b"aach").as_u32();
+pub const kAudioFormatMPEG4AAC_LD: AudioFormatID = FourCC::new(b"aacl").as_u32();
+pub const kAudioFormatMPEG4AAC_ELD: AudioFormatID = FourCC::new(b"aace").as_u32();
+pub const kAudioFormatMPEG4AAC_ELD_SBR: AudioFormatID = FourCC::new(b"aacf").as_u32();
+pub const kAudioFormatMPEG4AAC_ELD_V2: AudioFormatID = FourCC::new(b"aacg").as_u32();
+pub const kAudioFormatMPEG4AAC_HE_V2: AudioFormatID = FourCC::new(b"aacp").as_u32();
+pub const kAudioFormatMPEG4AAC_Spatial: AudioFormatID = FourCC::new(b"aacs").as_u32();
+pub const kAudioFormatMPEGD_USAC: AudioFormatID = FourCC::new(b"usac").as_u32();
+pub const kAudioFormatAMR: AudioFormatID = FourCC::new(b"samr").as_u32();
+pub const kAudioFormatAMR_WB: AudioFormatID = FourCC::new(b"sawb").as_u32();
+pub const kAudioFormatAudible: AudioFormatID = FourCC::new(b"AUDB").as_u32();
+pub const kAudioFormatiLBC: AudioFormatID = FourCC::new(b"ilbc").as_u32();
 pub const kAudioFormatDVIIntelIMA: AudioFormatID = 0x6D730011;
 pub const kAudioFormatMicrosoftGSM: AudioFormatID = 0x6D730031;
-pub const kAudioFormatAES3: AudioFormatID = fourcc(b"aes3");
-pub const kAudioFormatEnhancedAC3: AudioFormatID = fourcc(b"ec-3");
-pub const kAudioFormatFLAC: AudioFormatID = fourcc(b"flac");
-pub const kAudioFormatOpus: AudioFormatID = fourcc(b"opus");
+pub const kAudioFormatAES3: AudioFormatID = FourCC::new(b"aes3").as_u32();
+pub const kAudioFormatEnhancedAC3: AudioFormatID = FourCC::new(b"ec-3").as_u32();
+pub const kAudioFormatFLAC: AudioFormatID = FourCC::new(b"flac").as_u32();
+pub const kAudioFormatOpus: AudioFormatID = FourCC::new(b"opus").as_u32();
 
 pub const kAudioFormatFlagIsFloat: AudioFormatFlags = 1 << 0;
 pub const kAudioFormatFlagIsBigEndian: AudioFormatFlags = 1 << 1;
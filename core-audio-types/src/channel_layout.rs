@@ -0,0 +1,726 @@
+use std::{
+    mem::{align_of, size_of},
+    ptr::copy_nonoverlapping,
+    slice::from_raw_parts,
+};
+
+use core_foundation_sys::base::OSStatus;
+
+use crate::{base_types::*, four_cc::FourCC};
+
+pub const kChannelLayoutAtomType: u32 = FourCC::new(b"chan").as_u32();
+
+const LAYOUT_HEADER_SIZE: usize = 12;
+const DESCRIPTION_SIZE: usize = 20;
+const ATOM_HEADER_SIZE: usize = 12;
+
+const L: AudioChannelLabel = kAudioChannelLabel_Left;
+const R: AudioChannelLabel = kAudioChannelLabel_Right;
+const C: AudioChannelLabel = kAudioChannelLabel_Center;
+const LFE: AudioChannelLabel = kAudioChannelLabel_LFEScreen;
+const Ls: AudioChannelLabel = kAudioChannelLabel_LeftSurround;
+const Rs: AudioChannelLabel = kAudioChannelLabel_RightSurround;
+const Lc: AudioChannelLabel = kAudioChannelLabel_LeftCenter;
+const Rc: AudioChannelLabel = kAudioChannelLabel_RightCenter;
+const Cs: AudioChannelLabel = kAudioChannelLabel_CenterSurround;
+const Lsd: AudioChannelLabel = kAudioChannelLabel_LeftSurroundDirect;
+const Rsd: AudioChannelLabel = kAudioChannelLabel_RightSurroundDirect;
+const Ts: AudioChannelLabel = kAudioChannelLabel_TopCenterSurround;
+const Vhl: AudioChannelLabel = kAudioChannelLabel_VerticalHeightLeft;
+const Vhc: AudioChannelLabel = kAudioChannelLabel_VerticalHeightCenter;
+const Vhr: AudioChannelLabel = kAudioChannelLabel_VerticalHeightRight;
+const Tbl: AudioChannelLabel = kAudioChannelLabel_TopBackLeft;
+const Tbr: AudioChannelLabel = kAudioChannelLabel_TopBackRight;
+const Rls: AudioChannelLabel = kAudioChannelLabel_RearSurroundLeft;
+const Rrs: AudioChannelLabel = kAudioChannelLabel_RearSurroundRight;
+const Lw: AudioChannelLabel = kAudioChannelLabel_LeftWide;
+const Rw: AudioChannelLabel = kAudioChannelLabel_RightWide;
+const Lt: AudioChannelLabel = kAudioChannelLabel_LeftTotal;
+const Rt: AudioChannelLabel = kAudioChannelLabel_RightTotal;
+const Ltm: AudioChannelLabel = kAudioChannelLabel_LeftTopMiddle;
+const Rtm: AudioChannelLabel = kAudioChannelLabel_RightTopMiddle;
+const Ltr: AudioChannelLabel = kAudioChannelLabel_LeftTopRear;
+const Rtr: AudioChannelLabel = kAudioChannelLabel_RightTopRear;
+const Ctr: AudioChannelLabel = kAudioChannelLabel_CenterTopRear;
+const Lts: AudioChannelLabel = kAudioChannelLabel_LeftTopSurround;
+const Rts: AudioChannelLabel = kAudioChannelLabel_RightTopSurround;
+const Lbs: AudioChannelLabel = kAudioChannelLabel_LeftBackSurround;
+const Rbs: AudioChannelLabel = kAudioChannelLabel_RightBackSurround;
+const Csd: AudioChannelLabel = kAudioChannelLabel_CenterSurroundDirect;
+const LFE2: AudioChannelLabel = kAudioChannelLabel_LFE2;
+const LFE3: AudioChannelLabel = kAudioChannelLabel_LFE3;
+
+static CHANNEL_LAYOUT_TAG_LABELS: &[(AudioChannelLayoutTag, &[AudioChannelLabel])] = &[
+    (kAudioChannelLayoutTag_Mono, &[kAudioChannelLabel_Mono]),
+    (kAudioChannelLayoutTag_Stereo, &[L, R]),
+    (kAudioChannelLayoutTag_StereoHeadphones, &[kAudioChannelLabel_HeadphonesLeft, kAudioChannelLabel_HeadphonesRight]),
+    (kAudioChannelLayoutTag_MatrixStereo, &[Lt, Rt]),
+    (kAudioChannelLayoutTag_MidSide, &[kAudioChannelLabel_MS_Mid, kAudioChannelLabel_MS_Side]),
+    (kAudioChannelLayoutTag_XY, &[kAudioChannelLabel_XY_X, kAudioChannelLabel_XY_Y]),
+    (kAudioChannelLayoutTag_Binaural, &[kAudioChannelLabel_BinauralLeft, kAudioChannelLabel_BinauralRight]),
+    (
+        kAudioChannelLayoutTag_Ambisonic_B_Format,
+        &[kAudioChannelLabel_Ambisonic_W, kAudioChannelLabel_Ambisonic_X, kAudioChannelLabel_Ambisonic_Y, kAudioChannelLabel_Ambisonic_Z],
+    ),
+    (kAudioChannelLayoutTag_Quadraphonic, &[L, R, Ls, Rs]),
+    (kAudioChannelLayoutTag_Pentagonal, &[L, R, Ls, Rs, C]),
+    (kAudioChannelLayoutTag_Hexagonal, &[L, R, Ls, Rs, C, Cs]),
+    (kAudioChannelLayoutTag_Octagonal, &[L, R, Ls, Rs, C, Cs, Lw, Rw]),
+    (kAudioChannelLayoutTag_Cube, &[L, R, Ls, Rs, Vhl, Vhr, Tbl, Tbr]),
+    (kAudioChannelLayoutTag_MPEG_3_0_A, &[L, R, C]),
+    (kAudioChannelLayoutTag_MPEG_3_0_B, &[C, L, R]),
+    (kAudioChannelLayoutTag_MPEG_4_0_A, &[L, R, C, Cs]),
+    (kAudioChannelLayoutTag_MPEG_4_0_B, &[C, L, R, Cs]),
+    (kAudioChannelLayoutTag_MPEG_5_0_A, &[L, R, C, Ls, Rs]),
+    (kAudioChannelLayoutTag_MPEG_5_0_B, &[L, R, Ls, Rs, C]),
+    (kAudioChannelLayoutTag_MPEG_5_0_C, &[L, C, R, Ls, Rs]),
+    (kAudioChannelLayoutTag_MPEG_5_0_D, &[C, L, R, Ls, Rs]),
+    (kAudioChannelLayoutTag_MPEG_5_1_A, &[L, R, C, LFE, Ls, Rs]),
+    (kAudioChannelLayoutTag_MPEG_5_1_B, &[L, R, Ls, Rs, C, LFE]),
+    (kAudioChannelLayoutTag_MPEG_5_1_C, &[L, C, R, Ls, Rs, LFE]),
+    (kAudioChannelLayoutTag_MPEG_5_1_D, &[C, L, R, Ls, Rs, LFE]),
+    (kAudioChannelLayoutTag_MPEG_6_1_A, &[L, R, C, LFE, Ls, Rs, Cs]),
+    (kAudioChannelLayoutTag_MPEG_7_1_A, &[L, R, C, LFE, Ls, Rs, Lc, Rc]),
+    (kAudioChannelLayoutTag_MPEG_7_1_B, &[C, Lc, Rc, L, R, Ls, Rs, LFE]),
+    (kAudioChannelLayoutTag_MPEG_7_1_C, &[L, R, C, LFE, Ls, Rs, Rls, Rrs]),
+    (kAudioChannelLayoutTag_Emagic_Default_7_1, &[L, R, Ls, Rs, C, LFE, Lc, Rc]),
+    (kAudioChannelLayoutTag_SMPTE_DTV, &[L, R, C, LFE, Ls, Rs, Lt, Rt]),
+    (kAudioChannelLayoutTag_ITU_2_1, &[L, R, Cs]),
+    (kAudioChannelLayoutTag_ITU_2_2, &[L, R, Ls, Rs]),
+    (kAudioChannelLayoutTag_DVD_4, &[L, R, LFE]),
+    (kAudioChannelLayoutTag_DVD_5, &[L, R, LFE, Cs]),
+    (kAudioChannelLayoutTag_DVD_6, &[L, R, LFE, Ls, Rs]),
+    (kAudioChannelLayoutTag_DVD_10, &[L, R, C, LFE]),
+    (kAudioChannelLayoutTag_DVD_11, &[L, R, C, LFE, Cs]),
+    (kAudioChannelLayoutTag_DVD_18, &[L, R, Ls, Rs, LFE]),
+    (kAudioChannelLayoutTag_AudioUnit_6_0, &[L, R, Ls, Rs, C, Cs]),
+    (kAudioChannelLayoutTag_AudioUnit_7_0, &[L, R, Ls, Rs, C, Rls, Rrs]),
+    (kAudioChannelLayoutTag_AudioUnit_7_0_Front, &[L, R, Ls, Rs, C, Lc, Rc]),
+    (kAudioChannelLayoutTag_AAC_6_0, &[C, L, R, Ls, Rs, Cs]),
+    (kAudioChannelLayoutTag_AAC_6_1, &[C, L, R, Ls, Rs, Cs, LFE]),
+    (kAudioChannelLayoutTag_AAC_7_0, &[C, L, R, Ls, Rs, Rls, Rrs]),
+    (kAudioChannelLayoutTag_AAC_7_1_B, &[C, L, R, Ls, Rs, Rls, Rrs, LFE]),
+    (kAudioChannelLayoutTag_AAC_7_1_C, &[C, L, R, Ls, Rs, LFE, Vhl, Vhr]),
+    (kAudioChannelLayoutTag_AAC_Octagonal, &[C, L, R, Ls, Rs, Rls, Rrs, Cs]),
+    (kAudioChannelLayoutTag_AC3_1_0_1, &[C, LFE]),
+    (kAudioChannelLayoutTag_AC3_3_0, &[L, C, R]),
+    (kAudioChannelLayoutTag_AC3_3_1, &[L, C, R, Cs]),
+    (kAudioChannelLayoutTag_AC3_3_0_1, &[L, C, R, LFE]),
+    (kAudioChannelLayoutTag_AC3_2_1_1, &[L, R, Cs, LFE]),
+    (kAudioChannelLayoutTag_AC3_3_1_1, &[L, C, R, Cs, LFE]),
+    (kAudioChannelLayoutTag_EAC_6_0_A, &[L, C, R, Ls, Rs, Cs]),
+    (kAudioChannelLayoutTag_EAC_7_0_A, &[L, C, R, Ls, Rs, Rls, Rrs]),
+    (kAudioChannelLayoutTag_EAC3_6_1_A, &[L, C, R, Ls, Rs, LFE, Cs]),
+    (kAudioChannelLayoutTag_EAC3_6_1_B, &[L, C, R, Ls, Rs, LFE, Ts]),
+    (kAudioChannelLayoutTag_EAC3_6_1_C, &[L, C, R, Ls, Rs, LFE, Vhc]),
+    (kAudioChannelLayoutTag_EAC3_7_1_A, &[L, C, R, Ls, Rs, LFE, Rls, Rrs]),
+    (kAudioChannelLayoutTag_EAC3_7_1_B, &[L, C, R, Ls, Rs, LFE, Lc, Rc]),
+    (kAudioChannelLayoutTag_EAC3_7_1_C, &[L, C, R, Ls, Rs, LFE, Lsd, Rsd]),
+    (kAudioChannelLayoutTag_EAC3_7_1_D, &[L, C, R, Ls, Rs, LFE, Lw, Rw]),
+    (kAudioChannelLayoutTag_EAC3_7_1_E, &[L, C, R, Ls, Rs, LFE, Vhl, Vhr]),
+    (kAudioChannelLayoutTag_EAC3_7_1_F, &[L, C, R, Ls, Rs, LFE, Cs, Ts]),
+    (kAudioChannelLayoutTag_EAC3_7_1_G, &[L, C, R, Ls, Rs, LFE, Cs, Vhc]),
+    (kAudioChannelLayoutTag_EAC3_7_1_H, &[L, C, R, Ls, Rs, LFE, Ts, Vhc]),
+    (kAudioChannelLayoutTag_DTS_3_1, &[C, L, R, LFE]),
+    (kAudioChannelLayoutTag_DTS_4_1, &[C, L, R, Cs, LFE]),
+    (kAudioChannelLayoutTag_DTS_6_0_A, &[Lc, Rc, L, R, Ls, Rs]),
+    (kAudioChannelLayoutTag_DTS_6_0_B, &[C, L, R, Rls, Rrs, Ts]),
+    (kAudioChannelLayoutTag_DTS_6_0_C, &[C, Cs, L, R, Rls, Rrs]),
+    (kAudioChannelLayoutTag_DTS_6_1_A, &[Lc, Rc, L, R, Ls, Rs, LFE]),
+    (kAudioChannelLayoutTag_DTS_6_1_B, &[C, L, R, Rls, Rrs, Ts, LFE]),
+    (kAudioChannelLayoutTag_DTS_6_1_C, &[C, Cs, L, R, Rls, Rrs, LFE]),
+    (kAudioChannelLayoutTag_DTS_7_0, &[Lc, C, Rc, L, R, Ls, Rs]),
+    (kAudioChannelLayoutTag_DTS_7_1, &[Lc, C, Rc, L, R, Ls, Rs, LFE]),
+    (kAudioChannelLayoutTag_DTS_8_0_A, &[Lc, Rc, L, R, Ls, Rs, Rls, Rrs]),
+    (kAudioChannelLayoutTag_DTS_8_0_B, &[Lc, C, Rc, L, R, Ls, Cs, Rs]),
+    (kAudioChannelLayoutTag_DTS_8_1_A, &[Lc, Rc, L, R, Ls, Rs, Rls, Rrs, LFE]),
+    (kAudioChannelLayoutTag_DTS_8_1_B, &[Lc, C, Rc, L, R, Ls, Cs, Rs, LFE]),
+    (kAudioChannelLayoutTag_DTS_6_1_D, &[C, L, R, Ls, Rs, LFE, Cs]),
+    (kAudioChannelLayoutTag_WAVE_4_0_B, &[L, R, Rls, Rrs]),
+    (kAudioChannelLayoutTag_WAVE_5_0_B, &[L, R, C, Rls, Rrs]),
+    (kAudioChannelLayoutTag_WAVE_5_1_B, &[L, R, C, LFE, Rls, Rrs]),
+    (kAudioChannelLayoutTag_WAVE_6_1, &[L, R, C, LFE, Cs, Ls, Rs]),
+    (kAudioChannelLayoutTag_WAVE_7_1, &[L, R, C, LFE, Rls, Rrs, Ls, Rs]),
+    (kAudioChannelLayoutTag_Atmos_5_1_2, &[L, R, C, LFE, Ls, Rs, Ltm, Rtm]),
+    (kAudioChannelLayoutTag_Atmos_5_1_4, &[L, R, C, LFE, Ls, Rs, Vhl, Vhr, Ltr, Rtr]),
+    (kAudioChannelLayoutTag_Atmos_7_1_2, &[L, R, C, LFE, Ls, Rs, Rls, Rrs, Vhl, Vhr]),
+    (kAudioChannelLayoutTag_Atmos_7_1_4, &[L, R, C, LFE, Ls, Rs, Rls, Rrs, Vhl, Vhr, Ltr, Rtr]),
+    (kAudioChannelLayoutTag_Atmos_9_1_6, &[L, R, C, LFE, Ls, Rs, Rls, Rrs, Lw, Rw, Vhl, Vhr, Ltm, Rtm, Ltr, Rtr]),
+    (kAudioChannelLayoutTag_Ogg_5_0, &[L, C, R, Ls, Rs]),
+    (kAudioChannelLayoutTag_Ogg_5_1, &[L, C, R, Ls, Rs, LFE]),
+    (kAudioChannelLayoutTag_Ogg_6_1, &[L, C, R, Ls, Rs, Cs, LFE]),
+    (kAudioChannelLayoutTag_Ogg_7_1, &[L, C, R, Ls, Rs, Rls, Rrs, LFE]),
+    (kAudioChannelLayoutTag_TMH_10_2_std, &[L, R, C, Vhc, Lsd, Rsd, Ls, Rs, Vhl, Vhr, Lw, Rw, Csd, Cs, LFE, LFE2]),
+    (
+        kAudioChannelLayoutTag_TMH_10_2_full,
+        &[
+            L,
+            R,
+            C,
+            Vhc,
+            Lsd,
+            Rsd,
+            Ls,
+            Rs,
+            Vhl,
+            Vhr,
+            Lw,
+            Rw,
+            Csd,
+            Cs,
+            LFE,
+            LFE2,
+            Lc,
+            Rc,
+            kAudioChannelLabel_HearingImpaired,
+            kAudioChannelLabel_Narration,
+            kAudioChannelLabel_Haptic,
+        ],
+    ),
+    (kAudioChannelLayoutTag_Logic_4_0_C, &[L, R, Cs, C]),
+    (kAudioChannelLayoutTag_Logic_6_0_B, &[L, R, Ls, Rs, Cs, C]),
+    (kAudioChannelLayoutTag_Logic_6_1_B, &[L, R, Ls, Rs, Cs, C, LFE]),
+    (kAudioChannelLayoutTag_Logic_6_1_D, &[L, C, R, Ls, Cs, Rs, LFE]),
+    (kAudioChannelLayoutTag_Logic_7_1_B, &[L, R, Ls, Rs, Rls, Rrs, C, LFE]),
+    (kAudioChannelLayoutTag_Logic_Atmos_7_1_4_B, &[L, R, Rls, Rrs, Ls, Rs, C, LFE, Vhl, Vhr, Ltr, Rtr]),
+    (kAudioChannelLayoutTag_Logic_Atmos_7_1_6, &[L, R, Rls, Rrs, Ls, Rs, C, LFE, Vhl, Vhr, Ltm, Rtm, Ltr, Rtr]),
+    (
+        kAudioChannelLayoutTag_CICP_13,
+        &[
+            L,
+            R,
+            C,
+            LFE2,
+            Rls,
+            Rrs,
+            LFE3,
+            Lc,
+            Rc,
+            Cs,
+            Ls,
+            Rs,
+            Vhl,
+            Vhr,
+            Vhc,
+            Ts,
+            Ltr,
+            Rtr,
+            Ltm,
+            Rtm,
+            Ctr,
+            kAudioChannelLabel_CenterBottom,
+            kAudioChannelLabel_LeftBottom,
+            kAudioChannelLabel_RightBottomL,
+        ],
+    ),
+    (kAudioChannelLayoutTag_CICP_14, &[L, R, C, LFE, Ls, Rs, Vhl, Vhr]),
+    (kAudioChannelLayoutTag_CICP_15, &[L, R, C, LFE2, Rls, Rrs, LFE3, Ls, Rs, Vhl, Vhr, Ctr]),
+    (kAudioChannelLayoutTag_CICP_16, &[L, R, C, LFE, Ls, Rs, Vhl, Vhr, Lts, Rts]),
+    (kAudioChannelLayoutTag_CICP_17, &[L, R, C, LFE, Ls, Rs, Vhl, Vhr, Vhc, Lts, Rts, Ts]),
+    (kAudioChannelLayoutTag_CICP_18, &[L, R, C, LFE, Ls, Rs, Lbs, Rbs, Vhl, Vhr, Vhc, Lts, Rts, Ts]),
+    (kAudioChannelLayoutTag_CICP_19, &[L, R, C, LFE, Rls, Rrs, Ls, Rs, Vhl, Vhr, Ltr, Rtr]),
+    (
+        kAudioChannelLayoutTag_CICP_20,
+        &[L, R, C, LFE, Rls, Rrs, Ls, Rs, Vhl, Vhr, Ltr, Rtr, kAudioChannelLabel_LeftEdgeOfScreen, kAudioChannelLabel_RightEdgeOfScreen],
+    ),
+];
+
+static CHANNEL_BIT_LABELS: &[(AudioChannelBitmap, AudioChannelLabel)] = &[
+    (kAudioChannelBit_Left, L),
+    (kAudioChannelBit_Right, R),
+    (kAudioChannelBit_Center, C),
+    (kAudioChannelBit_LFEScreen, LFE),
+    (kAudioChannelBit_LeftSurround, Ls),
+    (kAudioChannelBit_RightSurround, Rs),
+    (kAudioChannelBit_LeftCenter, Lc),
+    (kAudioChannelBit_RightCenter, Rc),
+    (kAudioChannelBit_CenterSurround, Cs),
+    (kAudioChannelBit_LeftSurroundDirect, Lsd),
+    (kAudioChannelBit_RightSurroundDirect, Rsd),
+    (kAudioChannelBit_TopCenterSurround, Ts),
+    (kAudioChannelBit_VerticalHeightLeft, Vhl),
+    (kAudioChannelBit_VerticalHeightCenter, Vhc),
+    (kAudioChannelBit_VerticalHeightRight, Vhr),
+    (kAudioChannelBit_TopBackLeft, Tbl),
+    (kAudioChannelBit_TopBackCenter, kAudioChannelLabel_TopBackCenter),
+    (kAudioChannelBit_TopBackRight, Tbr),
+    (kAudioChannelBit_LeftTopMiddle, Ltm),
+    (kAudioChannelBit_RightTopMiddle, Rtm),
+    (kAudioChannelBit_LeftTopRear, Ltr),
+    (kAudioChannelBit_CenterTopRear, kAudioChannelLabel_CenterTopRear),
+    (kAudioChannelBit_RightTopRear, Rtr),
+];
+
+pub fn channel_labels_for_layout_tag(layout_tag: AudioChannelLayoutTag) -> Option<Vec<AudioChannelLabel>> {
+    let channel_count = AudioChannelLayoutTag_GetNumberOfChannels(layout_tag);
+    match layout_tag & 0xFFFF0000 {
+        kAudioChannelLayoutTag_DiscreteInOrder => return Some((0..channel_count).map(|i| kAudioChannelLabel_Discrete_0 | i).collect()),
+        kAudioChannelLayoutTag_HOA_ACN_SN3D => return Some((0..channel_count).map(|i| kAudioChannelLabel_HOA_ACN_0 | i).collect()),
+        kAudioChannelLayoutTag_HOA_ACN_N3D => return Some((0..channel_count).map(|i| kAudioChannelLabel_HOA_N3D | i).collect()),
+        _ => {}
+    }
+    CHANNEL_LAYOUT_TAG_LABELS.iter().find(|(tag, _)| *tag == layout_tag).map(|(_, labels)| labels.to_vec())
+}
+
+pub fn channel_labels_for_bitmap(bitmap: AudioChannelBitmap) -> Vec<AudioChannelLabel> {
+    CHANNEL_BIT_LABELS.iter().filter(|(bit, _)| bitmap & bit != 0).map(|(_, label)| *label).collect()
+}
+
+pub fn channel_bitmap_for_labels(labels: &[AudioChannelLabel]) -> Option<AudioChannelBitmap> {
+    let mut bitmap = 0;
+    for label in labels {
+        let (bit, _) = CHANNEL_BIT_LABELS.iter().find(|(_, bit_label)| bit_label == label)?;
+        if bitmap & bit != 0 {
+            return None;
+        }
+        bitmap |= bit;
+    }
+    // Bitmaps carry no ordering, so only labels already in bit order round trip
+    if channel_labels_for_bitmap(bitmap) == labels {
+        Some(bitmap)
+    } else {
+        None
+    }
+}
+
+/// # Safety
+///
+/// `layout_size` must not exceed the number of readable bytes starting at
+/// `layout`, which is usually more than `size_of::<AudioChannelLayout>()` for
+/// variable length layouts.
+pub unsafe fn channel_descriptions(layout: &AudioChannelLayout, layout_size: usize) -> &[AudioChannelDescription] {
+    let header_size = size_of::<AudioChannelLayout>() - size_of::<AudioChannelDescription>();
+    let available = layout_size.saturating_sub(header_size) / size_of::<AudioChannelDescription>();
+    let count = (layout.mNumberChannelDescriptions as usize).min(available);
+    from_raw_parts(layout.mChannelDescriptions.as_ptr(), count)
+}
+
+/// # Safety
+///
+/// `layout_size` must not exceed the number of readable bytes starting at
+/// `layout`, as for [`channel_descriptions`].
+pub unsafe fn channel_labels_for_layout(layout: &AudioChannelLayout, layout_size: usize) -> Option<Vec<AudioChannelLabel>> {
+    match layout.mChannelLayoutTag {
+        kAudioChannelLayoutTag_UseChannelDescriptions => {
+            Some(channel_descriptions(layout, layout_size).iter().map(|desc| desc.mChannelLabel).collect())
+        }
+        kAudioChannelLayoutTag_UseChannelBitmap => Some(channel_labels_for_bitmap(layout.mChannelBitmap)),
+        tag => channel_labels_for_layout_tag(tag),
+    }
+}
+
+/// # Safety
+///
+/// `layout_size` and `other_size` must not exceed the number of readable bytes
+/// starting at `layout` and `other` respectively, as for
+/// [`channel_descriptions`].
+pub unsafe fn channel_layouts_equivalent(layout: &AudioChannelLayout, layout_size: usize, other: &AudioChannelLayout, other_size: usize) -> bool {
+    if layout.mChannelLayoutTag == other.mChannelLayoutTag &&
+        layout.mChannelLayoutTag != kAudioChannelLayoutTag_UseChannelDescriptions &&
+        layout.mChannelLayoutTag != kAudioChannelLayoutTag_UseChannelBitmap
+    {
+        return true;
+    }
+    let descriptions = if layout.mChannelLayoutTag == kAudioChannelLayoutTag_UseChannelDescriptions {
+        channel_descriptions(layout, layout_size)
+    } else {
+        &[]
+    };
+    let other_descriptions = if other.mChannelLayoutTag == kAudioChannelLayoutTag_UseChannelDescriptions {
+        channel_descriptions(other, other_size)
+    } else {
+        &[]
+    };
+    // Positional channels can only be compared description by description
+    let uses_coordinates =
+        |descriptions: &[AudioChannelDescription]| descriptions.iter().any(|desc| desc.mChannelLabel == kAudioChannelLabel_UseCoordinates);
+    if uses_coordinates(descriptions) || uses_coordinates(other_descriptions) {
+        return descriptions == other_descriptions;
+    }
+    match (channel_labels_for_layout(layout, layout_size), channel_labels_for_layout(other, other_size)) {
+        (Some(labels), Some(other_labels)) => labels == other_labels,
+        _ => false,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChannelLayout {
+    Tag(AudioChannelLayoutTag),
+    Bitmap(AudioChannelBitmap),
+    Descriptions(Vec<AudioChannelDescription>),
+}
+
+impl ChannelLayout {
+    pub fn from_labels(labels: &[AudioChannelLabel]) -> Self {
+        ChannelLayout::Descriptions(
+            labels
+                .iter()
+                .map(|label| AudioChannelDescription {
+                    mChannelLabel: *label,
+                    ..Default::default()
+                })
+                .collect(),
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `layout_size` must not exceed the number of readable bytes starting at
+    /// `layout`, as for [`channel_descriptions`].
+    pub unsafe fn from_audio_channel_layout(layout: &AudioChannelLayout, layout_size: usize) -> Result<Self, OSStatus> {
+        match layout.mChannelLayoutTag {
+            kAudioChannelLayoutTag_UseChannelDescriptions => {
+                let descriptions = channel_descriptions(layout, layout_size);
+                if descriptions.len() != layout.mNumberChannelDescriptions as usize {
+                    return Err(kAudio_ParamError);
+                }
+                Ok(ChannelLayout::Descriptions(descriptions.to_vec()))
+            }
+            kAudioChannelLayoutTag_UseChannelBitmap => Ok(ChannelLayout::Bitmap(layout.mChannelBitmap)),
+            tag => Ok(ChannelLayout::Tag(tag)),
+        }
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, OSStatus> {
+        let read_u32 = |offset: usize| u32::from_ne_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+        Self::parse(data, read_u32)
+    }
+
+    pub fn from_chan_atom(data: &[u8]) -> Result<Self, OSStatus> {
+        if data.len() < ATOM_HEADER_SIZE {
+            return Err(kAudio_ParamError);
+        }
+        let read_u32 = |offset: usize| u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+        let size = read_u32(0) as usize;
+        if read_u32(4) != kChannelLayoutAtomType || size < ATOM_HEADER_SIZE || size > data.len() || data[8] != 0 {
+            return Err(kAudio_ParamError);
+        }
+        let payload = &data[ATOM_HEADER_SIZE..size];
+        Self::parse(payload, |offset| u32::from_be_bytes([payload[offset], payload[offset + 1], payload[offset + 2], payload[offset + 3]]))
+    }
+
+    fn parse<F>(data: &[u8], read_u32: F) -> Result<Self, OSStatus>
+    where
+        F: Fn(usize) -> u32,
+    {
+        if data.len() < LAYOUT_HEADER_SIZE {
+            return Err(kAudio_ParamError);
+        }
+        match read_u32(0) {
+            kAudioChannelLayoutTag_UseChannelDescriptions => {
+                let count = read_u32(8) as usize;
+                if (data.len() - LAYOUT_HEADER_SIZE) / DESCRIPTION_SIZE < count {
+                    return Err(kAudio_ParamError);
+                }
+                let descriptions = (0..count)
+                    .map(|i| {
+                        let offset = LAYOUT_HEADER_SIZE + i * DESCRIPTION_SIZE;
+                        AudioChannelDescription {
+                            mChannelLabel: read_u32(offset),
+                            mChannelFlags: read_u32(offset + 4),
+                            mCoordinates: [
+                                f32::from_bits(read_u32(offset + 8)),
+                                f32::from_bits(read_u32(offset + 12)),
+                                f32::from_bits(read_u32(offset + 16)),
+                            ],
+                        }
+                    })
+                    .collect();
+                Ok(ChannelLayout::Descriptions(descriptions))
+            }
+            kAudioChannelLayoutTag_UseChannelBitmap => Ok(ChannelLayout::Bitmap(read_u32(4))),
+            tag => Ok(ChannelLayout::Tag(tag)),
+        }
+    }
+
+    #[inline]
+    pub fn layout_tag(&self) -> AudioChannelLayoutTag {
+        match self {
+            ChannelLayout::Tag(tag) => *tag,
+            ChannelLayout::Bitmap(_) => kAudioChannelLayoutTag_UseChannelBitmap,
+            ChannelLayout::Descriptions(_) => kAudioChannelLayoutTag_UseChannelDescriptions,
+        }
+    }
+
+    #[inline]
+    pub fn bitmap(&self) -> AudioChannelBitmap {
+        match self {
+            ChannelLayout::Bitmap(bitmap) => *bitmap,
+            _ => 0,
+        }
+    }
+
+    #[inline]
+    pub fn descriptions(&self) -> &[AudioChannelDescription] {
+        match self {
+            ChannelLayout::Descriptions(descriptions) => descriptions,
+            _ => &[],
+        }
+    }
+
+    pub fn labels(&self) -> Option<Vec<AudioChannelLabel>> {
+        match self {
+            ChannelLayout::Tag(tag) => channel_labels_for_layout_tag(*tag),
+            ChannelLayout::Bitmap(bitmap) => Some(channel_labels_for_bitmap(*bitmap)),
+            ChannelLayout::Descriptions(descriptions) => Some(descriptions.iter().map(|desc| desc.mChannelLabel).collect()),
+        }
+    }
+
+    pub fn channel_count(&self) -> usize {
+        match self {
+            ChannelLayout::Tag(tag) => AudioChannelLayoutTag_GetNumberOfChannels(*tag) as usize,
+            ChannelLayout::Bitmap(bitmap) => bitmap.count_ones() as usize,
+            ChannelLayout::Descriptions(descriptions) => descriptions.len(),
+        }
+    }
+
+    #[inline]
+    pub fn byte_size(&self) -> usize {
+        LAYOUT_HEADER_SIZE + self.descriptions().len() * DESCRIPTION_SIZE
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.byte_size());
+        self.write(|value| data.extend_from_slice(&value.to_ne_bytes()));
+        data
+    }
+
+    pub fn to_chan_atom(&self) -> Vec<u8> {
+        let size = ATOM_HEADER_SIZE + self.byte_size();
+        let mut data = Vec::with_capacity(size);
+        data.extend_from_slice(&(size as u32).to_be_bytes());
+        data.extend_from_slice(&kChannelLayoutAtomType.to_be_bytes());
+        // Version and flags
+        data.extend_from_slice(&0u32.to_be_bytes());
+        self.write(|value| data.extend_from_slice(&value.to_be_bytes()));
+        data
+    }
+
+    fn write<F>(&self, mut write_u32: F)
+    where
+        F: FnMut(u32),
+    {
+        let descriptions = self.descriptions();
+        write_u32(self.layout_tag());
+        write_u32(self.bitmap());
+        write_u32(descriptions.len() as u32);
+        for desc in descriptions {
+            write_u32(desc.mChannelLabel);
+            write_u32(desc.mChannelFlags);
+            desc.mCoordinates.iter().for_each(|coordinate| write_u32(coordinate.to_bits()));
+        }
+    }
+
+    // The layout reference is only valid inside the closure and covers the reported
+    // size
+    pub fn with_audio_channel_layout<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&AudioChannelLayout, usize) -> R,
+    {
+        let size = self.byte_size();
+        let mut storage = vec![0u32; size.max(size_of::<AudioChannelLayout>()).div_ceil(align_of::<u32>())];
+        let data = self.to_bytes();
+        unsafe {
+            copy_nonoverlapping(data.as_ptr(), storage.as_mut_ptr() as *mut u8, size);
+            f(&*(storage.as_ptr() as *const AudioChannelLayout), size)
+        }
+    }
+
+    pub fn to_descriptions(&self) -> Option<Self> {
+        match self {
+            ChannelLayout::Descriptions(_) => Some(self.clone()),
+            _ => self.labels().map(|labels| Self::from_labels(&labels)),
+        }
+    }
+
+    // Picks the most compact form that describes the same channels
+    pub fn normalized(&self) -> Self {
+        let labels = match self {
+            ChannelLayout::Tag(_) => return self.clone(),
+            ChannelLayout::Bitmap(bitmap) => channel_labels_for_bitmap(*bitmap),
+            ChannelLayout::Descriptions(descriptions) => {
+                let plain = descriptions.iter().all(|desc| desc.mChannelLabel != kAudioChannelLabel_UseCoordinates && desc.mChannelFlags == 0);
+                if !plain {
+                    return self.clone();
+                }
+                descriptions.iter().map(|desc| desc.mChannelLabel).collect()
+            }
+        };
+        if let Some(tag) = layout_tag_for_labels(&labels) {
+            return ChannelLayout::Tag(tag);
+        }
+        match channel_bitmap_for_labels(&labels) {
+            Some(bitmap) if bitmap != 0 => ChannelLayout::Bitmap(bitmap),
+            _ => self.clone(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), OSStatus> {
+        match self {
+            ChannelLayout::Tag(tag) => {
+                let reserved = *tag >= kAudioChannelLayoutTag_BeginReserved && *tag <= kAudioChannelLayoutTag_EndReserved;
+                if matches!(*tag, kAudioChannelLayoutTag_UseChannelDescriptions | kAudioChannelLayoutTag_UseChannelBitmap) ||
+                    reserved ||
+                    AudioChannelLayoutTag_GetNumberOfChannels(*tag) == 0
+                {
+                    return Err(kAudio_ParamError);
+                }
+            }
+            ChannelLayout::Bitmap(bitmap) => {
+                let known = CHANNEL_BIT_LABELS.iter().fold(0, |known, (bit, _)| known | bit);
+                if *bitmap == 0 || bitmap & !known != 0 {
+                    return Err(kAudio_ParamError);
+                }
+            }
+            ChannelLayout::Descriptions(descriptions) => {
+                if descriptions.is_empty() {
+                    return Err(kAudio_ParamError);
+                }
+                descriptions.iter().try_for_each(validate_description)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_description(desc: &AudioChannelDescription) -> Result<(), OSStatus> {
+    if desc.mChannelLabel != kAudioChannelLabel_UseCoordinates {
+        return Ok(());
+    }
+    let rectangular = desc.mChannelFlags & kAudioChannelFlags_RectangularCoordinates != 0;
+    let spherical = desc.mChannelFlags & kAudioChannelFlags_SphericalCoordinates != 0;
+    let meters = desc.mChannelFlags & kAudioChannelFlags_Meters != 0;
+    let coordinates = desc.mCoordinates;
+    if rectangular == spherical || coordinates.iter().any(|coordinate| !coordinate.is_finite()) {
+        return Err(kAudio_ParamError);
+    }
+    let valid = if rectangular {
+        // Without the meters flag rectangular coordinates are unit relative
+        meters || coordinates.iter().all(|coordinate| (-1.0..=1.0).contains(coordinate))
+    } else {
+        let azimuth = coordinates[kAudioChannelCoordinates_Azimuth as usize];
+        let elevation = coordinates[kAudioChannelCoordinates_Elevation as usize];
+        let distance = coordinates[kAudioChannelCoordinates_Distance as usize];
+        (-360.0..=360.0).contains(&azimuth) && (-90.0..=90.0).contains(&elevation) && distance >= 0.0 && (meters || distance <= 1.0)
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(kAudio_ParamError)
+    }
+}
+
+pub fn layout_tag_for_labels(labels: &[AudioChannelLabel]) -> Option<AudioChannelLayoutTag> {
+    if labels.is_empty() || labels.len() > 0xFFFF {
+        return None;
+    }
+    if labels.iter().enumerate().all(|(i, label)| *label == kAudioChannelLabel_Discrete_0 | i as u32) {
+        return Some(kAudioChannelLayoutTag_DiscreteInOrder | labels.len() as u32);
+    }
+    CHANNEL_LAYOUT_TAG_LABELS.iter().find(|(_, tag_labels)| *tag_labels == labels).map(|(tag, _)| *tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_tag_channel_counts() {
+        for (tag, labels) in CHANNEL_LAYOUT_TAG_LABELS {
+            assert_eq!(AudioChannelLayoutTag_GetNumberOfChannels(*tag) as usize, labels.len(), "tag {:#x}", tag);
+            assert_eq!(ChannelLayout::Tag(*tag).channel_count(), labels.len());
+        }
+        assert_eq!(channel_labels_for_layout_tag(kAudioChannelLayoutTag_Stereo), Some(vec![L, R]));
+        assert_eq!(channel_labels_for_layout_tag(kAudioChannelLayoutTag_MPEG_5_1_A), Some(vec![L, R, C, LFE, Ls, Rs]));
+        assert_eq!(
+            channel_labels_for_layout_tag(kAudioChannelLayoutTag_DiscreteInOrder | 3),
+            Some(vec![kAudioChannelLabel_Discrete_0, kAudioChannelLabel_Discrete_1, kAudioChannelLabel_Discrete_2])
+        );
+        assert_eq!(layout_tag_for_labels(&[L, R, C, LFE, Ls, Rs]), Some(kAudioChannelLayoutTag_MPEG_5_1_A));
+        assert_eq!(
+            layout_tag_for_labels(&[kAudioChannelLabel_Discrete_0, kAudioChannelLabel_Discrete_1]),
+            Some(kAudioChannelLayoutTag_DiscreteInOrder | 2)
+        );
+        assert_eq!(layout_tag_for_labels(&[]), None);
+    }
+
+    #[test]
+    fn bitmaps_round_trip_in_bit_order() {
+        let bitmap = kAudioChannelBit_Left | kAudioChannelBit_Right | kAudioChannelBit_LFEScreen;
+        assert_eq!(channel_labels_for_bitmap(bitmap), vec![L, R, LFE]);
+        assert_eq!(channel_bitmap_for_labels(&[L, R, LFE]), Some(bitmap));
+        assert_eq!(channel_bitmap_for_labels(&[R, L]), None);
+        assert_eq!(channel_bitmap_for_labels(&[L, L]), None);
+        assert_eq!(ChannelLayout::Bitmap(bitmap).channel_count(), 3);
+    }
+
+    #[test]
+    fn serialized_layouts_round_trip() {
+        let mut positioned = AudioChannelDescription {
+            mChannelLabel: kAudioChannelLabel_UseCoordinates,
+            mChannelFlags: kAudioChannelFlags_SphericalCoordinates,
+            ..Default::default()
+        };
+        positioned.mCoordinates[kAudioChannelCoordinates_Azimuth as usize] = -30.0;
+        positioned.mCoordinates[kAudioChannelCoordinates_Distance as usize] = 1.0;
+        let layouts = [
+            ChannelLayout::Tag(kAudioChannelLayoutTag_Stereo),
+            ChannelLayout::Bitmap(kAudioChannelBit_Left | kAudioChannelBit_Right),
+            ChannelLayout::Descriptions(vec![
+                positioned,
+                AudioChannelDescription {
+                    mChannelLabel: C,
+                    ..Default::default()
+                },
+            ]),
+        ];
+        for layout in &layouts {
+            let bytes = layout.to_bytes();
+            assert_eq!(bytes.len(), layout.byte_size());
+            assert_eq!(ChannelLayout::from_bytes(&bytes), Ok(layout.clone()));
+            let atom = layout.to_chan_atom();
+            assert_eq!(&atom[4..8], b"chan");
+            assert_eq!(ChannelLayout::from_chan_atom(&atom), Ok(layout.clone()));
+            let copy = layout.with_audio_channel_layout(|layout, size| unsafe { ChannelLayout::from_audio_channel_layout(layout, size) });
+            assert_eq!(copy, Ok(layout.clone()));
+        }
+        // Descriptions that run past the end are rejected
+        let bytes = layouts[2].to_bytes();
+        assert_eq!(ChannelLayout::from_bytes(&bytes[..bytes.len() - 1]), Err(kAudio_ParamError));
+        assert_eq!(ChannelLayout::from_chan_atom(&layouts[0].to_bytes()), Err(kAudio_ParamError));
+    }
+
+    #[test]
+    fn normalized_layouts_pick_the_compact_form() {
+        assert_eq!(ChannelLayout::from_labels(&[L, R]).normalized(), ChannelLayout::Tag(kAudioChannelLayoutTag_Stereo));
+        assert_eq!(
+            ChannelLayout::Bitmap(kAudioChannelBit_Left | kAudioChannelBit_Right).normalized(),
+            ChannelLayout::Tag(kAudioChannelLayoutTag_Stereo)
+        );
+        assert_eq!(ChannelLayout::from_labels(&[L, LFE]).normalized(), ChannelLayout::Bitmap(kAudioChannelBit_Left | kAudioChannelBit_LFEScreen));
+        let unordered = ChannelLayout::from_labels(&[LFE, Ts, L]);
+        assert_eq!(unordered.normalized(), unordered);
+        assert_eq!(ChannelLayout::Tag(kAudioChannelLayoutTag_Stereo).to_descriptions(), Some(ChannelLayout::from_labels(&[L, R])));
+    }
+
+    #[test]
+    fn validate_layouts() {
+        assert_eq!(ChannelLayout::Tag(kAudioChannelLayoutTag_MPEG_5_1_A).validate(), Ok(()));
+        assert_eq!(ChannelLayout::Tag(kAudioChannelLayoutTag_UseChannelBitmap).validate(), Err(kAudio_ParamError));
+        assert_eq!(ChannelLayout::Tag(kAudioChannelLayoutTag_DiscreteInOrder).validate(), Err(kAudio_ParamError));
+        assert_eq!(ChannelLayout::Bitmap(0).validate(), Err(kAudio_ParamError));
+        assert_eq!(ChannelLayout::Descriptions(vec![]).validate(), Err(kAudio_ParamError));
+        let mut positioned = AudioChannelDescription {
+            mChannelLabel: kAudioChannelLabel_UseCoordinates,
+            mChannelFlags: kAudioChannelFlags_RectangularCoordinates,
+            mCoordinates: [0.5, 1.0, 0.0],
+        };
+        assert_eq!(ChannelLayout::Descriptions(vec![positioned]).validate(), Ok(()));
+        positioned.mCoordinates[0] = 2.0;
+        assert_eq!(ChannelLayout::Descriptions(vec![positioned]).validate(), Err(kAudio_ParamError));
+        positioned.mChannelFlags |= kAudioChannelFlags_Meters;
+        assert_eq!(ChannelLayout::Descriptions(vec![positioned]).validate(), Ok(()));
+        positioned.mChannelFlags |= kAudioChannelFlags_SphericalCoordinates;
+        assert_eq!(ChannelLayout::Descriptions(vec![positioned]).validate(), Err(kAudio_ParamError));
+    }
+}
@@ -0,0 +1,482 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use core_foundation_sys::base::OSStatus;
+
+use crate::{
+    audio_buffer_list::AudioBufferListBuf,
+    base_types::*,
+    channel_layout::{channel_labels_for_layout, ChannelLayout},
+    pcm_converter::{channel_location, PCMFormat},
+};
+
+const MAX_FOLD_DEPTH: usize = 8;
+
+const L: AudioChannelLabel = kAudioChannelLabel_Left;
+const R: AudioChannelLabel = kAudioChannelLabel_Right;
+const C: AudioChannelLabel = kAudioChannelLabel_Center;
+const M: AudioChannelLabel = kAudioChannelLabel_Mono;
+const LFE: AudioChannelLabel = kAudioChannelLabel_LFEScreen;
+const Ls: AudioChannelLabel = kAudioChannelLabel_LeftSurround;
+const Rs: AudioChannelLabel = kAudioChannelLabel_RightSurround;
+const Rls: AudioChannelLabel = kAudioChannelLabel_RearSurroundLeft;
+const Rrs: AudioChannelLabel = kAudioChannelLabel_RearSurroundRight;
+const Vhl: AudioChannelLabel = kAudioChannelLabel_VerticalHeightLeft;
+const Vhr: AudioChannelLabel = kAudioChannelLabel_VerticalHeightRight;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelMixOptions {
+    pub center_gain: f32,
+    pub surround_gain: f32,
+    pub height_gain: f32,
+    // Zero drops LFE channels that have no LFE destination
+    pub lfe_gain: f32,
+    pub normalize: bool,
+}
+
+impl Default for ChannelMixOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            center_gain: FRAC_1_SQRT_2,
+            surround_gain: FRAC_1_SQRT_2,
+            height_gain: FRAC_1_SQRT_2,
+            lfe_gain: 0.0,
+            normalize: true,
+        }
+    }
+}
+
+// Fold into the first alternative found in the destination, else the first
+fn fold_alternatives(label: AudioChannelLabel, options: &ChannelMixOptions) -> Vec<Vec<(AudioChannelLabel, f32)>> {
+    let center = options.center_gain;
+    let surround = options.surround_gain;
+    let height = options.height_gain;
+    match label {
+        kAudioChannelLabel_Left => vec![vec![(M, FRAC_1_SQRT_2)], vec![(C, FRAC_1_SQRT_2)]],
+        kAudioChannelLabel_Right => vec![vec![(M, FRAC_1_SQRT_2)], vec![(C, FRAC_1_SQRT_2)]],
+        kAudioChannelLabel_Center => vec![vec![(L, center), (R, center)], vec![(M, 1.0)]],
+        kAudioChannelLabel_Mono => vec![vec![(C, 1.0)], vec![(L, FRAC_1_SQRT_2), (R, FRAC_1_SQRT_2)]],
+        kAudioChannelLabel_LFEScreen => vec![vec![(C, options.lfe_gain)]],
+        kAudioChannelLabel_LFE2 | kAudioChannelLabel_LFE3 => vec![vec![(LFE, 1.0)]],
+        kAudioChannelLabel_LeftSurround => vec![vec![(L, surround)]],
+        kAudioChannelLabel_RightSurround => vec![vec![(R, surround)]],
+        kAudioChannelLabel_LeftCenter => vec![vec![(L, FRAC_1_SQRT_2), (C, FRAC_1_SQRT_2)], vec![(L, 1.0)]],
+        kAudioChannelLabel_RightCenter => vec![vec![(R, FRAC_1_SQRT_2), (C, FRAC_1_SQRT_2)], vec![(R, 1.0)]],
+        kAudioChannelLabel_CenterSurround | kAudioChannelLabel_CenterSurroundDirect => {
+            vec![vec![(Rls, FRAC_1_SQRT_2), (Rrs, FRAC_1_SQRT_2)], vec![(Ls, FRAC_1_SQRT_2), (Rs, FRAC_1_SQRT_2)]]
+        }
+        kAudioChannelLabel_RearSurroundLeft | kAudioChannelLabel_LeftSurroundDirect | kAudioChannelLabel_LeftSideSurround => {
+            vec![vec![(Ls, 1.0)]]
+        }
+        kAudioChannelLabel_RearSurroundRight | kAudioChannelLabel_RightSurroundDirect | kAudioChannelLabel_RightSideSurround => {
+            vec![vec![(Rs, 1.0)]]
+        }
+        kAudioChannelLabel_LeftBackSurround => vec![vec![(Rls, 1.0)]],
+        kAudioChannelLabel_RightBackSurround => vec![vec![(Rrs, 1.0)]],
+        kAudioChannelLabel_LeftWide | kAudioChannelLabel_LeftEdgeOfScreen | kAudioChannelLabel_LeftBottom => vec![vec![(L, 1.0)]],
+        kAudioChannelLabel_RightWide | kAudioChannelLabel_RightEdgeOfScreen | kAudioChannelLabel_RightBottomL => vec![vec![(R, 1.0)]],
+        kAudioChannelLabel_CenterBottom => vec![vec![(C, 1.0)]],
+        kAudioChannelLabel_VerticalHeightLeft => vec![vec![(L, height)]],
+        kAudioChannelLabel_VerticalHeightRight => vec![vec![(R, height)]],
+        kAudioChannelLabel_VerticalHeightCenter => vec![vec![(C, height)]],
+        kAudioChannelLabel_LeftTopMiddle | kAudioChannelLabel_LeftTopSurround => vec![vec![(Vhl, 1.0)]],
+        kAudioChannelLabel_RightTopMiddle | kAudioChannelLabel_RightTopSurround => vec![vec![(Vhr, 1.0)]],
+        kAudioChannelLabel_TopCenterSurround => vec![vec![(Vhl, FRAC_1_SQRT_2), (Vhr, FRAC_1_SQRT_2)]],
+        kAudioChannelLabel_TopBackLeft | kAudioChannelLabel_LeftTopRear => vec![vec![(Vhl, 1.0)], vec![(Ls, height)]],
+        kAudioChannelLabel_TopBackRight | kAudioChannelLabel_RightTopRear => vec![vec![(Vhr, 1.0)], vec![(Rs, height)]],
+        kAudioChannelLabel_TopBackCenter | kAudioChannelLabel_CenterTopRear => {
+            vec![vec![(Vhl, FRAC_1_SQRT_2), (Vhr, FRAC_1_SQRT_2)], vec![(Ls, FRAC_1_SQRT_2 * height), (Rs, FRAC_1_SQRT_2 * height)]]
+        }
+        kAudioChannelLabel_LeftTotal | kAudioChannelLabel_HeadphonesLeft | kAudioChannelLabel_BinauralLeft => vec![vec![(L, 1.0)]],
+        kAudioChannelLabel_RightTotal | kAudioChannelLabel_HeadphonesRight | kAudioChannelLabel_BinauralRight => vec![vec![(R, 1.0)]],
+        _ => vec![],
+    }
+}
+
+// Stereo pairs that carry the same signal under a different name
+fn equivalent_label(label: AudioChannelLabel, destination: &[AudioChannelLabel]) -> Option<AudioChannelLabel> {
+    let candidates: &[AudioChannelLabel] = match label {
+        kAudioChannelLabel_Left => &[kAudioChannelLabel_HeadphonesLeft, kAudioChannelLabel_BinauralLeft, kAudioChannelLabel_LeftTotal],
+        kAudioChannelLabel_Right => &[kAudioChannelLabel_HeadphonesRight, kAudioChannelLabel_BinauralRight, kAudioChannelLabel_RightTotal],
+        _ => &[],
+    };
+    candidates.iter().find(|candidate| destination.contains(candidate)).copied()
+}
+
+#[inline]
+fn is_positional(label: AudioChannelLabel) -> bool {
+    !matches!(label >> 16, 1..=4) && label != kAudioChannelLabel_Unknown && label != kAudioChannelLabel_Unused && label != kAudioChannelLabel_Discrete
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelMixMatrix {
+    input_channel_count: usize,
+    output_channel_count: usize,
+    // Row major, one row of input gains per output channel
+    coefficients: Vec<f32>,
+}
+
+impl ChannelMixMatrix {
+    pub fn new(input_channel_count: usize, output_channel_count: usize) -> Result<Self, OSStatus> {
+        // Interleaved frames are sized by the channel counts
+        if input_channel_count == 0 || output_channel_count == 0 {
+            return Err(kAudio_ParamError);
+        }
+        Ok(Self {
+            input_channel_count,
+            output_channel_count,
+            coefficients: vec![0.0; input_channel_count * output_channel_count],
+        })
+    }
+
+    pub fn identity(channel_count: usize) -> Result<Self, OSStatus> {
+        let mut matrix = Self::new(channel_count, channel_count)?;
+        (0..channel_count).for_each(|channel| matrix.set_coefficient(channel, channel, 1.0));
+        Ok(matrix)
+    }
+
+    pub fn from_labels(input: &[AudioChannelLabel], output: &[AudioChannelLabel], options: &ChannelMixOptions) -> Result<Self, OSStatus> {
+        let mut matrix = Self::new(input.len(), output.len())?;
+        for (input_channel, &label) in input.iter().enumerate() {
+            if is_positional(label) {
+                let mut gains = Vec::new();
+                fold_label(label, 1.0, output, options, 0, &mut gains);
+                for (target, gain) in gains {
+                    if let Some(output_channel) = output.iter().position(|&output_label| output_label == target) {
+                        matrix.coefficients[output_channel * input.len() + input_channel] += gain;
+                    }
+                }
+            } else if let Some(output_channel) = output.iter().position(|&output_label| output_label == label) {
+                matrix.set_coefficient(output_channel, input_channel, 1.0);
+            } else if output.get(input_channel).is_some_and(|&output_label| !is_positional(output_label)) {
+                // Discrete and unknown channels have no position, so they map by index
+                matrix.set_coefficient(input_channel, input_channel, 1.0);
+            }
+        }
+        if options.normalize {
+            matrix.normalize();
+        }
+        Ok(matrix)
+    }
+
+    /// # Safety
+    ///
+    /// `input_size` and `output_size` must not exceed the number of readable
+    /// bytes starting at `input` and `output`, as for
+    /// [`channel_descriptions`](crate::channel_layout::channel_descriptions).
+    pub unsafe fn from_layouts(
+        input: &AudioChannelLayout,
+        input_size: usize,
+        output: &AudioChannelLayout,
+        output_size: usize,
+        options: &ChannelMixOptions,
+    ) -> Result<Self, OSStatus> {
+        let input = channel_labels_for_layout(input, input_size).ok_or(kAudio_ParamError)?;
+        let output = channel_labels_for_layout(output, output_size).ok_or(kAudio_ParamError)?;
+        Self::from_labels(&input, &output, options)
+    }
+
+    pub fn from_channel_layouts(input: &ChannelLayout, output: &ChannelLayout, options: &ChannelMixOptions) -> Result<Self, OSStatus> {
+        let input = input.labels().ok_or(kAudio_ParamError)?;
+        let output = output.labels().ok_or(kAudio_ParamError)?;
+        Self::from_labels(&input, &output, options)
+    }
+
+    #[inline]
+    pub fn input_channel_count(&self) -> usize {
+        self.input_channel_count
+    }
+
+    #[inline]
+    pub fn output_channel_count(&self) -> usize {
+        self.output_channel_count
+    }
+
+    #[inline]
+    pub fn coefficients(&self) -> &[f32] {
+        &self.coefficients
+    }
+
+    #[inline]
+    pub fn coefficient(&self, output_channel: usize, input_channel: usize) -> f32 {
+        self.coefficients[output_channel * self.input_channel_count + input_channel]
+    }
+
+    #[inline]
+    pub fn set_coefficient(&mut self, output_channel: usize, input_channel: usize, gain: f32) {
+        self.coefficients[output_channel * self.input_channel_count + input_channel] = gain;
+    }
+
+    #[inline]
+    pub fn row(&self, output_channel: usize) -> &[f32] {
+        &self.coefficients[output_channel * self.input_channel_count..(output_channel + 1) * self.input_channel_count]
+    }
+
+    // Scales each row that mixes several inputs down to a gain sum of one so
+    // full scale input cannot clip, while pass-through rows keep unity gain
+    pub fn normalize(&mut self) {
+        for row in self.coefficients.chunks_exact_mut(self.input_channel_count) {
+            let inputs = row.iter().filter(|gain| **gain != 0.0).count();
+            let sum = row.iter().map(|gain| gain.abs()).sum::<f32>();
+            if inputs > 1 && sum > 1.0 {
+                row.iter_mut().for_each(|gain| *gain /= sum);
+            }
+        }
+    }
+
+    pub fn apply_interleaved(&self, input: &[f32], output: &mut [f32]) -> usize {
+        let frames = (input.len() / self.input_channel_count).min(output.len() / self.output_channel_count);
+        let input = input.chunks_exact(self.input_channel_count);
+        let output = output.chunks_exact_mut(self.output_channel_count);
+        for (input, output) in input.zip(output).take(frames) {
+            for (output_channel, sample) in output.iter_mut().enumerate() {
+                *sample = self.row(output_channel).iter().zip(input).map(|(gain, sample)| gain * sample).sum();
+            }
+        }
+        frames
+    }
+
+    pub fn apply_planar(&self, input: &[&[f32]], output: &mut [&mut [f32]]) -> Result<usize, OSStatus> {
+        if input.len() != self.input_channel_count || output.len() != self.output_channel_count {
+            return Err(kAudio_ParamError);
+        }
+        let frames = input.iter().map(|buffer| buffer.len()).chain(output.iter().map(|buffer| buffer.len())).min().unwrap_or(0);
+        for (output_channel, output) in output.iter_mut().enumerate() {
+            let output = &mut output[..frames];
+            output.fill(0.0);
+            for (gain, input) in self.row(output_channel).iter().zip(input) {
+                if *gain != 0.0 {
+                    output.iter_mut().zip(&input[..frames]).for_each(|(output, input)| *output += gain * input);
+                }
+            }
+        }
+        Ok(frames)
+    }
+}
+
+fn fold_label(
+    label: AudioChannelLabel,
+    gain: f32,
+    destination: &[AudioChannelLabel],
+    options: &ChannelMixOptions,
+    depth: usize,
+    gains: &mut Vec<(AudioChannelLabel, f32)>,
+) {
+    if gain == 0.0 || depth > MAX_FOLD_DEPTH {
+        return;
+    }
+    if destination.contains(&label) {
+        gains.push((label, gain));
+        return;
+    }
+    if let Some(equivalent) = equivalent_label(label, destination) {
+        gains.push((equivalent, gain));
+        return;
+    }
+    let alternatives = fold_alternatives(label, options);
+    let alternative =
+        alternatives.iter().find(|alternative| alternative.iter().all(|(target, _)| destination.contains(target))).or_else(|| alternatives.first());
+    if let Some(alternative) = alternative {
+        for &(target, target_gain) in alternative {
+            fold_label(target, gain * target_gain, destination, options, depth + 1, gains);
+        }
+    }
+}
+
+pub struct ChannelMixer {
+    source: PCMFormat,
+    destination: PCMFormat,
+    matrix: ChannelMixMatrix,
+    input_frame: Vec<f32>,
+}
+
+impl ChannelMixer {
+    pub fn new(source: &AudioStreamBasicDescription, destination: &AudioStreamBasicDescription, matrix: ChannelMixMatrix) -> Result<Self, OSStatus> {
+        let source_format = PCMFormat::from_stream_basic_description(source)?;
+        let destination_format = PCMFormat::from_stream_basic_description(destination)?;
+        let same_samples = PCMFormat {
+            channels_per_frame: destination_format.channels_per_frame,
+            ..source_format
+        } == destination_format;
+        // Sample format and rate changes belong to the PCM converter and the resampler
+        if !same_samples || source.mSampleRate != destination.mSampleRate {
+            return Err(kAudio_ParamError);
+        }
+        if matrix.input_channel_count != source_format.channels_per_frame || matrix.output_channel_count != destination_format.channels_per_frame {
+            return Err(kAudio_ParamError);
+        }
+        Ok(Self {
+            source: source_format,
+            destination: destination_format,
+            input_frame: vec![0.0; matrix.input_channel_count],
+            matrix,
+        })
+    }
+
+    /// # Safety
+    ///
+    /// `source_layout_size` and `destination_layout_size` must not exceed the
+    /// number of readable bytes starting at the matching layout, as for
+    /// [`ChannelMixMatrix::from_layouts`].
+    pub unsafe fn new_with_layouts(
+        source: &AudioStreamBasicDescription,
+        source_layout: &AudioChannelLayout,
+        source_layout_size: usize,
+        destination: &AudioStreamBasicDescription,
+        destination_layout: &AudioChannelLayout,
+        destination_layout_size: usize,
+        options: &ChannelMixOptions,
+    ) -> Result<Self, OSStatus> {
+        let matrix = ChannelMixMatrix::from_layouts(source_layout, source_layout_size, destination_layout, destination_layout_size, options)?;
+        Self::new(source, destination, matrix)
+    }
+
+    #[inline]
+    pub fn matrix(&self) -> &ChannelMixMatrix {
+        &self.matrix
+    }
+
+    pub fn process(&mut self, input: &[&[u8]], output: &mut [&mut [u8]]) -> Result<usize, OSStatus> {
+        if input.len() != self.source.buffer_count() || output.len() != self.destination.buffer_count() {
+            return Err(kAudio_ParamError);
+        }
+        let source_frame_size = self.source.bytes_per_buffer_frame();
+        let destination_frame_size = self.destination.bytes_per_buffer_frame();
+        let frames = input.iter().map(|buffer| buffer.len() / source_frame_size).min().unwrap_or(0);
+        if output.iter().any(|buffer| buffer.len() < frames * destination_frame_size) {
+            return Err(kAudio_ParamError);
+        }
+        let source = self.source;
+        let destination = self.destination;
+        for frame in 0..frames {
+            for (channel, sample) in self.input_frame.iter_mut().enumerate() {
+                let (buffer, offset) = channel_location(&source, input.len(), channel);
+                let position = frame * source_frame_size + offset;
+                *sample = source.read_sample(&input[buffer][position..position + source.bytes_per_sample]) as f32;
+            }
+            for channel in 0..destination.channels_per_frame {
+                let value = self.matrix.row(channel).iter().zip(&self.input_frame).map(|(gain, sample)| gain * sample).sum::<f32>();
+                let (buffer, offset) = channel_location(&destination, output.len(), channel);
+                let position = frame * destination_frame_size + offset;
+                destination.write_sample(value as f64, 0.0, false, &mut output[buffer][position..position + destination.bytes_per_sample]);
+            }
+        }
+        Ok(frames)
+    }
+
+    pub fn process_audio_buffer_list(&mut self, input: &AudioBufferListBuf, output: &mut AudioBufferListBuf) -> Result<usize, OSStatus> {
+        let input = (0..input.buffer_count()).filter_map(|index| input.bytes(index)).collect::<Vec<_>>();
+        for index in 0..output.buffer_count() {
+            output.set_data_byte_size(index, output.capacity(index).unwrap_or(0))?;
+        }
+        let frames = {
+            let mut buffers = output.buffers_mut::<u8>();
+            self.process(&input, &mut buffers)?
+        };
+        let byte_size = frames * self.destination.bytes_per_buffer_frame();
+        for index in 0..output.buffer_count() {
+            output.set_data_byte_size(index, byte_size)?;
+        }
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SURROUND_5_1: [AudioChannelLabel; 6] = [L, R, C, LFE, Ls, Rs];
+    const SURROUND_7_1: [AudioChannelLabel; 8] = [L, R, C, LFE, Ls, Rs, Rls, Rrs];
+
+    fn assert_row(matrix: &ChannelMixMatrix, output_channel: usize, expected: &[f32]) {
+        let row = matrix.row(output_channel);
+        assert!(row.iter().zip(expected).all(|(gain, expected)| (gain - expected).abs() < 1e-6), "row {}: {:?}", output_channel, row);
+    }
+
+    #[test]
+    fn downmix_7_1_to_5_1() {
+        let options = ChannelMixOptions {
+            normalize: false,
+            ..Default::default()
+        };
+        let matrix = ChannelMixMatrix::from_labels(&SURROUND_7_1, &SURROUND_5_1, &options).unwrap();
+        assert_eq!((matrix.input_channel_count(), matrix.output_channel_count()), (8, 6));
+        for channel in 0..4 {
+            let mut expected = [0.0; 8];
+            expected[channel] = 1.0;
+            assert_row(&matrix, channel, &expected);
+        }
+        assert_row(&matrix, 4, &[0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+        assert_row(&matrix, 5, &[0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0]);
+
+        // Only the surround rows mix two inputs, so only they are attenuated
+        let matrix = ChannelMixMatrix::from_labels(&SURROUND_7_1, &SURROUND_5_1, &ChannelMixOptions::default()).unwrap();
+        assert_row(&matrix, 0, &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_row(&matrix, 3, &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_row(&matrix, 4, &[0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.5, 0.0]);
+        assert_row(&matrix, 5, &[0.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn downmix_5_1_to_stereo() {
+        let options = ChannelMixOptions {
+            normalize: false,
+            ..Default::default()
+        };
+        let matrix = ChannelMixMatrix::from_labels(&SURROUND_5_1, &[L, R], &options).unwrap();
+        assert_row(&matrix, 0, &[1.0, 0.0, FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2, 0.0]);
+        assert_row(&matrix, 1, &[0.0, 1.0, FRAC_1_SQRT_2, 0.0, 0.0, FRAC_1_SQRT_2]);
+
+        let matrix = ChannelMixMatrix::from_labels(&SURROUND_5_1, &[L, R], &ChannelMixOptions::default()).unwrap();
+        let sum = 1.0 + 2.0 * FRAC_1_SQRT_2;
+        assert_row(&matrix, 0, &[1.0 / sum, 0.0, FRAC_1_SQRT_2 / sum, 0.0, FRAC_1_SQRT_2 / sum, 0.0]);
+        assert_row(&matrix, 1, &[0.0, 1.0 / sum, FRAC_1_SQRT_2 / sum, 0.0, 0.0, FRAC_1_SQRT_2 / sum]);
+
+        // Full scale on every input stays within full scale on every output
+        let mut output = [0.0; 2];
+        assert_eq!(matrix.apply_interleaved(&[1.0; 6], &mut output), 1);
+        assert!(output.iter().all(|sample| (sample - 1.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn downmix_stereo_to_mono() {
+        let options = ChannelMixOptions {
+            normalize: false,
+            ..Default::default()
+        };
+        let matrix = ChannelMixMatrix::from_labels(&[L, R], &[M], &options).unwrap();
+        assert_row(&matrix, 0, &[FRAC_1_SQRT_2, FRAC_1_SQRT_2]);
+        let matrix = ChannelMixMatrix::from_labels(&[L, R], &[M], &ChannelMixOptions::default()).unwrap();
+        assert_row(&matrix, 0, &[0.5, 0.5]);
+
+        // Mono to stereo has no mixed rows and is left alone
+        let matrix = ChannelMixMatrix::from_labels(&[M], &[L, R], &ChannelMixOptions::default()).unwrap();
+        assert_row(&matrix, 0, &[FRAC_1_SQRT_2]);
+        assert_row(&matrix, 1, &[FRAC_1_SQRT_2]);
+    }
+
+    #[test]
+    fn lfe_handling() {
+        // LFE passes through when the destination has one
+        let matrix = ChannelMixMatrix::from_labels(&[LFE, kAudioChannelLabel_LFE2], &SURROUND_5_1, &ChannelMixOptions::default()).unwrap();
+        assert_row(&matrix, 3, &[0.5, 0.5]);
+        assert!((0..6).filter(|&channel| channel != 3).all(|channel| matrix.row(channel) == [0.0, 0.0]));
+
+        // And is dropped by default when it does not
+        let matrix = ChannelMixMatrix::from_labels(&SURROUND_5_1, &[L, R], &ChannelMixOptions::default()).unwrap();
+        assert_eq!(matrix.coefficient(0, 3), 0.0);
+        assert_eq!(matrix.coefficient(1, 3), 0.0);
+
+        // A non-zero gain folds it through the center
+        let options = ChannelMixOptions {
+            lfe_gain: 0.5,
+            normalize: false,
+            ..Default::default()
+        };
+        let matrix = ChannelMixMatrix::from_labels(&SURROUND_5_1, &[L, R], &options).unwrap();
+        assert!((matrix.coefficient(0, 3) - 0.5 * FRAC_1_SQRT_2).abs() < 1e-6);
+        assert!((matrix.coefficient(1, 3) - 0.5 * FRAC_1_SQRT_2).abs() < 1e-6);
+        let matrix = ChannelMixMatrix::from_labels(&[L, R, C, LFE], &[L, R, C], &options).unwrap();
+        assert_row(&matrix, 2, &[0.0, 0.0, 1.0, 0.5]);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    error::Error,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+use crate::OSType;
+
+#[repr(transparent)]
+#[derive(Clone, Copy, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FourCC(pub u32);
+
+impl FourCC {
+    #[inline]
+    pub const fn new(code: &[u8; 4]) -> Self {
+        Self(((code[0] as u32) << 24) | ((code[1] as u32) << 16) | ((code[2] as u32) << 8) | (code[3] as u32))
+    }
+
+    #[inline]
+    pub const fn from_u32(value: u32) -> Self {
+        Self(value)
+    }
+
+    #[inline]
+    pub const fn as_u32(&self) -> u32 {
+        self.0
+    }
+
+    #[inline]
+    pub const fn to_bytes(&self) -> [u8; 4] {
+        self.0.to_be_bytes()
+    }
+
+    #[inline]
+    pub fn is_printable(&self) -> bool {
+        let bytes = self.to_bytes();
+        // Real codes never start with a space, so treat those as numeric
+        bytes[0] != b' ' && bytes.iter().all(|byte| (0x20..0x7F).contains(byte))
+    }
+}
+
+impl From<OSType> for FourCC {
+    #[inline]
+    fn from(value: OSType) -> Self {
+        Self(value)
+    }
+}
+
+impl From<FourCC> for OSType {
+    #[inline]
+    fn from(value: FourCC) -> Self {
+        value.0
+    }
+}
+
+impl Display for FourCC {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.is_printable() {
+            let bytes = self.to_bytes();
+            write!(f, "'{}{}{}{}'", bytes[0] as char, bytes[1] as char, bytes[2] as char, bytes[3] as char)
+        } else {
+            write!(f, "0x{:08X}", self.0)
+        }
+    }
+}
+
+impl Debug for FourCC {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "FourCC({})", self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseFourCCError;
+
+impl Display for ParseFourCCError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("invalid four character code")
+    }
+}
+
+impl Error for ParseFourCCError {}
+
+impl FromStr for FourCC {
+    type Err = ParseFourCCError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')).unwrap_or(s);
+        if let Some(hex) = code.strip_prefix("0x").or_else(|| code.strip_prefix("0X")) {
+            return u32::from_str_radix(hex, 16).map(Self).map_err(|_| ParseFourCCError);
+        }
+        let bytes = code.as_bytes();
+        if bytes.is_empty() || bytes.len() > 4 || !bytes.iter().all(|byte| (0x20..0x7F).contains(byte)) {
+            return Err(ParseFourCCError);
+        }
+        // Short codes such as "raw" are padded with trailing spaces
+        let mut padded = [b' '; 4];
+        padded[..bytes.len()].copy_from_slice(bytes);
+        Ok(Self::new(&padded))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn printable_codes_round_trip() {
+        let code = FourCC::new(b"avc1");
+        assert_eq!(code.as_u32(), 0x61766331);
+        assert!(code.is_printable());
+        assert_eq!(code.to_string(), "'avc1'");
+        assert_eq!(format!("{:?}", code), "FourCC('avc1')");
+        assert_eq!("'avc1'".parse(), Ok(code));
+        assert_eq!("avc1".parse(), Ok(code));
+        // Short codes are padded with spaces
+        assert_eq!("raw".parse(), Ok(FourCC::new(b"raw ")));
+        assert_eq!(FourCC::new(b"raw ").to_string(), "'raw '");
+    }
+
+    #[test]
+    fn numeric_codes_round_trip() {
+        let code = FourCC::from_u32(32);
+        assert!(!code.is_printable());
+        assert_eq!(code.to_string(), "0x00000020");
+        assert_eq!("0x00000020".parse(), Ok(code));
+        assert_eq!("0X20".parse(), Ok(code));
+        // A leading space is numeric even though every byte is printable
+        assert_eq!(FourCC::from_u32(0x20616263).to_string(), "0x20616263");
+        assert_eq!(OSType::from(FourCC::from(0x6C70636D)), 0x6C70636D);
+    }
+
+    #[test]
+    fn invalid_codes_are_rejected() {
+        assert_eq!("".parse::<FourCC>(), Err(ParseFourCCError));
+        assert_eq!("avc1x".parse::<FourCC>(), Err(ParseFourCCError));
+        assert_eq!("av\u{e9}".parse::<FourCC>(), Err(ParseFourCCError));
+        assert_eq!("0xZZ".parse::<FourCC>(), Err(ParseFourCCError));
+        assert_eq!("0x100000000".parse::<FourCC>(), Err(ParseFourCCError));
+    }
+}
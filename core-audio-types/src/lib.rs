@@ -7,5 +7,12 @@ extern crate libc;
 
 pub type OSType = u32;
 
+pub mod audio_buffer_list;
 pub mod base_types;
+pub mod channel_layout;
+pub mod channel_mixer;
+pub mod four_cc;
+pub mod pcm_converter;
+pub mod sample_rate_converter;
 pub mod session_types;
+pub mod stream_basic_description;
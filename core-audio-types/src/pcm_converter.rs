@@ -0,0 +1,563 @@
+use core_foundation_sys::base::OSStatus;
+
+use crate::base_types::*;
+
+const BLOCK_FRAMES: usize = 256;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PCMSampleType {
+    SignedInteger,
+    UnsignedInteger,
+    Float,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct PCMFormat {
+    pub sample_type: PCMSampleType,
+    pub valid_bits_per_channel: u32,
+    pub bytes_per_sample: usize,
+    pub channels_per_frame: usize,
+    pub is_big_endian: bool,
+    pub is_aligned_high: bool,
+    pub is_non_interleaved: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PCMDitherMode {
+    None,
+    Rectangular,
+    Triangular,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum FastSampleFormat {
+    Int16,
+    Int32,
+    Float32,
+}
+
+impl PCMFormat {
+    pub fn from_stream_basic_description(asbd: &AudioStreamBasicDescription) -> Result<Self, OSStatus> {
+        if asbd.mFormatID != kAudioFormatLinearPCM || asbd.mFramesPerPacket != 1 || asbd.mChannelsPerFrame == 0 || asbd.mBitsPerChannel == 0 {
+            return Err(kAudio_ParamError);
+        }
+        let flags = asbd.mFormatFlags;
+        let is_non_interleaved = flags & kAudioFormatFlagIsNonInterleaved != 0;
+        let channels_per_frame = asbd.mChannelsPerFrame as usize;
+        let bytes_per_sample = if is_non_interleaved {
+            asbd.mBytesPerFrame as usize
+        } else {
+            asbd.mBytesPerFrame as usize / channels_per_frame
+        };
+        if bytes_per_sample == 0 || bytes_per_sample > 8 || asbd.mBitsPerChannel as usize > bytes_per_sample * 8 {
+            return Err(kAudio_ParamError);
+        }
+        if !is_non_interleaved && bytes_per_sample * channels_per_frame != asbd.mBytesPerFrame as usize {
+            return Err(kAudio_ParamError);
+        }
+        let sample_type = if flags & kAudioFormatFlagIsFloat != 0 {
+            if !matches!((asbd.mBitsPerChannel, bytes_per_sample), (32, 4) | (64, 8)) {
+                return Err(kAudio_UnimplementedError);
+            }
+            PCMSampleType::Float
+        } else if flags & kAudioFormatFlagIsSignedInteger != 0 {
+            PCMSampleType::SignedInteger
+        } else {
+            PCMSampleType::UnsignedInteger
+        };
+        Ok(Self {
+            sample_type,
+            valid_bits_per_channel: asbd.mBitsPerChannel,
+            bytes_per_sample,
+            channels_per_frame,
+            is_big_endian: flags & kAudioFormatFlagIsBigEndian != 0,
+            is_aligned_high: flags & kAudioFormatFlagIsAlignedHigh != 0,
+            is_non_interleaved,
+        })
+    }
+
+    #[inline]
+    pub fn buffer_count(&self) -> usize {
+        if self.is_non_interleaved {
+            self.channels_per_frame
+        } else {
+            1
+        }
+    }
+
+    #[inline]
+    pub fn bytes_per_buffer_frame(&self) -> usize {
+        if self.is_non_interleaved {
+            self.bytes_per_sample
+        } else {
+            self.bytes_per_sample * self.channels_per_frame
+        }
+    }
+
+    #[inline]
+    fn is_native_endian(&self) -> bool {
+        self.is_big_endian == cfg!(target_endian = "big")
+    }
+
+    fn fast_sample_format(&self) -> Option<FastSampleFormat> {
+        let packed = self.valid_bits_per_channel as usize == self.bytes_per_sample * 8;
+        if !self.is_native_endian() || !packed {
+            return None;
+        }
+        match (self.sample_type, self.bytes_per_sample) {
+            (PCMSampleType::SignedInteger, 2) => Some(FastSampleFormat::Int16),
+            (PCMSampleType::SignedInteger, 4) => Some(FastSampleFormat::Int32),
+            (PCMSampleType::Float, 4) => Some(FastSampleFormat::Float32),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn read_sample(&self, bytes: &[u8]) -> f64 {
+        let container_bits = self.bytes_per_sample as u32 * 8;
+        let mut raw = 0u64;
+        if self.is_big_endian {
+            bytes.iter().for_each(|byte| raw = (raw << 8) | *byte as u64);
+        } else {
+            bytes.iter().rev().for_each(|byte| raw = (raw << 8) | *byte as u64);
+        }
+        match self.sample_type {
+            PCMSampleType::Float if self.bytes_per_sample == 4 => f32::from_bits(raw as u32) as f64,
+            PCMSampleType::Float => f64::from_bits(raw),
+            _ => {
+                let bits = self.valid_bits_per_channel;
+                let value = if self.is_aligned_high {
+                    raw >> (container_bits - bits)
+                } else {
+                    raw
+                } & mask(bits);
+                // Offset binary wraps into two's complement, 64 bit samples included
+                let value = if self.sample_type == PCMSampleType::UnsignedInteger {
+                    value.wrapping_sub(1u64 << (bits - 1)) as i64
+                } else {
+                    ((value << (64 - bits)) as i64) >> (64 - bits)
+                };
+                value as f64 / (1u64 << (bits - 1)) as f64
+            }
+        }
+    }
+
+    pub(crate) fn write_sample(&self, value: f64, dither: f64, clip: bool, bytes: &mut [u8]) {
+        let container_bits = self.bytes_per_sample as u32 * 8;
+        let raw = match self.sample_type {
+            PCMSampleType::Float => {
+                let value = if clip {
+                    value.clamp(-1.0, 1.0)
+                } else {
+                    value
+                };
+                if self.bytes_per_sample == 4 {
+                    (value as f32).to_bits() as u64
+                } else {
+                    value.to_bits()
+                }
+            }
+            _ => {
+                let bits = self.valid_bits_per_channel;
+                let scale = (1u64 << (bits - 1)) as f64;
+                // Integer destinations always saturate instead of wrapping around
+                let quantized = (value * scale + dither).round().clamp(-scale, scale - 1.0) as i64;
+                let value = if self.sample_type == PCMSampleType::UnsignedInteger {
+                    (quantized as u64).wrapping_add(1u64 << (bits - 1))
+                } else {
+                    quantized as u64
+                } & mask(bits);
+                if self.is_aligned_high {
+                    value << (container_bits - bits)
+                } else {
+                    value
+                }
+            }
+        };
+        if self.is_big_endian {
+            bytes.iter_mut().rev().enumerate().for_each(|(i, byte)| *byte = (raw >> (i * 8)) as u8);
+        } else {
+            bytes.iter_mut().enumerate().for_each(|(i, byte)| *byte = (raw >> (i * 8)) as u8);
+        }
+    }
+
+    #[inline]
+    fn effective_bits(&self) -> u32 {
+        match self.sample_type {
+            PCMSampleType::Float if self.bytes_per_sample == 4 => 24,
+            PCMSampleType::Float => 53,
+            _ => self.valid_bits_per_channel,
+        }
+    }
+}
+
+#[inline]
+fn mask(bits: u32) -> u64 {
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        (1u64 << bits) - 1
+    }
+}
+
+pub struct PCMConverter {
+    source: PCMFormat,
+    destination: PCMFormat,
+    dither_mode: PCMDitherMode,
+    clip_float_output: bool,
+    random_state: u32,
+    scratch: Vec<f64>,
+}
+
+impl PCMConverter {
+    pub fn new(source: &AudioStreamBasicDescription, destination: &AudioStreamBasicDescription) -> Result<Self, OSStatus> {
+        let source_format = PCMFormat::from_stream_basic_description(source)?;
+        let destination_format = PCMFormat::from_stream_basic_description(destination)?;
+        // Rate and channel count changes belong to the resampler and channel mixer
+        if source_format.channels_per_frame != destination_format.channels_per_frame || source.mSampleRate != destination.mSampleRate {
+            return Err(kAudio_ParamError);
+        }
+        Ok(Self {
+            source: source_format,
+            destination: destination_format,
+            dither_mode: PCMDitherMode::None,
+            clip_float_output: false,
+            random_state: 0x9E3779B9,
+            scratch: vec![0.0; BLOCK_FRAMES],
+        })
+    }
+
+    #[inline]
+    pub fn source_format(&self) -> &PCMFormat {
+        &self.source
+    }
+
+    #[inline]
+    pub fn destination_format(&self) -> &PCMFormat {
+        &self.destination
+    }
+
+    #[inline]
+    pub fn dither_mode(&self) -> PCMDitherMode {
+        self.dither_mode
+    }
+
+    #[inline]
+    pub fn set_dither_mode(&mut self, dither_mode: PCMDitherMode) {
+        self.dither_mode = dither_mode;
+    }
+
+    #[inline]
+    pub fn set_clip_float_output(&mut self, clip: bool) {
+        self.clip_float_output = clip;
+    }
+
+    #[inline]
+    fn dither_enabled(&self) -> bool {
+        self.dither_mode != PCMDitherMode::None &&
+            self.destination.sample_type != PCMSampleType::Float &&
+            self.destination.effective_bits() < self.source.effective_bits()
+    }
+
+    pub fn convert(&mut self, input: &[&[u8]], output: &mut [&mut [u8]]) -> Result<usize, OSStatus> {
+        if input.len() != self.source.buffer_count() || output.len() != self.destination.buffer_count() {
+            return Err(kAudio_ParamError);
+        }
+        let source_frame_size = self.source.bytes_per_buffer_frame();
+        let destination_frame_size = self.destination.bytes_per_buffer_frame();
+        let frames = input.iter().map(|buffer| buffer.len() / source_frame_size).min().unwrap_or(0);
+        if output.iter().any(|buffer| buffer.len() < frames * destination_frame_size) {
+            return Err(kAudio_ParamError);
+        }
+        let clips = self.clip_float_output && self.destination.sample_type == PCMSampleType::Float;
+        if self.source == self.destination && !clips {
+            for (input, output) in input.iter().zip(output.iter_mut()) {
+                output[..frames * destination_frame_size].copy_from_slice(&input[..frames * source_frame_size]);
+            }
+            return Ok(frames);
+        }
+        if self.convert_fast(input, output, frames) {
+            return Ok(frames);
+        }
+        let mut start = 0;
+        while start < frames {
+            let count = BLOCK_FRAMES.min(frames - start);
+            for channel in 0..self.source.channels_per_frame {
+                self.decode_channel(input, channel, start, count);
+                self.encode_channel(output, channel, start, count);
+            }
+            start += count;
+        }
+        Ok(frames)
+    }
+
+    fn decode_channel(&mut self, input: &[&[u8]], channel: usize, start: usize, count: usize) {
+        let format = &self.source;
+        let (buffer, offset) = channel_location(format, input.len(), channel);
+        let stride = format.bytes_per_buffer_frame();
+        let buffer = input[buffer];
+        for (i, sample) in self.scratch[..count].iter_mut().enumerate() {
+            let position = (start + i) * stride + offset;
+            *sample = format.read_sample(&buffer[position..position + format.bytes_per_sample]);
+        }
+    }
+
+    fn encode_channel(&mut self, output: &mut [&mut [u8]], channel: usize, start: usize, count: usize) {
+        let dither_enabled = self.dither_enabled();
+        let format = self.destination;
+        let (buffer, offset) = channel_location(&format, output.len(), channel);
+        let stride = format.bytes_per_buffer_frame();
+        for i in 0..count {
+            let dither = if dither_enabled {
+                self.next_dither()
+            } else {
+                0.0
+            };
+            let position = (start + i) * stride + offset;
+            format.write_sample(self.scratch[i], dither, self.clip_float_output, &mut output[buffer][position..position + format.bytes_per_sample]);
+        }
+    }
+
+    fn next_random(&mut self) -> f64 {
+        let mut state = self.random_state;
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        self.random_state = state;
+        state as f64 / u32::MAX as f64
+    }
+
+    fn next_dither(&mut self) -> f64 {
+        match self.dither_mode {
+            PCMDitherMode::None => 0.0,
+            PCMDitherMode::Rectangular => self.next_random() - 0.5,
+            PCMDitherMode::Triangular => self.next_random() - self.next_random(),
+        }
+    }
+
+    // Vectorisable loops for the native-endian packed formats that capture devices
+    // deliver
+    fn convert_fast(&self, input: &[&[u8]], output: &mut [&mut [u8]], frames: usize) -> bool {
+        if self.source.is_non_interleaved != self.destination.is_non_interleaved || self.dither_enabled() {
+            return false;
+        }
+        let (source, destination) = match (self.source.fast_sample_format(), self.destination.fast_sample_format()) {
+            (Some(source), Some(destination)) => (source, destination),
+            _ => return false,
+        };
+        let samples = frames * self.source.bytes_per_buffer_frame() / self.source.bytes_per_sample;
+        let clip = self.clip_float_output;
+        for (input, output) in input.iter().zip(output.iter_mut()) {
+            let input = &input[..samples * self.source.bytes_per_sample];
+            let output = &mut output[..samples * self.destination.bytes_per_sample];
+            match (source, destination) {
+                (FastSampleFormat::Int16, FastSampleFormat::Float32) => {
+                    for (input, output) in input.chunks_exact(2).zip(output.chunks_exact_mut(4)) {
+                        let value = i16::from_ne_bytes([input[0], input[1]]) as f32 * (1.0 / 32768.0);
+                        output.copy_from_slice(&value.to_ne_bytes());
+                    }
+                }
+                (FastSampleFormat::Int32, FastSampleFormat::Float32) => {
+                    for (input, output) in input.chunks_exact(4).zip(output.chunks_exact_mut(4)) {
+                        let value = i32::from_ne_bytes([input[0], input[1], input[2], input[3]]) as f64 * (1.0 / 2147483648.0);
+                        output.copy_from_slice(&(value as f32).to_ne_bytes());
+                    }
+                }
+                (FastSampleFormat::Float32, FastSampleFormat::Int16) => {
+                    for (input, output) in input.chunks_exact(4).zip(output.chunks_exact_mut(2)) {
+                        let value = f32::from_ne_bytes([input[0], input[1], input[2], input[3]]) * 32768.0;
+                        output.copy_from_slice(&(value.round().clamp(-32768.0, 32767.0) as i16).to_ne_bytes());
+                    }
+                }
+                (FastSampleFormat::Float32, FastSampleFormat::Int32) => {
+                    for (input, output) in input.chunks_exact(4).zip(output.chunks_exact_mut(4)) {
+                        let value = f32::from_ne_bytes([input[0], input[1], input[2], input[3]]) as f64 * 2147483648.0;
+                        output.copy_from_slice(&(value.round().clamp(-2147483648.0, 2147483647.0) as i32).to_ne_bytes());
+                    }
+                }
+                (FastSampleFormat::Int16, FastSampleFormat::Int32) => {
+                    for (input, output) in input.chunks_exact(2).zip(output.chunks_exact_mut(4)) {
+                        let value = (i16::from_ne_bytes([input[0], input[1]]) as i32) << 16;
+                        output.copy_from_slice(&value.to_ne_bytes());
+                    }
+                }
+                (FastSampleFormat::Float32, FastSampleFormat::Float32) if clip => {
+                    for (input, output) in input.chunks_exact(4).zip(output.chunks_exact_mut(4)) {
+                        let value = f32::from_ne_bytes([input[0], input[1], input[2], input[3]]).clamp(-1.0, 1.0);
+                        output.copy_from_slice(&value.to_ne_bytes());
+                    }
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+#[inline]
+pub(crate) fn channel_location(format: &PCMFormat, buffer_count: usize, channel: usize) -> (usize, usize) {
+    if format.is_non_interleaved && buffer_count > 1 {
+        (channel, 0)
+    } else {
+        (0, channel * format.bytes_per_sample)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lpcm(valid_bits: u32, total_bits: u32, is_float: bool, is_big_endian: bool, channels: u32) -> AudioStreamBasicDescription {
+        let mut asbd = AudioStreamBasicDescription::default();
+        FillOutASBDForLPCM(&mut asbd, 48000.0, channels, valid_bits, total_bits, is_float, is_big_endian, false);
+        asbd
+    }
+
+    fn convert(source: &AudioStreamBasicDescription, destination: &AudioStreamBasicDescription, input: &[u8]) -> Vec<u8> {
+        let mut converter = PCMConverter::new(source, destination).unwrap();
+        let frames = input.len() / source.mBytesPerFrame as usize;
+        let mut output = vec![0; frames * destination.mBytesPerFrame as usize];
+        assert_eq!(converter.convert(&[input], &mut [&mut output]).unwrap(), frames);
+        output
+    }
+
+    fn int24_le(values: &[i32]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_le_bytes()[..3].to_vec()).collect()
+    }
+
+    #[test]
+    fn int16_float_round_trip() {
+        let int16 = lpcm(16, 16, false, false, 2);
+        let float32 = lpcm(32, 32, true, false, 2);
+        let values = [i16::MIN, -1, 0, 1, 12345, i16::MAX];
+        let input: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+        let float = convert(&int16, &float32, &input);
+        let samples: Vec<f32> = float.chunks_exact(4).map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])).collect();
+        assert_eq!(samples[0], -1.0);
+        assert_eq!(samples[3], 1.0 / 32768.0);
+        assert_eq!(convert(&float32, &int16, &float), input);
+    }
+
+    #[test]
+    fn int24_float_round_trip() {
+        let int24 = lpcm(24, 24, false, false, 1);
+        let float32 = lpcm(32, 32, true, false, 1);
+        let input = int24_le(&[-8388608, -4194304, -1, 0, 1, 654321, 8388607]);
+        let float = convert(&int24, &float32, &input);
+        assert_eq!(&float[4..8], &(-0.5f32).to_le_bytes());
+        assert_eq!(convert(&float32, &int24, &float), input);
+    }
+
+    #[test]
+    fn int32_float_round_trip() {
+        let int32 = lpcm(32, 32, false, false, 1);
+        let float64 = lpcm(64, 64, true, false, 1);
+        let values = [i32::MIN, -123456789, -1, 0, 1, 987654321, i32::MAX];
+        let input: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+        assert_eq!(convert(&float64, &int32, &convert(&int32, &float64, &input)), input);
+
+        // Single precision keeps the top 24 bits
+        let float32 = lpcm(32, 32, true, false, 1);
+        let output = convert(&float32, &int32, &convert(&int32, &float32, &input));
+        for (input, output) in input.chunks_exact(4).zip(output.chunks_exact(4)) {
+            let input = i32::from_le_bytes([input[0], input[1], input[2], input[3]]) as i64;
+            let output = i32::from_le_bytes([output[0], output[1], output[2], output[3]]) as i64;
+            assert!((input - output).abs() <= 128);
+        }
+    }
+
+    #[test]
+    fn full_scale_input_clips() {
+        let float32 = lpcm(32, 32, true, false, 1);
+        let input: Vec<u8> = [1.0f32, 1.5, -1.0, -1.5].iter().flat_map(|value| value.to_le_bytes()).collect();
+        let int16 = convert(&float32, &lpcm(16, 16, false, false, 1), &input);
+        assert_eq!(int16, [i16::MAX, i16::MAX, i16::MIN, i16::MIN].iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<_>>());
+        // The generic path saturates too
+        let int24 = convert(&float32, &lpcm(24, 24, false, false, 1), &input);
+        assert_eq!(int24, int24_le(&[8388607, 8388607, -8388608, -8388608]));
+
+        // Float output only clips on request
+        assert_eq!(convert(&float32, &float32, &input), input);
+        let mut converter = PCMConverter::new(&float32, &float32).unwrap();
+        converter.set_clip_float_output(true);
+        let mut output = vec![0; input.len()];
+        converter.convert(&[&input], &mut [&mut output]).unwrap();
+        assert_eq!(output, [1.0f32, 1.0, -1.0, -1.0].iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn dither_stays_within_one_lsb() {
+        let float32 = lpcm(32, 32, true, false, 1);
+        let int16 = lpcm(16, 16, false, false, 1);
+        // A quarter of a 16-bit step above 1000
+        let value = (1000.25f64 / 32768.0) as f32;
+        let input: Vec<u8> = (0..4096).flat_map(|_| value.to_le_bytes()).collect();
+        for dither_mode in [PCMDitherMode::Rectangular, PCMDitherMode::Triangular] {
+            let mut converter = PCMConverter::new(&float32, &int16).unwrap();
+            converter.set_dither_mode(dither_mode);
+            let mut output = vec![0; input.len() / 2];
+            converter.convert(&[&input], &mut [&mut output]).unwrap();
+            let samples: Vec<i16> = output.chunks_exact(2).map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]])).collect();
+            assert!(samples.iter().all(|sample| (999..=1001).contains(sample)));
+            assert!(samples.contains(&1000) && samples.contains(&1001));
+        }
+
+        // Widening conversions are never dithered
+        let mut converter = PCMConverter::new(&int16, &float32).unwrap();
+        converter.set_dither_mode(PCMDitherMode::Triangular);
+        assert!(!converter.dither_enabled());
+    }
+
+    #[test]
+    fn big_and_little_endian_samples() {
+        let int16_be = lpcm(16, 16, false, true, 1);
+        let int16_le = lpcm(16, 16, false, false, 1);
+        let input = [0x12, 0x34, 0x80, 0x00];
+        assert_eq!(convert(&int16_be, &int16_le, &input), [0x34, 0x12, 0x00, 0x80]);
+        assert_eq!(convert(&int16_le, &int16_be, &[0x34, 0x12, 0x00, 0x80]), input);
+
+        let float32_be = lpcm(32, 32, true, true, 1);
+        let output = convert(&int16_be, &float32_be, &input);
+        assert_eq!(&output[4..], &(-1.0f32).to_be_bytes());
+        let int24_be = lpcm(24, 24, false, true, 1);
+        assert_eq!(convert(&float32_be, &int24_be, &output), [0x12, 0x34, 0x00, 0x80, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn aligned_high_samples() {
+        // 20 valid bits in the top of a 32 bit container
+        let aligned = lpcm(20, 32, false, false, 1);
+        let format = PCMFormat::from_stream_basic_description(&aligned).unwrap();
+        assert!(format.is_aligned_high);
+        assert_eq!(format.bytes_per_sample, 4);
+        let float32 = lpcm(32, 32, true, false, 1);
+        let input: Vec<u8> = [0.5f32, -1.0].iter().flat_map(|value| value.to_le_bytes()).collect();
+        let output = convert(&float32, &aligned, &input);
+        assert_eq!(output, [0x4000_0000u32, 0x8000_0000].iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<_>>());
+        assert_eq!(convert(&aligned, &float32, &output), input);
+        // The low padding bits are ignored on input
+        assert_eq!(convert(&aligned, &float32, &0x4000_0FFFu32.to_le_bytes()), 0.5f32.to_le_bytes());
+    }
+
+    #[test]
+    fn same_format_copies_samples() {
+        let float32 = lpcm(32, 32, true, false, 2);
+        // NaN payloads survive the copy unchanged
+        let input: Vec<u8> = [f32::from_bits(0x7FC0_1234), 2.0, -3.0, 0.25].iter().flat_map(|value| value.to_le_bytes()).collect();
+        assert_eq!(convert(&float32, &float32, &input), input);
+
+        let mut non_interleaved = AudioStreamBasicDescription::default();
+        FillOutASBDForLPCM(&mut non_interleaved, 48000.0, 2, 16, 16, false, false, true);
+        let mut converter = PCMConverter::new(&non_interleaved, &non_interleaved).unwrap();
+        let (left, right) = ([1, 2, 3, 4, 5], [6, 7, 8, 9, 10]);
+        let (mut left_output, mut right_output) = ([0; 4], [0; 4]);
+        assert_eq!(converter.convert(&[&left, &right], &mut [&mut left_output, &mut right_output]).unwrap(), 2);
+        assert_eq!((left_output, right_output), ([1, 2, 3, 4], [6, 7, 8, 9]));
+
+        // Rate changes are rejected
+        let resampled = AudioStreamBasicDescription {
+            mSampleRate: 44100.0,
+            ..float32
+        };
+        assert!(PCMConverter::new(&float32, &resampled).is_err());
+    }
+}
@@ -0,0 +1,551 @@
+use std::f64::consts::PI;
+
+use core_foundation_sys::base::OSStatus;
+
+use crate::{
+    audio_buffer_list::AudioBufferListBuf,
+    base_types::*,
+    pcm_converter::{channel_location, PCMFormat},
+};
+
+const MAX_TABLE_PHASES: u64 = 1024;
+const MAX_HALF_TAPS: usize = 512;
+const RATE_PRECISION: f64 = 1000.0;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SampleRateConverterQuality {
+    Low,
+    Medium,
+    High,
+    Max,
+}
+
+impl SampleRateConverterQuality {
+    // Half filter length in input samples, passband edge and Kaiser beta
+    #[inline]
+    fn parameters(&self) -> (usize, f64, f64) {
+        match self {
+            SampleRateConverterQuality::Low => (8, 0.85, 6.0),
+            SampleRateConverterQuality::Medium => (16, 0.9, 8.0),
+            SampleRateConverterQuality::High => (32, 0.945, 10.0),
+            SampleRateConverterQuality::Max => (64, 0.97, 12.0),
+        }
+    }
+}
+
+impl Default for SampleRateConverterQuality {
+    #[inline]
+    fn default() -> Self {
+        SampleRateConverterQuality::High
+    }
+}
+
+struct Kernel {
+    half_taps: usize,
+    cutoff: f64,
+    beta: f64,
+    i0_beta: f64,
+}
+
+impl Kernel {
+    fn new(quality: SampleRateConverterQuality, up: u64, down: u64) -> Self {
+        let (half_taps, rolloff, beta) = quality.parameters();
+        let scale = (up as f64 / down as f64).min(1.0);
+        // Widen the filter when decimating so the transition band stays as sharp, up to
+        // a length that still fits a table
+        let half_taps = ((half_taps as f64 / scale).ceil() as usize).min(MAX_HALF_TAPS);
+        Self {
+            half_taps,
+            cutoff: scale * rolloff,
+            beta,
+            i0_beta: bessel_i0(beta),
+        }
+    }
+
+    #[inline]
+    fn taps(&self) -> usize {
+        self.half_taps * 2
+    }
+
+    fn evaluate(&self, t: f64) -> f64 {
+        let half = self.half_taps as f64;
+        if t.abs() >= half {
+            return 0.0;
+        }
+        let x = self.cutoff * t;
+        let sinc = if x.abs() < 1e-12 {
+            1.0
+        } else {
+            (PI * x).sin() / (PI * x)
+        };
+        let r = t / half;
+        self.cutoff * sinc * bessel_i0(self.beta * (1.0 - r * r).sqrt()) / self.i0_beta
+    }
+
+    fn fill_phase(&self, phases: u64, phase: u64, coefficients: &mut [f32]) {
+        let fraction = phase as f64 / phases as f64;
+        let mut sum = 0.0;
+        for (j, coefficient) in coefficients.iter_mut().enumerate() {
+            let value = self.evaluate(fraction + self.half_taps as f64 - 1.0 - j as f64);
+            *coefficient = value as f32;
+            sum += value;
+        }
+        // Normalise every phase to unity gain so DC passes through without ripple
+        if sum != 0.0 {
+            coefficients.iter_mut().for_each(|coefficient| *coefficient = (*coefficient as f64 / sum) as f32);
+        }
+    }
+}
+
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..64 {
+        term *= (half / k as f64) * (half / k as f64);
+        sum += term;
+        if term < sum * 1e-16 {
+            break;
+        }
+    }
+    sum
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
+fn rational_ratio(input_sample_rate: f64, output_sample_rate: f64) -> Result<(u64, u64), OSStatus> {
+    if !(input_sample_rate.is_finite() && output_sample_rate.is_finite()) || input_sample_rate <= 0.0 || output_sample_rate <= 0.0 {
+        return Err(kAudio_ParamError);
+    }
+    let (input, output) = if input_sample_rate.fract() == 0.0 && output_sample_rate.fract() == 0.0 {
+        (input_sample_rate, output_sample_rate)
+    } else {
+        ((input_sample_rate * RATE_PRECISION).round(), (output_sample_rate * RATE_PRECISION).round())
+    };
+    if input < 1.0 || output < 1.0 || input > u32::MAX as f64 || output > u32::MAX as f64 {
+        return Err(kAudio_ParamError);
+    }
+    let (input, output) = (input as u64, output as u64);
+    let divisor = gcd(input, output);
+    Ok((output / divisor, input / divisor))
+}
+
+pub struct SampleRateConverter {
+    format: PCMFormat,
+    input_sample_rate: f64,
+    output_sample_rate: f64,
+    up: u64,
+    down: u64,
+    kernel: Kernel,
+    table: Vec<f32>,
+    table_phases: u64,
+    scratch: Vec<f32>,
+    history: Vec<Vec<f32>>,
+    history_start: i64,
+    position: i64,
+    phase: u64,
+    output_frame_count: u64,
+}
+
+impl SampleRateConverter {
+    pub fn new(
+        source: &AudioStreamBasicDescription,
+        destination: &AudioStreamBasicDescription,
+        quality: SampleRateConverterQuality,
+    ) -> Result<Self, OSStatus> {
+        let (up, down) = rational_ratio(source.mSampleRate, destination.mSampleRate)?;
+        Self::new_with_ratio(source, destination, up, down, quality)
+    }
+
+    pub fn new_with_ratio(
+        source: &AudioStreamBasicDescription,
+        destination: &AudioStreamBasicDescription,
+        up: u64,
+        down: u64,
+        quality: SampleRateConverterQuality,
+    ) -> Result<Self, OSStatus> {
+        let format = PCMFormat::from_stream_basic_description(source)?;
+        // Sample format and channel changes belong to the PCM converter
+        if format != PCMFormat::from_stream_basic_description(destination)? || up == 0 || down == 0 {
+            return Err(kAudio_ParamError);
+        }
+        let divisor = gcd(up, down);
+        let (up, down) = (up / divisor, down / divisor);
+        let kernel = Kernel::new(quality, up, down);
+        let taps = kernel.taps();
+        // Ratios with more phases than fit a table share an oversampled one that is
+        // interpolated between neighbouring phases, so it needs the phase at 1 too
+        let (table_phases, rows) = if up <= MAX_TABLE_PHASES {
+            (up, up)
+        } else {
+            (MAX_TABLE_PHASES, MAX_TABLE_PHASES + 1)
+        };
+        let mut table = vec![0.0; rows as usize * taps];
+        for (phase, coefficients) in table.chunks_exact_mut(taps).enumerate() {
+            kernel.fill_phase(table_phases, phase as u64, coefficients);
+        }
+        let mut converter = Self {
+            format,
+            input_sample_rate: source.mSampleRate,
+            output_sample_rate: destination.mSampleRate,
+            up,
+            down,
+            table,
+            table_phases,
+            scratch: vec![0.0; taps],
+            history: vec![Vec::new(); format.channels_per_frame],
+            history_start: 0,
+            position: 0,
+            phase: 0,
+            output_frame_count: 0,
+            kernel,
+        };
+        converter.reset();
+        Ok(converter)
+    }
+
+    pub fn reset(&mut self) {
+        // Prime with silence so output frame 0 lines up with input frame 0
+        let priming = self.kernel.half_taps - 1;
+        self.history.iter_mut().for_each(|history| {
+            history.clear();
+            history.resize(priming, 0.0);
+        });
+        self.history_start = -(priming as i64);
+        self.position = 0;
+        self.phase = 0;
+        self.output_frame_count = 0;
+    }
+
+    #[inline]
+    pub fn format(&self) -> &PCMFormat {
+        &self.format
+    }
+
+    #[inline]
+    pub fn input_sample_rate(&self) -> f64 {
+        self.input_sample_rate
+    }
+
+    #[inline]
+    pub fn output_sample_rate(&self) -> f64 {
+        self.output_sample_rate
+    }
+
+    #[inline]
+    pub fn ratio(&self) -> (u64, u64) {
+        (self.up, self.down)
+    }
+
+    #[inline]
+    pub fn latency_input_frames(&self) -> usize {
+        self.kernel.half_taps
+    }
+
+    #[inline]
+    pub fn latency_output_frames(&self) -> f64 {
+        self.kernel.half_taps as f64 * self.up as f64 / self.down as f64
+    }
+
+    #[inline]
+    pub fn latency_seconds(&self) -> f64 {
+        self.kernel.half_taps as f64 / self.input_sample_rate
+    }
+
+    #[inline]
+    pub fn input_frame_count(&self) -> u64 {
+        (self.history_start + self.history[0].len() as i64) as u64
+    }
+
+    #[inline]
+    pub fn output_frame_count(&self) -> u64 {
+        self.output_frame_count
+    }
+
+    // Output frames from the next input frame to the next frame to be produced
+    pub fn output_time_offset(&self) -> f64 {
+        let position = self.position as f64 + self.phase as f64 / self.up as f64;
+        (position - self.input_frame_count() as f64) * self.up as f64 / self.down as f64
+    }
+
+    // Maps the time stamp of the next input to the one of the next output
+    pub fn output_time_stamp(&self, input_time_stamp: &AudioTimeStamp) -> AudioTimeStamp {
+        let mut time_stamp = *input_time_stamp;
+        time_stamp.mSampleTime = input_time_stamp.mSampleTime * self.up as f64 / self.down as f64 + self.output_time_offset();
+        // Host, word clock and SMPTE times only describe the input frame
+        time_stamp.mFlags &= kAudioTimeStampSampleTimeValid | kAudioTimeStampRateScalarValid;
+        time_stamp
+    }
+
+    pub fn available_output_frames(&self, input_frames: usize) -> usize {
+        let last = self.history_start + self.history[0].len() as i64 + input_frames as i64 - 1;
+        let span = last - self.kernel.half_taps as i64 - self.position + 1;
+        if span <= 0 {
+            return 0;
+        }
+        let numerator = span as u128 * self.up as u128 - self.phase as u128;
+        numerator.div_ceil(self.down as u128).min(usize::MAX as u128) as usize
+    }
+
+    pub fn process(&mut self, input: &[&[u8]], output: &mut [&mut [u8]]) -> Result<usize, OSStatus> {
+        let format = self.format;
+        if input.len() != format.buffer_count() || output.len() != format.buffer_count() {
+            return Err(kAudio_ParamError);
+        }
+        let frame_size = format.bytes_per_buffer_frame();
+        let frames = input.iter().map(|buffer| buffer.len() / frame_size).min().unwrap_or(0);
+        for (channel, history) in self.history.iter_mut().enumerate() {
+            let (buffer, offset) = channel_location(&format, input.len(), channel);
+            let buffer = input[buffer];
+            history.extend((0..frames).map(|frame| {
+                let position = frame * frame_size + offset;
+                format.read_sample(&buffer[position..position + format.bytes_per_sample]) as f32
+            }));
+        }
+        Ok(self.render(output, None))
+    }
+
+    // Drains the frames held back by the filter latency
+    pub fn flush(&mut self, output: &mut [&mut [u8]]) -> Result<usize, OSStatus> {
+        if output.len() != self.format.buffer_count() {
+            return Err(kAudio_ParamError);
+        }
+        let end = self.input_frame_count() as i64;
+        let padding = self.kernel.half_taps;
+        self.history.iter_mut().for_each(|history| history.resize(history.len() + padding, 0.0));
+        let frames = self.render(output, Some(end));
+        self.history.iter_mut().for_each(|history| history.truncate(history.len() - padding));
+        Ok(frames)
+    }
+
+    pub fn process_audio_buffer_list(&mut self, input: &AudioBufferListBuf, output: &mut AudioBufferListBuf) -> Result<usize, OSStatus> {
+        let input = (0..input.buffer_count()).filter_map(|index| input.bytes(index)).collect::<Vec<_>>();
+        self.with_output_buffer_list(output, |converter, output| converter.process(&input, output))
+    }
+
+    pub fn flush_audio_buffer_list(&mut self, output: &mut AudioBufferListBuf) -> Result<usize, OSStatus> {
+        self.with_output_buffer_list(output, |converter, output| converter.flush(output))
+    }
+
+    fn with_output_buffer_list<F>(&mut self, output: &mut AudioBufferListBuf, f: F) -> Result<usize, OSStatus>
+    where
+        F: FnOnce(&mut Self, &mut [&mut [u8]]) -> Result<usize, OSStatus>,
+    {
+        for index in 0..output.buffer_count() {
+            output.set_data_byte_size(index, output.capacity(index).unwrap_or(0))?;
+        }
+        let frames = {
+            let mut buffers = output.buffers_mut::<u8>();
+            f(self, &mut buffers)?
+        };
+        let byte_size = frames * self.format.bytes_per_buffer_frame();
+        for index in 0..output.buffer_count() {
+            output.set_data_byte_size(index, byte_size)?;
+        }
+        Ok(frames)
+    }
+
+    fn render(&mut self, output: &mut [&mut [u8]], end: Option<i64>) -> usize {
+        let format = self.format;
+        let frame_size = format.bytes_per_buffer_frame();
+        let capacity = output.iter().map(|buffer| buffer.len() / frame_size).min().unwrap_or(0);
+        let half_taps = self.kernel.half_taps as i64;
+        let taps = self.kernel.taps();
+        let available = self.history_start + self.history[0].len() as i64;
+        let mut frames = 0;
+        while frames < capacity && self.position + half_taps < available && !matches!(end, Some(end) if self.position >= end) {
+            let start = (self.position - half_taps + 1 - self.history_start) as usize;
+            let coefficients = if self.table_phases == self.up {
+                &self.table[self.phase as usize * taps..(self.phase as usize + 1) * taps]
+            } else {
+                let position = self.phase as u128 * self.table_phases as u128;
+                let index = (position / self.up as u128) as usize;
+                let weight = ((position % self.up as u128) as f64 / self.up as f64) as f32;
+                let (lower, upper) = self.table[index * taps..(index + 2) * taps].split_at(taps);
+                for (coefficient, (lower, upper)) in self.scratch.iter_mut().zip(lower.iter().zip(upper)) {
+                    *coefficient = lower + (upper - lower) * weight;
+                }
+                &self.scratch[..]
+            };
+            for (channel, history) in self.history.iter().enumerate() {
+                let value = history[start..start + taps].iter().zip(coefficients).map(|(sample, coefficient)| sample * coefficient).sum::<f32>();
+                let (buffer, offset) = channel_location(&format, output.len(), channel);
+                let position = frames * frame_size + offset;
+                format.write_sample(value as f64, 0.0, false, &mut output[buffer][position..position + format.bytes_per_sample]);
+            }
+            self.phase += self.down;
+            self.position += (self.phase / self.up) as i64;
+            self.phase %= self.up;
+            frames += 1;
+        }
+        self.output_frame_count += frames as u64;
+        // Keep only the samples still reachable by the filter
+        let consumed = (self.position - half_taps + 1 - self.history_start).clamp(0, self.history[0].len() as i64) as usize;
+        if consumed > 0 {
+            self.history.iter_mut().for_each(|history| {
+                history.drain(..consumed);
+            });
+            self.history_start += consumed as i64;
+        }
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mono_float(sample_rate: f64) -> AudioStreamBasicDescription {
+        let mut asbd = AudioStreamBasicDescription::default();
+        FillOutASBDForLPCM(&mut asbd, sample_rate, 1, 32, 32, true, false, false);
+        asbd
+    }
+
+    fn converter(input_sample_rate: f64, output_sample_rate: f64) -> SampleRateConverter {
+        SampleRateConverter::new(&mono_float(input_sample_rate), &mono_float(output_sample_rate), SampleRateConverterQuality::default()).unwrap()
+    }
+
+    fn to_bytes(samples: &[f32]) -> Vec<u8> {
+        samples.iter().flat_map(|sample| sample.to_ne_bytes()).collect()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Vec<f32> {
+        bytes.chunks_exact(4).map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])).collect()
+    }
+
+    // Feeds the input in chunks, then flushes, returning every output sample
+    fn resample(converter: &mut SampleRateConverter, input: &[f32], chunk_frames: usize) -> Vec<f32> {
+        let mut output = Vec::new();
+        for chunk in input.chunks(chunk_frames) {
+            let mut buffer = vec![0; 4 * converter.available_output_frames(chunk.len())];
+            let frames = converter.process(&[&to_bytes(chunk)], &mut [&mut buffer]).unwrap();
+            output.extend(from_bytes(&buffer[..frames * 4]));
+        }
+        let mut buffer = vec![0; 4 * (converter.latency_output_frames().ceil() as usize + 1)];
+        let frames = converter.flush(&mut [&mut buffer]).unwrap();
+        output.extend(from_bytes(&buffer[..frames * 4]));
+        output
+    }
+
+    #[test]
+    fn output_frame_counts() {
+        let mut up = converter(44100.0, 48000.0);
+        assert_eq!(up.ratio(), (160, 147));
+        assert_eq!(resample(&mut up, &vec![0.0; 44100], 1024).len(), 48000);
+        assert_eq!(up.output_frame_count(), 48000);
+        assert_eq!(up.input_frame_count(), 44100);
+
+        let mut down = converter(48000.0, 44100.0);
+        assert_eq!(resample(&mut down, &vec![0.0; 48000], 480).len(), 44100);
+
+        // Fractional rates go through a rational approximation
+        assert_eq!(converter(48000.0 / 1.001, 48000.0).ratio(), (1000000, 999001));
+        assert!(SampleRateConverter::new(&mono_float(48000.0), &mono_float(0.0), SampleRateConverterQuality::Low).is_err());
+    }
+
+    #[test]
+    fn dc_and_sine_keep_their_amplitude() {
+        let mut up = converter(44100.0, 48000.0);
+        let skip = up.latency_output_frames().ceil() as usize * 2;
+        let output = resample(&mut up, &vec![0.5; 44100], 4096);
+        assert!(output[skip..output.len() - skip].iter().all(|sample| (sample - 0.5).abs() < 1e-4));
+
+        for (input_sample_rate, output_sample_rate) in [(44100.0, 48000.0), (48000.0, 44100.0)] {
+            let mut converter = converter(input_sample_rate, output_sample_rate);
+            let input: Vec<f32> =
+                (0..input_sample_rate as usize).map(|i| 0.5 * (2.0 * PI * 1000.0 * i as f64 / input_sample_rate).sin() as f32).collect();
+            let output = resample(&mut converter, &input, 1000);
+            let steady = &output[skip..output.len() - skip];
+            let peak = steady.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+            assert!((peak - 0.5).abs() < 0.005, "peak {}", peak);
+            // The output follows the same sine at the output rate
+            let error = steady.iter().enumerate().map(|(i, sample)| {
+                let expected = 0.5 * (2.0 * PI * 1000.0 * (i + skip) as f64 / output_sample_rate).sin();
+                (*sample as f64 - expected).abs()
+            });
+            assert!(error.fold(0.0, f64::max) < 0.005);
+        }
+    }
+
+    #[test]
+    fn fine_ratios_interpolate_the_kernel() {
+        let mut ntsc = converter(48000.0 / 1.001, 48000.0);
+        let (half_taps, taps) = (ntsc.kernel.half_taps, ntsc.kernel.taps());
+        assert_eq!(ntsc.table.len(), (MAX_TABLE_PHASES as usize + 1) * taps);
+        let mut exact = vec![0.0; taps];
+        for phase in [1, 999, 500000, 999999] {
+            ntsc.kernel.fill_phase(ntsc.up, phase, &mut exact);
+            // An impulse in the history picks out the coefficient of its tap
+            ntsc.history[0].resize(taps, 0.0);
+            ntsc.history[0][half_taps] = 1.0;
+            ntsc.phase = phase;
+            let mut output = vec![0; 4];
+            assert_eq!(ntsc.render(&mut [&mut output], None), 1);
+            assert!((from_bytes(&output)[0] - exact[half_taps]).abs() < 1e-5);
+            ntsc.reset();
+        }
+
+        let dc = resample(&mut ntsc, &vec![0.5; 48000], 4096);
+        let skip = ntsc.latency_output_frames().ceil() as usize * 2;
+        assert!(dc[skip..dc.len() - skip].iter().all(|sample| (sample - 0.5).abs() < 1e-4));
+
+        // Extreme decimation keeps the filter length bounded
+        let down = converter(192000.0, 8000.0 / 1.001);
+        assert_eq!(down.kernel.half_taps, MAX_HALF_TAPS);
+    }
+
+    #[test]
+    fn reported_latency_matches_delay() {
+        let mut up = converter(44100.0, 48000.0);
+        let (frames, impulse) = (4410, 2000);
+        let mut input = vec![0.0; frames];
+        input[impulse] = 1.0;
+        let mut output = vec![0; 4 * frames * 2];
+        let produced = up.process(&[&to_bytes(&input)], &mut [&mut output]).unwrap();
+        // Everything but the filter latency is produced right away
+        assert_eq!(produced, up.output_frame_count() as usize);
+        let expected = frames as f64 * 160.0 / 147.0;
+        assert!((expected - produced as f64 - up.latency_output_frames()).abs() <= 1.0);
+        assert_eq!(up.latency_seconds(), up.latency_input_frames() as f64 / 44100.0);
+        assert_eq!(up.available_output_frames(0), 0);
+
+        // Priming keeps the impulse at its input time
+        let output = from_bytes(&output[..produced * 4]);
+        let peak = (0..output.len()).max_by(|a, b| output[*a].abs().total_cmp(&output[*b].abs())).unwrap();
+        assert_eq!(peak, (impulse as f64 * 160.0 / 147.0).round() as usize);
+
+        let mut rest = vec![0; 4 * frames];
+        let flushed = up.flush(&mut [&mut rest]).unwrap();
+        assert_eq!(produced + flushed, expected.ceil() as usize);
+        assert!((flushed as f64 - up.latency_output_frames()).abs() <= 1.0);
+    }
+
+    #[test]
+    fn output_time_stamps_advance_by_output_frames() {
+        let mut up = converter(44100.0, 48000.0);
+        let mut input_time_stamp = AudioTimeStamp {
+            mSampleTime: 0.0,
+            mFlags: kAudioTimeStampSampleTimeValid | kAudioTimeStampHostTimeValid,
+            ..Default::default()
+        };
+        let mut output = vec![0; 4 * 2048];
+        let mut expected = up.output_time_stamp(&input_time_stamp).mSampleTime;
+        assert_eq!(expected, 0.0);
+        assert_eq!(up.output_time_stamp(&input_time_stamp).mFlags, kAudioTimeStampSampleTimeValid);
+        for chunk_frames in [441, 1000, 7, 1500, 1024] {
+            let frames = up.process(&[&vec![0; 4 * chunk_frames]], &mut [&mut output]).unwrap();
+            input_time_stamp.mSampleTime += chunk_frames as f64;
+            expected += frames as f64;
+            let output_time_stamp = up.output_time_stamp(&input_time_stamp);
+            assert!((output_time_stamp.mSampleTime - expected).abs() < 1e-6);
+            assert_eq!(output_time_stamp.mSampleTime.round() as u64, up.output_frame_count());
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::four_cc::FourCC;
+
 cfg_if! {
     if #[cfg(target_pointer_width = "64")] {
         use crate::libc::{c_long, c_ulong};
@@ -14,23 +16,18 @@ pub type AudioSessionID = u32;
 
 pub type AVAudioSessionErrorCode = AVAudioInteger;
 
-#[inline]
-const fn fourcc(code: &[u8; 4]) -> AVAudioInteger {
-    (((code[0] as u32) << 24) | ((code[1] as u32) << 16) | ((code[2] as u32) << 8) | ((code[3] as u32) << 0)) as AVAudioInteger
-}
-
 pub const AVAudioSessionErrorCodeNone: AVAudioSessionErrorCode = 0;
-pub const AVAudioSessionErrorCodeMediaServicesFailed: AVAudioSessionErrorCode = fourcc(b"msrv");
-pub const AVAudioSessionErrorCodeIsBusy: AVAudioSessionErrorCode = fourcc(b"!act");
-pub const AVAudioSessionErrorCodeIncompatibleCategory: AVAudioSessionErrorCode = fourcc(b"!cat");
-pub const AVAudioSessionErrorCodeCannotInterruptOthers: AVAudioSessionErrorCode = fourcc(b"!int");
-pub const AVAudioSessionErrorCodeMissingEntitlement: AVAudioSessionErrorCode = fourcc(b"ent?");
-pub const AVAudioSessionErrorCodeSiriIsRecording: AVAudioSessionErrorCode = fourcc(b"siri");
-pub const AVAudioSessionErrorCodeCannotStartPlaying: AVAudioSessionErrorCode = fourcc(b"!pla");
-pub const AVAudioSessionErrorCodeCannotStartRecording: AVAudioSessionErrorCode = fourcc(b"!rec");
+pub const AVAudioSessionErrorCodeMediaServicesFailed: AVAudioSessionErrorCode = FourCC::new(b"msrv").as_u32() as AVAudioInteger;
+pub const AVAudioSessionErrorCodeIsBusy: AVAudioSessionErrorCode = FourCC::new(b"!act").as_u32() as AVAudioInteger;
+pub const AVAudioSessionErrorCodeIncompatibleCategory: AVAudioSessionErrorCode = FourCC::new(b"!cat").as_u32() as AVAudioInteger;
+pub const AVAudioSessionErrorCodeCannotInterruptOthers: AVAudioSessionErrorCode = FourCC::new(b"!int").as_u32() as AVAudioInteger;
+pub const AVAudioSessionErrorCodeMissingEntitlement: AVAudioSessionErrorCode = FourCC::new(b"ent?").as_u32() as AVAudioInteger;
+pub const AVAudioSessionErrorCodeSiriIsRecording: AVAudioSessionErrorCode = FourCC::new(b"siri").as_u32() as AVAudioInteger;
+pub const AVAudioSessionErrorCodeCannotStartPlaying: AVAudioSessionErrorCode = FourCC::new(b"!pla").as_u32() as AVAudioInteger;
+pub const AVAudioSessionErrorCodeCannotStartRecording: AVAudioSessionErrorCode = FourCC::new(b"!rec").as_u32() as AVAudioInteger;
 pub const AVAudioSessionErrorCodeBadParam: AVAudioSessionErrorCode = -50;
-pub const AVAudioSessionErrorCodeInsufficientPriority: AVAudioSessionErrorCode = fourcc(b"!pri");
-pub const AVAudioSessionErrorCodeResourceNotAvailable: AVAudioSessionErrorCode = fourcc(b"!res");
-pub const AVAudioSessionErrorCodeUnspecified: AVAudioSessionErrorCode = fourcc(b"what");
-pub const AVAudioSessionErrorCodeExpiredSession: AVAudioSessionErrorCode = fourcc(b"!ses");
-pub const AVAudioSessionErrorCodeSessionNotActive: AVAudioSessionErrorCode = fourcc(b"inac");
+pub const AVAudioSessionErrorCodeInsufficientPriority: AVAudioSessionErrorCode = FourCC::new(b"!pri").as_u32() as AVAudioInteger;
+pub const AVAudioSessionErrorCodeResourceNotAvailable: AVAudioSessionErrorCode = FourCC::new(b"!res").as_u32() as AVAudioInteger;
+pub const AVAudioSessionErrorCodeUnspecified: AVAudioSessionErrorCode = FourCC::new(b"what").as_u32() as AVAudioInteger;
+pub const AVAudioSessionErrorCodeExpiredSession: AVAudioSessionErrorCode = FourCC::new(b"!ses").as_u32() as AVAudioInteger;
+pub const AVAudioSessionErrorCodeSessionNotActive: AVAudioSessionErrorCode = FourCC::new(b"inac").as_u32() as AVAudioInteger;
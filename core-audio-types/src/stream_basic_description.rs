@@ -0,0 +1,363 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use crate::{base_types::*, four_cc::FourCC};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamBasicDescriptionError {
+    InvalidSampleRate(f64),
+    ZeroChannels,
+    ZeroBitsPerChannel,
+    FramesPerPacketNotOne(u32),
+    BytesPerPacketMismatch { expected: u32, actual: u32 },
+    BytesPerFrameNotMultipleOfChannels { bytes_per_frame: u32, channels: u32 },
+    BitsExceedSampleWord { bits_per_channel: u32, sample_word_size: u32 },
+    PackedFlagMismatch { bits_per_channel: u32, sample_word_size: u32 },
+    ConflictingSampleTypeFlags,
+    UnsupportedFloatBitDepth(u32),
+    FractionBitsExceedBitsPerChannel(u32),
+}
+
+impl Display for StreamBasicDescriptionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            StreamBasicDescriptionError::InvalidSampleRate(rate) => write!(f, "invalid sample rate {}", rate),
+            StreamBasicDescriptionError::ZeroChannels => f.write_str("zero channels per frame"),
+            StreamBasicDescriptionError::ZeroBitsPerChannel => f.write_str("zero bits per channel"),
+            StreamBasicDescriptionError::FramesPerPacketNotOne(frames) => write!(f, "linear PCM needs 1 frame per packet, not {}", frames),
+            StreamBasicDescriptionError::BytesPerPacketMismatch {
+                expected,
+                actual,
+            } => write!(f, "{} bytes per packet, expected {}", actual, expected),
+            StreamBasicDescriptionError::BytesPerFrameNotMultipleOfChannels {
+                bytes_per_frame,
+                channels,
+            } => write!(f, "{} bytes per frame cannot hold {} interleaved channels", bytes_per_frame, channels),
+            StreamBasicDescriptionError::BitsExceedSampleWord {
+                bits_per_channel,
+                sample_word_size,
+            } => write!(f, "{} bits per channel do not fit in {} byte samples", bits_per_channel, sample_word_size),
+            StreamBasicDescriptionError::PackedFlagMismatch {
+                bits_per_channel,
+                sample_word_size,
+            } => write!(f, "packed flag set but {} bits per channel leave {} byte samples partly empty", bits_per_channel, sample_word_size),
+            StreamBasicDescriptionError::ConflictingSampleTypeFlags => f.write_str("both float and signed integer flags are set"),
+            StreamBasicDescriptionError::UnsupportedFloatBitDepth(bits) => write!(f, "{} bit float samples are not supported", bits),
+            StreamBasicDescriptionError::FractionBitsExceedBitsPerChannel(bits) => {
+                write!(f, "{} fraction bits exceed the bits per channel", bits)
+            }
+        }
+    }
+}
+
+impl Error for StreamBasicDescriptionError {}
+
+impl AudioStreamBasicDescription {
+    #[inline]
+    pub fn is_pcm(&self) -> bool {
+        self.mFormatID == kAudioFormatLinearPCM
+    }
+
+    #[inline]
+    pub fn is_float(&self) -> bool {
+        self.is_pcm() && self.mFormatFlags & kAudioFormatFlagIsFloat != 0
+    }
+
+    #[inline]
+    pub fn is_signed_integer(&self) -> bool {
+        self.is_pcm() && self.mFormatFlags & (kAudioFormatFlagIsFloat | kAudioFormatFlagIsSignedInteger) == kAudioFormatFlagIsSignedInteger
+    }
+
+    #[inline]
+    pub fn is_unsigned_integer(&self) -> bool {
+        self.is_pcm() && self.mFormatFlags & (kAudioFormatFlagIsFloat | kAudioFormatFlagIsSignedInteger) == 0
+    }
+
+    #[inline]
+    pub fn is_big_endian(&self) -> bool {
+        self.mFormatFlags & kAudioFormatFlagIsBigEndian != 0
+    }
+
+    #[inline]
+    pub fn is_native_endian(&self) -> bool {
+        IsAudioFormatNativeEndian(self)
+    }
+
+    #[inline]
+    pub fn is_packed(&self) -> bool {
+        self.mFormatFlags & kAudioFormatFlagIsPacked != 0
+    }
+
+    #[inline]
+    pub fn is_aligned_high(&self) -> bool {
+        self.mFormatFlags & kAudioFormatFlagIsAlignedHigh != 0
+    }
+
+    #[inline]
+    pub fn is_interleaved(&self) -> bool {
+        self.mFormatFlags & kAudioFormatFlagIsNonInterleaved == 0
+    }
+
+    #[inline]
+    pub fn is_mixable(&self) -> bool {
+        self.is_pcm() && self.mFormatFlags & kAudioFormatFlagIsNonMixable == 0
+    }
+
+    #[inline]
+    pub fn fraction_bits(&self) -> u32 {
+        (self.mFormatFlags & kLinearPCMFormatFlagsSampleFractionMask) >> kLinearPCMFormatFlagsSampleFractionShift
+    }
+
+    #[inline]
+    pub fn number_of_channel_streams(&self) -> u32 {
+        if self.is_interleaved() {
+            1
+        } else {
+            self.mChannelsPerFrame
+        }
+    }
+
+    #[inline]
+    pub fn number_of_interleaved_channels(&self) -> u32 {
+        if self.is_interleaved() {
+            self.mChannelsPerFrame
+        } else {
+            1
+        }
+    }
+
+    #[inline]
+    pub fn sample_word_size(&self) -> u32 {
+        self.mBytesPerFrame.checked_div(self.number_of_interleaved_channels()).unwrap_or(0)
+    }
+
+    #[inline]
+    pub fn bytes_per_frame_consistent(&self) -> bool {
+        let channels = self.number_of_interleaved_channels();
+        self.mBytesPerPacket == self.mBytesPerFrame.saturating_mul(self.mFramesPerPacket) &&
+            self.mBytesPerFrame.checked_rem(channels) == Some(0) &&
+            self.mBitsPerChannel <= self.sample_word_size().saturating_mul(8)
+    }
+
+    pub fn is_canonical(&self) -> bool {
+        self.is_pcm() &&
+            self.mFramesPerPacket == 1 &&
+            self.mFormatFlags & !kAudioFormatFlagIsNonInterleaved == kAudioFormatFlagsCanonical &&
+            self.mBitsPerChannel == self.sample_word_size().saturating_mul(8) &&
+            self.bytes_per_frame_consistent()
+    }
+
+    #[inline]
+    pub fn frames_to_bytes(&self, frames: u32) -> u32 {
+        frames.saturating_mul(self.mBytesPerFrame)
+    }
+
+    #[inline]
+    pub fn bytes_to_frames(&self, bytes: u32) -> u32 {
+        bytes.checked_div(self.mBytesPerFrame).unwrap_or(0)
+    }
+
+    pub fn validate(&self) -> Result<(), StreamBasicDescriptionError> {
+        match self.inconsistencies().first() {
+            Some(error) => Err(*error),
+            None => Ok(()),
+        }
+    }
+
+    pub fn inconsistencies(&self) -> Vec<StreamBasicDescriptionError> {
+        let mut errors = Vec::new();
+        // Zero is kAudioStreamAnyRate
+        if !self.mSampleRate.is_finite() || self.mSampleRate < 0.0 {
+            errors.push(StreamBasicDescriptionError::InvalidSampleRate(self.mSampleRate));
+        }
+        if self.mChannelsPerFrame == 0 {
+            errors.push(StreamBasicDescriptionError::ZeroChannels);
+        }
+        if !self.is_pcm() {
+            return errors;
+        }
+        if self.mBitsPerChannel == 0 {
+            errors.push(StreamBasicDescriptionError::ZeroBitsPerChannel);
+        }
+        if self.mFramesPerPacket != 1 {
+            errors.push(StreamBasicDescriptionError::FramesPerPacketNotOne(self.mFramesPerPacket));
+        }
+        let expected = self.mBytesPerFrame.saturating_mul(self.mFramesPerPacket);
+        if self.mBytesPerPacket != expected {
+            errors.push(StreamBasicDescriptionError::BytesPerPacketMismatch {
+                expected,
+                actual: self.mBytesPerPacket,
+            });
+        }
+        let channels = self.number_of_interleaved_channels();
+        if self.mBytesPerFrame.checked_rem(channels).is_some_and(|remainder| remainder != 0) {
+            errors.push(StreamBasicDescriptionError::BytesPerFrameNotMultipleOfChannels {
+                bytes_per_frame: self.mBytesPerFrame,
+                channels,
+            });
+        }
+        let sample_word_size = self.sample_word_size();
+        if self.mBitsPerChannel > sample_word_size.saturating_mul(8) {
+            errors.push(StreamBasicDescriptionError::BitsExceedSampleWord {
+                bits_per_channel: self.mBitsPerChannel,
+                sample_word_size,
+            });
+        } else if self.is_packed() && self.mBitsPerChannel != sample_word_size.saturating_mul(8) {
+            errors.push(StreamBasicDescriptionError::PackedFlagMismatch {
+                bits_per_channel: self.mBitsPerChannel,
+                sample_word_size,
+            });
+        }
+        if self.mFormatFlags & kAudioFormatFlagIsFloat != 0 {
+            if self.mFormatFlags & kAudioFormatFlagIsSignedInteger != 0 {
+                errors.push(StreamBasicDescriptionError::ConflictingSampleTypeFlags);
+            }
+            if self.mBitsPerChannel != 32 && self.mBitsPerChannel != 64 {
+                errors.push(StreamBasicDescriptionError::UnsupportedFloatBitDepth(self.mBitsPerChannel));
+            }
+        }
+        if self.fraction_bits() > self.mBitsPerChannel {
+            errors.push(StreamBasicDescriptionError::FractionBitsExceedBitsPerChannel(self.fraction_bits()));
+        }
+        errors
+    }
+}
+
+// Mirrors the CAStreamBasicDescription summary, e.g.
+// " 2 ch,  44100 Hz, 'lpcm' (0x0000000C) 16-bit little-endian signed integer"
+impl Display for AudioStreamBasicDescription {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{:2} ch, {:6.0} Hz, {} (0x{:08X}) ", self.mChannelsPerFrame, self.mSampleRate, FourCC(self.mFormatID), self.mFormatFlags)?;
+        if !self.is_pcm() {
+            if self.mFormatID == kAudioFormatAppleLossless {
+                let source_bits = match self.mFormatFlags {
+                    kAppleLosslessFormatFlag_16BitSourceData => 16,
+                    kAppleLosslessFormatFlag_20BitSourceData => 20,
+                    kAppleLosslessFormatFlag_24BitSourceData => 24,
+                    kAppleLosslessFormatFlag_32BitSourceData => 32,
+                    _ => 0,
+                };
+                if source_bits > 0 {
+                    write!(f, "from {}-bit source, ", source_bits)?;
+                } else {
+                    f.write_str("from UNKNOWN source bit depth, ")?;
+                }
+            }
+            return write!(
+                f,
+                "{} bits/channel, {} bytes/packet, {} frames/packet, {} bytes/frame",
+                self.mBitsPerChannel, self.mBytesPerPacket, self.mFramesPerPacket, self.mBytesPerFrame
+            );
+        }
+        let fraction_bits = self.fraction_bits();
+        if fraction_bits > 0 {
+            write!(f, "{}.{}-bit", self.mBitsPerChannel.saturating_sub(fraction_bits), fraction_bits)?;
+        } else {
+            write!(f, "{}-bit", self.mBitsPerChannel)?;
+        }
+        let sample_word_size = self.sample_word_size();
+        if sample_word_size > 1 {
+            f.write_str(if self.is_big_endian() {
+                " big-endian"
+            } else {
+                " little-endian"
+            })?;
+        }
+        if self.is_float() {
+            f.write_str(" float")?;
+        } else {
+            f.write_str(if self.mFormatFlags & kAudioFormatFlagIsSignedInteger != 0 {
+                " signed integer"
+            } else {
+                " unsigned integer"
+            })?;
+        }
+        if sample_word_size > 0 && self.mBitsPerChannel != sample_word_size.saturating_mul(8) {
+            write!(f, ", packed in {} bytes", sample_word_size)?;
+            f.write_str(if self.is_aligned_high() {
+                " high-aligned"
+            } else {
+                " low-aligned"
+            })?;
+        }
+        if !self.is_interleaved() && self.mChannelsPerFrame > 1 {
+            f.write_str(", deinterleaved")?;
+        }
+        if self.mFormatFlags & kAudioFormatFlagIsNonMixable != 0 {
+            f.write_str(", non-mixable")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lpcm(sample_rate: f64, channels: u32, bits: u32, is_float: bool, is_non_interleaved: bool) -> AudioStreamBasicDescription {
+        let mut asbd = AudioStreamBasicDescription::default();
+        FillOutASBDForLPCM(&mut asbd, sample_rate, channels, bits, bits, is_float, false, is_non_interleaved);
+        asbd
+    }
+
+    #[test]
+    fn valid_lpcm() {
+        let asbd = lpcm(44100.0, 2, 16, false, false);
+        assert_eq!(asbd.validate(), Ok(()));
+        assert!(asbd.bytes_per_frame_consistent());
+        assert!(asbd.inconsistencies().is_empty());
+        assert_eq!(asbd.to_string(), " 2 ch,  44100 Hz, 'lpcm' (0x0000000C) 16-bit little-endian signed integer");
+    }
+
+    #[test]
+    fn non_interleaved_lpcm() {
+        let asbd = lpcm(48000.0, 2, 32, true, true);
+        assert_eq!(asbd.validate(), Ok(()));
+        assert_eq!((asbd.number_of_channel_streams(), asbd.number_of_interleaved_channels(), asbd.sample_word_size()), (2, 1, 4));
+        assert!(asbd.is_canonical());
+        assert_eq!(asbd.to_string(), " 2 ch,  48000 Hz, 'lpcm' (0x00000029) 32-bit little-endian float, deinterleaved");
+    }
+
+    #[test]
+    fn zero_channels() {
+        let mut asbd = lpcm(44100.0, 2, 16, false, false);
+        asbd.mChannelsPerFrame = 0;
+        assert_eq!(asbd.validate(), Err(StreamBasicDescriptionError::ZeroChannels));
+        assert!(!asbd.bytes_per_frame_consistent());
+        assert_eq!(StreamBasicDescriptionError::ZeroChannels.to_string(), "zero channels per frame");
+        assert!(asbd.to_string().starts_with(" 0 ch,  44100 Hz, 'lpcm'"));
+    }
+
+    #[test]
+    fn mismatched_bytes_per_frame() {
+        let mut asbd = lpcm(44100.0, 2, 16, false, false);
+        asbd.mBytesPerFrame = 3;
+        let error = StreamBasicDescriptionError::BytesPerPacketMismatch {
+            expected: 3,
+            actual: 4,
+        };
+        assert_eq!(asbd.validate(), Err(error));
+        assert_eq!(error.to_string(), "4 bytes per packet, expected 3");
+
+        asbd.mBytesPerPacket = 3;
+        let error = StreamBasicDescriptionError::BytesPerFrameNotMultipleOfChannels {
+            bytes_per_frame: 3,
+            channels: 2,
+        };
+        assert_eq!(asbd.validate(), Err(error));
+        assert!(!asbd.bytes_per_frame_consistent());
+        assert_eq!(
+            asbd.inconsistencies(),
+            vec![
+                error,
+                StreamBasicDescriptionError::BitsExceedSampleWord {
+                    bits_per_channel: 16,
+                    sample_word_size: 1,
+                }
+            ]
+        );
+        assert_eq!(error.to_string(), "3 bytes per frame cannot hold 2 interleaved channels");
+    }
+}
@@ -0,0 +1,1012 @@
+use core_audio_types::{
+    base_types::{
+        kAudioChannelLabel_Center, kAudioChannelLabel_CenterSurround, kAudioChannelLabel_LFE2, kAudioChannelLabel_LFEScreen, kAudioChannelLabel_Left,
+        kAudioChannelLabel_LeftCenter, kAudioChannelLabel_LeftSurround, kAudioChannelLabel_LeftSurroundDirect, kAudioChannelLabel_LeftWide,
+        kAudioChannelLabel_RearSurroundLeft, kAudioChannelLabel_RearSurroundRight, kAudioChannelLabel_Right, kAudioChannelLabel_RightCenter,
+        kAudioChannelLabel_RightSurround, kAudioChannelLabel_RightSurroundDirect, kAudioChannelLabel_RightWide, kAudioChannelLabel_TopCenterSurround,
+        kAudioChannelLabel_VerticalHeightCenter, kAudioChannelLabel_VerticalHeightLeft, kAudioChannelLabel_VerticalHeightRight, kAudioFormatAC3,
+        kAudioFormatEnhancedAC3, AudioChannelLabel, AudioFormatID, AudioStreamBasicDescription, AudioStreamPacketDescription,
+    },
+    channel_layout::ChannelLayout,
+    four_cc::FourCC,
+};
+use core_foundation::{
+    base::{CFType, OSStatus, TCFType},
+    data::CFData,
+    dictionary::{CFDictionary, CFMutableDictionary},
+    string::CFString,
+};
+
+use crate::{
+    bit_reader::{BitReader, BitWriter},
+    format_description::{
+        kCMFormatDescriptionError_InvalidParameter, kCMFormatDescriptionExtension_SampleDescriptionExtensionAtoms, CMAudioFormatDescription,
+    },
+    sample_buffer::CMSampleBuffer,
+    sync_frame::{self, SyncFrameIter, SyncHeader},
+    time::CMTime,
+    OSType,
+};
+
+pub const AC3_SYNC_WORD: u16 = 0x0B77;
+pub const AC3_FRAMES_PER_PACKET: u32 = 1536;
+pub const AC3_BLOCK_SIZE: u32 = 256;
+pub const AC3_HEADER_SIZE: usize = 7;
+
+pub const kAC3SpecificBoxType: OSType = FourCC::new(b"dac3").as_u32();
+pub const kEAC3SpecificBoxType: OSType = FourCC::new(b"dec3").as_u32();
+
+const AC3_MAX_BSID: u8 = 8;
+const EAC3_MIN_BSID: u8 = 11;
+const EAC3_MAX_BSID: u8 = 16;
+
+const AC3_BIT_RATES: [u32; 19] = [32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 448, 512, 576, 640];
+const AC3_SAMPLE_RATES: [u32; 3] = [48000, 44100, 32000];
+const EAC3_BLOCK_COUNTS: [u32; 4] = [1, 2, 3, 6];
+
+const ACMOD_DUAL_MONO: u8 = 0;
+const ACMOD_STEREO: u8 = 2;
+const ACMOD_2F2R: u8 = 6;
+
+// Dependent substream channels in chan_loc order, MSB first
+const CHAN_LOC_LABELS: [&[AudioChannelLabel]; 9] = [
+    &[kAudioChannelLabel_LeftCenter, kAudioChannelLabel_RightCenter],
+    &[kAudioChannelLabel_RearSurroundLeft, kAudioChannelLabel_RearSurroundRight],
+    &[kAudioChannelLabel_CenterSurround],
+    &[kAudioChannelLabel_TopCenterSurround],
+    &[kAudioChannelLabel_LeftSurroundDirect, kAudioChannelLabel_RightSurroundDirect],
+    &[kAudioChannelLabel_LeftWide, kAudioChannelLabel_RightWide],
+    &[kAudioChannelLabel_VerticalHeightLeft, kAudioChannelLabel_VerticalHeightRight],
+    &[kAudioChannelLabel_VerticalHeightCenter],
+    &[kAudioChannelLabel_LFE2],
+];
+
+fn channel_labels(acmod: u8, lfeon: bool, chan_loc: u16) -> Vec<AudioChannelLabel> {
+    let mut labels = match acmod {
+        0 | 2 => vec![kAudioChannelLabel_Left, kAudioChannelLabel_Right],
+        1 => vec![kAudioChannelLabel_Center],
+        3 => vec![kAudioChannelLabel_Left, kAudioChannelLabel_Center, kAudioChannelLabel_Right],
+        4 => vec![kAudioChannelLabel_Left, kAudioChannelLabel_Right, kAudioChannelLabel_CenterSurround],
+        5 => vec![kAudioChannelLabel_Left, kAudioChannelLabel_Center, kAudioChannelLabel_Right, kAudioChannelLabel_CenterSurround],
+        6 => vec![kAudioChannelLabel_Left, kAudioChannelLabel_Right, kAudioChannelLabel_LeftSurround, kAudioChannelLabel_RightSurround],
+        _ => vec![
+            kAudioChannelLabel_Left,
+            kAudioChannelLabel_Center,
+            kAudioChannelLabel_Right,
+            kAudioChannelLabel_LeftSurround,
+            kAudioChannelLabel_RightSurround,
+        ],
+    };
+    if lfeon {
+        labels.push(kAudioChannelLabel_LFEScreen);
+    }
+    for (i, extra) in CHAN_LOC_LABELS.iter().enumerate() {
+        if chan_loc & (0x100 >> i) != 0 {
+            labels.extend_from_slice(extra);
+        }
+    }
+    labels
+}
+
+// chan_loc keeps chanmap locations 5 to 12 and LFE2 at 14
+#[inline]
+fn chan_loc_from_channel_map(channel_map: u16) -> u16 {
+    ((channel_map >> 2) & 0x1FE) | ((channel_map >> 1) & 0x1)
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Ac3Header {
+    pub fscod: u8,
+    pub frmsizecod: u8,
+    pub bsid: u8,
+    pub bsmod: u8,
+    pub acmod: u8,
+    pub lfeon: bool,
+}
+
+impl Ac3Header {
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        let invalid = kCMFormatDescriptionError_InvalidParameter;
+        let mut reader = BitReader::new(data);
+        if reader.read_bits(16).ok_or(invalid)? != AC3_SYNC_WORD as u32 {
+            return Err(invalid);
+        }
+        reader.skip_bits(16).ok_or(invalid)?;
+        let fscod = reader.read_bits(2).ok_or(invalid)? as u8;
+        let frmsizecod = reader.read_bits(6).ok_or(invalid)? as u8;
+        let bsid = reader.read_bits(5).ok_or(invalid)? as u8;
+        let bsmod = reader.read_bits(3).ok_or(invalid)? as u8;
+        let acmod = reader.read_bits(3).ok_or(invalid)? as u8;
+        if fscod == 3 || frmsizecod as usize >= AC3_BIT_RATES.len() * 2 || bsid > AC3_MAX_BSID {
+            return Err(invalid);
+        }
+        if acmod & 1 != 0 && acmod != 1 {
+            reader.skip_bits(2).ok_or(invalid)?;
+        }
+        if acmod & 4 != 0 {
+            reader.skip_bits(2).ok_or(invalid)?;
+        }
+        if acmod == ACMOD_STEREO {
+            reader.skip_bits(2).ok_or(invalid)?;
+        }
+        let lfeon = reader.read_bit().ok_or(invalid)?;
+        Ok(Self {
+            fscod,
+            frmsizecod,
+            bsid,
+            bsmod,
+            acmod,
+            lfeon,
+        })
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        AC3_SAMPLE_RATES[self.fscod as usize]
+    }
+
+    #[inline]
+    pub fn bit_rate(&self) -> u32 {
+        AC3_BIT_RATES[(self.frmsizecod >> 1) as usize] * 1000
+    }
+
+    // 44.1 kHz frames alternate between two sizes to keep the bit rate exact
+    #[inline]
+    pub fn frame_length(&self) -> usize {
+        let words = self.bit_rate() as u64 * AC3_FRAMES_PER_PACKET as u64 / (self.sample_rate() as u64 * 16);
+        let padding = if self.fscod == 1 {
+            (self.frmsizecod & 1) as u64
+        } else {
+            0
+        };
+        ((words + padding) * 2) as usize
+    }
+
+    #[inline]
+    pub fn channel_labels(&self) -> Vec<AudioChannelLabel> {
+        channel_labels(self.acmod, self.lfeon, 0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Eac3StreamType {
+    Independent,
+    Dependent,
+    Ac3Convert,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Eac3Header {
+    pub stream_type: Eac3StreamType,
+    pub substream_id: u8,
+    pub frame_length: usize,
+    pub fscod: u8,
+    pub fscod2: u8,
+    pub block_count: u32,
+    pub acmod: u8,
+    pub lfeon: bool,
+    pub bsid: u8,
+    pub bsmod: u8,
+    pub channel_map: Option<u16>,
+    pub extension_type_a: bool,
+    pub complexity_index_type_a: u8,
+}
+
+impl Eac3Header {
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        let mut reader = BitReader::new(data);
+        Self::read(&mut reader).ok_or(kCMFormatDescriptionError_InvalidParameter)
+    }
+
+    fn read(reader: &mut BitReader) -> Option<Self> {
+        if reader.read_bits(16)? != AC3_SYNC_WORD as u32 {
+            return None;
+        }
+        let stream_type = match reader.read_bits(2)? {
+            0 => Eac3StreamType::Independent,
+            1 => Eac3StreamType::Dependent,
+            2 => Eac3StreamType::Ac3Convert,
+            _ => return None,
+        };
+        let substream_id = reader.read_bits(3)? as u8;
+        let frame_length = (reader.read_bits(11)? as usize + 1) * 2;
+        let fscod = reader.read_bits(2)? as u8;
+        let (fscod2, numblkscod) = if fscod == 3 {
+            (reader.read_bits(2)? as u8, 3)
+        } else {
+            (0, reader.read_bits(2)? as usize)
+        };
+        let acmod = reader.read_bits(3)? as u8;
+        let lfeon = reader.read_bit()?;
+        let bsid = reader.read_bits(5)? as u8;
+        if !(EAC3_MIN_BSID..=EAC3_MAX_BSID).contains(&bsid) || fscod2 == 3 || frame_length < AC3_HEADER_SIZE {
+            return None;
+        }
+        let block_count = EAC3_BLOCK_COUNTS[numblkscod];
+        let program_count = if acmod == ACMOD_DUAL_MONO {
+            2
+        } else {
+            1
+        };
+        // dialnorm and compr for each program
+        for _ in 0..program_count {
+            reader.skip_bits(5)?;
+            if reader.read_bit()? {
+                reader.skip_bits(8)?;
+            }
+        }
+        let channel_map = if stream_type == Eac3StreamType::Dependent && reader.read_bit()? {
+            Some(reader.read_bits(16)? as u16)
+        } else {
+            None
+        };
+        // mixmdate
+        if reader.read_bit()? {
+            if acmod > ACMOD_STEREO {
+                reader.skip_bits(2)?;
+                if acmod & 1 != 0 {
+                    reader.skip_bits(6)?;
+                }
+                if acmod & 4 != 0 {
+                    reader.skip_bits(6)?;
+                }
+            }
+            if lfeon && reader.read_bit()? {
+                reader.skip_bits(5)?;
+            }
+            if stream_type == Eac3StreamType::Independent {
+                for _ in 0..program_count {
+                    if reader.read_bit()? {
+                        reader.skip_bits(6)?;
+                    }
+                }
+                if reader.read_bit()? {
+                    reader.skip_bits(6)?;
+                }
+                match reader.read_bits(2)? {
+                    1 => reader.skip_bits(5)?,
+                    2 => reader.skip_bits(12)?,
+                    3 => {
+                        let length = (reader.read_bits(5)? as usize + 2) * 8;
+                        reader.skip_bits(length)?
+                    }
+                    _ => {}
+                }
+                if acmod < ACMOD_STEREO {
+                    for _ in 0..program_count {
+                        if reader.read_bit()? {
+                            reader.skip_bits(14)?;
+                        }
+                    }
+                }
+                if reader.read_bit()? {
+                    for _ in 0..block_count {
+                        if block_count == 1 || reader.read_bit()? {
+                            reader.skip_bits(5)?;
+                        }
+                    }
+                }
+            }
+        }
+        // infomdate
+        let mut bsmod = 0;
+        if reader.read_bit()? {
+            bsmod = reader.read_bits(3)? as u8;
+            reader.skip_bits(2)?;
+            if acmod == ACMOD_STEREO {
+                reader.skip_bits(4)?;
+            }
+            if acmod >= ACMOD_2F2R {
+                reader.skip_bits(2)?;
+            }
+            for _ in 0..program_count {
+                if reader.read_bit()? {
+                    reader.skip_bits(8)?;
+                }
+            }
+            if fscod < 3 {
+                reader.skip_bits(1)?;
+            }
+        }
+        if stream_type == Eac3StreamType::Independent && block_count != 6 {
+            reader.skip_bits(1)?;
+        }
+        if stream_type == Eac3StreamType::Ac3Convert && (block_count == 6 || reader.read_bit()?) {
+            reader.skip_bits(6)?;
+        }
+        // The first additional info byte flags Atmos joint object coding
+        let mut extension_type_a = false;
+        let mut complexity_index_type_a = 0;
+        if reader.read_bit()? {
+            let length = reader.read_bits(6)? as usize + 1;
+            reader.skip_bits(7)?;
+            extension_type_a = reader.read_bit()?;
+            if extension_type_a && length > 1 {
+                complexity_index_type_a = reader.read_bits(8)? as u8;
+            }
+        }
+        Some(Self {
+            stream_type,
+            substream_id,
+            frame_length,
+            fscod,
+            fscod2,
+            block_count,
+            acmod,
+            lfeon,
+            bsid,
+            bsmod,
+            channel_map,
+            extension_type_a,
+            complexity_index_type_a,
+        })
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        if self.fscod == 3 {
+            AC3_SAMPLE_RATES[self.fscod2 as usize] / 2
+        } else {
+            AC3_SAMPLE_RATES[self.fscod as usize]
+        }
+    }
+
+    #[inline]
+    pub fn frame_count(&self) -> u32 {
+        self.block_count * AC3_BLOCK_SIZE
+    }
+
+    #[inline]
+    pub fn bit_rate(&self) -> u32 {
+        (self.frame_length as u64 * 8 * self.sample_rate() as u64 / self.frame_count() as u64) as u32
+    }
+
+    #[inline]
+    pub fn is_independent(&self) -> bool {
+        self.stream_type != Eac3StreamType::Dependent
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Ac3SyncFrame {
+    Ac3(Ac3Header),
+    Eac3(Eac3Header),
+}
+
+impl Ac3SyncFrame {
+    // Both syntaxes put bsid at the same position
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        if data.len() < AC3_HEADER_SIZE || u16::from_be_bytes([data[0], data[1]]) != AC3_SYNC_WORD {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        if data[5] >> 3 <= AC3_MAX_BSID {
+            Ac3Header::parse(data).map(Ac3SyncFrame::Ac3)
+        } else {
+            Eac3Header::parse(data).map(Ac3SyncFrame::Eac3)
+        }
+    }
+
+    #[inline]
+    pub fn frame_length(&self) -> usize {
+        match self {
+            Ac3SyncFrame::Ac3(header) => header.frame_length(),
+            Ac3SyncFrame::Eac3(header) => header.frame_length,
+        }
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        match self {
+            Ac3SyncFrame::Ac3(header) => header.sample_rate(),
+            Ac3SyncFrame::Eac3(header) => header.sample_rate(),
+        }
+    }
+
+    #[inline]
+    pub fn frame_count(&self) -> u32 {
+        match self {
+            Ac3SyncFrame::Ac3(_) => AC3_FRAMES_PER_PACKET,
+            Ac3SyncFrame::Eac3(header) => header.frame_count(),
+        }
+    }
+
+    #[inline]
+    pub fn bit_rate(&self) -> u32 {
+        match self {
+            Ac3SyncFrame::Ac3(header) => header.bit_rate(),
+            Ac3SyncFrame::Eac3(header) => header.bit_rate(),
+        }
+    }
+
+    // AC-3 frames act as independent substream 0 inside E-AC-3 streams
+    #[inline]
+    pub fn starts_access_unit(&self) -> bool {
+        match self {
+            Ac3SyncFrame::Ac3(_) => true,
+            Ac3SyncFrame::Eac3(header) => header.is_independent() && header.substream_id == 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Ac3SpecificBox {
+    pub fscod: u8,
+    pub bsid: u8,
+    pub bsmod: u8,
+    pub acmod: u8,
+    pub lfeon: bool,
+    pub bit_rate_code: u8,
+}
+
+impl Ac3SpecificBox {
+    pub fn from_header(header: &Ac3Header) -> Self {
+        Self {
+            fscod: header.fscod,
+            bsid: header.bsid,
+            bsmod: header.bsmod,
+            acmod: header.acmod,
+            lfeon: header.lfeon,
+            bit_rate_code: header.frmsizecod >> 1,
+        }
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        let mut reader = BitReader::new(data);
+        let mut read = || -> Option<Self> {
+            Some(Self {
+                fscod: reader.read_bits(2)? as u8,
+                bsid: reader.read_bits(5)? as u8,
+                bsmod: reader.read_bits(3)? as u8,
+                acmod: reader.read_bits(3)? as u8,
+                lfeon: reader.read_bit()?,
+                bit_rate_code: reader.read_bits(5)? as u8,
+            })
+        };
+        read().ok_or(kCMFormatDescriptionError_InvalidParameter)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = BitWriter::new();
+        writer.write_bits(self.fscod as u32, 2);
+        writer.write_bits(self.bsid as u32, 5);
+        writer.write_bits(self.bsmod as u32, 3);
+        writer.write_bits(self.acmod as u32, 3);
+        writer.write_bit(self.lfeon);
+        writer.write_bits(self.bit_rate_code as u32, 5);
+        writer.write_bits(0, 5);
+        writer.into_bytes()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Eac3IndependentSubstream {
+    pub fscod: u8,
+    pub bsid: u8,
+    pub asvc: bool,
+    pub bsmod: u8,
+    pub acmod: u8,
+    pub lfeon: bool,
+    pub dependent_substream_count: u8,
+    pub chan_loc: u16,
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Eac3SpecificBox {
+    // Kilobits per second of all substreams together
+    pub data_rate: u16,
+    pub substreams: Vec<Eac3IndependentSubstream>,
+    pub complexity_index_type_a: Option<u8>,
+}
+
+impl Eac3SpecificBox {
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        let mut reader = BitReader::new(data);
+        let mut read = || -> Option<Self> {
+            let data_rate = reader.read_bits(13)? as u16;
+            let substream_count = reader.read_bits(3)? as usize + 1;
+            let mut substreams = Vec::with_capacity(substream_count);
+            for _ in 0..substream_count {
+                let fscod = reader.read_bits(2)? as u8;
+                let bsid = reader.read_bits(5)? as u8;
+                reader.skip_bits(1)?;
+                let asvc = reader.read_bit()?;
+                let bsmod = reader.read_bits(3)? as u8;
+                let acmod = reader.read_bits(3)? as u8;
+                let lfeon = reader.read_bit()?;
+                reader.skip_bits(3)?;
+                let dependent_substream_count = reader.read_bits(4)? as u8;
+                let chan_loc = if dependent_substream_count > 0 {
+                    reader.read_bits(9)? as u16
+                } else {
+                    reader.skip_bits(1)?;
+                    0
+                };
+                substreams.push(Eac3IndependentSubstream {
+                    fscod,
+                    bsid,
+                    asvc,
+                    bsmod,
+                    acmod,
+                    lfeon,
+                    dependent_substream_count,
+                    chan_loc,
+                });
+            }
+            let complexity_index_type_a = if reader.bits_remaining() >= 16 {
+                reader.skip_bits(7)?;
+                let extension_type_a = reader.read_bit()?;
+                let complexity_index = reader.read_bits(8)? as u8;
+                extension_type_a.then_some(complexity_index)
+            } else {
+                None
+            };
+            Some(Self {
+                data_rate,
+                substreams,
+                complexity_index_type_a,
+            })
+        };
+        match read() {
+            Some(dec3) if !dec3.substreams.is_empty() => Ok(dec3),
+            _ => Err(kCMFormatDescriptionError_InvalidParameter),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = BitWriter::new();
+        writer.write_bits(self.data_rate as u32, 13);
+        writer.write_bits(self.substreams.len().saturating_sub(1) as u32, 3);
+        for substream in &self.substreams {
+            writer.write_bits(substream.fscod as u32, 2);
+            writer.write_bits(substream.bsid as u32, 5);
+            writer.write_bit(false);
+            writer.write_bit(substream.asvc);
+            writer.write_bits(substream.bsmod as u32, 3);
+            writer.write_bits(substream.acmod as u32, 3);
+            writer.write_bit(substream.lfeon);
+            writer.write_bits(0, 3);
+            writer.write_bits(substream.dependent_substream_count as u32, 4);
+            if substream.dependent_substream_count > 0 {
+                writer.write_bits(substream.chan_loc as u32, 9);
+            } else {
+                writer.write_bit(false);
+            }
+        }
+        if let Some(complexity_index) = self.complexity_index_type_a {
+            writer.write_bits(0, 7);
+            writer.write_bit(true);
+            writer.write_bits(complexity_index as u32, 8);
+        }
+        writer.into_bytes()
+    }
+
+    #[inline]
+    pub fn is_atmos(&self) -> bool {
+        self.complexity_index_type_a.is_some()
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Ac3Configuration {
+    Ac3(Ac3SpecificBox),
+    Eac3(Eac3SpecificBox),
+}
+
+impl Ac3Configuration {
+    // Builds the configuration from the sync frames of one access unit
+    pub fn from_access_unit(frames: &[Ac3SyncFrame]) -> Result<Self, OSStatus> {
+        let invalid = kCMFormatDescriptionError_InvalidParameter;
+        // Leading dependent substreams belong to an earlier access unit
+        let start = frames.iter().position(Ac3SyncFrame::starts_access_unit).ok_or(invalid)?;
+        let frames = &frames[start..];
+        match frames {
+            [Ac3SyncFrame::Ac3(header)] => return Ok(Ac3Configuration::Ac3(Ac3SpecificBox::from_header(header))),
+            [] => return Err(invalid),
+            _ => {}
+        }
+        let mut dec3 = Eac3SpecificBox::default();
+        let mut bit_rate = 0u64;
+        for frame in frames {
+            bit_rate += frame.bit_rate() as u64;
+            match frame {
+                Ac3SyncFrame::Ac3(header) => dec3.substreams.push(Eac3IndependentSubstream {
+                    fscod: header.fscod,
+                    bsid: header.bsid,
+                    bsmod: header.bsmod,
+                    acmod: header.acmod,
+                    lfeon: header.lfeon,
+                    ..Default::default()
+                }),
+                Ac3SyncFrame::Eac3(header) if header.is_independent() => {
+                    if dec3.substreams.is_empty() && header.extension_type_a {
+                        dec3.complexity_index_type_a = Some(header.complexity_index_type_a);
+                    }
+                    dec3.substreams.push(Eac3IndependentSubstream {
+                        fscod: header.fscod,
+                        bsid: header.bsid,
+                        asvc: false,
+                        bsmod: header.bsmod,
+                        acmod: header.acmod,
+                        lfeon: header.lfeon,
+                        dependent_substream_count: 0,
+                        chan_loc: 0,
+                    })
+                }
+                Ac3SyncFrame::Eac3(header) => {
+                    let substream = dec3.substreams.last_mut().ok_or(invalid)?;
+                    substream.dependent_substream_count += 1;
+                    substream.chan_loc |= header.channel_map.map_or(0, chan_loc_from_channel_map);
+                }
+            }
+        }
+        dec3.data_rate = (bit_rate / 1000).min(0x1FFF) as u16;
+        Ok(Ac3Configuration::Eac3(dec3))
+    }
+
+    pub fn parse(box_type: OSType, data: &[u8]) -> Result<Self, OSStatus> {
+        match box_type {
+            kAC3SpecificBoxType => Ac3SpecificBox::parse(data).map(Ac3Configuration::Ac3),
+            kEAC3SpecificBoxType => Eac3SpecificBox::parse(data).map(Ac3Configuration::Eac3),
+            _ => Err(kCMFormatDescriptionError_InvalidParameter),
+        }
+    }
+
+    #[inline]
+    pub fn box_type(&self) -> OSType {
+        match self {
+            Ac3Configuration::Ac3(_) => kAC3SpecificBoxType,
+            Ac3Configuration::Eac3(_) => kEAC3SpecificBoxType,
+        }
+    }
+
+    #[inline]
+    pub fn format_id(&self) -> AudioFormatID {
+        match self {
+            Ac3Configuration::Ac3(_) => kAudioFormatAC3,
+            Ac3Configuration::Eac3(_) => kAudioFormatEnhancedAC3,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Ac3Configuration::Ac3(dac3) => dac3.to_bytes(),
+            Ac3Configuration::Eac3(dec3) => dec3.to_bytes(),
+        }
+    }
+
+    // Full box with size and type, as stored in a sample entry
+    pub fn to_box(&self) -> Vec<u8> {
+        let payload = self.to_bytes();
+        let mut data = Vec::with_capacity(payload.len() + 8);
+        data.extend_from_slice(&(payload.len() as u32 + 8).to_be_bytes());
+        data.extend_from_slice(&self.box_type().to_be_bytes());
+        data.extend_from_slice(&payload);
+        data
+    }
+
+    #[inline]
+    pub fn is_atmos(&self) -> bool {
+        matches!(self, Ac3Configuration::Eac3(dec3) if dec3.is_atmos())
+    }
+
+    // Layout of the first program, joint object coding keeps its bed layout
+    pub fn channel_labels(&self) -> Vec<AudioChannelLabel> {
+        match self {
+            Ac3Configuration::Ac3(dac3) => channel_labels(dac3.acmod, dac3.lfeon, 0),
+            Ac3Configuration::Eac3(dec3) => {
+                dec3.substreams.first().map_or_else(Vec::new, |substream| channel_labels(substream.acmod, substream.lfeon, substream.chan_loc))
+            }
+        }
+    }
+
+    #[inline]
+    pub fn channel_layout(&self) -> ChannelLayout {
+        ChannelLayout::from_labels(&self.channel_labels()).normalized()
+    }
+
+    // Packets may share a format description when only the rate differs
+    pub fn is_same_stream(&self, other: &Ac3Configuration) -> bool {
+        match (self, other) {
+            (Ac3Configuration::Ac3(a), Ac3Configuration::Ac3(b)) => {
+                Ac3SpecificBox {
+                    bit_rate_code: b.bit_rate_code,
+                    ..*a
+                } == *b
+            }
+            (Ac3Configuration::Eac3(a), Ac3Configuration::Eac3(b)) => {
+                a.substreams == b.substreams && a.complexity_index_type_a == b.complexity_index_type_a
+            }
+            _ => false,
+        }
+    }
+
+    pub fn extensions(&self) -> CFDictionary<CFString, CFType> {
+        let mut atoms = CFMutableDictionary::<CFString, CFType>::new();
+        let box_type = self.box_type().to_be_bytes();
+        atoms.add(&CFString::new(&String::from_utf8_lossy(&box_type)), &CFData::from_buffer(&self.to_bytes()).as_CFType());
+        let mut extensions = CFMutableDictionary::<CFString, CFType>::new();
+        unsafe {
+            extensions.add(
+                &CFString::wrap_under_get_rule(kCMFormatDescriptionExtension_SampleDescriptionExtensionAtoms),
+                &atoms.to_immutable().as_CFType(),
+            );
+        }
+        extensions.to_immutable()
+    }
+}
+
+impl SyncHeader for Ac3SyncFrame {
+    const HEADER_SIZE: usize = AC3_HEADER_SIZE;
+
+    #[inline]
+    fn parse_header(data: &[u8]) -> Option<Self> {
+        Self::parse(data).ok()
+    }
+
+    #[inline]
+    fn frame_length(&self) -> usize {
+        Ac3SyncFrame::frame_length(self)
+    }
+
+    fn is_boundary(&self, next: &[u8]) -> bool {
+        next.len() < 2 || u16::from_be_bytes([next[0], next[1]]) == AC3_SYNC_WORD
+    }
+
+    // Substreams of an access unit differ in their configuration
+    #[inline]
+    fn is_continued_by(&self, _next: &Self) -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Ac3Frame<'a> {
+    pub sync_frame: Ac3SyncFrame,
+    pub data: &'a [u8],
+    pub offset: usize,
+}
+
+pub struct Ac3FrameIter<'a>(SyncFrameIter<'a, Ac3SyncFrame>);
+
+impl<'a> Ac3FrameIter<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self(SyncFrameIter::new(data, 0))
+    }
+
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.0.position()
+    }
+}
+
+impl<'a> Iterator for Ac3FrameIter<'a> {
+    type Item = Ac3Frame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|frame| Ac3Frame {
+            sync_frame: frame.header,
+            data: frame.data,
+            offset: frame.offset,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Ac3PacketList {
+    pub configuration: Ac3Configuration,
+    pub sample_rate: u32,
+    pub frames_per_packet: u32,
+    pub data: Vec<u8>,
+    pub packet_descriptions: Vec<AudioStreamPacketDescription>,
+    pub consumed: usize,
+}
+
+impl Ac3PacketList {
+    // One packet per access unit, resume from `consumed`
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        let mut list: Option<Self> = None;
+        let mut frames_per_access_unit = 0;
+        let mut access_unit: Vec<Ac3Frame> = Vec::new();
+        let mut frames = Ac3FrameIter::new(data).peekable();
+        while let Some(frame) = frames.next() {
+            // Skip substreams of an access unit cut off at the start
+            if access_unit.is_empty() && !frame.sync_frame.starts_access_unit() {
+                continue;
+            }
+            access_unit.push(frame);
+            let complete = match frames.peek() {
+                Some(next) => next.sync_frame.starts_access_unit(),
+                // The last access unit may still be missing substreams
+                None => list.is_none() || access_unit.len() == frames_per_access_unit,
+            };
+            if !complete {
+                continue;
+            }
+            let sync_frames: Vec<Ac3SyncFrame> = access_unit.iter().map(|frame| frame.sync_frame).collect();
+            let configuration = Ac3Configuration::from_access_unit(&sync_frames)?;
+            if list.as_ref().is_some_and(|list| !list.configuration.is_same_stream(&configuration)) {
+                break;
+            }
+            if list.is_none() {
+                frames_per_access_unit = access_unit.len();
+            }
+            let list = list.get_or_insert_with(|| Self {
+                configuration,
+                sample_rate: sync_frames[0].sample_rate(),
+                frames_per_packet: sync_frames[0].frame_count(),
+                data: Vec::new(),
+                packet_descriptions: Vec::new(),
+                consumed: 0,
+            });
+            let end = access_unit[access_unit.len() - 1].offset + access_unit[access_unit.len() - 1].data.len();
+            list.packet_descriptions.push(AudioStreamPacketDescription {
+                mStartOffset: list.data.len() as i64,
+                mVariableFramesInPacket: 0,
+                mDataByteSize: access_unit.iter().map(|frame| frame.data.len() as u32).sum(),
+            });
+            for frame in access_unit.drain(..) {
+                list.data.extend_from_slice(frame.data);
+            }
+            list.consumed = end;
+        }
+        list.ok_or(kCMFormatDescriptionError_InvalidParameter)
+    }
+
+    #[inline]
+    pub fn packet_count(&self) -> usize {
+        self.packet_descriptions.len()
+    }
+
+    #[inline]
+    pub fn frame_count(&self) -> u64 {
+        self.packet_descriptions.len() as u64 * self.frames_per_packet as u64
+    }
+
+    pub fn stream_basic_description(&self) -> AudioStreamBasicDescription {
+        AudioStreamBasicDescription {
+            mSampleRate: self.sample_rate as f64,
+            mFormatID: self.configuration.format_id(),
+            mFormatFlags: 0,
+            mBytesPerPacket: 0,
+            mFramesPerPacket: self.frames_per_packet,
+            mBytesPerFrame: 0,
+            mChannelsPerFrame: self.configuration.channel_labels().len() as u32,
+            mBitsPerChannel: 0,
+            mReserved: 0,
+        }
+    }
+
+    pub fn new_format_description(&self) -> Result<CMAudioFormatDescription, OSStatus> {
+        CMAudioFormatDescription::new_with_channel_layout(
+            &self.stream_basic_description(),
+            Some(&self.configuration.channel_layout()),
+            &self.configuration.to_bytes(),
+            Some(&self.configuration.extensions()),
+        )
+    }
+
+    pub fn new_sample_buffer(
+        &self,
+        format_description: &CMAudioFormatDescription,
+        presentation_time_stamp: CMTime,
+    ) -> Result<CMSampleBuffer, OSStatus> {
+        sync_frame::new_sample_buffer(&self.data, &self.packet_descriptions, format_description, presentation_time_stamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 48 kbit/s, 48 kHz, stereo
+    const AC3_FRAME_HEADER: [u8; 7] = [0x0B, 0x77, 0x00, 0x00, 0x04, 0x40, 0x40];
+    const EAC3_FRAME_LENGTH: usize = 64;
+
+    // Six block 48 kHz substream without mixing or info metadata
+    fn eac3_frame(stream_type: u32, substream_id: u32, acmod: u32, lfeon: bool, channel_map: Option<u16>) -> Vec<u8> {
+        let mut writer = BitWriter::new();
+        writer.write_bits(AC3_SYNC_WORD as u32, 16);
+        writer.write_bits(stream_type, 2);
+        writer.write_bits(substream_id, 3);
+        writer.write_bits(EAC3_FRAME_LENGTH as u32 / 2 - 1, 11);
+        writer.write_bits(0, 2);
+        writer.write_bits(3, 2);
+        writer.write_bits(acmod, 3);
+        writer.write_bit(lfeon);
+        writer.write_bits(EAC3_MAX_BSID as u32, 5);
+        writer.write_bits(0, 6);
+        if let Some(channel_map) = channel_map {
+            writer.write_bit(true);
+            writer.write_bits(channel_map as u32, 16);
+        } else if stream_type == 1 {
+            writer.write_bit(false);
+        }
+        writer.write_bits(0, 3);
+        let mut frame = writer.into_bytes();
+        frame.resize(EAC3_FRAME_LENGTH, 0);
+        frame
+    }
+
+    // 5.1 independent substream extended to 7.1 with Lrs and Rrs
+    fn eac3_access_unit() -> Vec<u8> {
+        let mut data = eac3_frame(0, 0, 7, true, None);
+        data.extend_from_slice(&eac3_frame(1, 0, 2, false, Some(0x0200)));
+        data
+    }
+
+    #[test]
+    fn dac3_round_trip() {
+        let header = Ac3Header::parse(&AC3_FRAME_HEADER).unwrap();
+        assert_eq!(header.sample_rate(), 48000);
+        assert_eq!(header.bit_rate(), 48000);
+        assert_eq!(header.frame_length(), 192);
+        assert_eq!(header.channel_labels(), [kAudioChannelLabel_Left, kAudioChannelLabel_Right]);
+        let configuration = Ac3Configuration::from_access_unit(&[Ac3SyncFrame::Ac3(header)]).unwrap();
+        let bytes = configuration.to_bytes();
+        assert_eq!(bytes, [0x10, 0x10, 0x40]);
+        assert_eq!(Ac3Configuration::parse(kAC3SpecificBoxType, &bytes).unwrap(), configuration);
+        assert_eq!(&configuration.to_box()[4..8], b"dac3");
+    }
+
+    #[test]
+    fn dec3_round_trip() {
+        let data = eac3_access_unit();
+        let sync_frames: Vec<Ac3SyncFrame> = Ac3FrameIter::new(&data).map(|frame| frame.sync_frame).collect();
+        assert_eq!(sync_frames.len(), 2);
+        let configuration = Ac3Configuration::from_access_unit(&sync_frames).unwrap();
+        let dec3 = match &configuration {
+            Ac3Configuration::Eac3(dec3) => dec3,
+            _ => panic!("expected a dec3 configuration"),
+        };
+        assert_eq!(dec3.data_rate, 32);
+        assert_eq!(dec3.substreams.len(), 1);
+        assert_eq!(dec3.substreams[0].dependent_substream_count, 1);
+        assert_eq!(dec3.substreams[0].chan_loc, 0x80);
+        assert_eq!(configuration.channel_labels().len(), 8);
+        assert_eq!(Ac3Configuration::parse(kEAC3SpecificBoxType, &configuration.to_bytes()).unwrap(), configuration);
+
+        let atmos = Ac3Configuration::Eac3(Eac3SpecificBox {
+            complexity_index_type_a: Some(16),
+            ..dec3.clone()
+        });
+        assert_eq!(Ac3Configuration::parse(kEAC3SpecificBoxType, &atmos.to_bytes()).unwrap(), atmos);
+        assert!(atmos.is_atmos());
+    }
+
+    #[test]
+    fn leading_dependent_substreams_are_skipped() {
+        let mut data = eac3_frame(1, 0, 2, false, Some(0x0200));
+        data.extend_from_slice(&eac3_access_unit());
+        data.extend_from_slice(&eac3_access_unit());
+        let sync_frames: Vec<Ac3SyncFrame> = Ac3FrameIter::new(&data).map(|frame| frame.sync_frame).collect();
+        assert_eq!(Ac3Configuration::from_access_unit(&sync_frames[..3]).unwrap(), Ac3Configuration::from_access_unit(&sync_frames[1..3]).unwrap());
+        assert!(Ac3Configuration::from_access_unit(&sync_frames[..1]).is_err());
+
+        let list = Ac3PacketList::parse(&data).unwrap();
+        assert_eq!(list.packet_count(), 2);
+        assert_eq!(list.packet_descriptions[1].mStartOffset, 2 * EAC3_FRAME_LENGTH as i64);
+        assert_eq!(list.packet_descriptions[1].mDataByteSize, 2 * EAC3_FRAME_LENGTH as u32);
+        assert_eq!(list.data, data[EAC3_FRAME_LENGTH..]);
+        assert_eq!(list.consumed, data.len());
+        assert_eq!(list.frames_per_packet, 1536);
+    }
+
+    #[test]
+    fn substreams_chain_up_to_trailing_data() {
+        let mut data = eac3_access_unit();
+        data.extend_from_slice(&eac3_access_unit());
+        let end = data.len();
+        data.extend_from_slice(b"TAG0000000");
+        let list = Ac3PacketList::parse(&data).unwrap();
+        assert_eq!(list.packet_count(), 2);
+        assert_eq!(list.data, data[..end]);
+        assert_eq!(list.consumed, end);
+
+        // A lone sync frame needs a sync word after it
+        let mut data = AC3_FRAME_HEADER.to_vec();
+        data.resize(192, 0);
+        data.extend_from_slice(b"TAG0000000");
+        assert_eq!(Ac3FrameIter::new(&data).count(), 0);
+    }
+}
@@ -0,0 +1,742 @@
+use std::convert::TryFrom;
+
+use core_audio_types::{
+    base_types::{
+        kAudioChannelLayoutTag_AAC_3_0, kAudioChannelLayoutTag_AAC_4_0, kAudioChannelLayoutTag_AAC_5_0, kAudioChannelLayoutTag_AAC_5_1,
+        kAudioChannelLayoutTag_AAC_7_1, kAudioChannelLayoutTag_Mono, kAudioChannelLayoutTag_Stereo, kAudioFormatMPEG4AAC, AudioChannelLayoutTag,
+        AudioStreamBasicDescription, AudioStreamPacketDescription,
+    },
+    channel_layout::ChannelLayout,
+};
+use core_foundation::base::OSStatus;
+
+use crate::{
+    bit_reader::{BitReader, BitWriter},
+    format_description::{kCMFormatDescriptionError_InvalidParameter, CMAudioFormatDescription},
+    sample_buffer::CMSampleBuffer,
+    sync_frame::{self, SyncFrameIter, SyncHeader},
+    time::CMTime,
+};
+
+pub const ADTS_HEADER_SIZE: usize = 7;
+pub const AAC_FRAMES_PER_PACKET: u32 = 1024;
+pub const AAC_SAMPLING_FREQUENCIES: [u32; 13] = [96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350];
+
+const ADTS_SYNC_WORD: u32 = 0xFFF;
+const ADTS_MAX_FRAME_LENGTH: usize = 0x1FFF;
+const ADTS_BUFFER_FULLNESS_VBR: u16 = 0x7FF;
+const ADTS_CRC_SIZE: usize = 2;
+const CRC_PROTECTED_ELEMENT_BITS: usize = 192;
+
+const AAC_ELEMENT_SCE: u32 = 0;
+const AAC_ELEMENT_LFE: u32 = 3;
+
+const AUDIO_OBJECT_TYPE_ESCAPE: u32 = 31;
+const AUDIO_OBJECT_TYPE_SBR: u32 = 5;
+const AUDIO_OBJECT_TYPE_PS: u32 = 29;
+const SAMPLING_FREQUENCY_INDEX_ESCAPE: u32 = 15;
+
+const ES_DESCRIPTOR_TAG: u8 = 0x03;
+const DECODER_CONFIG_DESCRIPTOR_TAG: u8 = 0x04;
+const DECODER_SPECIFIC_INFO_TAG: u8 = 0x05;
+const SL_CONFIG_DESCRIPTOR_TAG: u8 = 0x06;
+const OBJECT_TYPE_INDICATION_MPEG4_AUDIO: u8 = 0x40;
+const STREAM_TYPE_AUDIO: u8 = 0x05;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AdtsCrcStatus {
+    Absent,
+    Valid,
+    Invalid,
+    // CRC of a channel pair needs full spectral decoding, and only the header CRC
+    // of a multi block frame is checked
+    Unverified,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct AdtsHeader {
+    pub is_mpeg2: bool,
+    pub protection_absent: bool,
+    pub audio_object_type: u8,
+    pub sampling_frequency_index: u8,
+    pub private_bit: bool,
+    pub channel_configuration: u8,
+    pub original_copy: bool,
+    pub home: bool,
+    pub copyright_id_bit: bool,
+    pub copyright_id_start: bool,
+    pub frame_length: usize,
+    pub buffer_fullness: u16,
+    pub raw_data_block_count: usize,
+}
+
+impl AdtsHeader {
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        let mut reader = BitReader::new(data);
+        let invalid = kCMFormatDescriptionError_InvalidParameter;
+        if reader.read_bits(12).ok_or(invalid)? != ADTS_SYNC_WORD {
+            return Err(invalid);
+        }
+        let is_mpeg2 = reader.read_bit().ok_or(invalid)?;
+        if reader.read_bits(2).ok_or(invalid)? != 0 {
+            return Err(invalid);
+        }
+        let protection_absent = reader.read_bit().ok_or(invalid)?;
+        let audio_object_type = reader.read_bits(2).ok_or(invalid)? as u8 + 1;
+        let sampling_frequency_index = reader.read_bits(4).ok_or(invalid)? as u8;
+        let private_bit = reader.read_bit().ok_or(invalid)?;
+        let channel_configuration = reader.read_bits(3).ok_or(invalid)? as u8;
+        let original_copy = reader.read_bit().ok_or(invalid)?;
+        let home = reader.read_bit().ok_or(invalid)?;
+        let copyright_id_bit = reader.read_bit().ok_or(invalid)?;
+        let copyright_id_start = reader.read_bit().ok_or(invalid)?;
+        let frame_length = reader.read_bits(13).ok_or(invalid)? as usize;
+        let buffer_fullness = reader.read_bits(11).ok_or(invalid)? as u16;
+        let raw_data_block_count = reader.read_bits(2).ok_or(invalid)? as usize + 1;
+        let header = Self {
+            is_mpeg2,
+            protection_absent,
+            audio_object_type,
+            sampling_frequency_index,
+            private_bit,
+            channel_configuration,
+            original_copy,
+            home,
+            copyright_id_bit,
+            copyright_id_start,
+            frame_length,
+            buffer_fullness,
+            raw_data_block_count,
+        };
+        if sampling_frequency_index as usize >= AAC_SAMPLING_FREQUENCIES.len() || frame_length < header.header_length() {
+            return Err(invalid);
+        }
+        Ok(header)
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        AAC_SAMPLING_FREQUENCIES[self.sampling_frequency_index as usize]
+    }
+
+    #[inline]
+    pub fn channel_count(&self) -> u32 {
+        match self.channel_configuration {
+            7 => 8,
+            configuration => configuration as u32,
+        }
+    }
+
+    // Includes block positions and header CRC when protected
+    #[inline]
+    pub fn header_length(&self) -> usize {
+        if self.protection_absent {
+            ADTS_HEADER_SIZE
+        } else if self.raw_data_block_count > 1 {
+            ADTS_HEADER_SIZE + (self.raw_data_block_count - 1) * 2 + ADTS_CRC_SIZE
+        } else {
+            ADTS_HEADER_SIZE + ADTS_CRC_SIZE
+        }
+    }
+
+    #[inline]
+    pub fn frame_count(&self) -> u32 {
+        self.raw_data_block_count as u32 * AAC_FRAMES_PER_PACKET
+    }
+
+    // Frames of one stream share every field of the fixed header
+    #[inline]
+    pub fn is_same_stream(&self, other: &AdtsHeader) -> bool {
+        self.is_mpeg2 == other.is_mpeg2 &&
+            self.protection_absent == other.protection_absent &&
+            self.audio_object_type == other.audio_object_type &&
+            self.sampling_frequency_index == other.sampling_frequency_index &&
+            self.channel_configuration == other.channel_configuration
+    }
+
+    pub fn audio_specific_config(&self) -> Vec<u8> {
+        let mut writer = BitWriter::new();
+        writer.write_bits(self.audio_object_type as u32, 5);
+        writer.write_bits(self.sampling_frequency_index as u32, 4);
+        writer.write_bits(self.channel_configuration as u32, 4);
+        // GASpecificConfig with 1024 sample frames, no core coder and no extension
+        writer.write_bits(0, 3);
+        writer.into_bytes()
+    }
+
+    #[inline]
+    pub fn magic_cookie(&self) -> Vec<u8> {
+        audio_specific_config_to_magic_cookie(&self.audio_specific_config())
+    }
+
+    pub fn stream_basic_description(&self) -> Result<AudioStreamBasicDescription, OSStatus> {
+        // Configuration 0 defers to an in-band program config element
+        if self.channel_configuration == 0 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        Ok(AudioStreamBasicDescription {
+            mSampleRate: self.sample_rate() as f64,
+            mFormatID: kAudioFormatMPEG4AAC,
+            mFormatFlags: self.audio_object_type as u32,
+            mBytesPerPacket: 0,
+            mFramesPerPacket: AAC_FRAMES_PER_PACKET,
+            mBytesPerFrame: 0,
+            mChannelsPerFrame: self.channel_count(),
+            mBitsPerChannel: 0,
+            mReserved: 0,
+        })
+    }
+
+    pub fn channel_layout(&self) -> Option<ChannelLayout> {
+        channel_layout_tag_for_configuration(self.channel_configuration).map(ChannelLayout::Tag)
+    }
+
+    pub fn write(&self, payload_length: usize) -> Result<Vec<u8>, OSStatus> {
+        // Only unprotected single block frames are written
+        if !self.protection_absent || self.raw_data_block_count != 1 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let frame_length = ADTS_HEADER_SIZE + payload_length;
+        if frame_length > ADTS_MAX_FRAME_LENGTH || self.audio_object_type == 0 || self.audio_object_type > 4 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let mut writer = BitWriter::new();
+        writer.write_bits(ADTS_SYNC_WORD, 12);
+        writer.write_bit(self.is_mpeg2);
+        writer.write_bits(0, 2);
+        writer.write_bit(true);
+        writer.write_bits(self.audio_object_type as u32 - 1, 2);
+        writer.write_bits(self.sampling_frequency_index as u32, 4);
+        writer.write_bit(self.private_bit);
+        writer.write_bits(self.channel_configuration as u32, 3);
+        writer.write_bit(self.original_copy);
+        writer.write_bit(self.home);
+        writer.write_bit(self.copyright_id_bit);
+        writer.write_bit(self.copyright_id_start);
+        writer.write_bits(frame_length as u32, 13);
+        writer.write_bits(self.buffer_fullness as u32, 11);
+        writer.write_bits(0, 2);
+        Ok(writer.into_bytes())
+    }
+}
+
+fn channel_layout_tag_for_configuration(channel_configuration: u8) -> Option<AudioChannelLayoutTag> {
+    match channel_configuration {
+        1 => Some(kAudioChannelLayoutTag_Mono),
+        2 => Some(kAudioChannelLayoutTag_Stereo),
+        3 => Some(kAudioChannelLayoutTag_AAC_3_0),
+        4 => Some(kAudioChannelLayoutTag_AAC_4_0),
+        5 => Some(kAudioChannelLayoutTag_AAC_5_0),
+        6 => Some(kAudioChannelLayoutTag_AAC_5_1),
+        7 => Some(kAudioChannelLayoutTag_AAC_7_1),
+        _ => None,
+    }
+}
+
+fn crc16(data: &[u8], start_bit: usize, bit_count: usize, padded_bit_count: usize, mut crc: u16) -> u16 {
+    for i in 0..padded_bit_count {
+        let position = start_bit + i;
+        let bit = if i < bit_count && position < data.len() * 8 {
+            (data[position / 8] >> (7 - position % 8)) & 1
+        } else {
+            0
+        };
+        let feedback = ((crc >> 15) as u8 ^ bit) & 1;
+        crc <<= 1;
+        if feedback != 0 {
+            crc ^= 0x8005;
+        }
+    }
+    crc
+}
+
+impl SyncHeader for AdtsHeader {
+    const HEADER_SIZE: usize = ADTS_HEADER_SIZE;
+
+    #[inline]
+    fn parse_header(data: &[u8]) -> Option<Self> {
+        Self::parse(data).ok()
+    }
+
+    #[inline]
+    fn frame_length(&self) -> usize {
+        self.frame_length
+    }
+
+    // Only the sync word of the next frame is checked
+    fn is_boundary(&self, next: &[u8]) -> bool {
+        next.len() < 2 || (next[0] == 0xFF && next[1] & 0xF6 == 0xF0)
+    }
+
+    #[inline]
+    fn is_continued_by(&self, next: &Self) -> bool {
+        self.is_same_stream(next)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AdtsFrame<'a> {
+    pub header: AdtsHeader,
+    pub data: &'a [u8],
+    pub offset: usize,
+}
+
+impl<'a> AdtsFrame<'a> {
+    pub fn raw_data_blocks(&self) -> Result<Vec<&'a [u8]>, OSStatus> {
+        let header_length = self.header.header_length();
+        let payload = &self.data[header_length..];
+        // A single block is covered by the header CRC
+        if self.header.raw_data_block_count == 1 {
+            return Ok(vec![payload]);
+        }
+        if self.header.protection_absent {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let mut positions = vec![0];
+        for i in 0..self.header.raw_data_block_count - 1 {
+            let offset = ADTS_HEADER_SIZE + i * 2;
+            positions.push(u16::from_be_bytes([self.data[offset], self.data[offset + 1]]) as usize);
+        }
+        positions.push(payload.len());
+        // Every block is followed by its own CRC
+        positions
+            .windows(2)
+            .map(|window| {
+                let end = window[1].checked_sub(ADTS_CRC_SIZE).filter(|end| *end >= window[0] && window[1] <= payload.len());
+                end.map(|end| &payload[window[0]..end]).ok_or(kCMFormatDescriptionError_InvalidParameter)
+            })
+            .collect()
+    }
+
+    pub fn crc_status(&self) -> AdtsCrcStatus {
+        if self.header.protection_absent {
+            return AdtsCrcStatus::Absent;
+        }
+        let header = &self.header;
+        let header_crc_offset = header.header_length() - ADTS_CRC_SIZE;
+        let stored = u16::from_be_bytes([self.data[header_crc_offset], self.data[header_crc_offset + 1]]);
+        let header_bits = header_crc_offset * 8;
+        let crc = crc16(self.data, 0, header_bits, header_bits, 0xFFFF);
+        if header.raw_data_block_count > 1 {
+            return if crc == stored {
+                AdtsCrcStatus::Unverified
+            } else {
+                AdtsCrcStatus::Invalid
+            };
+        }
+        if header.channel_configuration != 1 {
+            return AdtsCrcStatus::Unverified;
+        }
+        let block = match self.raw_data_blocks() {
+            Ok(blocks) => blocks[0],
+            Err(_) => return AdtsCrcStatus::Invalid,
+        };
+        let element = BitReader::new(block).read_bits(3);
+        if element != Some(AAC_ELEMENT_SCE) && element != Some(AAC_ELEMENT_LFE) {
+            return AdtsCrcStatus::Unverified;
+        }
+        // SCE and LFE protect 192 bits after the element id, zero padded
+        let available = (block.len() * 8).saturating_sub(3);
+        let crc = crc16(block, 3, available.min(CRC_PROTECTED_ELEMENT_BITS), CRC_PROTECTED_ELEMENT_BITS, crc);
+        if crc == stored {
+            AdtsCrcStatus::Valid
+        } else {
+            AdtsCrcStatus::Invalid
+        }
+    }
+}
+
+pub struct AdtsFrameIter<'a>(SyncFrameIter<'a, AdtsHeader>);
+
+impl<'a> AdtsFrameIter<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self(SyncFrameIter::new(data, 0))
+    }
+
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.0.position()
+    }
+}
+
+impl<'a> Iterator for AdtsFrameIter<'a> {
+    type Item = AdtsFrame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|frame| AdtsFrame {
+            header: frame.header,
+            data: frame.data,
+            offset: frame.offset,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AdtsPacketList {
+    pub header: AdtsHeader,
+    pub data: Vec<u8>,
+    pub packet_descriptions: Vec<AudioStreamPacketDescription>,
+    pub invalid_crc_count: usize,
+    pub skipped_frame_count: usize,
+    pub consumed: usize,
+}
+
+impl AdtsPacketList {
+    // Splits the frames up to a configuration change into their raw data blocks,
+    // resume from `consumed`
+    pub fn parse(data: &[u8], drop_invalid_crc: bool) -> Result<Self, OSStatus> {
+        let mut frames = AdtsFrameIter::new(data);
+        let first = frames.next().ok_or(kCMFormatDescriptionError_InvalidParameter)?;
+        let mut list = Self {
+            header: first.header,
+            data: Vec::new(),
+            packet_descriptions: Vec::new(),
+            invalid_crc_count: 0,
+            skipped_frame_count: 0,
+            consumed: 0,
+        };
+        let mut frame = Some(first);
+        while let Some(current) = frame {
+            if !current.header.is_same_stream(&list.header) {
+                break;
+            }
+            list.consumed = current.offset + current.data.len();
+            if current.crc_status() == AdtsCrcStatus::Invalid {
+                list.invalid_crc_count += 1;
+                if drop_invalid_crc {
+                    frame = frames.next();
+                    continue;
+                }
+            }
+            let blocks = match current.raw_data_blocks() {
+                Ok(blocks) => blocks,
+                // Unprotected frames carry no block positions to split at
+                Err(_) if current.header.protection_absent => {
+                    list.skipped_frame_count += 1;
+                    frame = frames.next();
+                    continue;
+                }
+                Err(status) => return Err(status),
+            };
+            for block in blocks {
+                list.packet_descriptions.push(AudioStreamPacketDescription {
+                    mStartOffset: list.data.len() as i64,
+                    mVariableFramesInPacket: 0,
+                    mDataByteSize: block.len() as u32,
+                });
+                list.data.extend_from_slice(block);
+            }
+            frame = frames.next();
+        }
+        Ok(list)
+    }
+
+    #[inline]
+    pub fn packet_count(&self) -> usize {
+        self.packet_descriptions.len()
+    }
+
+    #[inline]
+    pub fn frame_count(&self) -> u64 {
+        self.packet_descriptions.len() as u64 * AAC_FRAMES_PER_PACKET as u64
+    }
+
+    #[inline]
+    pub fn stream_basic_description(&self) -> Result<AudioStreamBasicDescription, OSStatus> {
+        self.header.stream_basic_description()
+    }
+
+    #[inline]
+    pub fn magic_cookie(&self) -> Vec<u8> {
+        self.header.magic_cookie()
+    }
+
+    pub fn new_format_description(&self) -> Result<CMAudioFormatDescription, OSStatus> {
+        let asbd = self.stream_basic_description()?;
+        CMAudioFormatDescription::new_with_channel_layout(&asbd, self.header.channel_layout().as_ref(), &self.magic_cookie(), None)
+    }
+
+    pub fn new_sample_buffer(
+        &self,
+        format_description: &CMAudioFormatDescription,
+        presentation_time_stamp: CMTime,
+    ) -> Result<CMSampleBuffer, OSStatus> {
+        sync_frame::new_sample_buffer(&self.data, &self.packet_descriptions, format_description, presentation_time_stamp)
+    }
+}
+
+fn write_descriptor(tag: u8, payload: &[u8], output: &mut Vec<u8>) {
+    output.push(tag);
+    // Four byte size fields, as written by the system encoders
+    let size = payload.len() as u32;
+    output.extend_from_slice(&[
+        0x80 | ((size >> 21) & 0x7F) as u8,
+        0x80 | ((size >> 14) & 0x7F) as u8,
+        0x80 | ((size >> 7) & 0x7F) as u8,
+        (size & 0x7F) as u8,
+    ]);
+    output.extend_from_slice(payload);
+}
+
+fn read_descriptor(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let mut size = 0usize;
+    let mut offset = 1;
+    loop {
+        let byte = *data.get(offset)?;
+        offset += 1;
+        size = (size << 7) | (byte & 0x7F) as usize;
+        if byte & 0x80 == 0 || offset > 4 {
+            break;
+        }
+    }
+    let payload = data.get(offset..offset + size)?;
+    Some((tag, payload, &data[offset + size..]))
+}
+
+pub fn audio_specific_config_to_magic_cookie(audio_specific_config: &[u8]) -> Vec<u8> {
+    let mut decoder_specific_info = Vec::new();
+    write_descriptor(DECODER_SPECIFIC_INFO_TAG, audio_specific_config, &mut decoder_specific_info);
+    let mut decoder_config = vec![OBJECT_TYPE_INDICATION_MPEG4_AUDIO, (STREAM_TYPE_AUDIO << 2) | 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    decoder_config.extend_from_slice(&decoder_specific_info);
+    let mut es = vec![0, 0, 0];
+    write_descriptor(DECODER_CONFIG_DESCRIPTOR_TAG, &decoder_config, &mut es);
+    write_descriptor(SL_CONFIG_DESCRIPTOR_TAG, &[2], &mut es);
+    let mut cookie = Vec::new();
+    write_descriptor(ES_DESCRIPTOR_TAG, &es, &mut cookie);
+    cookie
+}
+
+// Accepts an ES descriptor, an esds atom or a bare AudioSpecificConfig
+pub fn magic_cookie_to_audio_specific_config(magic_cookie: &[u8]) -> Option<&[u8]> {
+    let cookie = if magic_cookie.len() >= 12 && &magic_cookie[4..8] == b"esds" {
+        &magic_cookie[12..]
+    } else {
+        magic_cookie
+    };
+    if cookie.first() != Some(&ES_DESCRIPTOR_TAG) {
+        return Some(cookie);
+    }
+    let (_, es, _) = read_descriptor(cookie)?;
+    let flags = *es.get(2)?;
+    let mut offset = 3;
+    if flags & 0x80 != 0 {
+        offset += 2;
+    }
+    if flags & 0x40 != 0 {
+        offset += 1 + *es.get(offset)? as usize;
+    }
+    if flags & 0x20 != 0 {
+        offset += 2;
+    }
+    let mut descriptors = es.get(offset..)?;
+    while let Some((tag, payload, rest)) = read_descriptor(descriptors) {
+        if tag == DECODER_CONFIG_DESCRIPTOR_TAG {
+            let (tag, info, _) = read_descriptor(payload.get(13..)?)?;
+            return if tag == DECODER_SPECIFIC_INFO_TAG {
+                Some(info)
+            } else {
+                None
+            };
+        }
+        descriptors = rest;
+    }
+    None
+}
+
+pub struct AdtsWriter {
+    header: AdtsHeader,
+}
+
+impl AdtsWriter {
+    pub fn new(magic_cookie: &[u8]) -> Result<Self, OSStatus> {
+        let invalid = kCMFormatDescriptionError_InvalidParameter;
+        let config = magic_cookie_to_audio_specific_config(magic_cookie).ok_or(invalid)?;
+        let mut reader = BitReader::new(config);
+        let mut audio_object_type = read_audio_object_type(&mut reader).ok_or(invalid)?;
+        let sampling_frequency_index = reader.read_bits(4).ok_or(invalid)?;
+        if sampling_frequency_index == SAMPLING_FREQUENCY_INDEX_ESCAPE {
+            return Err(invalid);
+        }
+        let channel_configuration = reader.read_bits(4).ok_or(invalid)?;
+        // Explicit SBR/PS signalling carries the core object type
+        if audio_object_type == AUDIO_OBJECT_TYPE_SBR || audio_object_type == AUDIO_OBJECT_TYPE_PS {
+            let extension_index = reader.read_bits(4).ok_or(invalid)?;
+            if extension_index == SAMPLING_FREQUENCY_INDEX_ESCAPE {
+                reader.skip_bits(24).ok_or(invalid)?;
+            }
+            audio_object_type = read_audio_object_type(&mut reader).ok_or(invalid)?;
+        }
+        if !(1..=4).contains(&audio_object_type) || sampling_frequency_index as usize >= AAC_SAMPLING_FREQUENCIES.len() || channel_configuration > 7 {
+            return Err(invalid);
+        }
+        Ok(Self {
+            header: AdtsHeader {
+                is_mpeg2: false,
+                protection_absent: true,
+                audio_object_type: audio_object_type as u8,
+                sampling_frequency_index: sampling_frequency_index as u8,
+                private_bit: false,
+                channel_configuration: channel_configuration as u8,
+                original_copy: false,
+                home: false,
+                copyright_id_bit: false,
+                copyright_id_start: false,
+                frame_length: 0,
+                buffer_fullness: ADTS_BUFFER_FULLNESS_VBR,
+                raw_data_block_count: 1,
+            },
+        })
+    }
+
+    #[inline]
+    pub fn header(&self) -> &AdtsHeader {
+        &self.header
+    }
+
+    pub fn write_packet(&self, packet: &[u8], output: &mut Vec<u8>) -> Result<(), OSStatus> {
+        output.extend_from_slice(&self.header.write(packet.len())?);
+        output.extend_from_slice(packet);
+        Ok(())
+    }
+
+    pub fn write_packets(&self, data: &[u8], packet_descriptions: &[AudioStreamPacketDescription]) -> Result<Vec<u8>, OSStatus> {
+        let mut output = Vec::with_capacity(data.len() + packet_descriptions.len() * ADTS_HEADER_SIZE);
+        for desc in packet_descriptions {
+            let start = usize::try_from(desc.mStartOffset).map_err(|_| kCMFormatDescriptionError_InvalidParameter)?;
+            let packet = data.get(start..start + desc.mDataByteSize as usize).ok_or(kCMFormatDescriptionError_InvalidParameter)?;
+            self.write_packet(packet, &mut output)?;
+        }
+        Ok(output)
+    }
+}
+
+fn read_audio_object_type(reader: &mut BitReader) -> Option<u32> {
+    let audio_object_type = reader.read_bits(5)?;
+    if audio_object_type == AUDIO_OBJECT_TYPE_ESCAPE {
+        Some(32 + reader.read_bits(6)?)
+    } else {
+        Some(audio_object_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // AAC LC, 44.1 kHz, stereo, unprotected, 4 payload bytes
+    const STEREO_HEADER: [u8; 7] = [0xFF, 0xF1, 0x50, 0x80, 0x01, 0x7F, 0xFC];
+
+    fn stereo_frame(payload: &[u8; 4]) -> Vec<u8> {
+        let mut frame = STEREO_HEADER.to_vec();
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[test]
+    fn header_fields_and_frame_length() {
+        let header = AdtsHeader::parse(&STEREO_HEADER).unwrap();
+        assert!(header.protection_absent);
+        assert_eq!(header.audio_object_type, 2);
+        assert_eq!(header.sample_rate(), 44100);
+        assert_eq!(header.channel_count(), 2);
+        assert_eq!(header.frame_length, 11);
+        assert_eq!(header.header_length(), ADTS_HEADER_SIZE);
+        assert_eq!(header.frame_count(), AAC_FRAMES_PER_PACKET);
+        assert_eq!(header.write(4).unwrap(), STEREO_HEADER);
+        assert_eq!(header.audio_specific_config(), [0x12, 0x10]);
+
+        // Protected multi-block headers add the block positions and the header CRC
+        let protected = AdtsHeader {
+            protection_absent: false,
+            raw_data_block_count: 3,
+            ..header
+        };
+        assert_eq!(protected.header_length(), ADTS_HEADER_SIZE + 2 * 2 + ADTS_CRC_SIZE);
+        assert_eq!(protected.frame_count(), 3 * AAC_FRAMES_PER_PACKET);
+
+        // Frame length shorter than the header
+        assert!(AdtsHeader::parse(&[0xFF, 0xF1, 0x50, 0x80, 0x00, 0xDF, 0xFC]).is_err());
+    }
+
+    #[test]
+    fn packet_list_splits_frames() {
+        let mut data = vec![0x00, 0x01];
+        data.extend_from_slice(&stereo_frame(&[1, 2, 3, 4]));
+        data.extend_from_slice(&stereo_frame(&[5, 6, 7, 8]));
+        // Partial trailing frame
+        data.extend_from_slice(&STEREO_HEADER[..5]);
+        let list = AdtsPacketList::parse(&data, false).unwrap();
+        assert_eq!(list.packet_count(), 2);
+        assert_eq!(list.data, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(list.packet_descriptions[1].mStartOffset, 4);
+        assert_eq!(list.packet_descriptions[1].mDataByteSize, 4);
+        assert_eq!(list.consumed, 24);
+        assert_eq!(list.frame_count(), 2 * AAC_FRAMES_PER_PACKET as u64);
+    }
+
+    #[test]
+    fn writer_round_trips_magic_cookie() {
+        let header = AdtsHeader::parse(&STEREO_HEADER).unwrap();
+        let writer = AdtsWriter::new(&header.magic_cookie()).unwrap();
+        let mut output = Vec::new();
+        writer.write_packet(&[1, 2, 3, 4], &mut output).unwrap();
+        assert_eq!(output, stereo_frame(&[1, 2, 3, 4]));
+
+        // HE-AAC with explicit SBR signalling writes the core object type
+        let writer = AdtsWriter::new(&[0x2B, 0x11, 0x88]).unwrap();
+        assert_eq!(writer.header().audio_object_type, 2);
+        assert_eq!(writer.header().sampling_frequency_index, 6);
+        assert_eq!(writer.header().channel_configuration, 2);
+
+        // Sampling frequency indices past the table are rejected
+        assert!(AdtsWriter::new(&[0x2E, 0x91, 0x88]).is_err());
+        assert!(AdtsWriter::new(&[0x16, 0x90]).is_err());
+    }
+
+    #[test]
+    fn unprotected_multi_block_frames_are_skipped() {
+        // Same stream with two raw data blocks and no positions
+        let mut data = vec![0xFF, 0xF1, 0x50, 0x80, 0x01, 0x7F, 0xFD, 9, 9, 9, 9];
+        data.extend_from_slice(&stereo_frame(&[1, 2, 3, 4]));
+        let list = AdtsPacketList::parse(&data, false).unwrap();
+        assert_eq!(list.skipped_frame_count, 1);
+        assert_eq!(list.packet_count(), 1);
+        assert_eq!(list.data, [1, 2, 3, 4]);
+        assert_eq!(list.consumed, data.len());
+    }
+
+    #[test]
+    fn single_channel_element_crc() {
+        let mut data = vec![0xFF, 0xF0, 0x50, 0x40, 0x02, 0x7F, 0xFC, 0xFE, 0x4D];
+        data.extend_from_slice(&[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99]);
+        let frame = AdtsFrameIter::new(&data).next().unwrap();
+        assert_eq!(frame.header.header_length(), 9);
+        assert_eq!(frame.crc_status(), AdtsCrcStatus::Valid);
+        assert_eq!(frame.raw_data_blocks().unwrap(), [&data[9..]]);
+
+        data[12] ^= 0x01;
+        let frame = AdtsFrameIter::new(&data).next().unwrap();
+        assert_eq!(frame.crc_status(), AdtsCrcStatus::Invalid);
+        let list = AdtsPacketList::parse(&data, true).unwrap();
+        assert_eq!(list.invalid_crc_count, 1);
+        assert_eq!(list.packet_count(), 0);
+    }
+
+    #[test]
+    fn multi_block_header_crc() {
+        // Two blocks of three bytes, each followed by its own CRC
+        let data = [0xFF, 0xF0, 0x50, 0x80, 0x02, 0xBF, 0xFD, 0x00, 0x05, 0xA5, 0x56, 1, 2, 3, 0, 0, 4, 5, 6, 0, 0];
+        let frame = AdtsFrameIter::new(&data).next().unwrap();
+        assert_eq!(frame.header.raw_data_block_count, 2);
+        assert_eq!(frame.header.header_length(), 11);
+        assert_eq!(frame.crc_status(), AdtsCrcStatus::Unverified);
+        assert_eq!(frame.raw_data_blocks().unwrap(), [&[1, 2, 3][..], &[4, 5, 6][..]]);
+
+        let mut corrupted = data;
+        corrupted[2] ^= 0x02;
+        let frame = AdtsFrameIter::new(&corrupted).next().unwrap();
+        assert_eq!(frame.crc_status(), AdtsCrcStatus::Invalid);
+    }
+}
@@ -0,0 +1,217 @@
+use core_audio_types::{
+    base_types::{AudioChannelLayout, AudioStreamBasicDescription},
+    channel_layout::channel_layouts_equivalent,
+};
+use core_foundation::{
+    base::{CFType, TCFType},
+    dictionary::CFDictionary,
+    string::CFString,
+};
+
+use crate::format_description::{
+    kCMAudioFormatDescriptionMask_ChannelLayout, kCMAudioFormatDescriptionMask_Extensions, kCMAudioFormatDescriptionMask_MagicCookie,
+    kCMAudioFormatDescriptionMask_StreamBasicDescription, CMAudioFormatDescription, CMAudioFormatDescriptionMask, TCMFormatDescription,
+};
+
+#[derive(Clone, Default)]
+pub struct AudioFormatDescriptionParts<'a> {
+    pub stream_basic_description: Option<&'a AudioStreamBasicDescription>,
+    pub magic_cookie: Option<&'a [u8]>,
+    // Private so the size handed to channel_layouts_equivalent is always trusted
+    channel_layout: Option<(&'a AudioChannelLayout, usize)>,
+    pub extensions: Option<CFDictionary<CFString, CFType>>,
+}
+
+impl<'a> AudioFormatDescriptionParts<'a> {
+    #[inline]
+    pub fn new(stream_basic_description: Option<&'a AudioStreamBasicDescription>) -> Self {
+        Self {
+            stream_basic_description,
+            ..Default::default()
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `layout_size` must not exceed the number of readable bytes starting at
+    /// `channel_layout`, which `compare` reads up to.
+    #[inline]
+    pub unsafe fn with_channel_layout(mut self, channel_layout: &'a AudioChannelLayout, layout_size: usize) -> Self {
+        self.channel_layout = Some((channel_layout, layout_size));
+        self
+    }
+
+    #[inline]
+    pub fn channel_layout(&self) -> Option<(&'a AudioChannelLayout, usize)> {
+        self.channel_layout
+    }
+
+    #[inline]
+    pub fn from_format_description(desc: &'a CMAudioFormatDescription) -> Self {
+        Self {
+            stream_basic_description: desc.get_stream_basic_description(),
+            magic_cookie: desc.get_magic_cookie(),
+            channel_layout: desc.get_channel_layout(),
+            extensions: desc.as_buffer().get_extensions(),
+        }
+    }
+
+    pub fn compare(&self, other: &Self, equality_mask: CMAudioFormatDescriptionMask) -> (bool, CMAudioFormatDescriptionMask) {
+        let mut mask = 0;
+        if equality_mask & kCMAudioFormatDescriptionMask_StreamBasicDescription != 0 &&
+            stream_basic_descriptions_equal(self.stream_basic_description, other.stream_basic_description)
+        {
+            mask |= kCMAudioFormatDescriptionMask_StreamBasicDescription;
+        }
+        if equality_mask & kCMAudioFormatDescriptionMask_MagicCookie != 0 && magic_cookies_equal(self.magic_cookie, other.magic_cookie) {
+            mask |= kCMAudioFormatDescriptionMask_MagicCookie;
+        }
+        if equality_mask & kCMAudioFormatDescriptionMask_ChannelLayout != 0 && channel_layouts_equal(self.channel_layout, other.channel_layout) {
+            mask |= kCMAudioFormatDescriptionMask_ChannelLayout;
+        }
+        if equality_mask & kCMAudioFormatDescriptionMask_Extensions != 0 && extensions_equal(self.extensions.as_ref(), other.extensions.as_ref()) {
+            mask |= kCMAudioFormatDescriptionMask_Extensions;
+        }
+        (mask == equality_mask, mask)
+    }
+}
+
+fn stream_basic_descriptions_equal(asbd: Option<&AudioStreamBasicDescription>, other: Option<&AudioStreamBasicDescription>) -> bool {
+    match (asbd, other) {
+        (Some(asbd), Some(other)) => {
+            // mReserved is padding and never takes part in the comparison
+            asbd.mSampleRate == other.mSampleRate &&
+                asbd.mFormatID == other.mFormatID &&
+                asbd.mFormatFlags == other.mFormatFlags &&
+                asbd.mBytesPerPacket == other.mBytesPerPacket &&
+                asbd.mFramesPerPacket == other.mFramesPerPacket &&
+                asbd.mBytesPerFrame == other.mBytesPerFrame &&
+                asbd.mChannelsPerFrame == other.mChannelsPerFrame &&
+                asbd.mBitsPerChannel == other.mBitsPerChannel
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn magic_cookies_equal(cookie: Option<&[u8]>, other: Option<&[u8]>) -> bool {
+    cookie.unwrap_or_default() == other.unwrap_or_default()
+}
+
+fn channel_layouts_equal(layout: Option<(&AudioChannelLayout, usize)>, other: Option<(&AudioChannelLayout, usize)>) -> bool {
+    match (layout, other) {
+        (Some((layout, layout_size)), Some((other, other_size))) => unsafe { channel_layouts_equivalent(layout, layout_size, other, other_size) },
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn extensions_equal(extensions: Option<&CFDictionary<CFString, CFType>>, other: Option<&CFDictionary<CFString, CFType>>) -> bool {
+    match (extensions, other) {
+        (Some(extensions), Some(other)) => extensions.as_CFType() == other.as_CFType(),
+        (Some(extensions), None) | (None, Some(extensions)) => extensions.is_empty(),
+        (None, None) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core_audio_types::{
+        base_types::{
+            kAudioChannelBit_Left, kAudioChannelBit_Right, kAudioChannelLabel_Left, kAudioChannelLabel_Right, kAudioChannelLayoutTag_Stereo,
+            kAudioChannelLayoutTag_StereoHeadphones, kAudioFormatFlagsNativeFloatPacked, kAudioFormatLinearPCM,
+        },
+        channel_layout::ChannelLayout,
+    };
+
+    use super::*;
+    use crate::format_description::kCMAudioFormatDescriptionMask_All;
+
+    fn stereo_float() -> AudioStreamBasicDescription {
+        AudioStreamBasicDescription {
+            mSampleRate: 48000.0,
+            mFormatID: kAudioFormatLinearPCM,
+            mFormatFlags: kAudioFormatFlagsNativeFloatPacked,
+            mBytesPerPacket: 8,
+            mFramesPerPacket: 1,
+            mBytesPerFrame: 8,
+            mChannelsPerFrame: 2,
+            mBitsPerChannel: 32,
+            mReserved: 0,
+        }
+    }
+
+    fn layouts_equal(layout: &ChannelLayout, other: &ChannelLayout) -> bool {
+        let asbd = stereo_float();
+        layout.with_audio_channel_layout(|layout, layout_size| {
+            other.with_audio_channel_layout(|other, other_size| {
+                let parts = unsafe { AudioFormatDescriptionParts::new(Some(&asbd)).with_channel_layout(layout, layout_size) };
+                let other_parts = unsafe { AudioFormatDescriptionParts::new(Some(&asbd)).with_channel_layout(other, other_size) };
+                parts.compare(&other_parts, kCMAudioFormatDescriptionMask_ChannelLayout).0
+            })
+        })
+    }
+
+    #[test]
+    fn stream_basic_descriptions_ignore_reserved() {
+        let asbd = stereo_float();
+        let reserved = AudioStreamBasicDescription {
+            mReserved: 1,
+            ..asbd
+        };
+        let parts = AudioFormatDescriptionParts::new(Some(&asbd));
+        assert_eq!(
+            parts.compare(&AudioFormatDescriptionParts::new(Some(&reserved)), kCMAudioFormatDescriptionMask_All),
+            (true, kCMAudioFormatDescriptionMask_All)
+        );
+
+        let resampled = AudioStreamBasicDescription {
+            mSampleRate: 44100.0,
+            ..asbd
+        };
+        let mask = kCMAudioFormatDescriptionMask_All & !kCMAudioFormatDescriptionMask_StreamBasicDescription;
+        assert_eq!(parts.compare(&AudioFormatDescriptionParts::new(Some(&resampled)), kCMAudioFormatDescriptionMask_All), (false, mask));
+        assert_eq!(parts.compare(&AudioFormatDescriptionParts::new(None), kCMAudioFormatDescriptionMask_All), (false, mask));
+        // Parts outside the equality mask are never compared
+        assert_eq!(parts.compare(&AudioFormatDescriptionParts::new(Some(&resampled)), mask), (true, mask));
+    }
+
+    #[test]
+    fn missing_magic_cookie_equals_empty_cookie() {
+        let asbd = stereo_float();
+        let parts = AudioFormatDescriptionParts::new(Some(&asbd));
+        let empty = AudioFormatDescriptionParts {
+            magic_cookie: Some(&[]),
+            ..parts.clone()
+        };
+        let cookie = AudioFormatDescriptionParts {
+            magic_cookie: Some(&[0x12, 0x10]),
+            ..parts.clone()
+        };
+        assert!(parts.compare(&empty, kCMAudioFormatDescriptionMask_MagicCookie).0);
+        assert_eq!(
+            parts.compare(&cookie, kCMAudioFormatDescriptionMask_All),
+            (false, kCMAudioFormatDescriptionMask_All & !kCMAudioFormatDescriptionMask_MagicCookie)
+        );
+    }
+
+    #[test]
+    fn channel_layouts_compare_by_channel_labels() {
+        let tag = ChannelLayout::Tag(kAudioChannelLayoutTag_Stereo);
+        let bitmap = ChannelLayout::Bitmap(kAudioChannelBit_Left | kAudioChannelBit_Right);
+        let descriptions = ChannelLayout::from_labels(&[kAudioChannelLabel_Left, kAudioChannelLabel_Right]);
+        assert!(layouts_equal(&tag, &bitmap));
+        assert!(layouts_equal(&tag, &descriptions));
+        assert!(layouts_equal(&bitmap, &descriptions));
+        assert!(!layouts_equal(&tag, &ChannelLayout::Tag(kAudioChannelLayoutTag_StereoHeadphones)));
+        // Channel order is significant
+        assert!(!layouts_equal(&descriptions, &ChannelLayout::from_labels(&[kAudioChannelLabel_Right, kAudioChannelLabel_Left])));
+
+        // A layout on only one side differs
+        let asbd = stereo_float();
+        tag.with_audio_channel_layout(|layout, layout_size| {
+            let parts = unsafe { AudioFormatDescriptionParts::new(Some(&asbd)).with_channel_layout(layout, layout_size) };
+            assert!(!parts.compare(&AudioFormatDescriptionParts::new(Some(&asbd)), kCMAudioFormatDescriptionMask_ChannelLayout).0);
+        });
+    }
+}
@@ -0,0 +1,87 @@
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+        }
+    }
+
+    #[inline]
+    pub fn bits_remaining(&self) -> usize {
+        (self.data.len() * 8).saturating_sub(self.position)
+    }
+
+    #[inline]
+    pub fn read_bit(&mut self) -> Option<bool> {
+        self.read_bits(1).map(|bit| bit != 0)
+    }
+
+    pub fn read_bits(&mut self, count: u32) -> Option<u32> {
+        debug_assert!(count <= 32);
+        if count as usize > self.bits_remaining() {
+            return None;
+        }
+        let mut value = 0u64;
+        for _ in 0..count {
+            let byte = self.data[self.position / 8];
+            let bit = (byte >> (7 - (self.position % 8))) & 1;
+            value = (value << 1) | bit as u64;
+            self.position += 1;
+        }
+        Some(value as u32)
+    }
+
+    #[inline]
+    pub fn skip_bits(&mut self, count: usize) -> Option<()> {
+        if count > self.bits_remaining() {
+            return None;
+        }
+        self.position += count;
+        Some(())
+    }
+}
+
+pub(crate) struct BitWriter {
+    data: Vec<u8>,
+    bit_count: usize,
+}
+
+impl BitWriter {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            bit_count: 0,
+        }
+    }
+
+    pub fn write_bits(&mut self, value: u32, count: u32) {
+        debug_assert!(count <= 32);
+        for i in (0..count).rev() {
+            if self.bit_count == self.data.len() * 8 {
+                self.data.push(0);
+            }
+            if (value >> i) & 1 != 0 {
+                let last = self.data.len() - 1;
+                self.data[last] |= 1 << (7 - (self.bit_count % 8));
+            }
+            self.bit_count += 1;
+        }
+    }
+
+    #[inline]
+    pub fn write_bit(&mut self, bit: bool) {
+        self.write_bits(bit as u32, 1)
+    }
+
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
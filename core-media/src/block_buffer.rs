@@ -1,4 +1,9 @@
-use std::{ptr::null_mut, slice::from_raw_parts_mut};
+use std::{
+    cell::UnsafeCell,
+    ptr::{null, null_mut},
+    slice::from_raw_parts_mut,
+    sync::{Arc, OnceLock},
+};
 
 use core_foundation::base::{kCFAllocatorDefault, Boolean, CFAllocatorRef, CFTypeID, OSStatus, TCFType};
 use libc::{c_void, size_t};
@@ -376,3 +381,601 @@ impl CMBlockBuffer {
         unsafe { CMBlockBufferIsEmpty(self.as_concrete_TypeRef()) != 0 }
     }
 }
+
+pub trait TBlockBuffer {
+    fn get_data_length(&self) -> usize;
+    fn is_range_contiguous(&self, offset: usize, length: usize) -> bool;
+    fn access_data_bytes<'a>(&'a self, offset: usize, temporary_block: &'a mut [u8]) -> Result<&'a [u8], OSStatus>;
+    fn copy_data_bytes(&self, offset_to_data: usize, destination: &mut [u8]) -> Result<(), OSStatus>;
+    fn replace_data_bytes(&mut self, source_bytes: &[u8], offset_into_destination: usize) -> Result<(), OSStatus>;
+    fn fill_data_bytes(&mut self, fill_byte: u8, offset_into_destination: usize, data_length: usize) -> Result<(), OSStatus>;
+    fn get_data(&self, offset: usize) -> Result<&[u8], OSStatus>;
+    fn append_data_bytes(&mut self, source_bytes: &[u8]) -> Result<(), OSStatus>;
+}
+
+impl TBlockBuffer for CMBlockBuffer {
+    #[inline]
+    fn get_data_length(&self) -> usize {
+        CMBlockBuffer::get_data_length(self)
+    }
+
+    #[inline]
+    fn is_range_contiguous(&self, offset: usize, length: usize) -> bool {
+        CMBlockBuffer::is_range_contiguous(self, offset, length)
+    }
+
+    #[inline]
+    fn access_data_bytes<'a>(&'a self, offset: usize, temporary_block: &'a mut [u8]) -> Result<&'a [u8], OSStatus> {
+        CMBlockBuffer::access_data_bytes(self, offset, temporary_block).map(|data| &*data)
+    }
+
+    #[inline]
+    fn copy_data_bytes(&self, offset_to_data: usize, destination: &mut [u8]) -> Result<(), OSStatus> {
+        CMBlockBuffer::copy_data_bytes(self, offset_to_data, destination)
+    }
+
+    #[inline]
+    fn replace_data_bytes(&mut self, source_bytes: &[u8], offset_into_destination: usize) -> Result<(), OSStatus> {
+        CMBlockBuffer::replace_data_bytes(self, source_bytes, offset_into_destination)
+    }
+
+    #[inline]
+    fn fill_data_bytes(&mut self, fill_byte: u8, offset_into_destination: usize, data_length: usize) -> Result<(), OSStatus> {
+        CMBlockBuffer::fill_data_bytes(self, fill_byte, offset_into_destination, data_length)
+    }
+
+    #[inline]
+    fn get_data(&self, offset: usize) -> Result<&[u8], OSStatus> {
+        CMBlockBuffer::get_data(self, offset).map(|data| &*data)
+    }
+
+    fn append_data_bytes(&mut self, source_bytes: &[u8]) -> Result<(), OSStatus> {
+        if source_bytes.is_empty() {
+            return Ok(());
+        }
+        let offset = CMBlockBuffer::get_data_length(self);
+        // Let CoreMedia allocate the block so it never frees memory owned by Rust
+        let status = unsafe {
+            CMBlockBufferAppendMemoryBlock(
+                self.as_concrete_TypeRef(),
+                null_mut(),
+                source_bytes.len(),
+                kCFAllocatorDefault,
+                null(),
+                0,
+                source_bytes.len(),
+                kCMBlockBufferAssureMemoryNowFlag,
+            )
+        };
+        if status != kCMBlockBufferNoErr {
+            return Err(status);
+        }
+        CMBlockBuffer::replace_data_bytes(self, source_bytes, offset)
+    }
+}
+
+// Shared by every referencing buffer, safe writes copy it first when shared
+#[derive(Debug)]
+struct BlockBufferMemory {
+    length: usize,
+    data: OnceLock<UnsafeCell<Box<[u8]>>>,
+}
+
+// Safe code only mutates unshared memory, shared writes are unsafe
+unsafe impl Send for BlockBufferMemory {}
+unsafe impl Sync for BlockBufferMemory {}
+
+impl BlockBufferMemory {
+    fn new(memory_block: Option<Vec<u8>>, length: usize) -> Arc<Self> {
+        let data = OnceLock::new();
+        if let Some(memory_block) = memory_block {
+            let _ = data.set(UnsafeCell::new(memory_block.into_boxed_slice()));
+        }
+        Arc::new(Self {
+            length,
+            data,
+        })
+    }
+
+    #[inline]
+    fn allocate(&self) {
+        self.data.get_or_init(|| UnsafeCell::new(vec![0; self.length].into_boxed_slice()));
+    }
+
+    #[inline]
+    fn bytes(&self) -> Option<&[u8]> {
+        self.data.get().map(|data| unsafe { &**data.get() })
+    }
+
+    #[inline]
+    fn bytes_mut(&mut self) -> Option<&mut [u8]> {
+        self.data.get_mut().map(|data| &mut **data.get_mut())
+    }
+
+    #[inline]
+    fn bytes_ptr(&self) -> Option<*mut Box<[u8]>> {
+        self.data.get().map(UnsafeCell::get)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BlockBufferSegment {
+    block: Arc<BlockBufferMemory>,
+    offset: usize,
+    length: usize,
+}
+
+impl BlockBufferSegment {
+    #[inline]
+    fn bytes(&self) -> Result<&[u8], OSStatus> {
+        self.block.bytes().map(|block| &block[self.offset..self.offset + self.length]).ok_or(kCMBlockBufferUnallocatedBlock)
+    }
+
+    fn bytes_mut(&mut self) -> Result<&mut [u8], OSStatus> {
+        if Arc::get_mut(&mut self.block).is_none() {
+            *self = self.copied()?;
+        }
+        let (offset, length) = (self.offset, self.length);
+        let block = Arc::get_mut(&mut self.block).and_then(BlockBufferMemory::bytes_mut).ok_or(kCMBlockBufferUnallocatedBlock)?;
+        Ok(&mut block[offset..offset + length])
+    }
+
+    #[inline]
+    unsafe fn shared_bytes_mut(&mut self) -> Result<&mut [u8], OSStatus> {
+        let block = self.block.bytes_ptr().ok_or(kCMBlockBufferUnallocatedBlock)?;
+        let block = &mut **block;
+        Ok(&mut block[self.offset..self.offset + self.length])
+    }
+
+    #[inline]
+    fn copied(&self) -> Result<Self, OSStatus> {
+        Ok(Self {
+            block: BlockBufferMemory::new(Some(self.bytes()?.to_vec()), self.length),
+            offset: 0,
+            length: self.length,
+        })
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Self {
+        Self {
+            block: self.block.clone(),
+            offset: self.offset + offset,
+            length,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BlockBuffer {
+    segments: Vec<BlockBufferSegment>,
+    data_length: usize,
+}
+
+impl BlockBuffer {
+    #[inline]
+    pub fn new_empty(sub_block_capacity: usize) -> Self {
+        Self {
+            segments: Vec::with_capacity(sub_block_capacity),
+            data_length: 0,
+        }
+    }
+
+    pub fn new_with_memory_block(
+        memory_block: Option<Vec<u8>>,
+        block_length: usize,
+        offset_to_data: usize,
+        data_length: usize,
+        flags: CMBlockBufferFlags,
+    ) -> Result<Self, OSStatus> {
+        let mut block_buffer = Self::new_empty(1);
+        block_buffer.append_memory_block(memory_block, block_length, offset_to_data, data_length, flags)?;
+        Ok(block_buffer)
+    }
+
+    pub fn new_with_buffer_reference(&self, offset_to_data: usize, data_length: usize, flags: CMBlockBufferFlags) -> Result<Self, OSStatus> {
+        let mut block_buffer = Self::new_empty(0);
+        block_buffer.append_buffer_reference(self, offset_to_data, data_length, flags)?;
+        Ok(block_buffer)
+    }
+
+    pub fn new_contiguous(&self, offset_to_data: usize, data_length: usize) -> Result<Self, OSStatus> {
+        let data_length = self.resolve_length(offset_to_data, data_length, 0)?;
+        if self.is_range_contiguous(offset_to_data, data_length) {
+            return self.new_with_buffer_reference(offset_to_data, data_length, 0);
+        }
+        let mut data = vec![0; data_length];
+        self.copy_data_bytes(offset_to_data, &mut data)?;
+        Ok(Self::from(data))
+    }
+
+    pub fn append_memory_block(
+        &mut self,
+        memory_block: Option<Vec<u8>>,
+        block_length: usize,
+        offset_to_data: usize,
+        data_length: usize,
+        flags: CMBlockBufferFlags,
+    ) -> Result<(), OSStatus> {
+        if let Some(memory_block) = memory_block.as_ref() {
+            if memory_block.len() != block_length {
+                return Err(kCMBlockBufferBadLengthParameterErr);
+            }
+        }
+        if offset_to_data > block_length {
+            return Err(kCMBlockBufferBadOffsetParameterErr);
+        }
+        if data_length == 0 || data_length > block_length - offset_to_data {
+            return Err(kCMBlockBufferBadLengthParameterErr);
+        }
+        let segment = BlockBufferSegment {
+            block: BlockBufferMemory::new(memory_block, block_length),
+            offset: offset_to_data,
+            length: data_length,
+        };
+        if flags & kCMBlockBufferAssureMemoryNowFlag != 0 {
+            segment.block.allocate();
+        }
+        self.segments.push(segment);
+        self.data_length += data_length;
+        Ok(())
+    }
+
+    pub fn append_buffer_reference(
+        &mut self,
+        target_block_buf: &BlockBuffer,
+        offset_to_data: usize,
+        data_length: usize,
+        flags: CMBlockBufferFlags,
+    ) -> Result<(), OSStatus> {
+        let data_length = target_block_buf.resolve_length(offset_to_data, data_length, flags)?;
+        let mut segments = Vec::new();
+        target_block_buf.for_each_segment(offset_to_data, data_length, |segment, offset, length| {
+            segments.push(segment.slice(offset, length));
+        });
+        let always_copy = flags & kCMBlockBufferAlwaysCopyDataFlag != 0;
+        // Copies are made from the referenced bytes, so those are allocated first
+        if always_copy || flags & kCMBlockBufferAssureMemoryNowFlag != 0 {
+            segments.iter().for_each(|segment| segment.block.allocate());
+        }
+        if always_copy {
+            segments = segments.iter().map(BlockBufferSegment::copied).collect::<Result<_, _>>()?;
+        }
+        self.segments.extend(segments);
+        self.data_length += data_length;
+        Ok(())
+    }
+
+    pub fn assure_block_memory(&mut self) -> Result<(), OSStatus> {
+        self.segments.iter().for_each(|segment| segment.block.allocate());
+        Ok(())
+    }
+
+    pub fn get_data(&self, offset: usize) -> Result<&[u8], OSStatus> {
+        self.check_range(offset, 1)?;
+        let (index, segment_offset) = self.locate(offset).ok_or(kCMBlockBufferBadOffsetParameterErr)?;
+        // Only the run up to the end of the containing segment is returned, as with
+        // CMBlockBufferGetDataPointer
+        self.segments[index].bytes().map(|bytes| &bytes[segment_offset..])
+    }
+
+    #[inline]
+    pub fn get_data_length(&self) -> usize {
+        self.data_length
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn is_range_contiguous(&self, offset: usize, length: usize) -> bool {
+        let length = if length == 0 {
+            self.data_length.saturating_sub(offset)
+        } else {
+            length
+        };
+        if length == 0 || self.check_range(offset, length).is_err() {
+            return false;
+        }
+        match self.locate(offset) {
+            Some((index, segment_offset)) => self.segments[index].length - segment_offset >= length,
+            None => false,
+        }
+    }
+
+    pub fn access_data_bytes<'a>(&'a self, offset: usize, temporary_block: &'a mut [u8]) -> Result<&'a [u8], OSStatus> {
+        let length = temporary_block.len();
+        self.check_range(offset, length)?;
+        if self.is_range_contiguous(offset, length) {
+            let (index, segment_offset) = self.locate(offset).ok_or(kCMBlockBufferBadOffsetParameterErr)?;
+            return self.segments[index].bytes().map(|bytes| &bytes[segment_offset..segment_offset + length]);
+        }
+        self.copy_data_bytes(offset, temporary_block)?;
+        Ok(temporary_block)
+    }
+
+    pub fn copy_data_bytes(&self, offset_to_data: usize, destination: &mut [u8]) -> Result<(), OSStatus> {
+        self.check_range(offset_to_data, destination.len())?;
+        let mut status = Ok(());
+        let mut position = 0;
+        self.for_each_segment(offset_to_data, destination.len(), |segment, offset, length| {
+            if status.is_ok() {
+                status = segment.bytes().map(|bytes| destination[position..position + length].copy_from_slice(&bytes[offset..offset + length]));
+                position += length;
+            }
+        });
+        status
+    }
+
+    // Blocks shared with other buffers are copied before writing, so the write
+    // only shows through this buffer
+    pub fn replace_data_bytes(&mut self, source_bytes: &[u8], offset_into_destination: usize) -> Result<(), OSStatus> {
+        self.check_range(offset_into_destination, source_bytes.len())?;
+        let mut position = 0;
+        self.try_for_each_segment_mut(offset_into_destination, source_bytes.len(), false, |bytes| {
+            bytes.copy_from_slice(&source_bytes[position..position + bytes.len()]);
+            position += bytes.len();
+        })
+    }
+
+    pub fn fill_data_bytes(&mut self, fill_byte: u8, offset_into_destination: usize, data_length: usize) -> Result<(), OSStatus> {
+        let data_length = self.resolve_length(offset_into_destination, data_length, 0)?;
+        self.try_for_each_segment_mut(offset_into_destination, data_length, false, |bytes| bytes.fill(fill_byte))
+    }
+
+    /// Writes into the shared memory blocks, so the bytes show through every
+    /// buffer referencing them, as with `CMBlockBufferReplaceDataBytes`.
+    ///
+    /// # Safety
+    ///
+    /// No other buffer sharing the written blocks may read or write them while
+    /// this runs, on this or any other thread, and no slice borrowed from such
+    /// a buffer through `get_data` or `access_data_bytes` may still be
+    /// alive.
+    pub unsafe fn replace_shared_data_bytes(&mut self, source_bytes: &[u8], offset_into_destination: usize) -> Result<(), OSStatus> {
+        self.check_range(offset_into_destination, source_bytes.len())?;
+        let mut position = 0;
+        self.try_for_each_segment_mut(offset_into_destination, source_bytes.len(), true, |bytes| {
+            bytes.copy_from_slice(&source_bytes[position..position + bytes.len()]);
+            position += bytes.len();
+        })
+    }
+
+    /// Fills the shared memory blocks, like `replace_shared_data_bytes`.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`BlockBuffer::replace_shared_data_bytes`].
+    pub unsafe fn fill_shared_data_bytes(&mut self, fill_byte: u8, offset_into_destination: usize, data_length: usize) -> Result<(), OSStatus> {
+        let data_length = self.resolve_length(offset_into_destination, data_length, 0)?;
+        self.try_for_each_segment_mut(offset_into_destination, data_length, true, |bytes| bytes.fill(fill_byte))
+    }
+
+    fn check_range(&self, offset: usize, length: usize) -> Result<(), OSStatus> {
+        if self.segments.is_empty() {
+            return Err(kCMBlockBufferEmptyBBufErr);
+        }
+        if offset >= self.data_length {
+            return Err(kCMBlockBufferBadOffsetParameterErr);
+        }
+        if length > self.data_length - offset {
+            return Err(kCMBlockBufferBadLengthParameterErr);
+        }
+        Ok(())
+    }
+
+    // A zero length refers to the rest of the buffer, as in the CMBlockBuffer API
+    fn resolve_length(&self, offset: usize, length: usize, flags: CMBlockBufferFlags) -> Result<usize, OSStatus> {
+        if length == 0 && flags & kCMBlockBufferPermitEmptyReferenceFlag != 0 && self.segments.is_empty() {
+            return Ok(0);
+        }
+        let length = if length == 0 {
+            self.data_length.saturating_sub(offset)
+        } else {
+            length
+        };
+        self.check_range(offset, length)?;
+        Ok(length)
+    }
+
+    fn locate(&self, offset: usize) -> Option<(usize, usize)> {
+        let mut start = 0;
+        for (index, segment) in self.segments.iter().enumerate() {
+            if offset < start + segment.length {
+                return Some((index, offset - start));
+            }
+            start += segment.length;
+        }
+        None
+    }
+
+    fn for_each_segment<F>(&self, offset: usize, length: usize, mut f: F)
+    where
+        F: FnMut(&BlockBufferSegment, usize, usize),
+    {
+        let mut start = 0;
+        let end = offset + length;
+        for segment in &self.segments {
+            let segment_end = start + segment.length;
+            if segment_end > offset && start < end {
+                let from = offset.max(start);
+                let to = end.min(segment_end);
+                f(segment, from - start, to - from);
+            }
+            if segment_end >= end {
+                break;
+            }
+            start = segment_end;
+        }
+    }
+
+    // Callers passing shared uphold the contract of replace_shared_data_bytes
+    fn try_for_each_segment_mut<F>(&mut self, offset: usize, length: usize, shared: bool, mut f: F) -> Result<(), OSStatus>
+    where
+        F: FnMut(&mut [u8]),
+    {
+        let mut start = 0;
+        let end = offset + length;
+        for segment in &mut self.segments {
+            let segment_end = start + segment.length;
+            if segment_end > offset && start < end {
+                let from = offset.max(start);
+                let to = end.min(segment_end);
+                let bytes = if shared {
+                    unsafe { segment.shared_bytes_mut()? }
+                } else {
+                    segment.bytes_mut()?
+                };
+                f(&mut bytes[from - start..to - start]);
+            }
+            if segment_end >= end {
+                break;
+            }
+            start = segment_end;
+        }
+        Ok(())
+    }
+}
+
+impl From<Vec<u8>> for BlockBuffer {
+    #[inline]
+    fn from(data: Vec<u8>) -> Self {
+        let data_length = data.len();
+        if data_length == 0 {
+            return Self::default();
+        }
+        Self {
+            segments: vec![BlockBufferSegment {
+                block: BlockBufferMemory::new(Some(data), data_length),
+                offset: 0,
+                length: data_length,
+            }],
+            data_length,
+        }
+    }
+}
+
+impl TBlockBuffer for BlockBuffer {
+    #[inline]
+    fn get_data_length(&self) -> usize {
+        BlockBuffer::get_data_length(self)
+    }
+
+    #[inline]
+    fn is_range_contiguous(&self, offset: usize, length: usize) -> bool {
+        BlockBuffer::is_range_contiguous(self, offset, length)
+    }
+
+    #[inline]
+    fn access_data_bytes<'a>(&'a self, offset: usize, temporary_block: &'a mut [u8]) -> Result<&'a [u8], OSStatus> {
+        BlockBuffer::access_data_bytes(self, offset, temporary_block)
+    }
+
+    #[inline]
+    fn copy_data_bytes(&self, offset_to_data: usize, destination: &mut [u8]) -> Result<(), OSStatus> {
+        BlockBuffer::copy_data_bytes(self, offset_to_data, destination)
+    }
+
+    #[inline]
+    fn replace_data_bytes(&mut self, source_bytes: &[u8], offset_into_destination: usize) -> Result<(), OSStatus> {
+        BlockBuffer::replace_data_bytes(self, source_bytes, offset_into_destination)
+    }
+
+    #[inline]
+    fn fill_data_bytes(&mut self, fill_byte: u8, offset_into_destination: usize, data_length: usize) -> Result<(), OSStatus> {
+        BlockBuffer::fill_data_bytes(self, fill_byte, offset_into_destination, data_length)
+    }
+
+    #[inline]
+    fn get_data(&self, offset: usize) -> Result<&[u8], OSStatus> {
+        BlockBuffer::get_data(self, offset)
+    }
+
+    #[inline]
+    fn append_data_bytes(&mut self, source_bytes: &[u8]) -> Result<(), OSStatus> {
+        if source_bytes.is_empty() {
+            return Ok(());
+        }
+        BlockBuffer::append_memory_block(self, Some(source_bytes.to_vec()), source_bytes.len(), 0, source_bytes.len(), 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_writes_are_visible_through_references() {
+        let mut source = BlockBuffer::from(b"0123".to_vec());
+        source.append_memory_block(None, 4, 0, 4, kCMBlockBufferAssureMemoryNowFlag).unwrap();
+        let mut reference = source.new_with_buffer_reference(2, 4, 0).unwrap();
+        assert!(!reference.is_range_contiguous(0, 0));
+
+        unsafe { reference.replace_shared_data_bytes(b"abcd", 0) }.unwrap();
+        let mut data = [0; 8];
+        source.copy_data_bytes(0, &mut data).unwrap();
+        assert_eq!(&data, b"01abcd\0\0");
+
+        unsafe { source.fill_shared_data_bytes(b'x', 6, 0) }.unwrap();
+        let mut temporary = [0; 4];
+        assert_eq!(reference.access_data_bytes(0, &mut temporary).unwrap(), b"abcd");
+        assert_eq!(source.get_data(4).unwrap(), b"cdxx");
+
+        // Copied references no longer share memory
+        let mut copy = BlockBuffer::new_empty(0);
+        copy.append_buffer_reference(&source, 0, 2, kCMBlockBufferAlwaysCopyDataFlag).unwrap();
+        unsafe { copy.replace_shared_data_bytes(b"zz", 0) }.unwrap();
+        assert_eq!(source.get_data(0).unwrap(), b"01ab");
+    }
+
+    #[test]
+    fn safe_writes_copy_shared_blocks() {
+        let mut source = BlockBuffer::from(b"0123".to_vec());
+        let clone = source.clone();
+        let mut reference = source.new_with_buffer_reference(1, 2, 0).unwrap();
+        let borrowed = clone.get_data(0).unwrap();
+
+        source.replace_data_bytes(b"ab", 0).unwrap();
+        reference.fill_data_bytes(b'x', 0, 0).unwrap();
+        assert_eq!(borrowed, b"0123");
+        assert_eq!(source.get_data(0).unwrap(), b"ab23");
+        assert_eq!(reference.get_data(0).unwrap(), b"xx");
+
+        // Once the block is no longer shared the write happens in place
+        drop(clone);
+        drop(reference);
+        let block = source.get_data(0).unwrap().as_ptr();
+        source.replace_data_bytes(b"c", 3).unwrap();
+        assert_eq!(source.get_data(0).unwrap(), b"ab2c");
+        assert_eq!(source.get_data(0).unwrap().as_ptr(), block);
+    }
+
+    #[test]
+    fn unallocated_blocks() {
+        let mut buffer = BlockBuffer::new_with_memory_block(None, 8, 2, 4, 0).unwrap();
+        let reference = buffer.new_with_buffer_reference(0, 0, 0).unwrap();
+        assert_eq!(buffer.get_data(0), Err(kCMBlockBufferUnallocatedBlock));
+        assert_eq!(buffer.replace_data_bytes(b"ab", 0), Err(kCMBlockBufferUnallocatedBlock));
+
+        buffer.assure_block_memory().unwrap();
+        unsafe { buffer.replace_shared_data_bytes(b"ab", 1) }.unwrap();
+        assert_eq!(reference.get_data(0).unwrap(), b"\0ab\0");
+        assert_eq!(BlockBuffer::new_with_memory_block(Some(vec![0; 4]), 8, 0, 4, 0).err(), Some(kCMBlockBufferBadLengthParameterErr));
+    }
+
+    #[test]
+    fn always_copy_never_shares_blocks() {
+        let source = BlockBuffer::from(b"0123".to_vec());
+        let copy = source.new_with_buffer_reference(1, 2, kCMBlockBufferAlwaysCopyDataFlag).unwrap();
+        assert_eq!(copy.get_data(0).unwrap(), b"12");
+        assert_ne!(copy.get_data(0).unwrap().as_ptr(), source.get_data(1).unwrap().as_ptr());
+
+        // Unallocated blocks are allocated before copying instead of being shared
+        let source = BlockBuffer::new_with_memory_block(None, 4, 0, 4, 0).unwrap();
+        let mut copy = source.new_with_buffer_reference(0, 0, kCMBlockBufferAlwaysCopyDataFlag).unwrap();
+        assert_eq!(copy.get_data(0).unwrap(), b"\0\0\0\0");
+        unsafe { copy.replace_shared_data_bytes(b"ab", 0) }.unwrap();
+        assert_eq!(source.get_data(0).unwrap(), b"\0\0\0\0");
+        assert_eq!(copy.get_data(0).unwrap(), b"ab\0\0");
+    }
+}
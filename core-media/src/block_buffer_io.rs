@@ -0,0 +1,385 @@
+use std::{
+    cmp::min,
+    io::{self, BufRead, Read, Seek, SeekFrom, Write},
+};
+
+use core_foundation::base::OSStatus;
+
+use crate::block_buffer::TBlockBuffer;
+
+const DEFAULT_WRITER_BLOCK_SIZE: usize = 64 * 1024;
+
+fn status_to_io_error(status: OSStatus) -> io::Error {
+    io::Error::other(format!("block buffer error {}", status))
+}
+
+pub struct BlockBufferCursor<B: TBlockBuffer> {
+    buffer: B,
+    position: usize,
+    // Start and end of the allocated run found by the last Buf::advance, reused
+    // while the position stays inside it
+    #[cfg(feature = "bytes")]
+    allocated_run: Option<(usize, usize)>,
+}
+
+impl<B: TBlockBuffer> BlockBufferCursor<B> {
+    #[inline]
+    pub fn new(buffer: B) -> Self {
+        Self {
+            buffer,
+            position: 0,
+            #[cfg(feature = "bytes")]
+            allocated_run: None,
+        }
+    }
+
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    #[inline]
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.buffer.get_data_length().saturating_sub(self.position)
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &B {
+        &self.buffer
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut B {
+        #[cfg(feature = "bytes")]
+        {
+            self.allocated_run = None;
+        }
+        &mut self.buffer
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+
+    fn chunk(&self) -> Result<&[u8], OSStatus> {
+        if self.remaining() == 0 {
+            return Ok(&[]);
+        }
+        self.buffer.get_data(self.position)
+    }
+
+    // End of the allocated bytes from the position on, an unallocated block has no
+    // bytes to expose
+    #[cfg(feature = "bytes")]
+    fn allocated_end(&self) -> usize {
+        if let Some((start, end)) = self.allocated_run {
+            if (start..=end).contains(&self.position) {
+                return end;
+            }
+        }
+        let mut position = self.position;
+        while position < self.buffer.get_data_length() {
+            match self.buffer.get_data(position) {
+                Ok(chunk) if !chunk.is_empty() => position += chunk.len(),
+                _ => break,
+            }
+        }
+        position.max(self.position)
+    }
+}
+
+impl<B: TBlockBuffer> Read for BlockBufferCursor<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = min(buf.len(), self.remaining());
+        if length == 0 {
+            return Ok(0);
+        }
+        self.buffer.copy_data_bytes(self.position, &mut buf[..length]).map_err(status_to_io_error)?;
+        self.position += length;
+        Ok(length)
+    }
+}
+
+impl<B: TBlockBuffer> BufRead for BlockBufferCursor<B> {
+    #[inline]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.chunk().map_err(status_to_io_error)
+    }
+
+    #[inline]
+    fn consume(&mut self, amount: usize) {
+        self.position = min(self.position + amount, self.buffer.get_data_length());
+    }
+}
+
+impl<B: TBlockBuffer> Seek for BlockBufferCursor<B> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match position {
+            SeekFrom::Start(offset) => {
+                self.position = offset as usize;
+                return Ok(offset);
+            }
+            SeekFrom::End(offset) => (self.buffer.get_data_length() as u64, offset),
+            SeekFrom::Current(offset) => (self.position as u64, offset),
+        };
+        match base.checked_add_signed(offset) {
+            Some(position) => {
+                self.position = position as usize;
+                Ok(position)
+            }
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative or overflowing position")),
+        }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<B: TBlockBuffer> bytes::Buf for BlockBufferCursor<B> {
+    // Only counts the allocated bytes up to the next unallocated block
+    #[inline]
+    fn remaining(&self) -> usize {
+        self.allocated_end() - self.position
+    }
+
+    #[inline]
+    fn chunk(&self) -> &[u8] {
+        BlockBufferCursor::chunk(self).unwrap_or_default()
+    }
+
+    fn advance(&mut self, count: usize) {
+        let end = self.allocated_end();
+        assert!(count <= end - self.position, "cannot advance past the end of the block buffer");
+        self.allocated_run = Some((self.position, end));
+        self.position += count;
+    }
+}
+
+// Pending bytes are appended on flush and when the writer is dropped, like
+// BufWriter
+pub struct BlockBufferWriter<B: TBlockBuffer> {
+    // Only taken by into_inner
+    buffer: Option<B>,
+    pending: Vec<u8>,
+    block_size: usize,
+}
+
+impl<B: TBlockBuffer> BlockBufferWriter<B> {
+    #[inline]
+    pub fn new(buffer: B) -> Self {
+        Self::with_block_size(buffer, DEFAULT_WRITER_BLOCK_SIZE)
+    }
+
+    #[inline]
+    pub fn with_block_size(buffer: B, block_size: usize) -> Self {
+        let block_size = block_size.max(1);
+        Self {
+            buffer: Some(buffer),
+            pending: Vec::with_capacity(block_size),
+            block_size,
+        }
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &B {
+        self.buffer.as_ref().expect("block buffer writer used after into_inner")
+    }
+
+    #[inline]
+    pub fn data_length(&self) -> usize {
+        self.get_ref().get_data_length() + self.pending.len()
+    }
+
+    pub fn into_inner(mut self) -> Result<B, OSStatus> {
+        self.flush_pending()?;
+        Ok(self.buffer.take().expect("block buffer writer used after into_inner"))
+    }
+
+    fn flush_pending(&mut self) -> Result<(), OSStatus> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        if let Some(buffer) = self.buffer.as_mut() {
+            buffer.append_data_bytes(&self.pending)?;
+        }
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+impl<B: TBlockBuffer> Drop for BlockBufferWriter<B> {
+    fn drop(&mut self) {
+        // Errors are ignored as there is no way to report them, call flush to handle
+        // them
+        let _ = self.flush_pending();
+    }
+}
+
+impl<B: TBlockBuffer> Write for BlockBufferWriter<B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // A full block is appended before taking more bytes, so a failed append
+        // never consumes any of buf and is retried by the next call
+        if self.pending.len() >= self.block_size {
+            self.flush_pending().map_err(status_to_io_error)?;
+        }
+        let length = min(buf.len(), self.block_size - self.pending.len());
+        self.pending.extend_from_slice(&buf[..length]);
+        Ok(length)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_pending().map_err(status_to_io_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    use super::*;
+    use crate::block_buffer::{BlockBuffer, kCMBlockBufferBlockAllocationFailedErr};
+
+    // Delegates to a native buffer and records appends, so they can be checked
+    // after the writer is gone
+    #[derive(Default)]
+    struct RecordingBuffer {
+        buffer: BlockBuffer,
+        appended: Arc<Mutex<Vec<Vec<u8>>>>,
+        failing: Arc<AtomicBool>,
+    }
+
+    impl TBlockBuffer for RecordingBuffer {
+        fn get_data_length(&self) -> usize {
+            self.buffer.get_data_length()
+        }
+
+        fn is_range_contiguous(&self, offset: usize, length: usize) -> bool {
+            self.buffer.is_range_contiguous(offset, length)
+        }
+
+        fn access_data_bytes<'a>(&'a self, offset: usize, temporary_block: &'a mut [u8]) -> Result<&'a [u8], OSStatus> {
+            self.buffer.access_data_bytes(offset, temporary_block)
+        }
+
+        fn copy_data_bytes(&self, offset_to_data: usize, destination: &mut [u8]) -> Result<(), OSStatus> {
+            self.buffer.copy_data_bytes(offset_to_data, destination)
+        }
+
+        fn replace_data_bytes(&mut self, source_bytes: &[u8], offset_into_destination: usize) -> Result<(), OSStatus> {
+            self.buffer.replace_data_bytes(source_bytes, offset_into_destination)
+        }
+
+        fn fill_data_bytes(&mut self, fill_byte: u8, offset_into_destination: usize, data_length: usize) -> Result<(), OSStatus> {
+            self.buffer.fill_data_bytes(fill_byte, offset_into_destination, data_length)
+        }
+
+        fn get_data(&self, offset: usize) -> Result<&[u8], OSStatus> {
+            self.buffer.get_data(offset)
+        }
+
+        fn append_data_bytes(&mut self, source_bytes: &[u8]) -> Result<(), OSStatus> {
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(kCMBlockBufferBlockAllocationFailedErr);
+            }
+            self.appended.lock().unwrap().push(source_bytes.to_vec());
+            TBlockBuffer::append_data_bytes(&mut self.buffer, source_bytes)
+        }
+    }
+
+    #[test]
+    fn writer_appends_full_blocks() {
+        let mut writer = BlockBufferWriter::with_block_size(BlockBuffer::default(), 4);
+        writer.write_all(b"0123456789").unwrap();
+        assert_eq!(writer.get_ref().get_data_length(), 8);
+        assert_eq!(writer.data_length(), 10);
+        let buffer = writer.into_inner().unwrap();
+
+        let mut data = Vec::new();
+        BlockBufferCursor::new(buffer).read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"0123456789");
+    }
+
+    #[test]
+    fn writer_flushes_on_drop() {
+        let appended = Arc::new(Mutex::new(Vec::new()));
+        let buffer = RecordingBuffer {
+            appended: appended.clone(),
+            ..Default::default()
+        };
+        let mut writer = BlockBufferWriter::with_block_size(buffer, 4);
+        writer.write_all(b"abcdef").unwrap();
+        assert_eq!(*appended.lock().unwrap(), vec![b"abcd".to_vec()]);
+        assert_eq!(writer.get_ref().get_data(0).unwrap(), b"abcd");
+        drop(writer);
+        assert_eq!(*appended.lock().unwrap(), vec![b"abcd".to_vec(), b"ef".to_vec()]);
+    }
+
+    #[test]
+    fn failed_appends_consume_nothing() {
+        let failing = Arc::new(AtomicBool::new(true));
+        let buffer = RecordingBuffer {
+            failing: failing.clone(),
+            ..Default::default()
+        };
+        let mut writer = BlockBufferWriter::with_block_size(buffer, 4);
+        assert_eq!(writer.write(b"abcdef").unwrap(), 4);
+        // The full block cannot be appended, so no more bytes are taken
+        assert!(writer.write(b"ef").is_err());
+        assert!(writer.write(b"ef").is_err());
+        assert!(writer.write_all(b"ef").is_err());
+        assert_eq!(writer.data_length(), 4);
+
+        failing.store(false, Ordering::SeqCst);
+        writer.write_all(b"ef").unwrap();
+        let mut data = Vec::new();
+        BlockBufferCursor::new(writer.into_inner().unwrap()).read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"abcdef");
+    }
+
+    #[test]
+    fn cursor_reads_and_seeks_across_blocks() {
+        let mut buffer = BlockBuffer::from(b"abc".to_vec());
+        buffer.append_memory_block(Some(b"defg".to_vec()), 4, 1, 3, 0).unwrap();
+        let mut cursor = BlockBufferCursor::new(buffer);
+        assert_eq!(cursor.fill_buf().unwrap(), b"abc");
+        cursor.consume(2);
+        let mut data = [0; 3];
+        cursor.read_exact(&mut data).unwrap();
+        assert_eq!(&data, b"cef");
+        assert_eq!(cursor.seek(SeekFrom::End(-1)).unwrap(), 5);
+        assert_eq!(cursor.fill_buf().unwrap(), b"g");
+        assert!(cursor.seek(SeekFrom::Current(-6)).is_err());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn buf_stops_at_unallocated_block() {
+        use bytes::Buf;
+
+        let mut buffer = BlockBuffer::from(b"a".to_vec());
+        buffer.append_memory_block(Some(b"b".to_vec()), 1, 0, 1, 0).unwrap();
+        buffer.append_memory_block(None, 4, 0, 4, 0).unwrap();
+        let mut cursor = BlockBufferCursor::new(buffer);
+        assert_eq!(Buf::remaining(&cursor), 2);
+        assert_eq!(Buf::chunk(&cursor), b"a");
+        assert_eq!(cursor.get_u16(), u16::from_be_bytes(*b"ab"));
+        assert_eq!(Buf::remaining(&cursor), 0);
+        assert!(Buf::chunk(&cursor).is_empty());
+        assert!(!cursor.has_remaining());
+
+        // Allocating the block extends the run
+        cursor.get_mut().assure_block_memory().unwrap();
+        assert_eq!(Buf::remaining(&cursor), 4);
+        cursor.advance(3);
+        cursor.set_position(1);
+        assert_eq!(Buf::remaining(&cursor), 5);
+    }
+}
@@ -1,6 +1,10 @@
 use std::{
+    cmp::Ordering,
+    collections::VecDeque,
     mem,
     ptr::{null, null_mut},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    thread::{self, ThreadId},
 };
 
 use block::{Block, ConcreteBlock};
@@ -10,7 +14,11 @@ use core_foundation::{
 };
 use libc::{c_void, size_t};
 
-use crate::{base::CMItemCount, time::CMTime};
+use crate::{
+    base::CMItemCount,
+    sample_buffer::CMSampleBuffer,
+    time::{add_times, compare_times, is_numeric, make_time, subtract_times, CMTime},
+};
 
 pub const kCMBufferQueueError_AllocationFailed: OSStatus = -12760;
 pub const kCMBufferQueueError_RequiredParameterMissing: OSStatus = -12761;
@@ -553,3 +561,744 @@ impl CMBufferQueue {
         }
     }
 }
+
+pub trait BufferTiming {
+    fn get_decode_time_stamp(&self) -> CMTime;
+    fn get_presentation_time_stamp(&self) -> CMTime;
+    fn get_duration(&self) -> CMTime;
+
+    #[inline]
+    fn is_data_ready(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn get_size(&self) -> usize {
+        0
+    }
+}
+
+impl BufferTiming for CMSampleBuffer {
+    #[inline]
+    fn get_decode_time_stamp(&self) -> CMTime {
+        CMSampleBuffer::get_decode_time_stamp(self)
+    }
+
+    #[inline]
+    fn get_presentation_time_stamp(&self) -> CMTime {
+        CMSampleBuffer::get_output_presentation_time_stamp(self)
+    }
+
+    #[inline]
+    fn get_duration(&self) -> CMTime {
+        CMSampleBuffer::get_output_duration(self)
+    }
+
+    #[inline]
+    fn is_data_ready(&self) -> bool {
+        CMSampleBuffer::is_data_ready(self)
+    }
+
+    #[inline]
+    fn get_size(&self) -> usize {
+        CMSampleBuffer::get_total_sample_size(self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BufferQueueOrder {
+    Unsorted,
+    SortedByOutputPresentationTimeStamp,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BufferQueueTriggerToken(u64);
+
+type TriggerClosure = Arc<dyn Fn(BufferQueueTriggerToken) + Send + Sync>;
+type ValidationClosure<T> = Arc<dyn Fn(&T) -> Result<(), OSStatus> + Send + Sync>;
+
+struct BufferQueueTrigger {
+    token: BufferQueueTriggerToken,
+    condition: CMBufferQueueTriggerCondition,
+    time: CMTime,
+    threshold: usize,
+    closure: Option<TriggerClosure>,
+    // Level conditions fire on false to true transitions
+    state: bool,
+    last_time: CMTime,
+}
+
+struct BufferQueueState<T> {
+    buffers: VecDeque<T>,
+    // Kept up to date as buffers come and go so triggers don't rescan the queue,
+    // the extremes are only rescanned after the buffer holding one leaves
+    duration: CMTime,
+    min_presentation_time_stamp: Option<CMTime>,
+    max_presentation_time_stamp: Option<CMTime>,
+    end_of_data: bool,
+    triggers: Vec<BufferQueueTrigger>,
+    next_token: u64,
+    validation: Option<ValidationClosure<T>>,
+    firing_threads: Vec<ThreadId>,
+}
+
+impl<T: BufferTiming> BufferQueueState<T> {
+    fn new() -> Self {
+        Self {
+            buffers: VecDeque::new(),
+            duration: make_time(0, 1),
+            min_presentation_time_stamp: Some(CMTime::default()),
+            max_presentation_time_stamp: Some(CMTime::default()),
+            end_of_data: false,
+            triggers: Vec::new(),
+            next_token: 0,
+            validation: None,
+            firing_threads: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, index: usize, buffer: T) {
+        let duration = buffer.get_duration();
+        if is_numeric(&duration) {
+            self.duration = add_times(&self.duration, &duration);
+        }
+        let presentation_time_stamp = buffer.get_presentation_time_stamp();
+        if is_numeric(&presentation_time_stamp) {
+            let extremes = [(&mut self.min_presentation_time_stamp, Ordering::Less), (&mut self.max_presentation_time_stamp, Ordering::Greater)];
+            for (extreme, ordering) in extremes {
+                if let Some(extreme) =
+                    extreme.as_mut().filter(|extreme| !is_numeric(extreme) || compare_times(&presentation_time_stamp, extreme) == ordering)
+                {
+                    *extreme = presentation_time_stamp;
+                }
+            }
+        }
+        self.buffers.insert(index, buffer);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        let buffer = self.buffers.pop_front()?;
+        if self.buffers.is_empty() {
+            self.clear_totals();
+            return Some(buffer);
+        }
+        let duration = buffer.get_duration();
+        if is_numeric(&duration) {
+            self.duration = subtract_times(&self.duration, &duration);
+        }
+        let presentation_time_stamp = buffer.get_presentation_time_stamp();
+        if is_numeric(&presentation_time_stamp) {
+            for extreme in [&mut self.min_presentation_time_stamp, &mut self.max_presentation_time_stamp] {
+                if extreme.is_some_and(|extreme| compare_times(&extreme, &presentation_time_stamp) == Ordering::Equal) {
+                    *extreme = None;
+                }
+            }
+        }
+        Some(buffer)
+    }
+
+    fn take_buffers(&mut self) -> VecDeque<T> {
+        self.clear_totals();
+        mem::take(&mut self.buffers)
+    }
+
+    fn clear_totals(&mut self) {
+        self.duration = make_time(0, 1);
+        self.min_presentation_time_stamp = Some(CMTime::default());
+        self.max_presentation_time_stamp = Some(CMTime::default());
+    }
+
+    fn presentation_time_stamps(&self) -> impl Iterator<Item = CMTime> + '_ {
+        self.buffers.iter().map(|buffer| buffer.get_presentation_time_stamp()).filter(is_numeric)
+    }
+
+    fn min_presentation_time_stamp(&mut self) -> CMTime {
+        match self.min_presentation_time_stamp {
+            Some(min) => min,
+            None => {
+                let min = self.presentation_time_stamps().min_by(compare_times).unwrap_or_default();
+                *self.min_presentation_time_stamp.insert(min)
+            }
+        }
+    }
+
+    fn max_presentation_time_stamp(&mut self) -> CMTime {
+        match self.max_presentation_time_stamp {
+            Some(max) => max,
+            None => {
+                let max = self.presentation_time_stamps().max_by(compare_times).unwrap_or_default();
+                *self.max_presentation_time_stamp.insert(max)
+            }
+        }
+    }
+
+    fn is_at_end_of_data(&self) -> bool {
+        self.end_of_data && self.buffers.is_empty()
+    }
+
+    fn test_condition(&self, condition: CMBufferQueueTriggerCondition, time: &CMTime, threshold: usize) -> bool {
+        match condition {
+            kCMBufferQueueTrigger_WhenDurationBecomesLessThan => compare_times(&self.duration, time) == Ordering::Less,
+            kCMBufferQueueTrigger_WhenDurationBecomesLessThanOrEqualTo => compare_times(&self.duration, time) != Ordering::Greater,
+            kCMBufferQueueTrigger_WhenDurationBecomesGreaterThan => compare_times(&self.duration, time) == Ordering::Greater,
+            kCMBufferQueueTrigger_WhenDurationBecomesGreaterThanOrEqualTo => compare_times(&self.duration, time) != Ordering::Less,
+            kCMBufferQueueTrigger_WhenDataBecomesReady => self.buffers.front().is_some_and(|buffer| buffer.is_data_ready()),
+            kCMBufferQueueTrigger_WhenEndOfDataReached => self.is_at_end_of_data(),
+            kCMBufferQueueTrigger_WhenBufferCountBecomesLessThan => self.buffers.len() < threshold,
+            kCMBufferQueueTrigger_WhenBufferCountBecomesGreaterThan => self.buffers.len() > threshold,
+            kCMBufferQueueTrigger_WhenDurationBecomesGreaterThanOrEqualToAndBufferCountBecomesGreaterThan => {
+                compare_times(&self.duration, time) != Ordering::Less && self.buffers.len() > threshold
+            }
+            _ => false,
+        }
+    }
+
+    fn watched_time(&mut self, condition: CMBufferQueueTriggerCondition) -> CMTime {
+        match condition {
+            kCMBufferQueueTrigger_WhenMinPresentationTimeStampChanges => self.min_presentation_time_stamp(),
+            kCMBufferQueueTrigger_WhenMaxPresentationTimeStampChanges => self.max_presentation_time_stamp(),
+            _ => CMTime::default(),
+        }
+    }
+
+    fn update_triggers(&mut self, reset: bool) -> Vec<(BufferQueueTriggerToken, TriggerClosure)> {
+        let mut fired = Vec::new();
+        for index in 0..self.triggers.len() {
+            let trigger = &self.triggers[index];
+            let (condition, time, threshold) = (trigger.condition, trigger.time, trigger.threshold);
+            let (state, last_time) = (self.test_condition(condition, &time, threshold), self.watched_time(condition));
+            let trigger = &mut self.triggers[index];
+            let fires = match condition {
+                kCMBufferQueueTrigger_WhenMinPresentationTimeStampChanges | kCMBufferQueueTrigger_WhenMaxPresentationTimeStampChanges => {
+                    compare_times(&last_time, &trigger.last_time) != Ordering::Equal
+                }
+                kCMBufferQueueTrigger_WhenReset => reset,
+                _ => state && !trigger.state,
+            };
+            trigger.state = state;
+            trigger.last_time = last_time;
+            if let (true, Some(closure)) = (fires, trigger.closure.as_ref()) {
+                fired.push((trigger.token, closure.clone()));
+            }
+        }
+        fired
+    }
+}
+
+// Native counterpart of CMBufferQueue
+pub struct BufferQueue<T: BufferTiming> {
+    capacity: usize,
+    order: BufferQueueOrder,
+    state: Mutex<BufferQueueState<T>>,
+}
+
+impl<T: BufferTiming> BufferQueue<T> {
+    // A capacity of zero means unlimited
+    pub fn new(capacity: usize, order: BufferQueueOrder) -> Self {
+        Self {
+            capacity,
+            order,
+            state: Mutex::new(BufferQueueState::new()),
+        }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    pub fn order(&self) -> BufferQueueOrder {
+        self.order
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, BufferQueueState<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_for_modification(&self) -> Result<MutexGuard<'_, BufferQueueState<T>>, OSStatus> {
+        let state = self.lock();
+        if state.firing_threads.contains(&thread::current().id()) {
+            return Err(kCMBufferQueueError_CannotModifyQueueFromTriggerCallback);
+        }
+        Ok(state)
+    }
+
+    // Trigger closures run without the lock held so they can query the queue
+    fn fire_triggers(&self, mut state: MutexGuard<'_, BufferQueueState<T>>, reset: bool) {
+        let fired = state.update_triggers(reset);
+        if fired.is_empty() {
+            return;
+        }
+        let current = thread::current().id();
+        state.firing_threads.push(current);
+        drop(state);
+        for (token, closure) in fired {
+            closure(token);
+        }
+        let mut state = self.lock();
+        if let Some(index) = state.firing_threads.iter().position(|thread| *thread == current) {
+            state.firing_threads.swap_remove(index);
+        }
+    }
+
+    pub fn enqueue(&self, buffer: T) -> Result<(), OSStatus> {
+        let validation = self.lock_for_modification()?.validation.clone();
+        if let Some(validation) = validation {
+            validation(&buffer)?;
+        }
+        let mut state = self.lock_for_modification()?;
+        if state.end_of_data {
+            return Err(kCMBufferQueueError_EnqueueAfterEndOfData);
+        }
+        if self.capacity > 0 && state.buffers.len() >= self.capacity {
+            return Err(kCMBufferQueueError_QueueIsFull);
+        }
+        let index = match self.order {
+            BufferQueueOrder::Unsorted => state.buffers.len(),
+            BufferQueueOrder::SortedByOutputPresentationTimeStamp => {
+                let presentation_time_stamp = buffer.get_presentation_time_stamp();
+                state
+                    .buffers
+                    .iter()
+                    .rposition(|queued| compare_times(&queued.get_presentation_time_stamp(), &presentation_time_stamp) != Ordering::Greater)
+                    .map_or(0, |index| index + 1)
+            }
+        };
+        state.insert(index, buffer);
+        self.fire_triggers(state, false);
+        Ok(())
+    }
+
+    // Fails inside trigger callbacks rather than looking like an empty queue
+    pub fn dequeue(&self) -> Result<Option<T>, OSStatus> {
+        let mut state = self.lock_for_modification()?;
+        let buffer = match state.pop_front() {
+            Some(buffer) => buffer,
+            None => return Ok(None),
+        };
+        self.fire_triggers(state, false);
+        Ok(Some(buffer))
+    }
+
+    pub fn dequeue_if_data_ready(&self) -> Result<Option<T>, OSStatus> {
+        let mut state = self.lock_for_modification()?;
+        if !state.buffers.front().is_some_and(|buffer| buffer.is_data_ready()) {
+            return Ok(None);
+        }
+        let buffer = state.pop_front();
+        self.fire_triggers(state, false);
+        Ok(buffer)
+    }
+
+    pub fn copy_head(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.lock().buffers.front().cloned()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lock().buffers.is_empty()
+    }
+
+    pub fn mark_end_of_data(&self) -> Result<(), OSStatus> {
+        let mut state = self.lock_for_modification()?;
+        state.end_of_data = true;
+        self.fire_triggers(state, false);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn contains_end_of_data(&self) -> bool {
+        self.lock().end_of_data
+    }
+
+    #[inline]
+    pub fn is_at_end_of_data(&self) -> bool {
+        self.lock().is_at_end_of_data()
+    }
+
+    // Re-tests data ready triggers after a queued buffer became ready
+    pub fn notify_data_ready(&self) {
+        self.fire_triggers(self.lock(), false);
+    }
+
+    pub fn reset(&self) -> Result<(), OSStatus> {
+        self.reset_with_callback(drop)
+    }
+
+    pub fn reset_with_callback<F>(&self, callback: F) -> Result<(), OSStatus>
+    where
+        F: FnMut(T),
+    {
+        let mut state = self.lock_for_modification()?;
+        let buffers = state.take_buffers();
+        state.end_of_data = false;
+        drop(state);
+        buffers.into_iter().for_each(callback);
+        self.fire_triggers(self.lock(), true);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get_buffer_count(&self) -> usize {
+        self.lock().buffers.len()
+    }
+
+    #[inline]
+    pub fn get_duration(&self) -> CMTime {
+        self.lock().duration
+    }
+
+    pub fn get_min_decode_time_stamp(&self) -> CMTime {
+        self.lock().buffers.iter().map(|buffer| buffer.get_decode_time_stamp()).filter(is_numeric).min_by(compare_times).unwrap_or_default()
+    }
+
+    pub fn get_first_decode_time_stamp(&self) -> CMTime {
+        self.lock().buffers.front().map(|buffer| buffer.get_decode_time_stamp()).unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn get_min_presentation_time_stamp(&self) -> CMTime {
+        self.lock().min_presentation_time_stamp()
+    }
+
+    pub fn get_first_presentation_time_stamp(&self) -> CMTime {
+        self.lock().buffers.front().map(|buffer| buffer.get_presentation_time_stamp()).unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn get_max_presentation_time_stamp(&self) -> CMTime {
+        self.lock().max_presentation_time_stamp()
+    }
+
+    pub fn get_end_presentation_time_stamp(&self) -> CMTime {
+        self.lock()
+            .buffers
+            .iter()
+            .filter_map(|buffer| {
+                let presentation_time_stamp = buffer.get_presentation_time_stamp();
+                let duration = buffer.get_duration();
+                if !is_numeric(&presentation_time_stamp) {
+                    return None;
+                }
+                Some(if is_numeric(&duration) {
+                    add_times(&presentation_time_stamp, &duration)
+                } else {
+                    presentation_time_stamp
+                })
+            })
+            .max_by(compare_times)
+            .unwrap_or_default()
+    }
+
+    pub fn get_total_size(&self) -> usize {
+        self.lock().buffers.iter().map(|buffer| buffer.get_size()).sum()
+    }
+
+    fn install_trigger(
+        &self,
+        condition: CMBufferQueueTriggerCondition,
+        time: CMTime,
+        threshold: usize,
+        closure: Option<TriggerClosure>,
+    ) -> Result<BufferQueueTriggerToken, OSStatus> {
+        match condition {
+            kCMBufferQueueTrigger_WhenDurationBecomesLessThan..=kCMBufferQueueTrigger_WhenDurationBecomesGreaterThanOrEqualTo |
+            kCMBufferQueueTrigger_WhenDurationBecomesGreaterThanOrEqualToAndBufferCountBecomesGreaterThan => {
+                if !is_numeric(&time) {
+                    return Err(kCMBufferQueueError_BadTriggerDuration);
+                }
+            }
+            kCMBufferQueueTrigger_WhenMinPresentationTimeStampChanges..=kCMBufferQueueTrigger_WhenBufferCountBecomesGreaterThan => {}
+            _ => return Err(kCMBufferQueueError_InvalidTriggerCondition),
+        }
+        let mut state = self.lock();
+        let token = BufferQueueTriggerToken(state.next_token);
+        state.next_token += 1;
+        let trigger = BufferQueueTrigger {
+            token,
+            condition,
+            time,
+            threshold,
+            closure,
+            state: state.test_condition(condition, &time, threshold),
+            last_time: state.watched_time(condition),
+        };
+        state.triggers.push(trigger);
+        Ok(token)
+    }
+
+    pub fn install_trigger_closure<F>(
+        &self,
+        condition: CMBufferQueueTriggerCondition,
+        time: CMTime,
+        closure: Option<F>,
+    ) -> Result<BufferQueueTriggerToken, OSStatus>
+    where
+        F: Fn(BufferQueueTriggerToken) + Send + Sync + 'static,
+    {
+        // CoreMedia pairs the duration with a buffer count greater than one
+        let threshold = if condition == kCMBufferQueueTrigger_WhenDurationBecomesGreaterThanOrEqualToAndBufferCountBecomesGreaterThan {
+            1
+        } else {
+            0
+        };
+        self.install_trigger(condition, time, threshold, closure.map(|closure| Arc::new(closure) as TriggerClosure))
+    }
+
+    pub fn install_trigger_with_integer_threshold_closure<F>(
+        &self,
+        condition: CMBufferQueueTriggerCondition,
+        threshold: usize,
+        closure: Option<F>,
+    ) -> Result<BufferQueueTriggerToken, OSStatus>
+    where
+        F: Fn(BufferQueueTriggerToken) + Send + Sync + 'static,
+    {
+        self.install_trigger(condition, CMTime::default(), threshold, closure.map(|closure| Arc::new(closure) as TriggerClosure))
+    }
+
+    pub fn remove_trigger(&self, token: BufferQueueTriggerToken) -> Result<(), OSStatus> {
+        let mut state = self.lock();
+        let index = state.triggers.iter().position(|trigger| trigger.token == token).ok_or(kCMBufferQueueError_InvalidTriggerToken)?;
+        state.triggers.remove(index);
+        Ok(())
+    }
+
+    pub fn test_trigger(&self, token: BufferQueueTriggerToken) -> bool {
+        let state = self.lock();
+        state
+            .triggers
+            .iter()
+            .find(|trigger| trigger.token == token)
+            .is_some_and(|trigger| state.test_condition(trigger.condition, &trigger.time, trigger.threshold))
+    }
+
+    // The callback runs with the queue locked and must not call back into it
+    pub fn call_for_each_buffer<F>(&self, callback: F) -> Result<(), OSStatus>
+    where
+        F: FnMut(&T) -> Result<(), OSStatus>,
+    {
+        self.lock().buffers.iter().try_for_each(callback)
+    }
+
+    pub fn set_validation_closure<F>(&self, closure: Option<F>) -> Result<(), OSStatus>
+    where
+        F: Fn(&T) -> Result<(), OSStatus> + Send + Sync + 'static,
+    {
+        self.lock_for_modification()?.validation = closure.map(|closure| Arc::new(closure) as ValidationClosure<T>);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Weak, atomic::Ordering as AtomicOrdering};
+
+    use super::*;
+    use crate::test_support::MockBuffer;
+
+    type Fired = Arc<Mutex<Vec<BufferQueueTriggerToken>>>;
+
+    fn recorder() -> (Fired, impl Fn(BufferQueueTriggerToken) + Send + Sync + 'static) {
+        let fired = Fired::default();
+        let sink = fired.clone();
+        (fired, move |token| sink.lock().unwrap().push(token))
+    }
+
+    fn fired_count(fired: &Fired) -> usize {
+        fired.lock().unwrap().len()
+    }
+
+    #[test]
+    fn count_triggers() {
+        let queue = BufferQueue::new(0, BufferQueueOrder::Unsorted);
+        let (greater, closure) = recorder();
+        let greater_token =
+            queue.install_trigger_with_integer_threshold_closure(kCMBufferQueueTrigger_WhenBufferCountBecomesGreaterThan, 1, Some(closure)).unwrap();
+        let (less, closure) = recorder();
+        queue.install_trigger_with_integer_threshold_closure(kCMBufferQueueTrigger_WhenBufferCountBecomesLessThan, 2, Some(closure)).unwrap();
+
+        queue.enqueue(MockBuffer::new(0)).unwrap();
+        assert_eq!(fired_count(&greater), 0);
+        queue.enqueue(MockBuffer::new(1)).unwrap();
+        assert_eq!(*greater.lock().unwrap(), vec![greater_token]);
+        // Only the transition fires
+        queue.enqueue(MockBuffer::new(2)).unwrap();
+        assert_eq!(fired_count(&greater), 1);
+        assert!(queue.test_trigger(greater_token));
+
+        queue.dequeue().unwrap().unwrap();
+        assert_eq!(fired_count(&less), 0);
+        queue.dequeue().unwrap().unwrap();
+        assert_eq!(fired_count(&less), 1);
+        queue.dequeue().unwrap().unwrap();
+        assert_eq!(fired_count(&less), 1);
+        assert!(!queue.test_trigger(greater_token));
+    }
+
+    #[test]
+    fn duration_triggers() {
+        let queue = BufferQueue::new(0, BufferQueueOrder::Unsorted);
+        let (greater_or_equal, closure) = recorder();
+        queue.install_trigger_closure(kCMBufferQueueTrigger_WhenDurationBecomesGreaterThanOrEqualTo, make_time(1, 15), Some(closure)).unwrap();
+        let (less, closure) = recorder();
+        queue.install_trigger_closure(kCMBufferQueueTrigger_WhenDurationBecomesLessThan, make_time(1, 15), Some(closure)).unwrap();
+        assert_eq!(
+            queue.install_trigger_closure(kCMBufferQueueTrigger_WhenDurationBecomesGreaterThan, CMTime::default(), None::<fn(_)>),
+            Err(kCMBufferQueueError_BadTriggerDuration)
+        );
+
+        queue.enqueue(MockBuffer::new(0)).unwrap();
+        assert_eq!(fired_count(&greater_or_equal), 0);
+        queue.enqueue(MockBuffer::new(1)).unwrap();
+        assert_eq!(fired_count(&greater_or_equal), 1);
+        assert_eq!(compare_times(&queue.get_duration(), &make_time(2, 30)), Ordering::Equal);
+
+        queue.dequeue().unwrap().unwrap();
+        assert_eq!(fired_count(&less), 1);
+    }
+
+    #[test]
+    fn duration_and_count_trigger() {
+        let queue = BufferQueue::new(0, BufferQueueOrder::Unsorted);
+        let (fired, closure) = recorder();
+        let condition = kCMBufferQueueTrigger_WhenDurationBecomesGreaterThanOrEqualToAndBufferCountBecomesGreaterThan;
+        let token = queue.install_trigger_closure(condition, make_time(1, 30), Some(closure)).unwrap();
+
+        // A single buffer covers the duration but the count must exceed one
+        queue.enqueue(MockBuffer::new(0)).unwrap();
+        assert_eq!(fired_count(&fired), 0);
+        assert!(!queue.test_trigger(token));
+        queue.enqueue(MockBuffer::new(1)).unwrap();
+        assert_eq!(fired_count(&fired), 1);
+    }
+
+    #[test]
+    fn data_ready_trigger() {
+        let queue = BufferQueue::new(0, BufferQueueOrder::Unsorted);
+        let (ready, closure) = recorder();
+        queue.install_trigger_closure(kCMBufferQueueTrigger_WhenDataBecomesReady, CMTime::default(), Some(closure)).unwrap();
+
+        let buffer = MockBuffer::new(0);
+        buffer.ready.store(false, AtomicOrdering::SeqCst);
+        queue.enqueue(buffer.clone()).unwrap();
+        assert_eq!(fired_count(&ready), 0);
+        assert!(queue.dequeue_if_data_ready().unwrap().is_none());
+
+        buffer.ready.store(true, AtomicOrdering::SeqCst);
+        queue.notify_data_ready();
+        assert_eq!(fired_count(&ready), 1);
+        assert_eq!(queue.dequeue_if_data_ready().unwrap().map(|buffer| buffer.time), Some(0));
+    }
+
+    #[test]
+    fn end_of_data_trigger() {
+        let queue = BufferQueue::new(0, BufferQueueOrder::Unsorted);
+        let (end, closure) = recorder();
+        queue.install_trigger_closure(kCMBufferQueueTrigger_WhenEndOfDataReached, CMTime::default(), Some(closure)).unwrap();
+
+        queue.enqueue(MockBuffer::new(0)).unwrap();
+        queue.mark_end_of_data().unwrap();
+        assert!(queue.contains_end_of_data());
+        assert_eq!(fired_count(&end), 0);
+        assert_eq!(queue.enqueue(MockBuffer::new(1)), Err(kCMBufferQueueError_EnqueueAfterEndOfData));
+
+        queue.dequeue().unwrap().unwrap();
+        assert!(queue.is_at_end_of_data());
+        assert_eq!(fired_count(&end), 1);
+    }
+
+    #[test]
+    fn reset_trigger() {
+        let queue = BufferQueue::new(0, BufferQueueOrder::Unsorted);
+        let (reset, closure) = recorder();
+        queue.install_trigger_closure(kCMBufferQueueTrigger_WhenReset, CMTime::default(), Some(closure)).unwrap();
+
+        queue.enqueue(MockBuffer::new(0)).unwrap();
+        queue.enqueue(MockBuffer::new(1)).unwrap();
+        queue.mark_end_of_data().unwrap();
+        assert_eq!(fired_count(&reset), 0);
+
+        let mut removed = Vec::new();
+        queue.reset_with_callback(|buffer| removed.push(buffer.time)).unwrap();
+        assert_eq!(removed, vec![0, 1]);
+        assert_eq!(fired_count(&reset), 1);
+        assert!(queue.is_empty());
+        assert!(!queue.contains_end_of_data());
+        queue.enqueue(MockBuffer::new(2)).unwrap();
+    }
+
+    #[test]
+    fn presentation_time_stamp_triggers() {
+        let queue = BufferQueue::new(0, BufferQueueOrder::Unsorted);
+        let (min, closure) = recorder();
+        queue.install_trigger_closure(kCMBufferQueueTrigger_WhenMinPresentationTimeStampChanges, CMTime::default(), Some(closure)).unwrap();
+        let (max, closure) = recorder();
+        queue.install_trigger_closure(kCMBufferQueueTrigger_WhenMaxPresentationTimeStampChanges, CMTime::default(), Some(closure)).unwrap();
+
+        // Decode order of an I P B B group
+        for time in [1, 4, 2, 3] {
+            queue.enqueue(MockBuffer::new(time)).unwrap();
+        }
+        assert_eq!((fired_count(&min), fired_count(&max)), (1, 2));
+        assert_eq!(compare_times(&queue.get_duration(), &make_time(4, 30)), Ordering::Equal);
+
+        // Dequeuing the buffer holding an extreme finds the next one
+        queue.dequeue().unwrap().unwrap();
+        assert_eq!((fired_count(&min), fired_count(&max)), (2, 2));
+        assert_eq!(compare_times(&queue.get_min_presentation_time_stamp(), &make_time(2, 30)), Ordering::Equal);
+        queue.dequeue().unwrap().unwrap();
+        assert_eq!((fired_count(&min), fired_count(&max)), (2, 3));
+        assert_eq!(compare_times(&queue.get_max_presentation_time_stamp(), &make_time(3, 30)), Ordering::Equal);
+        assert_eq!(compare_times(&queue.get_duration(), &make_time(2, 30)), Ordering::Equal);
+
+        queue.reset().unwrap();
+        assert_eq!((fired_count(&min), fired_count(&max)), (3, 4));
+        assert_eq!(compare_times(&queue.get_duration(), &make_time(0, 1)), Ordering::Equal);
+        assert_eq!(queue.get_max_presentation_time_stamp().flags, CMTime::default().flags);
+    }
+
+    #[test]
+    fn cannot_modify_queue_from_trigger() {
+        let queue = Arc::new(BufferQueue::new(0, BufferQueueOrder::Unsorted));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let weak: Weak<BufferQueue<MockBuffer>> = Arc::downgrade(&queue);
+        let recorded = results.clone();
+        queue
+            .install_trigger_with_integer_threshold_closure(
+                kCMBufferQueueTrigger_WhenBufferCountBecomesGreaterThan,
+                0,
+                Some(move |_| {
+                    let queue = weak.upgrade().unwrap();
+                    // Reading the queue is allowed, modifying it is not
+                    let count = queue.get_buffer_count();
+                    recorded.lock().unwrap().push((
+                        count,
+                        queue.enqueue(MockBuffer::new(1)),
+                        queue.reset(),
+                        queue.dequeue().map(|buffer| buffer.is_some()),
+                    ));
+                }),
+            )
+            .unwrap();
+
+        queue.enqueue(MockBuffer::new(0)).unwrap();
+        assert_eq!(
+            *results.lock().unwrap(),
+            vec![(
+                1,
+                Err(kCMBufferQueueError_CannotModifyQueueFromTriggerCallback),
+                Err(kCMBufferQueueError_CannotModifyQueueFromTriggerCallback),
+                Err(kCMBufferQueueError_CannotModifyQueueFromTriggerCallback)
+            )]
+        );
+        assert_eq!(queue.get_buffer_count(), 1);
+        // The restriction ends once the triggers have run
+        queue.enqueue(MockBuffer::new(1)).unwrap();
+    }
+}
@@ -0,0 +1,771 @@
+use std::cmp::Ordering;
+
+use core_foundation::base::OSStatus;
+
+use crate::{
+    block_buffer::{CMBlockBuffer, TBlockBuffer},
+    format_description::{CMClosedCaptionFormatDescription, TCMFormatDescription},
+    sample_buffer::{kCMSampleBufferError_InvalidEntryCount, kCMSampleBufferError_RequiredParameterMissing, CMSampleBuffer, CMSampleTimingInfo},
+    time::{compare_times, kCMTimeInvalid, CMTime},
+};
+
+const H264_NAL_UNIT_TYPE_SEI: u8 = 6;
+const HEVC_NAL_UNIT_TYPE_PREFIX_SEI: u8 = 39;
+const HEVC_NAL_UNIT_TYPE_SUFFIX_SEI: u8 = 40;
+const SEI_PAYLOAD_TYPE_USER_DATA_REGISTERED: u32 = 4;
+const ITU_T_T35_COUNTRY_CODE_USA: u8 = 0xB5;
+const ATSC_PROVIDER_CODE: u16 = 0x0031;
+const DIRECTV_PROVIDER_CODE: u16 = 0x002F;
+const ATSC_USER_IDENTIFIER: &[u8; 4] = b"GA94";
+const CC_DATA_USER_DATA_TYPE_CODE: u8 = 0x03;
+
+const CEA608_ROWS: usize = 15;
+const CEA608_COLUMNS: usize = 32;
+const CEA608_PADDING: [u8; 2] = [0x80, 0x80];
+const CEA608_FIELD1_ATOM_TYPE: &[u8; 4] = b"cdat";
+const CEA608_FIELD2_ATOM_TYPE: &[u8; 4] = b"cdt2";
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ClosedCaptionCodec {
+    H264,
+    HEVC,
+}
+
+impl ClosedCaptionCodec {
+    fn nal_header_size(&self) -> usize {
+        match self {
+            ClosedCaptionCodec::H264 => 1,
+            ClosedCaptionCodec::HEVC => 2,
+        }
+    }
+
+    fn is_sei(&self, nal_unit: &[u8]) -> bool {
+        match self {
+            ClosedCaptionCodec::H264 => nal_unit[0] & 0x1F == H264_NAL_UNIT_TYPE_SEI,
+            ClosedCaptionCodec::HEVC => {
+                let nal_unit_type = (nal_unit[0] >> 1) & 0x3F;
+                nal_unit_type == HEVC_NAL_UNIT_TYPE_PREFIX_SEI || nal_unit_type == HEVC_NAL_UNIT_TYPE_SUFFIX_SEI
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CcType {
+    Cea608Field1,
+    Cea608Field2,
+    DtvccPacketData,
+    DtvccPacketStart,
+}
+
+impl CcType {
+    fn from_bits(bits: u8) -> Self {
+        match bits & 0x03 {
+            0 => CcType::Cea608Field1,
+            1 => CcType::Cea608Field2,
+            2 => CcType::DtvccPacketData,
+            _ => CcType::DtvccPacketStart,
+        }
+    }
+
+    #[inline]
+    pub fn is_cea608(&self) -> bool {
+        matches!(self, CcType::Cea608Field1 | CcType::Cea608Field2)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CcData {
+    pub valid: bool,
+    pub cc_type: CcType,
+    pub data: [u8; 2],
+}
+
+fn remove_emulation_prevention_bytes(data: &[u8]) -> Vec<u8> {
+    let mut rbsp = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+    for byte in data {
+        if zeros >= 2 && *byte == 0x03 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if *byte == 0 {
+            zeros + 1
+        } else {
+            0
+        };
+        rbsp.push(*byte);
+    }
+    rbsp
+}
+
+fn read_sei_value(rbsp: &[u8], offset: &mut usize) -> Option<u32> {
+    let mut value = 0u32;
+    loop {
+        let byte = *rbsp.get(*offset)?;
+        *offset += 1;
+        value = value.checked_add(byte as u32)?;
+        if byte != 0xFF {
+            return Some(value);
+        }
+    }
+}
+
+fn parse_cc_data(payload: &[u8], cc_data: &mut Vec<CcData>) {
+    if payload.len() < 3 || payload[0] != ITU_T_T35_COUNTRY_CODE_USA {
+        return;
+    }
+    let provider_code = u16::from_be_bytes([payload[1], payload[2]]);
+    let user_data = match provider_code {
+        ATSC_PROVIDER_CODE if payload[3..].starts_with(ATSC_USER_IDENTIFIER) => &payload[7..],
+        DIRECTV_PROVIDER_CODE => &payload[3..],
+        _ => return,
+    };
+    if user_data.len() < 3 || user_data[0] != CC_DATA_USER_DATA_TYPE_CODE {
+        return;
+    }
+    // process_cc_data_flag
+    if user_data[1] & 0x40 == 0 {
+        return;
+    }
+    let cc_count = (user_data[1] & 0x1F) as usize;
+    // Skips em_data
+    for triplet in user_data[3..].chunks_exact(3).take(cc_count) {
+        cc_data.push(CcData {
+            valid: triplet[0] & 0x04 != 0,
+            cc_type: CcType::from_bits(triplet[0]),
+            data: [triplet[1], triplet[2]],
+        });
+    }
+}
+
+fn parse_sei_rbsp(rbsp: &[u8], cc_data: &mut Vec<CcData>) {
+    let mut offset = 0;
+    // Stops at rbsp_trailing_bits
+    while offset < rbsp.len() && rbsp[offset] != 0x80 {
+        let payload_type = match read_sei_value(rbsp, &mut offset) {
+            Some(payload_type) => payload_type,
+            None => return,
+        };
+        let payload_size = match read_sei_value(rbsp, &mut offset) {
+            Some(payload_size) => payload_size as usize,
+            None => return,
+        };
+        let payload = match rbsp.get(offset..offset + payload_size) {
+            Some(payload) => payload,
+            None => return,
+        };
+        if payload_type == SEI_PAYLOAD_TYPE_USER_DATA_REGISTERED {
+            parse_cc_data(payload, cc_data);
+        }
+        offset += payload_size;
+    }
+}
+
+// Samples hold length-prefixed NAL units as in avcC and hvcC streams
+pub fn extract_cc_data(sample: &[u8], codec: ClosedCaptionCodec, nal_unit_header_length: usize) -> Vec<CcData> {
+    let mut cc_data = Vec::new();
+    if !(1..=4).contains(&nal_unit_header_length) {
+        return cc_data;
+    }
+    let mut offset = 0;
+    while offset + nal_unit_header_length <= sample.len() {
+        let length = sample[offset..offset + nal_unit_header_length].iter().fold(0usize, |length, byte| (length << 8) | *byte as usize);
+        offset += nal_unit_header_length;
+        let nal_unit = match sample.get(offset..offset + length) {
+            Some(nal_unit) => nal_unit,
+            None => break,
+        };
+        offset += length;
+        if nal_unit.len() > codec.nal_header_size() && codec.is_sei(nal_unit) {
+            parse_sei_rbsp(&remove_emulation_prevention_bytes(&nal_unit[codec.nal_header_size()..]), &mut cc_data);
+        }
+    }
+    cc_data
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClosedCaptionFrame {
+    pub presentation_time: CMTime,
+    pub duration: CMTime,
+    pub cc_data: Vec<CcData>,
+}
+
+impl ClosedCaptionFrame {
+    pub fn cea608_pairs(&self, cc_type: CcType) -> impl Iterator<Item = [u8; 2]> + '_ {
+        self.cc_data.iter().filter(move |cc| cc.valid && cc.cc_type == cc_type && cc.data != CEA608_PADDING).map(|cc| cc.data)
+    }
+
+    // QuickTime c608 sample data, byte pairs keep their parity bits
+    pub fn to_cea608_sample(&self) -> Vec<u8> {
+        let mut sample = Vec::new();
+        for (cc_type, atom_type) in [(CcType::Cea608Field1, CEA608_FIELD1_ATOM_TYPE), (CcType::Cea608Field2, CEA608_FIELD2_ATOM_TYPE)] {
+            let pairs: Vec<[u8; 2]> = self.cea608_pairs(cc_type).collect();
+            if pairs.is_empty() {
+                continue;
+            }
+            sample.extend_from_slice(&(8 + pairs.len() as u32 * 2).to_be_bytes());
+            sample.extend_from_slice(atom_type);
+            pairs.iter().for_each(|pair| sample.extend_from_slice(pair));
+        }
+        sample
+    }
+}
+
+// Takes samples in decode order and returns caption data in presentation order
+pub struct ClosedCaptionExtractor {
+    codec: ClosedCaptionCodec,
+    nal_unit_header_length: usize,
+    reorder_depth: usize,
+    pending: Vec<ClosedCaptionFrame>,
+}
+
+impl ClosedCaptionExtractor {
+    // The reorder depth can come from SampleTimingAnalysis::reorder_depth
+    pub fn new(codec: ClosedCaptionCodec, nal_unit_header_length: usize, reorder_depth: usize) -> Self {
+        Self {
+            codec,
+            nal_unit_header_length,
+            reorder_depth,
+            pending: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn reorder_depth(&self) -> usize {
+        self.reorder_depth
+    }
+
+    pub fn push(&mut self, sample: &[u8], presentation_time: CMTime, duration: CMTime) -> Vec<ClosedCaptionFrame> {
+        let frame = ClosedCaptionFrame {
+            presentation_time,
+            duration,
+            cc_data: extract_cc_data(sample, self.codec, self.nal_unit_header_length),
+        };
+        let index = self.pending.iter().position(|pending| compare_times(&pending.presentation_time, &presentation_time) == Ordering::Greater);
+        self.pending.insert(index.unwrap_or(self.pending.len()), frame);
+        let ready = self.pending.len().saturating_sub(self.reorder_depth);
+        self.pending.drain(..ready).filter(|frame| !frame.cc_data.is_empty()).collect()
+    }
+
+    pub fn push_sample_buffer(&mut self, sample_buffer: &CMSampleBuffer) -> Result<Vec<ClosedCaptionFrame>, OSStatus> {
+        let data_buffer = sample_buffer.get_data_buffer().ok_or(kCMSampleBufferError_RequiredParameterMissing)?;
+        let mut sample = vec![0; data_buffer.get_data_length()];
+        data_buffer.copy_data_bytes(0, &mut sample)?;
+        Ok(self.push(&sample, sample_buffer.get_presentation_time_stamp(), sample_buffer.get_duration()))
+    }
+
+    pub fn flush(&mut self) -> Vec<ClosedCaptionFrame> {
+        self.pending.drain(..).filter(|frame| !frame.cc_data.is_empty()).collect()
+    }
+}
+
+// One c608 sample per frame, frames without CEA-608 data are skipped
+pub fn new_cea608_sample_buffer(
+    format_description: &CMClosedCaptionFormatDescription,
+    frames: &[ClosedCaptionFrame],
+) -> Result<CMSampleBuffer, OSStatus> {
+    let mut block_buffer = CMBlockBuffer::new_empty(0, 0)?;
+    let mut timing_infos = Vec::new();
+    let mut sample_sizes = Vec::new();
+    for frame in frames {
+        let sample = frame.to_cea608_sample();
+        if sample.is_empty() {
+            continue;
+        }
+        block_buffer.append_data_bytes(&sample)?;
+        timing_infos.push(CMSampleTimingInfo {
+            duration: frame.duration,
+            presentationTimeStamp: frame.presentation_time,
+            decodeTimeStamp: unsafe { kCMTimeInvalid },
+        });
+        sample_sizes.push(sample.len());
+    }
+    if timing_infos.is_empty() {
+        return Err(kCMSampleBufferError_InvalidEntryCount);
+    }
+    CMSampleBuffer::new_ready(&block_buffer, Some(&format_description.as_buffer()), timing_infos.len() as _, Some(&timing_infos), Some(&sample_sizes))
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Cea608Channel {
+    CC1,
+    CC2,
+    CC3,
+    CC4,
+}
+
+impl Cea608Channel {
+    #[inline]
+    pub fn cc_type(&self) -> CcType {
+        match self {
+            Cea608Channel::CC1 | Cea608Channel::CC2 => CcType::Cea608Field1,
+            Cea608Channel::CC3 | Cea608Channel::CC4 => CcType::Cea608Field2,
+        }
+    }
+
+    #[inline]
+    fn data_channel(&self) -> u8 {
+        match self {
+            Cea608Channel::CC1 | Cea608Channel::CC3 => 1,
+            Cea608Channel::CC2 | Cea608Channel::CC4 => 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Cea608Mode {
+    PopOn,
+    RollUp(u8),
+    PaintOn,
+    Text,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Cea608Row {
+    // Zero based
+    pub row: u8,
+    pub column: u8,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Cea608Screen {
+    pub rows: Vec<Cea608Row>,
+}
+
+impl Cea608Screen {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn text(&self) -> String {
+        self.rows.iter().map(|row| row.text.as_str()).collect::<Vec<_>>().join("\n")
+    }
+}
+
+// An empty screen means the captions were cleared
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cea608Event {
+    pub time: CMTime,
+    pub screen: Cea608Screen,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+struct Cea608Memory {
+    cells: [[char; CEA608_COLUMNS]; CEA608_ROWS],
+}
+
+impl Cea608Memory {
+    fn new() -> Self {
+        Self {
+            cells: [[' '; CEA608_COLUMNS]; CEA608_ROWS],
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    fn clear_row(&mut self, row: usize) {
+        self.cells[row] = [' '; CEA608_COLUMNS];
+    }
+
+    fn screen(&self) -> Cea608Screen {
+        let rows = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter_map(|(row, cells)| {
+                let first = cells.iter().position(|c| *c != ' ')?;
+                let last = cells.iter().rposition(|c| *c != ' ')?;
+                Some(Cea608Row {
+                    row: row as u8,
+                    column: first as u8,
+                    text: cells[first..=last].iter().collect(),
+                })
+            })
+            .collect();
+        Cea608Screen {
+            rows,
+        }
+    }
+}
+
+// Rows for the preamble address codes, by the low bits of the first byte
+const PAC_ROWS: [[usize; 2]; 8] = [[10, 10], [0, 1], [2, 3], [11, 12], [13, 14], [4, 5], [6, 7], [8, 9]];
+
+const SPECIAL_CHARACTERS: [char; 16] = ['®', '°', '½', '¿', '™', '¢', '£', '♪', 'à', ' ', 'è', 'â', 'ê', 'î', 'ô', 'û'];
+
+const EXTENDED_CHARACTERS: [[char; 32]; 2] = [
+    [
+        'Á', 'É', 'Ó', 'Ú', 'Ü', 'ü', '‘', '¡', '*', '’', '—', '©', '℠', '•', '“', '”', 'À', 'Â', 'Ç', 'È', 'Ê', 'Ë', 'ë', 'Î', 'Ï', 'ï', 'Ô', 'Ù',
+        'ù', 'Û', '«', '»',
+    ],
+    [
+        'Ã', 'ã', 'Í', 'Ì', 'ì', 'Ò', 'ò', 'Õ', 'õ', '{', '}', '\\', '^', '_', '|', '~', 'Ä', 'ä', 'Ö', 'ö', 'ß', '¥', '¤', '│', 'Å', 'å', 'Ø', 'ø',
+        '┌', '┐', '└', '┘',
+    ],
+];
+
+fn basic_character(byte: u8) -> char {
+    match byte {
+        0x2A => 'á',
+        0x5C => 'é',
+        0x5E => 'í',
+        0x5F => 'ó',
+        0x60 => 'ú',
+        0x7B => 'ç',
+        0x7C => '÷',
+        0x7D => 'Ñ',
+        0x7E => 'ñ',
+        0x7F => '█',
+        _ => byte as char,
+    }
+}
+
+pub struct Cea608Decoder {
+    channel: Cea608Channel,
+    mode: Option<Cea608Mode>,
+    data_channel: u8,
+    displayed: Cea608Memory,
+    non_displayed: Cea608Memory,
+    row: usize,
+    column: usize,
+    last_control: Option<[u8; 2]>,
+    in_xds: bool,
+}
+
+impl Cea608Decoder {
+    pub fn new(channel: Cea608Channel) -> Self {
+        Self {
+            channel,
+            mode: None,
+            data_channel: 1,
+            displayed: Cea608Memory::new(),
+            non_displayed: Cea608Memory::new(),
+            row: CEA608_ROWS - 1,
+            column: 0,
+            last_control: None,
+            in_xds: false,
+        }
+    }
+
+    #[inline]
+    pub fn channel(&self) -> Cea608Channel {
+        self.channel
+    }
+
+    #[inline]
+    pub fn mode(&self) -> Option<Cea608Mode> {
+        self.mode
+    }
+
+    #[inline]
+    pub fn screen(&self) -> Cea608Screen {
+        self.displayed.screen()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.channel);
+    }
+
+    pub fn decode_frame(&mut self, frame: &ClosedCaptionFrame) -> Vec<Cea608Event> {
+        frame.cc_data.iter().filter_map(|cc| self.decode(frame.presentation_time, cc)).collect()
+    }
+
+    // Returns an event when the displayed captions change
+    pub fn decode(&mut self, time: CMTime, cc: &CcData) -> Option<Cea608Event> {
+        if !cc.valid || cc.cc_type != self.channel.cc_type() {
+            return None;
+        }
+        let first = cc.data[0] & 0x7F;
+        let second = cc.data[1] & 0x7F;
+        if first == 0 && second == 0 {
+            return None;
+        }
+        let displayed = self.displayed;
+        if (0x10..=0x1F).contains(&first) {
+            self.decode_control(first, second);
+        } else if (0x01..=0x0F).contains(&first) {
+            // Extended data services on field 2
+            self.in_xds = first != 0x0F;
+            self.last_control = None;
+        } else if first >= 0x20 {
+            self.last_control = None;
+            if self.in_xds || self.data_channel != self.channel.data_channel() {
+                return None;
+            }
+            self.put_character(basic_character(first));
+            if second >= 0x20 {
+                self.put_character(basic_character(second));
+            }
+        }
+        if self.displayed == displayed {
+            return None;
+        }
+        Some(Cea608Event {
+            time,
+            screen: self.displayed.screen(),
+        })
+    }
+
+    fn decode_control(&mut self, first: u8, second: u8) {
+        // Control codes are sent twice for redundancy
+        if self.last_control == Some([first, second]) {
+            self.last_control = None;
+            return;
+        }
+        self.last_control = Some([first, second]);
+        if second < 0x20 {
+            return;
+        }
+        self.in_xds = false;
+        self.data_channel = if first & 0x08 != 0 {
+            2
+        } else {
+            1
+        };
+        if self.data_channel != self.channel.data_channel() {
+            return;
+        }
+        let first = first & !0x08;
+        match (first, second) {
+            (0x10, 0x60..=0x7F) => {}
+            (_, 0x40..=0x7F) => self.preamble_address(first, second),
+            (0x11, 0x20..=0x2F) => self.put_character(' '),
+            (0x11, 0x30..=0x3F) => self.put_character(SPECIAL_CHARACTERS[(second & 0x0F) as usize]),
+            (0x12..=0x13, 0x20..=0x3F) => {
+                // Replaces the basic character sent before it
+                self.backspace();
+                self.put_character(EXTENDED_CHARACTERS[(first - 0x12) as usize][(second - 0x20) as usize]);
+            }
+            (0x14..=0x15, 0x20..=0x2F) => self.miscellaneous_control(second),
+            (0x17, 0x21..=0x23) => self.column = (self.column + (second - 0x20) as usize).min(CEA608_COLUMNS - 1),
+            _ => {}
+        }
+    }
+
+    fn preamble_address(&mut self, first: u8, second: u8) {
+        let row = PAC_ROWS[(first & 0x07) as usize][((second & 0x20) != 0) as usize];
+        if let Some(Cea608Mode::RollUp(depth)) = self.mode {
+            if row != self.row {
+                self.move_roll_up_window(row, depth as usize);
+            }
+        }
+        self.row = row;
+        self.column = if second & 0x10 != 0 {
+            ((second & 0x0E) as usize) << 1
+        } else {
+            0
+        };
+    }
+
+    fn miscellaneous_control(&mut self, command: u8) {
+        match command {
+            // RCL
+            0x20 => self.mode = Some(Cea608Mode::PopOn),
+            // BS
+            0x21 => self.backspace(),
+            // DER
+            0x24 => {
+                let (row, column) = (self.row, self.column);
+                if let Some(memory) = self.target_memory() {
+                    memory.cells[row][column..].iter_mut().for_each(|c| *c = ' ');
+                }
+            }
+            // RU2, RU3 and RU4
+            0x25..=0x27 => {
+                let depth = command - 0x23;
+                if !matches!(self.mode, Some(Cea608Mode::RollUp(_))) {
+                    self.displayed.clear();
+                    self.non_displayed.clear();
+                    self.row = CEA608_ROWS - 1;
+                    self.column = 0;
+                }
+                self.mode = Some(Cea608Mode::RollUp(depth));
+                (0..(self.row + 1).saturating_sub(depth as usize)).for_each(|row| self.displayed.clear_row(row));
+            }
+            // RDC
+            0x29 => self.mode = Some(Cea608Mode::PaintOn),
+            // TR and RTD
+            0x2A | 0x2B => self.mode = Some(Cea608Mode::Text),
+            // EDM
+            0x2C => self.displayed.clear(),
+            // CR
+            0x2D => {
+                if let Some(Cea608Mode::RollUp(depth)) = self.mode {
+                    let top = (self.row + 1).saturating_sub(depth as usize);
+                    for row in top..self.row {
+                        self.displayed.cells[row] = self.displayed.cells[row + 1];
+                    }
+                    if top > 0 {
+                        self.displayed.clear_row(top - 1);
+                    }
+                    self.displayed.clear_row(self.row);
+                    self.column = 0;
+                }
+            }
+            // ENM
+            0x2E => self.non_displayed.clear(),
+            // EOC
+            0x2F => {
+                std::mem::swap(&mut self.displayed, &mut self.non_displayed);
+                self.mode = Some(Cea608Mode::PopOn);
+            }
+            _ => {}
+        }
+    }
+
+    fn move_roll_up_window(&mut self, base_row: usize, depth: usize) {
+        let depth = depth.min(base_row + 1);
+        let mut moved = Cea608Memory::new();
+        for offset in 0..depth.min(self.row + 1) {
+            moved.cells[base_row - offset] = self.displayed.cells[self.row - offset];
+        }
+        self.displayed = moved;
+    }
+
+    fn target_memory(&mut self) -> Option<&mut Cea608Memory> {
+        match self.mode? {
+            Cea608Mode::PopOn => Some(&mut self.non_displayed),
+            Cea608Mode::RollUp(_) | Cea608Mode::PaintOn => Some(&mut self.displayed),
+            Cea608Mode::Text => None,
+        }
+    }
+
+    fn put_character(&mut self, character: char) {
+        let (row, column) = (self.row, self.column);
+        if let Some(memory) = self.target_memory() {
+            memory.cells[row][column] = character;
+            // The last column is overwritten once the row is full
+            self.column = (column + 1).min(CEA608_COLUMNS - 1);
+        }
+    }
+
+    fn backspace(&mut self) {
+        if self.column == 0 {
+            return;
+        }
+        self.column -= 1;
+        let (row, column) = (self.row, self.column);
+        if let Some(memory) = self.target_memory() {
+            memory.cells[row][column] = ' ';
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::make_time;
+
+    // ATSC A/53 user data, the CEA-608 pairs carry odd parity
+    const CC_DATA_PAYLOAD: [u8; 26] = [
+        0xB5, 0x00, 0x31, b'G', b'A', b'9', b'4', 0x03, 0x45, 0xFF, 0xFC, 0x94, 0x20, 0xFD, 0x80, 0x80, 0xFF, 0x02, 0x21, 0xFE, 0x41, 0x42, 0xFA,
+        0x00, 0x00, 0xFF,
+    ];
+
+    fn sei_sample(nal_header: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut nal_unit = nal_header.to_vec();
+        // Unregistered user data first, 00 00 01 needs an emulation prevention byte
+        nal_unit.extend_from_slice(&[0x05, 0x04, 0x00, 0x00, 0x03, 0x01, 0xAA]);
+        nal_unit.extend_from_slice(&[SEI_PAYLOAD_TYPE_USER_DATA_REGISTERED as u8, payload.len() as u8]);
+        nal_unit.extend_from_slice(payload);
+        nal_unit.push(0x80);
+        let mut sample = (nal_unit.len() as u32).to_be_bytes().to_vec();
+        sample.extend_from_slice(&nal_unit);
+        sample
+    }
+
+    fn cc(cc_type: CcType, data: [u8; 2]) -> CcData {
+        CcData {
+            valid: true,
+            cc_type,
+            data,
+        }
+    }
+
+    fn expected_cc_data() -> Vec<CcData> {
+        vec![
+            cc(CcType::Cea608Field1, [0x94, 0x20]),
+            cc(CcType::Cea608Field2, [0x80, 0x80]),
+            cc(CcType::DtvccPacketStart, [0x02, 0x21]),
+            cc(CcType::DtvccPacketData, [0x41, 0x42]),
+            CcData {
+                valid: false,
+                cc_type: CcType::DtvccPacketData,
+                data: [0x00, 0x00],
+            },
+        ]
+    }
+
+    #[test]
+    fn extract_atsc_cc_data() {
+        assert_eq!(extract_cc_data(&sei_sample(&[0x06], &CC_DATA_PAYLOAD), ClosedCaptionCodec::H264, 4), expected_cc_data());
+        assert_eq!(extract_cc_data(&sei_sample(&[0x4E, 0x01], &CC_DATA_PAYLOAD), ClosedCaptionCodec::HEVC, 4), expected_cc_data());
+        // Not an SEI NAL unit
+        assert!(extract_cc_data(&sei_sample(&[0x05], &CC_DATA_PAYLOAD), ClosedCaptionCodec::H264, 4).is_empty());
+    }
+
+    #[test]
+    fn cea608_sample_skips_padding() {
+        let frame = ClosedCaptionFrame {
+            presentation_time: make_time(0, 30),
+            duration: make_time(1, 30),
+            cc_data: expected_cc_data(),
+        };
+        assert_eq!(frame.to_cea608_sample(), [0, 0, 0, 10, b'c', b'd', b'a', b't', 0x94, 0x20]);
+    }
+
+    #[test]
+    fn extractor_returns_presentation_order() {
+        let sample = sei_sample(&[0x06], &CC_DATA_PAYLOAD);
+        let mut extractor = ClosedCaptionExtractor::new(ClosedCaptionCodec::H264, 4, 1);
+        let duration = make_time(1, 30);
+        assert!(extractor.push(&sample, make_time(0, 30), duration).is_empty());
+        let ready = extractor.push(&sample, make_time(2, 30), duration);
+        assert_eq!(ready.iter().map(|frame| frame.presentation_time.value).collect::<Vec<_>>(), [0]);
+        let ready = extractor.push(&sample, make_time(1, 30), duration);
+        assert_eq!(ready.iter().map(|frame| frame.presentation_time.value).collect::<Vec<_>>(), [1]);
+        let ready = extractor.flush();
+        assert_eq!(ready.iter().map(|frame| frame.presentation_time.value).collect::<Vec<_>>(), [2]);
+    }
+
+    fn decode_pairs(decoder: &mut Cea608Decoder, pairs: &[[u8; 2]]) -> Vec<Cea608Screen> {
+        pairs.iter().filter_map(|pair| decoder.decode(make_time(0, 30), &cc(CcType::Cea608Field1, *pair))).map(|event| event.screen).collect()
+    }
+
+    #[test]
+    fn cea608_pop_on() {
+        let mut decoder = Cea608Decoder::new(Cea608Channel::CC1);
+        // RCL twice, PAC row 15 indent 4, "Hi", "É" replacing "E", EOC twice
+        let pairs = [[0x94, 0x20], [0x94, 0x20], [0x94, 0xF2], [0xC8, 0xE9], [0x45, 0x80], [0x92, 0xA1], [0x94, 0x2F], [0x94, 0x2F]];
+        let screens = decode_pairs(&mut decoder, &pairs);
+        assert_eq!(decoder.mode(), Some(Cea608Mode::PopOn));
+        assert_eq!(
+            screens,
+            [Cea608Screen {
+                rows: vec![Cea608Row {
+                    row: 14,
+                    column: 4,
+                    text: "HiÉ".to_string(),
+                }],
+            }]
+        );
+        // EDM clears the display, CC2 data is ignored
+        assert_eq!(decode_pairs(&mut decoder, &[[0x1C, 0x2C], [0x94, 0x2C]]), [Cea608Screen::default()]);
+    }
+
+    #[test]
+    fn cea608_roll_up() {
+        let mut decoder = Cea608Decoder::new(Cea608Channel::CC1);
+        // RU2, PAC row 15, "AB", CR, "CD"
+        let screens = decode_pairs(&mut decoder, &[[0x94, 0x25], [0x94, 0x70], [0xC1, 0xC2], [0x94, 0xAD], [0x43, 0xC4]]);
+        assert_eq!(decoder.mode(), Some(Cea608Mode::RollUp(2)));
+        assert_eq!(screens.len(), 3);
+        assert_eq!(screens[2].text(), "AB\nCD");
+        assert_eq!(screens[2].rows.iter().map(|row| row.row).collect::<Vec<_>>(), [13, 14]);
+    }
+}
@@ -0,0 +1,317 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use core_audio_types::four_cc::FourCC;
+use core_foundation::{
+    base::{CFType, CFTypeRef, OSStatus, TCFType},
+    data::CFData,
+    dictionary::CFDictionary,
+    string::CFString,
+};
+use libc::c_void;
+
+use crate::{
+    bit_reader::{BitReader, BitWriter},
+    format_description::{
+        kCMFormatDescriptionError_InvalidParameter, kCMFormatDescriptionError_ValueNotAvailable,
+        kCMFormatDescriptionExtension_SampleDescriptionExtensionAtoms, kCMVideoCodecType_AV1, kCMVideoCodecType_DolbyVisionHEVC,
+        kCMVideoCodecType_H264, kCMVideoCodecType_H264ParameterSetsInBand, kCMVideoCodecType_HEVC, kCMVideoCodecType_HEVCParameterSetsInBand,
+        CMVideoCodecType, CMVideoFormatDescription, TCMFormatDescription,
+    },
+    OSType,
+};
+
+pub const DOLBY_VISION_CONFIGURATION_RECORD_SIZE: usize = 24;
+
+pub const kDolbyVisionConfigurationBoxType_dvcC: OSType = FourCC::new(b"dvcC").as_u32();
+pub const kDolbyVisionConfigurationBoxType_dvvC: OSType = FourCC::new(b"dvvC").as_u32();
+pub const kDolbyVisionConfigurationBoxType_dvwC: OSType = FourCC::new(b"dvwC").as_u32();
+
+pub const kDolbyVisionCodecType_HEVC: CMVideoCodecType = kCMVideoCodecType_DolbyVisionHEVC;
+pub const kDolbyVisionCodecType_HEVCParameterSetsInBand: CMVideoCodecType = FourCC::new(b"dvhe").as_u32();
+pub const kDolbyVisionCodecType_AVC: CMVideoCodecType = FourCC::new(b"dva1").as_u32();
+pub const kDolbyVisionCodecType_AVCParameterSetsInBand: CMVideoCodecType = FourCC::new(b"dvav").as_u32();
+pub const kDolbyVisionCodecType_AV1: CMVideoCodecType = FourCC::new(b"dav1").as_u32();
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct DolbyVisionConfigurationRecord {
+    pub version_major: u8,
+    pub version_minor: u8,
+    pub profile: u8,
+    pub level: u8,
+    pub rpu_present: bool,
+    pub el_present: bool,
+    pub bl_present: bool,
+    pub bl_signal_compatibility_id: u8,
+}
+
+impl DolbyVisionConfigurationRecord {
+    #[inline]
+    pub fn new(profile: u8, level: u8, bl_signal_compatibility_id: u8) -> Self {
+        Self {
+            version_major: 1,
+            version_minor: 0,
+            profile,
+            level,
+            rpu_present: true,
+            el_present: false,
+            bl_present: true,
+            bl_signal_compatibility_id,
+        }
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        // Older muxers write only the first 5 bytes, the remainder is reserved
+        if data.len() < 5 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let mut reader = BitReader::new(data);
+        let mut read = || -> Option<Self> {
+            Some(Self {
+                version_major: reader.read_bits(8)? as u8,
+                version_minor: reader.read_bits(8)? as u8,
+                profile: reader.read_bits(7)? as u8,
+                level: reader.read_bits(6)? as u8,
+                rpu_present: reader.read_bit()?,
+                el_present: reader.read_bit()?,
+                bl_present: reader.read_bit()?,
+                bl_signal_compatibility_id: reader.read_bits(4)? as u8,
+            })
+        };
+        read().ok_or(kCMFormatDescriptionError_InvalidParameter)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = BitWriter::new();
+        writer.write_bits(self.version_major as u32, 8);
+        writer.write_bits(self.version_minor as u32, 8);
+        writer.write_bits(self.profile as u32, 7);
+        writer.write_bits(self.level as u32, 6);
+        writer.write_bit(self.rpu_present);
+        writer.write_bit(self.el_present);
+        writer.write_bit(self.bl_present);
+        writer.write_bits(self.bl_signal_compatibility_id as u32, 4);
+        let mut bytes = writer.into_bytes();
+        bytes.resize(DOLBY_VISION_CONFIGURATION_RECORD_SIZE, 0);
+        bytes
+    }
+
+    #[inline]
+    pub fn box_type(&self) -> OSType {
+        match self.profile {
+            0..=7 => kDolbyVisionConfigurationBoxType_dvcC,
+            8..=10 => kDolbyVisionConfigurationBoxType_dvvC,
+            _ => kDolbyVisionConfigurationBoxType_dvwC,
+        }
+    }
+
+    pub fn from_format_description(desc: &CMVideoFormatDescription) -> Result<Self, OSStatus> {
+        let atoms = unsafe { CFString::wrap_under_get_rule(kCMFormatDescriptionExtension_SampleDescriptionExtensionAtoms) };
+        let atoms = desc
+            .as_buffer()
+            .get_extension(&atoms)
+            .and_then(|atoms| atoms.downcast::<CFDictionary>())
+            .ok_or(kCMFormatDescriptionError_ValueNotAvailable)?;
+        for box_type in ["dvcC", "dvvC", "dvwC"] {
+            let key = CFString::from_static_string(box_type);
+            let data = atoms
+                .find(key.as_concrete_TypeRef() as *const c_void)
+                .and_then(|value| unsafe { CFType::wrap_under_get_rule(*value as CFTypeRef) }.downcast::<CFData>());
+            if let Some(data) = data {
+                return Self::parse(data.bytes());
+            }
+        }
+        Err(kCMFormatDescriptionError_ValueNotAvailable)
+    }
+
+    pub fn compatible_codec_types(&self) -> &'static [CMVideoCodecType] {
+        match self.profile {
+            4 | 5 | 7 => &[kDolbyVisionCodecType_HEVC, kDolbyVisionCodecType_HEVCParameterSetsInBand],
+            8 => &[
+                kDolbyVisionCodecType_HEVC,
+                kDolbyVisionCodecType_HEVCParameterSetsInBand,
+                kCMVideoCodecType_HEVC,
+                kCMVideoCodecType_HEVCParameterSetsInBand,
+            ],
+            9 => &[
+                kDolbyVisionCodecType_AVC,
+                kDolbyVisionCodecType_AVCParameterSetsInBand,
+                kCMVideoCodecType_H264,
+                kCMVideoCodecType_H264ParameterSetsInBand,
+            ],
+            10 => &[kDolbyVisionCodecType_AV1, kCMVideoCodecType_AV1],
+            _ => &[],
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), OSStatus> {
+        let compatibility_ids: &[u8] = match self.profile {
+            4 | 7 => &[0, 2, 6],
+            5 => &[0],
+            8 => &[1, 2, 4],
+            9 => &[2],
+            10 => &[0, 1, 2, 4],
+            _ => return Err(kCMFormatDescriptionError_InvalidParameter),
+        };
+        if self.version_major == 0 || !(1..=13).contains(&self.level) || !compatibility_ids.contains(&self.bl_signal_compatibility_id) {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        Ok(())
+    }
+
+    pub fn validate_codec_type(&self, codec_type: CMVideoCodecType) -> Result<(), OSStatus> {
+        self.validate()?;
+        if !self.compatible_codec_types().contains(&codec_type) {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        // Plain HEVC, AVC and AV1 sample entries need a compatible base layer
+        let is_dolby_vision_codec_type = matches!(
+            codec_type,
+            kDolbyVisionCodecType_HEVC |
+                kDolbyVisionCodecType_HEVCParameterSetsInBand |
+                kDolbyVisionCodecType_AVC |
+                kDolbyVisionCodecType_AVCParameterSetsInBand |
+                kDolbyVisionCodecType_AV1
+        );
+        if !is_dolby_vision_codec_type && self.bl_signal_compatibility_id == 0 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        Ok(())
+    }
+
+    pub fn codec_string(&self, codec_type: CMVideoCodecType) -> String {
+        let code = codec_type.to_be_bytes();
+        format!("{}.{:02}.{:02}", String::from_utf8_lossy(&code), self.profile, self.level)
+    }
+
+    pub fn parse_codec_string(codec_string: &str) -> Result<(CMVideoCodecType, u8, u8), OSStatus> {
+        let mut parts = codec_string.split('.');
+        let (code, profile, level) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(code), Some(profile), Some(level), None) => (code.as_bytes(), profile, level),
+            _ => return Err(kCMFormatDescriptionError_InvalidParameter),
+        };
+        if code.len() != 4 || profile.len() != 2 || level.len() != 2 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let codec_type = FourCC::new(&[code[0], code[1], code[2], code[3]]).as_u32();
+        let profile = profile.parse::<u8>().map_err(|_| kCMFormatDescriptionError_InvalidParameter)?;
+        let level = level.parse::<u8>().map_err(|_| kCMFormatDescriptionError_InvalidParameter)?;
+        Ok((codec_type, profile, level))
+    }
+}
+
+impl Display for DolbyVisionConfigurationRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "Dolby Vision {}.{} profile {} level {} (rpu: {}, el: {}, bl: {}, compatibility id: {})",
+            self.version_major,
+            self.version_minor,
+            self.profile,
+            self.level,
+            self.rpu_present,
+            self.el_present,
+            self.bl_present,
+            self.bl_signal_compatibility_id
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // dvcC, profile 5 level 6, RPU and base layer, no compatible base layer
+    const PROFILE_5_RECORD: [u8; 5] = [0x01, 0x00, 0x0A, 0x35, 0x00];
+    // dvvC, profile 8 level 6 with HDR10 (8.1) and HLG (8.4) compatible base layers
+    const PROFILE_8_1_RECORD: [u8; 5] = [0x01, 0x00, 0x10, 0x35, 0x10];
+    const PROFILE_8_4_RECORD: [u8; 5] = [0x01, 0x00, 0x10, 0x35, 0x40];
+
+    fn padded(record: &[u8]) -> Vec<u8> {
+        let mut bytes = record.to_vec();
+        bytes.resize(DOLBY_VISION_CONFIGURATION_RECORD_SIZE, 0);
+        bytes
+    }
+
+    #[test]
+    fn parse_configuration_records() {
+        let fixtures = [
+            (PROFILE_5_RECORD, 5, 0, kDolbyVisionConfigurationBoxType_dvcC),
+            (PROFILE_8_1_RECORD, 8, 1, kDolbyVisionConfigurationBoxType_dvvC),
+            (PROFILE_8_4_RECORD, 8, 4, kDolbyVisionConfigurationBoxType_dvvC),
+        ];
+        for (record, profile, bl_signal_compatibility_id, box_type) in fixtures {
+            let config = DolbyVisionConfigurationRecord::parse(&padded(&record)).unwrap();
+            assert_eq!(config, DolbyVisionConfigurationRecord::new(profile, 6, bl_signal_compatibility_id));
+            assert_eq!(config.box_type(), box_type);
+            assert_eq!(config.validate(), Ok(()));
+            assert_eq!(config.to_bytes(), padded(&record));
+            // Short records from older muxers
+            assert_eq!(DolbyVisionConfigurationRecord::parse(&record), Ok(config));
+        }
+        assert_eq!(DolbyVisionConfigurationRecord::parse(&PROFILE_5_RECORD[..4]), Err(kCMFormatDescriptionError_InvalidParameter));
+        assert_eq!(
+            DolbyVisionConfigurationRecord::parse(&PROFILE_8_1_RECORD).unwrap().to_string(),
+            "Dolby Vision 1.0 profile 8 level 6 (rpu: true, el: false, bl: true, compatibility id: 1)"
+        );
+    }
+
+    #[test]
+    fn validate_configuration_records() {
+        let valid = DolbyVisionConfigurationRecord::new(8, 6, 1);
+        assert_eq!(valid.validate(), Ok(()));
+        let invalid = [
+            DolbyVisionConfigurationRecord {
+                version_major: 0,
+                ..valid
+            },
+            DolbyVisionConfigurationRecord {
+                level: 0,
+                ..valid
+            },
+            DolbyVisionConfigurationRecord {
+                level: 14,
+                ..valid
+            },
+            DolbyVisionConfigurationRecord::new(8, 6, 0),
+            DolbyVisionConfigurationRecord::new(5, 6, 1),
+            DolbyVisionConfigurationRecord::new(6, 6, 0),
+        ];
+        for config in invalid {
+            assert_eq!(config.validate(), Err(kCMFormatDescriptionError_InvalidParameter), "{}", config);
+        }
+    }
+
+    #[test]
+    fn validate_codec_types() {
+        let profile_5 = DolbyVisionConfigurationRecord::new(5, 6, 0);
+        assert_eq!(profile_5.validate_codec_type(kDolbyVisionCodecType_HEVC), Ok(()));
+        assert_eq!(profile_5.validate_codec_type(kDolbyVisionCodecType_HEVCParameterSetsInBand), Ok(()));
+        assert_eq!(profile_5.validate_codec_type(kCMVideoCodecType_HEVC), Err(kCMFormatDescriptionError_InvalidParameter));
+
+        // Profile 8 streams can be played as plain HEVC
+        let profile_8_4 = DolbyVisionConfigurationRecord::new(8, 6, 4);
+        assert_eq!(profile_8_4.validate_codec_type(kCMVideoCodecType_HEVC), Ok(()));
+        assert_eq!(profile_8_4.validate_codec_type(kCMVideoCodecType_HEVCParameterSetsInBand), Ok(()));
+        assert_eq!(profile_8_4.validate_codec_type(kDolbyVisionCodecType_AV1), Err(kCMFormatDescriptionError_InvalidParameter));
+
+        let profile_10 = DolbyVisionConfigurationRecord::new(10, 9, 0);
+        assert_eq!(profile_10.validate_codec_type(kDolbyVisionCodecType_AV1), Ok(()));
+        assert_eq!(profile_10.validate_codec_type(kCMVideoCodecType_AV1), Err(kCMFormatDescriptionError_InvalidParameter));
+    }
+
+    #[test]
+    fn codec_strings_round_trip() {
+        let fixtures = [
+            (DolbyVisionConfigurationRecord::new(8, 6, 1), kDolbyVisionCodecType_HEVC, "dvh1.08.06"),
+            (DolbyVisionConfigurationRecord::new(5, 9, 0), kDolbyVisionCodecType_HEVCParameterSetsInBand, "dvhe.05.09"),
+            (DolbyVisionConfigurationRecord::new(10, 13, 1), kDolbyVisionCodecType_AV1, "dav1.10.13"),
+        ];
+        for (config, codec_type, codec_string) in fixtures {
+            assert_eq!(config.codec_string(codec_type), codec_string);
+            assert_eq!(DolbyVisionConfigurationRecord::parse_codec_string(codec_string), Ok((codec_type, config.profile, config.level)));
+        }
+        for codec_string in ["dvh1.8.06", "dvh1.08", "dvh1.08.06.01", "dvh.08.06", "dvh1.0x.06", ""] {
+            assert_eq!(DolbyVisionConfigurationRecord::parse_codec_string(codec_string), Err(kCMFormatDescriptionError_InvalidParameter));
+        }
+    }
+}
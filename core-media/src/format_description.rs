@@ -4,7 +4,11 @@ use std::{
     slice::from_raw_parts,
 };
 
-use core_audio_types::base_types::{AudioChannelLayout, AudioFormatListItem, AudioStreamBasicDescription};
+use core_audio_types::{
+    base_types::{AudioChannelLayout, AudioFormatListItem, AudioStreamBasicDescription},
+    channel_layout::ChannelLayout,
+    four_cc::FourCC,
+};
 use core_foundation::{
     array::{CFArray, CFArrayRef},
     base::{kCFAllocatorDefault, Boolean, CFAllocatorRef, CFType, CFTypeID, CFTypeRef, OSStatus, TCFType, TCFTypeRef},
@@ -21,7 +25,7 @@ use libc::{c_int, c_void, size_t};
 #[cfg(feature = "objc")]
 use objc2::encode::{Encoding, RefEncode};
 
-use crate::{time::CMTime, OSType};
+use crate::{audio_format_comparison::AudioFormatDescriptionParts, time::CMTime, OSType};
 
 pub const kCMFormatDescriptionError_InvalidParameter: OSStatus = -12710;
 pub const kCMFormatDescriptionError_AllocationFailed: OSStatus = -12711;
@@ -31,20 +35,15 @@ type FourCharCode = u32;
 
 pub type CMMediaType = FourCharCode;
 
-#[inline]
-const fn fourcc(code: &[u8; 4]) -> u32 {
-    ((code[0] as u32) << 24) | ((code[1] as u32) << 16) | ((code[2] as u32) << 8) | ((code[3] as u32) << 0)
-}
-
-pub const kCMMediaType_Video: CMMediaType = fourcc(b"vide");
-pub const kCMMediaType_Audio: CMMediaType = fourcc(b"soun");
-pub const kCMMediaType_Muxed: CMMediaType = fourcc(b"muxx");
-pub const kCMMediaType_Text: CMMediaType = fourcc(b"text");
-pub const kCMMediaType_ClosedCaption: CMMediaType = fourcc(b"clcp");
-pub const kCMMediaType_Subtitle: CMMediaType = fourcc(b"sbtl");
-pub const kCMMediaType_TimeCode: CMMediaType = fourcc(b"tmcd");
-pub const kCMMediaType_Metadata: CMMediaType = fourcc(b"meta");
-pub const kCMMediaType_TaggedBufferGroup: CMMediaType = fourcc(b"tbgr");
+pub const kCMMediaType_Video: CMMediaType = FourCC::new(b"vide").as_u32();
+pub const kCMMediaType_Audio: CMMediaType = FourCC::new(b"soun").as_u32();
+pub const kCMMediaType_Muxed: CMMediaType = FourCC::new(b"muxx").as_u32();
+pub const kCMMediaType_Text: CMMediaType = FourCC::new(b"text").as_u32();
+pub const kCMMediaType_ClosedCaption: CMMediaType = FourCC::new(b"clcp").as_u32();
+pub const kCMMediaType_Subtitle: CMMediaType = FourCC::new(b"sbtl").as_u32();
+pub const kCMMediaType_TimeCode: CMMediaType = FourCC::new(b"tmcd").as_u32();
+pub const kCMMediaType_Metadata: CMMediaType = FourCC::new(b"meta").as_u32();
+pub const kCMMediaType_TaggedBufferGroup: CMMediaType = FourCC::new(b"tbgr").as_u32();
 
 #[repr(C)]
 pub struct opaqueCMFormatDescription(c_void);
@@ -81,8 +80,8 @@ extern "C" {
 
 pub type CMAudioCodecType = FourCharCode;
 
-pub const kCMAudioCodecType_AAC_LCProtected: CMAudioCodecType = fourcc(b"paac");
-pub const kCMAudioCodecType_AAC_AudibleProtected: CMAudioCodecType = fourcc(b"aaac");
+pub const kCMAudioCodecType_AAC_LCProtected: CMAudioCodecType = FourCC::new(b"paac").as_u32();
+pub const kCMAudioCodecType_AAC_AudibleProtected: CMAudioCodecType = FourCC::new(b"aaac").as_u32();
 
 pub type CMAudioFormatDescriptionRef = CMFormatDescriptionRef;
 
@@ -136,62 +135,66 @@ pub type CMVideoFormatDescriptionRef = CMFormatDescriptionRef;
 pub type CMPixelFormatType = FourCharCode;
 
 pub const kCMPixelFormat_32ARGB: CMPixelFormatType = 32;
-pub const kCMPixelFormat_32BGRA: CMPixelFormatType = fourcc(b"BGRA");
+pub const kCMPixelFormat_32BGRA: CMPixelFormatType = FourCC::new(b"BGRA").as_u32();
 pub const kCMPixelFormat_24RGB: CMPixelFormatType = 24;
 pub const kCMPixelFormat_16BE555: CMPixelFormatType = 16;
-pub const kCMPixelFormat_16BE565: CMPixelFormatType = fourcc(b"B565");
-pub const kCMPixelFormat_16LE555: CMPixelFormatType = fourcc(b"L555");
-pub const kCMPixelFormat_16LE565: CMPixelFormatType = fourcc(b"L565");
-pub const kCMPixelFormat_16LE5551: CMPixelFormatType = fourcc(b"5551");
-pub const kCMPixelFormat_422YpCbCr8: CMPixelFormatType = fourcc(b"2vuy");
-pub const kCMPixelFormat_422YpCbCr8_yuvs: CMPixelFormatType = fourcc(b"yuvs");
-pub const kCMPixelFormat_444YpCbCr8: CMPixelFormatType = fourcc(b"v308");
-pub const kCMPixelFormat_4444YpCbCrA8: CMPixelFormatType = fourcc(b"v408");
-pub const kCMPixelFormat_422YpCbCr16: CMPixelFormatType = fourcc(b"v216");
-pub const kCMPixelFormat_422YpCbCr10: CMPixelFormatType = fourcc(b"v210");
-pub const kCMPixelFormat_444YpCbCr10: CMPixelFormatType = fourcc(b"v410");
+pub const kCMPixelFormat_16BE565: CMPixelFormatType = FourCC::new(b"B565").as_u32();
+pub const kCMPixelFormat_16LE555: CMPixelFormatType = FourCC::new(b"L555").as_u32();
+pub const kCMPixelFormat_16LE565: CMPixelFormatType = FourCC::new(b"L565").as_u32();
+pub const kCMPixelFormat_16LE5551: CMPixelFormatType = FourCC::new(b"5551").as_u32();
+pub const kCMPixelFormat_422YpCbCr8: CMPixelFormatType = FourCC::new(b"2vuy").as_u32();
+pub const kCMPixelFormat_422YpCbCr8_yuvs: CMPixelFormatType = FourCC::new(b"yuvs").as_u32();
+pub const kCMPixelFormat_444YpCbCr8: CMPixelFormatType = FourCC::new(b"v308").as_u32();
+pub const kCMPixelFormat_4444YpCbCrA8: CMPixelFormatType = FourCC::new(b"v408").as_u32();
+pub const kCMPixelFormat_422YpCbCr16: CMPixelFormatType = FourCC::new(b"v216").as_u32();
+pub const kCMPixelFormat_422YpCbCr10: CMPixelFormatType = FourCC::new(b"v210").as_u32();
+pub const kCMPixelFormat_444YpCbCr10: CMPixelFormatType = FourCC::new(b"v410").as_u32();
 pub const kCMPixelFormat_8IndexedGray_WhiteIsZero: CMPixelFormatType = 0x00000028;
 
 pub type CMVideoCodecType = FourCharCode;
 
 pub const kCMVideoCodecType_422YpCbCr8: CMVideoCodecType = kCMPixelFormat_422YpCbCr8;
-pub const kCMVideoCodecType_Animation: CMVideoCodecType = fourcc(b"rle ");
-pub const kCMVideoCodecType_Cinepak: CMVideoCodecType = fourcc(b"cvid");
-pub const kCMVideoCodecType_JPEG: CMVideoCodecType = fourcc(b"jpeg");
-pub const kCMVideoCodecType_JPEG_OpenDML: CMVideoCodecType = fourcc(b"dmb1");
-pub const kCMVideoCodecType_SorensonVideo: CMVideoCodecType = fourcc(b"SVQ1");
-pub const kCMVideoCodecType_SorensonVideo3: CMVideoCodecType = fourcc(b"SVQ3");
-pub const kCMVideoCodecType_H263: CMVideoCodecType = fourcc(b"h263");
-pub const kCMVideoCodecType_H264: CMVideoCodecType = fourcc(b"avc1");
-pub const kCMVideoCodecType_HEVC: CMVideoCodecType = fourcc(b"hvc1");
-pub const kCMVideoCodecType_HEVCWithAlpha: CMVideoCodecType = fourcc(b"muxa");
-pub const kCMVideoCodecType_DolbyVisionHEVC: CMVideoCodecType = fourcc(b"dvh1");
-pub const kCMVideoCodecType_MPEG4Video: CMVideoCodecType = fourcc(b"mp4v");
-pub const kCMVideoCodecType_MPEG2Video: CMVideoCodecType = fourcc(b"mp2v");
-pub const kCMVideoCodecType_MPEG1Video: CMVideoCodecType = fourcc(b"mp1v");
-pub const kCMVideoCodecType_VP9: CMVideoCodecType = fourcc(b"vp09");
-pub const kCMVideoCodecType_DVCNTSC: CMVideoCodecType = fourcc(b"dvc ");
-pub const kCMVideoCodecType_DVCPAL: CMVideoCodecType = fourcc(b"dvcp");
-pub const kCMVideoCodecType_DVCProPAL: CMVideoCodecType = fourcc(b"dvpp");
-pub const kCMVideoCodecType_DVCPro50NTSC: CMVideoCodecType = fourcc(b"dv5n");
-pub const kCMVideoCodecType_DVCPro50PAL: CMVideoCodecType = fourcc(b"dv5p");
-pub const kCMVideoCodecType_DVCPROHD720p60: CMVideoCodecType = fourcc(b"dvhp");
-pub const kCMVideoCodecType_DVCPROHD720p50: CMVideoCodecType = fourcc(b"dvhq");
-pub const kCMVideoCodecType_DVCPROHD1080i60: CMVideoCodecType = fourcc(b"dvh6");
-pub const kCMVideoCodecType_DVCPROHD1080i50: CMVideoCodecType = fourcc(b"dvh5");
-pub const kCMVideoCodecType_DVCPROHD1080p30: CMVideoCodecType = fourcc(b"dvh3");
-pub const kCMVideoCodecType_DVCPROHD1080p25: CMVideoCodecType = fourcc(b"dvh2");
-pub const kCMVideoCodecType_AppleProRes4444XQ: CMVideoCodecType = fourcc(b"ap4x");
-pub const kCMVideoCodecType_AppleProRes4444: CMVideoCodecType = fourcc(b"ap4h");
-pub const kCMVideoCodecType_AppleProRes422HQ: CMVideoCodecType = fourcc(b"apch");
-pub const kCMVideoCodecType_AppleProRes422: CMVideoCodecType = fourcc(b"apcn");
-pub const kCMVideoCodecType_AppleProRes422LT: CMVideoCodecType = fourcc(b"apcs");
-pub const kCMVideoCodecType_AppleProRes422Proxy: CMVideoCodecType = fourcc(b"apco");
-pub const kCMVideoCodecType_AppleProResRAW: CMVideoCodecType = fourcc(b"aprn");
-pub const kCMVideoCodecType_AppleProResRAWHQ: CMVideoCodecType = fourcc(b"aprh");
-pub const kCMVideoCodecType_DisparityHEVC: CMVideoCodecType = fourcc(b"dish");
-pub const kCMVideoCodecType_DepthHEVC: CMVideoCodecType = fourcc(b"deph");
-pub const kCMVideoCodecType_AV1: CMVideoCodecType = fourcc(b"av01");
+pub const kCMVideoCodecType_Animation: CMVideoCodecType = FourCC::new(b"rle ").as_u32();
+pub const kCMVideoCodecType_Cinepak: CMVideoCodecType = FourCC::new(b"cvid").as_u32();
+pub const kCMVideoCodecType_JPEG: CMVideoCodecType = FourCC::new(b"jpeg").as_u32();
+pub const kCMVideoCodecType_JPEG_OpenDML: CMVideoCodecType = FourCC::new(b"dmb1").as_u32();
+pub const kCMVideoCodecType_SorensonVideo: CMVideoCodecType = FourCC::new(b"SVQ1").as_u32();
+pub const kCMVideoCodecType_SorensonVideo3: CMVideoCodecType = FourCC::new(b"SVQ3").as_u32();
+pub const kCMVideoCodecType_H263: CMVideoCodecType = FourCC::new(b"h263").as_u32();
+pub const kCMVideoCodecType_H264: CMVideoCodecType = FourCC::new(b"avc1").as_u32();
+pub const kCMVideoCodecType_HEVC: CMVideoCodecType = FourCC::new(b"hvc1").as_u32();
+// Sample entries that carry parameter sets in band rather than in the
+// configuration record
+pub const kCMVideoCodecType_H264ParameterSetsInBand: CMVideoCodecType = FourCC::new(b"avc3").as_u32();
+pub const kCMVideoCodecType_HEVCParameterSetsInBand: CMVideoCodecType = FourCC::new(b"hev1").as_u32();
+pub const kCMVideoCodecType_HEVCWithAlpha: CMVideoCodecType = FourCC::new(b"muxa").as_u32();
+pub const kCMVideoCodecType_DolbyVisionHEVC: CMVideoCodecType = FourCC::new(b"dvh1").as_u32();
+pub const kCMVideoCodecType_MPEG4Video: CMVideoCodecType = FourCC::new(b"mp4v").as_u32();
+pub const kCMVideoCodecType_MPEG2Video: CMVideoCodecType = FourCC::new(b"mp2v").as_u32();
+pub const kCMVideoCodecType_MPEG1Video: CMVideoCodecType = FourCC::new(b"mp1v").as_u32();
+pub const kCMVideoCodecType_VP9: CMVideoCodecType = FourCC::new(b"vp09").as_u32();
+pub const kCMVideoCodecType_DVCNTSC: CMVideoCodecType = FourCC::new(b"dvc ").as_u32();
+pub const kCMVideoCodecType_DVCPAL: CMVideoCodecType = FourCC::new(b"dvcp").as_u32();
+pub const kCMVideoCodecType_DVCProPAL: CMVideoCodecType = FourCC::new(b"dvpp").as_u32();
+pub const kCMVideoCodecType_DVCPro50NTSC: CMVideoCodecType = FourCC::new(b"dv5n").as_u32();
+pub const kCMVideoCodecType_DVCPro50PAL: CMVideoCodecType = FourCC::new(b"dv5p").as_u32();
+pub const kCMVideoCodecType_DVCPROHD720p60: CMVideoCodecType = FourCC::new(b"dvhp").as_u32();
+pub const kCMVideoCodecType_DVCPROHD720p50: CMVideoCodecType = FourCC::new(b"dvhq").as_u32();
+pub const kCMVideoCodecType_DVCPROHD1080i60: CMVideoCodecType = FourCC::new(b"dvh6").as_u32();
+pub const kCMVideoCodecType_DVCPROHD1080i50: CMVideoCodecType = FourCC::new(b"dvh5").as_u32();
+pub const kCMVideoCodecType_DVCPROHD1080p30: CMVideoCodecType = FourCC::new(b"dvh3").as_u32();
+pub const kCMVideoCodecType_DVCPROHD1080p25: CMVideoCodecType = FourCC::new(b"dvh2").as_u32();
+pub const kCMVideoCodecType_AppleProRes4444XQ: CMVideoCodecType = FourCC::new(b"ap4x").as_u32();
+pub const kCMVideoCodecType_AppleProRes4444: CMVideoCodecType = FourCC::new(b"ap4h").as_u32();
+pub const kCMVideoCodecType_AppleProRes422HQ: CMVideoCodecType = FourCC::new(b"apch").as_u32();
+pub const kCMVideoCodecType_AppleProRes422: CMVideoCodecType = FourCC::new(b"apcn").as_u32();
+pub const kCMVideoCodecType_AppleProRes422LT: CMVideoCodecType = FourCC::new(b"apcs").as_u32();
+pub const kCMVideoCodecType_AppleProRes422Proxy: CMVideoCodecType = FourCC::new(b"apco").as_u32();
+pub const kCMVideoCodecType_AppleProResRAW: CMVideoCodecType = FourCC::new(b"aprn").as_u32();
+pub const kCMVideoCodecType_AppleProResRAWHQ: CMVideoCodecType = FourCC::new(b"aprh").as_u32();
+pub const kCMVideoCodecType_DisparityHEVC: CMVideoCodecType = FourCC::new(b"dish").as_u32();
+pub const kCMVideoCodecType_DepthHEVC: CMVideoCodecType = FourCC::new(b"deph").as_u32();
+pub const kCMVideoCodecType_AV1: CMVideoCodecType = FourCC::new(b"av01").as_u32();
 
 #[repr(C, align(4))]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
@@ -213,6 +216,7 @@ extern "C" {
     pub static kCMFormatDescriptionKey_CleanApertureHorizontalOffsetRational: CFStringRef;
     pub static kCMFormatDescriptionKey_CleanApertureVerticalOffsetRational: CFStringRef;
     pub static kCMFormatDescriptionExtension_FieldCount: CFStringRef;
+    pub static kCMFormatDescriptionExtension_FieldDetail: CFStringRef;
     pub static kCMFormatDescriptionFieldDetail_TemporalTopFirst: CFStringRef;
     pub static kCMFormatDescriptionFieldDetail_TemporalBottomFirst: CFStringRef;
     pub static kCMFormatDescriptionFieldDetail_SpatialFirstLineEarly: CFStringRef;
@@ -260,44 +264,44 @@ extern "C" {
     pub static kCMFormatDescriptionExtension_ProtectedContentOriginalFormat: CFStringRef;
 }
 
-pub const kCMMPEG2VideoProfile_HDV_720p30: i32 = fourcc(b"hdv1") as i32;
-pub const kCMMPEG2VideoProfile_HDV_1080i60: i32 = fourcc(b"hdv2") as i32;
-pub const kCMMPEG2VideoProfile_HDV_1080i50: i32 = fourcc(b"hdv3") as i32;
-pub const kCMMPEG2VideoProfile_HDV_720p24: i32 = fourcc(b"hdv4") as i32;
-pub const kCMMPEG2VideoProfile_HDV_720p25: i32 = fourcc(b"hdv5") as i32;
-pub const kCMMPEG2VideoProfile_HDV_1080p24: i32 = fourcc(b"hdv6") as i32;
-pub const kCMMPEG2VideoProfile_HDV_1080p25: i32 = fourcc(b"hdv7") as i32;
-pub const kCMMPEG2VideoProfile_HDV_1080p30: i32 = fourcc(b"hdv8") as i32;
-pub const kCMMPEG2VideoProfile_HDV_720p60: i32 = fourcc(b"hdv9") as i32;
-pub const kCMMPEG2VideoProfile_HDV_720p50: i32 = fourcc(b"hdva") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD_1080i60_VBR35: i32 = fourcc(b"xdv2") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD_1080i50_VBR35: i32 = fourcc(b"xdv3") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD_1080p24_VBR35: i32 = fourcc(b"xdv6") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD_1080p25_VBR35: i32 = fourcc(b"xdv7") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD_1080p30_VBR35: i32 = fourcc(b"xdv8") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_EX_720p24_VBR35: i32 = fourcc(b"xdv4") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_EX_720p25_VBR35: i32 = fourcc(b"xdv5") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_EX_720p30_VBR35: i32 = fourcc(b"xdv1") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_EX_720p50_VBR35: i32 = fourcc(b"xdva") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_EX_720p60_VBR35: i32 = fourcc(b"xdv9") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_EX_1080i60_VBR35: i32 = fourcc(b"xdvb") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_EX_1080i50_VBR35: i32 = fourcc(b"xdvc") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_EX_1080p24_VBR35: i32 = fourcc(b"xdvd") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_EX_1080p25_VBR35: i32 = fourcc(b"xdve") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_EX_1080p30_VBR35: i32 = fourcc(b"xdvf") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD422_720p50_CBR50: i32 = fourcc(b"xd5a") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD422_720p60_CBR50: i32 = fourcc(b"xd59") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD422_1080i60_CBR50: i32 = fourcc(b"xd5b") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD422_1080i50_CBR50: i32 = fourcc(b"xd5c") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD422_1080p24_CBR50: i32 = fourcc(b"xd5d") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD422_1080p25_CBR50: i32 = fourcc(b"xd5e") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD422_1080p30_CBR50: i32 = fourcc(b"xd5f") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD_540p: i32 = fourcc(b"xdhd") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD422_540p: i32 = fourcc(b"xdh2") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD422_720p24_CBR50: i32 = fourcc(b"xd54") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD422_720p25_CBR50: i32 = fourcc(b"xd55") as i32;
-pub const kCMMPEG2VideoProfile_XDCAM_HD422_720p30_CBR50: i32 = fourcc(b"xd51") as i32;
-pub const kCMMPEG2VideoProfile_XF: i32 = fourcc(b"xfz1") as i32;
+pub const kCMMPEG2VideoProfile_HDV_720p30: i32 = FourCC::new(b"hdv1").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_HDV_1080i60: i32 = FourCC::new(b"hdv2").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_HDV_1080i50: i32 = FourCC::new(b"hdv3").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_HDV_720p24: i32 = FourCC::new(b"hdv4").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_HDV_720p25: i32 = FourCC::new(b"hdv5").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_HDV_1080p24: i32 = FourCC::new(b"hdv6").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_HDV_1080p25: i32 = FourCC::new(b"hdv7").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_HDV_1080p30: i32 = FourCC::new(b"hdv8").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_HDV_720p60: i32 = FourCC::new(b"hdv9").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_HDV_720p50: i32 = FourCC::new(b"hdva").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD_1080i60_VBR35: i32 = FourCC::new(b"xdv2").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD_1080i50_VBR35: i32 = FourCC::new(b"xdv3").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD_1080p24_VBR35: i32 = FourCC::new(b"xdv6").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD_1080p25_VBR35: i32 = FourCC::new(b"xdv7").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD_1080p30_VBR35: i32 = FourCC::new(b"xdv8").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_EX_720p24_VBR35: i32 = FourCC::new(b"xdv4").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_EX_720p25_VBR35: i32 = FourCC::new(b"xdv5").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_EX_720p30_VBR35: i32 = FourCC::new(b"xdv1").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_EX_720p50_VBR35: i32 = FourCC::new(b"xdva").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_EX_720p60_VBR35: i32 = FourCC::new(b"xdv9").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_EX_1080i60_VBR35: i32 = FourCC::new(b"xdvb").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_EX_1080i50_VBR35: i32 = FourCC::new(b"xdvc").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_EX_1080p24_VBR35: i32 = FourCC::new(b"xdvd").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_EX_1080p25_VBR35: i32 = FourCC::new(b"xdve").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_EX_1080p30_VBR35: i32 = FourCC::new(b"xdvf").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD422_720p50_CBR50: i32 = FourCC::new(b"xd5a").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD422_720p60_CBR50: i32 = FourCC::new(b"xd59").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD422_1080i60_CBR50: i32 = FourCC::new(b"xd5b").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD422_1080i50_CBR50: i32 = FourCC::new(b"xd5c").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD422_1080p24_CBR50: i32 = FourCC::new(b"xd5d").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD422_1080p25_CBR50: i32 = FourCC::new(b"xd5e").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD422_1080p30_CBR50: i32 = FourCC::new(b"xd5f").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD_540p: i32 = FourCC::new(b"xdhd").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD422_540p: i32 = FourCC::new(b"xdh2").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD422_720p24_CBR50: i32 = FourCC::new(b"xd54").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD422_720p25_CBR50: i32 = FourCC::new(b"xd55").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XDCAM_HD422_720p30_CBR50: i32 = FourCC::new(b"xd51").as_u32() as i32;
+pub const kCMMPEG2VideoProfile_XF: i32 = FourCC::new(b"xfz1").as_u32() as i32;
 
 extern "C" {
     pub static kCMFormatDescriptionExtension_TemporalQuality: CFStringRef;
@@ -389,17 +393,17 @@ pub type CMTaggedBufferGroupFormatDescriptionRef = CMFormatDescriptionRef;
 
 pub type CMTaggedBufferGroupFormatType = FourCharCode;
 
-pub const kCMTaggedBufferGroupFormatType_TaggedBufferGroup: CMTaggedBufferGroupFormatType = fourcc(b"tbgr");
+pub const kCMTaggedBufferGroupFormatType_TaggedBufferGroup: CMTaggedBufferGroupFormatType = FourCC::new(b"tbgr").as_u32();
 
 pub type CMMuxedFormatDescriptionRef = CMFormatDescriptionRef;
 
 pub type CMMuxedStreamType = FourCharCode;
 
-pub const kCMMuxedStreamType_MPEG1System: CMMuxedStreamType = fourcc(b"mp1s");
-pub const kCMMuxedStreamType_MPEG2Transport: CMMuxedStreamType = fourcc(b"mp2t");
-pub const kCMMuxedStreamType_MPEG2Program: CMMuxedStreamType = fourcc(b"mp2p");
-pub const kCMMuxedStreamType_DV: CMMuxedStreamType = fourcc(b"dv  ");
-pub const kCMMuxedStreamType_EmbeddedDeviceScreenRecording: CMMuxedStreamType = fourcc(b"isr ");
+pub const kCMMuxedStreamType_MPEG1System: CMMuxedStreamType = FourCC::new(b"mp1s").as_u32();
+pub const kCMMuxedStreamType_MPEG2Transport: CMMuxedStreamType = FourCC::new(b"mp2t").as_u32();
+pub const kCMMuxedStreamType_MPEG2Program: CMMuxedStreamType = FourCC::new(b"mp2p").as_u32();
+pub const kCMMuxedStreamType_DV: CMMuxedStreamType = FourCC::new(b"dv  ").as_u32();
+pub const kCMMuxedStreamType_EmbeddedDeviceScreenRecording: CMMuxedStreamType = FourCC::new(b"isr ").as_u32();
 
 extern "C" {
     pub fn CMMuxedFormatDescriptionCreate(
@@ -414,16 +418,16 @@ pub type CMClosedCaptionFormatDescriptionRef = CMFormatDescriptionRef;
 
 pub type CMClosedCaptionFormatType = FourCharCode;
 
-pub const kCMClosedCaptionFormatType_CEA608: CMClosedCaptionFormatType = fourcc(b"c608");
-pub const kCMClosedCaptionFormatType_CEA708: CMClosedCaptionFormatType = fourcc(b"c708");
-pub const kCMClosedCaptionFormatType_ATSC: CMClosedCaptionFormatType = fourcc(b"atcc");
+pub const kCMClosedCaptionFormatType_CEA608: CMClosedCaptionFormatType = FourCC::new(b"c608").as_u32();
+pub const kCMClosedCaptionFormatType_CEA708: CMClosedCaptionFormatType = FourCC::new(b"c708").as_u32();
+pub const kCMClosedCaptionFormatType_ATSC: CMClosedCaptionFormatType = FourCC::new(b"atcc").as_u32();
 
 pub type CMTextFormatDescriptionRef = CMFormatDescriptionRef;
 
 pub type CMTextFormatType = FourCharCode;
 
-pub const kCMTextFormatType_QTText: CMTextFormatType = fourcc(b"text");
-pub const kCMTextFormatType_3GText: CMTextFormatType = fourcc(b"tx3g");
+pub const kCMTextFormatType_QTText: CMTextFormatType = FourCC::new(b"text").as_u32();
+pub const kCMTextFormatType_3GText: CMTextFormatType = FourCC::new(b"tx3g").as_u32();
 
 pub type CMTextDisplayFlags = u32;
 
@@ -501,17 +505,17 @@ extern "C" {
 
 pub type CMSubtitleFormatType = FourCharCode;
 
-pub const kCMSubtitleFormatType_3GText: CMSubtitleFormatType = fourcc(b"tx3g");
-pub const kCMSubtitleFormatType_WebVTT: CMSubtitleFormatType = fourcc(b"wvtt");
+pub const kCMSubtitleFormatType_3GText: CMSubtitleFormatType = FourCC::new(b"tx3g").as_u32();
+pub const kCMSubtitleFormatType_WebVTT: CMSubtitleFormatType = FourCC::new(b"wvtt").as_u32();
 
 pub type CMTimeCodeFormatDescriptionRef = CMFormatDescriptionRef;
 
 pub type CMTimeCodeFormatType = FourCharCode;
 
-pub const kCMTimeCodeFormatType_TimeCode32: CMTimeCodeFormatType = fourcc(b"tmcd");
-pub const kCMTimeCodeFormatType_TimeCode64: CMTimeCodeFormatType = fourcc(b"tc64");
-pub const kCMTimeCodeFormatType_Counter32: CMTimeCodeFormatType = fourcc(b"cn32");
-pub const kCMTimeCodeFormatType_Counter64: CMTimeCodeFormatType = fourcc(b"cn64");
+pub const kCMTimeCodeFormatType_TimeCode32: CMTimeCodeFormatType = FourCC::new(b"tmcd").as_u32();
+pub const kCMTimeCodeFormatType_TimeCode64: CMTimeCodeFormatType = FourCC::new(b"tc64").as_u32();
+pub const kCMTimeCodeFormatType_Counter32: CMTimeCodeFormatType = FourCC::new(b"cn32").as_u32();
+pub const kCMTimeCodeFormatType_Counter64: CMTimeCodeFormatType = FourCC::new(b"cn64").as_u32();
 
 pub const kCMTimeCodeFlag_DropFrame: u32 = 1 << 0;
 pub const kCMTimeCodeFlag_24HourMax: u32 = 1 << 1;
@@ -540,10 +544,10 @@ pub type CMMetadataFormatDescriptionRef = CMFormatDescriptionRef;
 
 pub type CMMetadataFormatType = FourCharCode;
 
-pub const kCMMetadataFormatType_ICY: CMMetadataFormatType = fourcc(b"icy ");
-pub const kCMMetadataFormatType_ID3: CMMetadataFormatType = fourcc(b"id3 ");
-pub const kCMMetadataFormatType_Boxed: CMMetadataFormatType = fourcc(b"mebx");
-pub const kCMMetadataFormatType_EMSG: CMMetadataFormatType = fourcc(b"emsg");
+pub const kCMMetadataFormatType_ICY: CMMetadataFormatType = FourCC::new(b"icy ").as_u32();
+pub const kCMMetadataFormatType_ID3: CMMetadataFormatType = FourCC::new(b"id3 ").as_u32();
+pub const kCMMetadataFormatType_Boxed: CMMetadataFormatType = FourCC::new(b"mebx").as_u32();
+pub const kCMMetadataFormatType_EMSG: CMMetadataFormatType = FourCC::new(b"emsg").as_u32();
 
 extern "C" {
     pub static kCMFormatDescriptionExtensionKey_MetadataKeyTable: CFStringRef;
@@ -773,6 +777,38 @@ impl CMAudioFormatDescription {
         }
     }
 
+    pub fn new_with_channel_layout(
+        asbd: &AudioStreamBasicDescription,
+        layout: Option<&ChannelLayout>,
+        magic_cookie: &[u8],
+        extensions: Option<&CFDictionary<CFString, CFType>>,
+    ) -> Result<Self, OSStatus> {
+        let create = |layout: *const AudioChannelLayout, layout_size: usize| {
+            let mut format_description: CMAudioFormatDescriptionRef = null_mut();
+            let status = unsafe {
+                CMAudioFormatDescriptionCreate(
+                    kCFAllocatorDefault,
+                    asbd,
+                    layout_size,
+                    layout,
+                    magic_cookie.len(),
+                    magic_cookie.as_ptr() as *const _,
+                    extensions.map_or(null(), |exts| exts.as_concrete_TypeRef()),
+                    &mut format_description,
+                )
+            };
+            if status == 0 {
+                Ok(unsafe { TCFType::wrap_under_create_rule(format_description) })
+            } else {
+                Err(status)
+            }
+        };
+        match layout {
+            Some(layout) => layout.with_audio_channel_layout(|layout, layout_size| create(layout, layout_size)),
+            None => create(null(), 0),
+        }
+    }
+
     #[inline]
     pub fn get_magic_cookie(&self) -> Option<&[u8]> {
         unsafe {
@@ -799,6 +835,11 @@ impl CMAudioFormatDescription {
         }
     }
 
+    pub fn copy_channel_layout(&self) -> Option<ChannelLayout> {
+        let (layout, layout_size) = self.get_channel_layout()?;
+        unsafe { ChannelLayout::from_audio_channel_layout(layout, layout_size).ok() }
+    }
+
     #[inline]
     pub fn get_format_list(&self) -> Option<&[AudioFormatListItem]> {
         unsafe {
@@ -850,11 +891,17 @@ impl CMAudioFormatDescription {
     }
 
     #[inline]
-    pub fn equal(&self, other: &Self, eequality_mask: CMAudioFormatDescriptionMask) -> (bool, CMAudioFormatDescriptionMask) {
+    pub fn equal(&self, other: &Self, equality_mask: CMAudioFormatDescriptionMask) -> (bool, CMAudioFormatDescriptionMask) {
         let mut mask = 0;
-        let equal = unsafe { CMAudioFormatDescriptionEqual(self.as_concrete_TypeRef(), other.as_concrete_TypeRef(), eequality_mask, &mut mask) != 0 };
+        let equal = unsafe { CMAudioFormatDescriptionEqual(self.as_concrete_TypeRef(), other.as_concrete_TypeRef(), equality_mask, &mut mask) != 0 };
         (equal, mask)
     }
+
+    #[inline]
+    pub fn compare(&self, other: &Self, equality_mask: CMAudioFormatDescriptionMask) -> (bool, CMAudioFormatDescriptionMask) {
+        AudioFormatDescriptionParts::from_format_description(self)
+            .compare(&AudioFormatDescriptionParts::from_format_description(other), equality_mask)
+    }
 }
 
 impl TCMFormatDescription for CMVideoFormatDescription {}
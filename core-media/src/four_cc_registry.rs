@@ -0,0 +1,282 @@
+use core_audio_types::{base_types::*, four_cc::FourCC};
+use core_video::pixel_buffer::*;
+
+use crate::format_description::*;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FourCCNamespace {
+    MediaType,
+    VideoCodecType,
+    AudioFormatID,
+    PixelFormatType,
+    MuxedStreamType,
+    ClosedCaptionFormatType,
+    TextFormatType,
+    SubtitleFormatType,
+    TimeCodeFormatType,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FourCCEntry {
+    pub namespace: FourCCNamespace,
+    pub code: FourCC,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+const fn entry(namespace: FourCCNamespace, code: u32, name: &'static str, description: &'static str) -> FourCCEntry {
+    FourCCEntry {
+        namespace,
+        code: FourCC::from_u32(code),
+        name,
+        description,
+    }
+}
+
+static FOUR_CC_ENTRIES: &[FourCCEntry] = &[
+    entry(FourCCNamespace::MediaType, kCMMediaType_Video, "kCMMediaType_Video", "Video"),
+    entry(FourCCNamespace::MediaType, kCMMediaType_Audio, "kCMMediaType_Audio", "Audio"),
+    entry(FourCCNamespace::MediaType, kCMMediaType_Muxed, "kCMMediaType_Muxed", "Muxed audio and video"),
+    entry(FourCCNamespace::MediaType, kCMMediaType_Text, "kCMMediaType_Text", "Text"),
+    entry(FourCCNamespace::MediaType, kCMMediaType_ClosedCaption, "kCMMediaType_ClosedCaption", "Closed captions"),
+    entry(FourCCNamespace::MediaType, kCMMediaType_Subtitle, "kCMMediaType_Subtitle", "Subtitles"),
+    entry(FourCCNamespace::MediaType, kCMMediaType_TimeCode, "kCMMediaType_TimeCode", "Time code"),
+    entry(FourCCNamespace::MediaType, kCMMediaType_Metadata, "kCMMediaType_Metadata", "Timed metadata"),
+    entry(FourCCNamespace::MediaType, kCMMediaType_TaggedBufferGroup, "kCMMediaType_TaggedBufferGroup", "Tagged buffer group"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_422YpCbCr8, "kCMVideoCodecType_422YpCbCr8", "Component Y'CbCr 8-bit 4:2:2"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_Animation, "kCMVideoCodecType_Animation", "Apple Animation (RLE)"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_Cinepak, "kCMVideoCodecType_Cinepak", "Cinepak"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_JPEG, "kCMVideoCodecType_JPEG", "Photo JPEG"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_JPEG_OpenDML, "kCMVideoCodecType_JPEG_OpenDML", "Motion JPEG (OpenDML)"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_SorensonVideo, "kCMVideoCodecType_SorensonVideo", "Sorenson Video"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_SorensonVideo3, "kCMVideoCodecType_SorensonVideo3", "Sorenson Video 3"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_H263, "kCMVideoCodecType_H263", "ITU-T H.263"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_H264, "kCMVideoCodecType_H264", "ITU-T H.264 / AVC"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_HEVC, "kCMVideoCodecType_HEVC", "ITU-T H.265 / HEVC"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_HEVCWithAlpha, "kCMVideoCodecType_HEVCWithAlpha", "HEVC with alpha"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DolbyVisionHEVC, "kCMVideoCodecType_DolbyVisionHEVC", "Dolby Vision HEVC"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_MPEG4Video, "kCMVideoCodecType_MPEG4Video", "MPEG-4 Part 2 video"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_MPEG2Video, "kCMVideoCodecType_MPEG2Video", "MPEG-2 video"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_MPEG1Video, "kCMVideoCodecType_MPEG1Video", "MPEG-1 video"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_VP9, "kCMVideoCodecType_VP9", "VP9"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DVCNTSC, "kCMVideoCodecType_DVCNTSC", "DV NTSC"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DVCPAL, "kCMVideoCodecType_DVCPAL", "DV PAL"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DVCProPAL, "kCMVideoCodecType_DVCProPAL", "DVCPRO PAL"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DVCPro50NTSC, "kCMVideoCodecType_DVCPro50NTSC", "DVCPRO50 NTSC"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DVCPro50PAL, "kCMVideoCodecType_DVCPro50PAL", "DVCPRO50 PAL"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DVCPROHD720p60, "kCMVideoCodecType_DVCPROHD720p60", "DVCPRO HD 720p60"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DVCPROHD720p50, "kCMVideoCodecType_DVCPROHD720p50", "DVCPRO HD 720p50"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DVCPROHD1080i60, "kCMVideoCodecType_DVCPROHD1080i60", "DVCPRO HD 1080i60"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DVCPROHD1080i50, "kCMVideoCodecType_DVCPROHD1080i50", "DVCPRO HD 1080i50"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DVCPROHD1080p30, "kCMVideoCodecType_DVCPROHD1080p30", "DVCPRO HD 1080p30"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DVCPROHD1080p25, "kCMVideoCodecType_DVCPROHD1080p25", "DVCPRO HD 1080p25"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_AppleProRes4444XQ, "kCMVideoCodecType_AppleProRes4444XQ", "Apple ProRes 4444 XQ"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_AppleProRes4444, "kCMVideoCodecType_AppleProRes4444", "Apple ProRes 4444"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_AppleProRes422HQ, "kCMVideoCodecType_AppleProRes422HQ", "Apple ProRes 422 HQ"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_AppleProRes422, "kCMVideoCodecType_AppleProRes422", "Apple ProRes 422"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_AppleProRes422LT, "kCMVideoCodecType_AppleProRes422LT", "Apple ProRes 422 LT"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_AppleProRes422Proxy, "kCMVideoCodecType_AppleProRes422Proxy", "Apple ProRes 422 Proxy"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_AppleProResRAW, "kCMVideoCodecType_AppleProResRAW", "Apple ProRes RAW"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_AppleProResRAWHQ, "kCMVideoCodecType_AppleProResRAWHQ", "Apple ProRes RAW HQ"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DisparityHEVC, "kCMVideoCodecType_DisparityHEVC", "HEVC disparity"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_DepthHEVC, "kCMVideoCodecType_DepthHEVC", "HEVC depth"),
+    entry(FourCCNamespace::VideoCodecType, kCMVideoCodecType_AV1, "kCMVideoCodecType_AV1", "AOMedia Video 1"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatLinearPCM, "kAudioFormatLinearPCM", "Linear PCM"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatAC3, "kAudioFormatAC3", "Dolby AC-3"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormat60958AC3, "kAudioFormat60958AC3", "AC-3 over IEC 60958"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatAppleIMA4, "kAudioFormatAppleIMA4", "Apple IMA 4:1 ADPCM"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEG4AAC, "kAudioFormatMPEG4AAC", "MPEG-4 AAC"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEG4CELP, "kAudioFormatMPEG4CELP", "MPEG-4 CELP"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEG4HVXC, "kAudioFormatMPEG4HVXC", "MPEG-4 HVXC"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEG4TwinVQ, "kAudioFormatMPEG4TwinVQ", "MPEG-4 TwinVQ"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMACE3, "kAudioFormatMACE3", "MACE 3:1"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMACE6, "kAudioFormatMACE6", "MACE 6:1"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatULaw, "kAudioFormatULaw", "mu-law 2:1"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatALaw, "kAudioFormatALaw", "A-law 2:1"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatQDesign, "kAudioFormatQDesign", "QDesign music"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatQDesign2, "kAudioFormatQDesign2", "QDesign2 music"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatQUALCOMM, "kAudioFormatQUALCOMM", "QUALCOMM PureVoice"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEGLayer1, "kAudioFormatMPEGLayer1", "MPEG-1/2 Layer I"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEGLayer2, "kAudioFormatMPEGLayer2", "MPEG-1/2 Layer II"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEGLayer3, "kAudioFormatMPEGLayer3", "MPEG-1/2 Layer III"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatTimeCode, "kAudioFormatTimeCode", "Time code"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMIDIStream, "kAudioFormatMIDIStream", "MIDI stream"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatParameterValueStream, "kAudioFormatParameterValueStream", "Audio unit parameter values"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatAppleLossless, "kAudioFormatAppleLossless", "Apple Lossless"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEG4AAC_HE, "kAudioFormatMPEG4AAC_HE", "MPEG-4 HE-AAC"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEG4AAC_LD, "kAudioFormatMPEG4AAC_LD", "MPEG-4 AAC Low Delay"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEG4AAC_ELD, "kAudioFormatMPEG4AAC_ELD", "MPEG-4 AAC Enhanced Low Delay"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEG4AAC_ELD_SBR, "kAudioFormatMPEG4AAC_ELD_SBR", "MPEG-4 AAC-ELD with SBR"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEG4AAC_ELD_V2, "kAudioFormatMPEG4AAC_ELD_V2", "MPEG-4 AAC-ELD v2"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEG4AAC_HE_V2, "kAudioFormatMPEG4AAC_HE_V2", "MPEG-4 HE-AAC v2"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEG4AAC_Spatial, "kAudioFormatMPEG4AAC_Spatial", "MPEG-4 Spatial Audio"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMPEGD_USAC, "kAudioFormatMPEGD_USAC", "MPEG-D USAC"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatAMR, "kAudioFormatAMR", "AMR narrowband"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatAMR_WB, "kAudioFormatAMR_WB", "AMR wideband"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatAudible, "kAudioFormatAudible", "Audible"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatiLBC, "kAudioFormatiLBC", "iLBC"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatDVIIntelIMA, "kAudioFormatDVIIntelIMA", "DVI/Intel IMA ADPCM"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatMicrosoftGSM, "kAudioFormatMicrosoftGSM", "Microsoft GSM 6.10"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatAES3, "kAudioFormatAES3", "AES3-2003"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatEnhancedAC3, "kAudioFormatEnhancedAC3", "Dolby E-AC-3"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatFLAC, "kAudioFormatFLAC", "Free Lossless Audio Codec"),
+    entry(FourCCNamespace::AudioFormatID, kAudioFormatOpus, "kAudioFormatOpus", "Opus"),
+    entry(FourCCNamespace::AudioFormatID, kCMAudioCodecType_AAC_LCProtected, "kCMAudioCodecType_AAC_LCProtected", "Protected AAC-LC"),
+    entry(FourCCNamespace::AudioFormatID, kCMAudioCodecType_AAC_AudibleProtected, "kCMAudioCodecType_AAC_AudibleProtected", "Audible protected AAC"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_1Monochrome, "kCVPixelFormatType_1Monochrome", "1 bit indexed"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_2Indexed, "kCVPixelFormatType_2Indexed", "2 bit indexed"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_4Indexed, "kCVPixelFormatType_4Indexed", "4 bit indexed"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_8Indexed, "kCVPixelFormatType_8Indexed", "8 bit indexed"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_1IndexedGray_WhiteIsZero, "kCVPixelFormatType_1IndexedGray_WhiteIsZero", "1 bit indexed gray, white is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_2IndexedGray_WhiteIsZero, "kCVPixelFormatType_2IndexedGray_WhiteIsZero", "2 bit indexed gray, white is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_4IndexedGray_WhiteIsZero, "kCVPixelFormatType_4IndexedGray_WhiteIsZero", "4 bit indexed gray, white is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_8IndexedGray_WhiteIsZero, "kCVPixelFormatType_8IndexedGray_WhiteIsZero", "8 bit indexed gray, white is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_16BE555, "kCVPixelFormatType_16BE555", "16 bit BE RGB 555"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_16LE555, "kCVPixelFormatType_16LE555", "16 bit LE RGB 555"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_16LE5551, "kCVPixelFormatType_16LE5551", "16 bit LE RGB 5551"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_16BE565, "kCVPixelFormatType_16BE565", "16 bit BE RGB 565"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_16LE565, "kCVPixelFormatType_16LE565", "16 bit LE RGB 565"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_24RGB, "kCVPixelFormatType_24RGB", "24 bit RGB"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_24BGR, "kCVPixelFormatType_24BGR", "24 bit BGR"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_32ARGB, "kCVPixelFormatType_32ARGB", "32 bit ARGB"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_32BGRA, "kCVPixelFormatType_32BGRA", "32 bit BGRA"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_32ABGR, "kCVPixelFormatType_32ABGR", "32 bit ABGR"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_32RGBA, "kCVPixelFormatType_32RGBA", "32 bit RGBA"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_64ARGB, "kCVPixelFormatType_64ARGB", "64 bit ARGB, 16-bit big-endian samples"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_48RGB, "kCVPixelFormatType_48RGB", "48 bit RGB, 16-bit big-endian samples"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_32AlphaGray, "kCVPixelFormatType_32AlphaGray", "32 bit AlphaGray, 16-bit big-endian samples, black is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_16Gray, "kCVPixelFormatType_16Gray", "16 bit Grayscale, 16-bit big-endian samples, black is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_30RGB, "kCVPixelFormatType_30RGB", "30 bit RGB, 10-bit big-endian samples, 2 unused padding bits (at least significant end)"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_422YpCbCr8, "kCVPixelFormatType_422YpCbCr8", "Component Y'CbCr 8-bit 4:2:2, ordered Cb Y'0 Cr Y'1"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_4444YpCbCrA8, "kCVPixelFormatType_4444YpCbCrA8", "Component Y'CbCrA 8-bit 4:4:4:4, ordered Cb Y' Cr A"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_4444YpCbCrA8R, "kCVPixelFormatType_4444YpCbCrA8R", "Component Y'CbCrA 8-bit 4:4:4:4, rendering format. full range alpha, zero biased YUV"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_4444AYpCbCr8, "kCVPixelFormatType_4444AYpCbCr8", "Component Y'CbCrA 8-bit 4:4:4:4"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_4444AYpCbCr16, "kCVPixelFormatType_4444AYpCbCr16", "Component Y'CbCrA 16-bit 4:4:4:4"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_4444AYpCbCrFloat, "kCVPixelFormatType_4444AYpCbCrFloat", "Component AY'CbCr single precision floating-point 4:4:4:4"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_444YpCbCr8, "kCVPixelFormatType_444YpCbCr8", "Component Y'CbCr 8-bit 4:4:4"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_422YpCbCr16, "kCVPixelFormatType_422YpCbCr16", "Component Y'CbCr 10,12,14,16-bit 4:2:2"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_422YpCbCr10, "kCVPixelFormatType_422YpCbCr10", "Component Y'CbCr 10-bit 4:2:2"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_444YpCbCr10, "kCVPixelFormatType_444YpCbCr10", "Component Y'CbCr 10-bit 4:4:4"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_420YpCbCr8Planar, "kCVPixelFormatType_420YpCbCr8Planar", "Planar Component Y'CbCr 8-bit 4:2:0"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_420YpCbCr8PlanarFullRange, "kCVPixelFormatType_420YpCbCr8PlanarFullRange", "Planar Component Y'CbCr 8-bit 4:2:0, full range"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_422YpCbCr_4A_8BiPlanar, "kCVPixelFormatType_422YpCbCr_4A_8BiPlanar", "First plane: Video-range Component Y'CbCr 8-bit 4:2:2"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange, "kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange", "Bi-Planar Component Y'CbCr 8-bit 4:2:0, video-range (luma=[16,235] chroma=[16,240])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_420YpCbCr8BiPlanarFullRange, "kCVPixelFormatType_420YpCbCr8BiPlanarFullRange", "Bi-Planar Component Y'CbCr 8-bit 4:2:0, full-range (luma=[0,255] chroma=[1,255])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_422YpCbCr8BiPlanarVideoRange, "kCVPixelFormatType_422YpCbCr8BiPlanarVideoRange", "Bi-Planar Component Y'CbCr 8-bit 4:2:2, video-range (luma=[16,235] chroma=[16,240])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_422YpCbCr8BiPlanarFullRange, "kCVPixelFormatType_422YpCbCr8BiPlanarFullRange", "Bi-Planar Component Y'CbCr 8-bit 4:2:2, full-range (luma=[0,255] chroma=[1,255])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_444YpCbCr8BiPlanarVideoRange, "kCVPixelFormatType_444YpCbCr8BiPlanarVideoRange", "Bi-Planar Component Y'CbCr 8-bit 4:4:4, video-range (luma=[16,235] chroma=[16,240])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_444YpCbCr8BiPlanarFullRange, "kCVPixelFormatType_444YpCbCr8BiPlanarFullRange", "Bi-Planar Component Y'CbCr 8-bit 4:4:4, full-range (luma=[0,255] chroma=[1,255])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_422YpCbCr8_yuvs, "kCVPixelFormatType_422YpCbCr8_yuvs", "Component Y'CbCr 8-bit 4:2:2, ordered Y'0 Cb Y'1 Cr"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_422YpCbCr8FullRange, "kCVPixelFormatType_422YpCbCr8FullRange", "Component Y'CbCr 8-bit 4:2:2, full range, ordered Y'0 Cb Y'1 Cr"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_OneComponent8, "kCVPixelFormatType_OneComponent8", "8 bit one component, black is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_TwoComponent8, "kCVPixelFormatType_TwoComponent8", "8 bit two component, black is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_30RGBLEPackedWideGamut, "kCVPixelFormatType_30RGBLEPackedWideGamut", "little-endian RGB101010, 2 MSB are zero, wide-gamut (384-895)"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_ARGB2101010LEPacked, "kCVPixelFormatType_ARGB2101010LEPacked", "little-endian ARGB2101010 full-range ARGB"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_40ARGBLEWideGamut, "kCVPixelFormatType_40ARGBLEWideGamut", "little-endian ARGB10101010, each 10 bits in the MSBs of 16bits, wide-gamut (384-895, including alpha)"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_40ARGBLEWideGamutPremultiplied, "kCVPixelFormatType_40ARGBLEWideGamutPremultiplied", "little-endian ARGB10101010, each 10 bits in the MSBs of 16bits, wide-gamut (384-895, including alpha). Alpha premultiplied"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_OneComponent10, "kCVPixelFormatType_OneComponent10", "10 bit little-endian one component, stored as 10 MSBs of 16 bits, black is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_OneComponent12, "kCVPixelFormatType_OneComponent12", "12 bit little-endian one component, stored as 12 MSBs of 16 bits, black is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_OneComponent16, "kCVPixelFormatType_OneComponent16", "16 bit little-endian one component, black is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_TwoComponent16, "kCVPixelFormatType_TwoComponent16", "16 bit little-endian two component, black is zero"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_OneComponent16Half, "kCVPixelFormatType_OneComponent16Half", "16 bit one component IEEE half-precision float, 16-bit little-endian samples"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_OneComponent32Float, "kCVPixelFormatType_OneComponent32Float", "32 bit one component IEEE float, 32-bit little-endian samples"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_TwoComponent16Half, "kCVPixelFormatType_TwoComponent16Half", "16 bit two component IEEE half-precision float, 16-bit little-endian samples"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_TwoComponent32Float, "kCVPixelFormatType_TwoComponent32Float", "32 bit two component IEEE float, 32-bit little-endian samples"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_64RGBAHalf, "kCVPixelFormatType_64RGBAHalf", "64 bit RGBA IEEE half-precision float, 16-bit little-endian samples"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_128RGBAFloat, "kCVPixelFormatType_128RGBAFloat", "128 bit RGBA IEEE float, 32-bit little-endian samples"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_14Bayer_GRBG, "kCVPixelFormatType_14Bayer_GRBG", "Bayer 14-bit Little-Endian, packed in 16-bits"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_14Bayer_RGGB, "kCVPixelFormatType_14Bayer_RGGB", "Bayer 14-bit Little-Endian, packed in 16-bits"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_14Bayer_BGGR, "kCVPixelFormatType_14Bayer_BGGR", "Bayer 14-bit Little-Endian, packed in 16-bits"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_14Bayer_GBRG, "kCVPixelFormatType_14Bayer_GBRG", "Bayer 14-bit Little-Endian, packed in 16-bits"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_DisparityFloat16, "kCVPixelFormatType_DisparityFloat16", "IEEE754-2008 binary16 (half float), describing the normalized shift when comparing two images. Units are 1/meters: ( pixelShift / (pixelFocalLength * baselineInMeters) )"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_DisparityFloat32, "kCVPixelFormatType_DisparityFloat32", "IEEE754-2008 binary32 float, describing the normalized shift when comparing two images. Units are 1/meters: ( pixelShift / (pixelFocalLength * baselineInMeters) )"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_DepthFloat16, "kCVPixelFormatType_DepthFloat16", "IEEE754-2008 binary16 (half float), describing the depth (distance to an object) in meters"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_DepthFloat32, "kCVPixelFormatType_DepthFloat32", "IEEE754-2008 binary32 float, describing the depth (distance to an object) in meters"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange, "kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange", "2 plane YCbCr10 4:2:0, each 10 bits in the MSBs of 16bits, video-range (luma=[64,940] chroma=[64,960])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange, "kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange", "2 plane YCbCr10 4:2:2, each 10 bits in the MSBs of 16bits, video-range (luma=[64,940] chroma=[64,960])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_444YpCbCr10BiPlanarVideoRange, "kCVPixelFormatType_444YpCbCr10BiPlanarVideoRange", "2 plane YCbCr10 4:4:4, each 10 bits in the MSBs of 16bits, video-range (luma=[64,940] chroma=[64,960])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_420YpCbCr10BiPlanarFullRange, "kCVPixelFormatType_420YpCbCr10BiPlanarFullRange", "2 plane YCbCr10 4:2:0, each 10 bits in the MSBs of 16bits, full-range (Y range 0-1023)"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_422YpCbCr10BiPlanarFullRange, "kCVPixelFormatType_422YpCbCr10BiPlanarFullRange", "2 plane YCbCr10 4:2:2, each 10 bits in the MSBs of 16bits, full-range (Y range 0-1023)"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_444YpCbCr10BiPlanarFullRange, "kCVPixelFormatType_444YpCbCr10BiPlanarFullRange", "2 plane YCbCr10 4:4:4, each 10 bits in the MSBs of 16bits, full-range (Y range 0-1023)"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_420YpCbCr8VideoRange_8A_TriPlanar, "kCVPixelFormatType_420YpCbCr8VideoRange_8A_TriPlanar", "first and second planes as per 420YpCbCr8BiPlanarVideoRange (420v), alpha 8 bits in third plane full-range"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_16VersatileBayer, "kCVPixelFormatType_16VersatileBayer", "Single plane Bayer 16-bit little-endian sensor element (\"sensel\") samples from full-size decoding of ProRes RAW images"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_64RGBA_DownscaledProResRAW, "kCVPixelFormatType_64RGBA_DownscaledProResRAW", "Single plane 64-bit RGBA (16-bit little-endian samples) from downscaled decoding of ProRes RAW images"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_422YpCbCr16BiPlanarVideoRange, "kCVPixelFormatType_422YpCbCr16BiPlanarVideoRange", "2 plane YCbCr16 4:2:2, video-range (luma=[4096,60160] chroma=[4096,61440])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_444YpCbCr16BiPlanarVideoRange, "kCVPixelFormatType_444YpCbCr16BiPlanarVideoRange", "2 plane YCbCr16 4:4:4, video-range (luma=[4096,60160] chroma=[4096,61440])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_444YpCbCr16VideoRange_16A_TriPlanar, "kCVPixelFormatType_444YpCbCr16VideoRange_16A_TriPlanar", "3 plane video-range YCbCr16 4:4:4 with 16-bit full-range alpha (luma=[4096,60160] chroma=[4096,61440] alpha=[0,65535])"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_Lossless_32BGRA, "kCVPixelFormatType_Lossless_32BGRA", "Lossless-compressed form of kCVPixelFormatType_32BGRA"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_Lossless_420YpCbCr8BiPlanarVideoRange, "kCVPixelFormatType_Lossless_420YpCbCr8BiPlanarVideoRange", "Lossless-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_Lossless_420YpCbCr8BiPlanarFullRange, "kCVPixelFormatType_Lossless_420YpCbCr8BiPlanarFullRange", "Lossless-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarFullRange"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_Lossless_420YpCbCr10PackedBiPlanarVideoRange, "kCVPixelFormatType_Lossless_420YpCbCr10PackedBiPlanarVideoRange", "Lossless-compressed-packed form of kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_Lossless_422YpCbCr10PackedBiPlanarVideoRange, "kCVPixelFormatType_Lossless_422YpCbCr10PackedBiPlanarVideoRange", "Lossless-compressed form of kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_Lossy_32BGRA, "kCVPixelFormatType_Lossy_32BGRA", "Lossy-compressed form of kCVPixelFormatType_32BGRA. No CVPlanarPixelBufferInfo struct"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_Lossy_420YpCbCr8BiPlanarVideoRange, "kCVPixelFormatType_Lossy_420YpCbCr8BiPlanarVideoRange", "Lossy-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_Lossy_420YpCbCr8BiPlanarFullRange, "kCVPixelFormatType_Lossy_420YpCbCr8BiPlanarFullRange", "Lossy-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarFullRange"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_Lossy_420YpCbCr10PackedBiPlanarVideoRange, "kCVPixelFormatType_Lossy_420YpCbCr10PackedBiPlanarVideoRange", "Lossy-compressed form of kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange"),
+    entry(FourCCNamespace::PixelFormatType, kCVPixelFormatType_Lossy_422YpCbCr10PackedBiPlanarVideoRange, "kCVPixelFormatType_Lossy_422YpCbCr10PackedBiPlanarVideoRange", "Lossy-compressed form of kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange"),
+    entry(FourCCNamespace::MuxedStreamType, kCMMuxedStreamType_MPEG1System, "kCMMuxedStreamType_MPEG1System", "MPEG-1 system stream"),
+    entry(FourCCNamespace::MuxedStreamType, kCMMuxedStreamType_MPEG2Transport, "kCMMuxedStreamType_MPEG2Transport", "MPEG-2 transport stream"),
+    entry(FourCCNamespace::MuxedStreamType, kCMMuxedStreamType_MPEG2Program, "kCMMuxedStreamType_MPEG2Program", "MPEG-2 program stream"),
+    entry(FourCCNamespace::MuxedStreamType, kCMMuxedStreamType_DV, "kCMMuxedStreamType_DV", "DV stream"),
+    entry(FourCCNamespace::MuxedStreamType, kCMMuxedStreamType_EmbeddedDeviceScreenRecording, "kCMMuxedStreamType_EmbeddedDeviceScreenRecording", "Embedded device screen recording"),
+    entry(FourCCNamespace::ClosedCaptionFormatType, kCMClosedCaptionFormatType_CEA608, "kCMClosedCaptionFormatType_CEA608", "CEA-608 captions"),
+    entry(FourCCNamespace::ClosedCaptionFormatType, kCMClosedCaptionFormatType_CEA708, "kCMClosedCaptionFormatType_CEA708", "CEA-708 captions"),
+    entry(FourCCNamespace::ClosedCaptionFormatType, kCMClosedCaptionFormatType_ATSC, "kCMClosedCaptionFormatType_ATSC", "ATSC/52 part 4 captions"),
+    entry(FourCCNamespace::TextFormatType, kCMTextFormatType_QTText, "kCMTextFormatType_QTText", "QuickTime text"),
+    entry(FourCCNamespace::TextFormatType, kCMTextFormatType_3GText, "kCMTextFormatType_3GText", "3GPP timed text"),
+    entry(FourCCNamespace::SubtitleFormatType, kCMSubtitleFormatType_3GText, "kCMSubtitleFormatType_3GText", "3GPP timed text"),
+    entry(FourCCNamespace::SubtitleFormatType, kCMSubtitleFormatType_WebVTT, "kCMSubtitleFormatType_WebVTT", "WebVTT"),
+    entry(FourCCNamespace::TimeCodeFormatType, kCMTimeCodeFormatType_TimeCode32, "kCMTimeCodeFormatType_TimeCode32", "32-bit time code"),
+    entry(FourCCNamespace::TimeCodeFormatType, kCMTimeCodeFormatType_TimeCode64, "kCMTimeCodeFormatType_TimeCode64", "64-bit time code"),
+    entry(FourCCNamespace::TimeCodeFormatType, kCMTimeCodeFormatType_Counter32, "kCMTimeCodeFormatType_Counter32", "32-bit counter"),
+    entry(FourCCNamespace::TimeCodeFormatType, kCMTimeCodeFormatType_Counter64, "kCMTimeCodeFormatType_Counter64", "64-bit counter"),
+];
+
+#[inline]
+pub fn entries() -> &'static [FourCCEntry] {
+    FOUR_CC_ENTRIES
+}
+
+pub fn lookup(namespace: FourCCNamespace, code: impl Into<FourCC>) -> Option<&'static FourCCEntry> {
+    let code = code.into();
+    FOUR_CC_ENTRIES.iter().find(|entry| entry.namespace == namespace && entry.code == code)
+}
+
+pub fn lookup_all(code: impl Into<FourCC>) -> impl Iterator<Item = &'static FourCCEntry> {
+    let code = code.into();
+    FOUR_CC_ENTRIES.iter().filter(move |entry| entry.code == code)
+}
+
+pub fn lookup_name(name: &str) -> Option<&'static FourCCEntry> {
+    FOUR_CC_ENTRIES.iter().find(|entry| entry.name == name)
+}
+
+pub fn describe(namespace: FourCCNamespace, code: impl Into<FourCC>) -> String {
+    let code = code.into();
+    match lookup(namespace, code) {
+        Some(entry) => format!("{} ({}, {})", code, entry.name, entry.description),
+        None => code.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_by_namespace_and_name() {
+        let entry = lookup(FourCCNamespace::VideoCodecType, FourCC::new(b"avc1")).unwrap();
+        assert_eq!(entry.name, "kCMVideoCodecType_H264");
+        assert_eq!(lookup_name("kCMVideoCodecType_H264"), Some(entry));
+        assert_eq!(lookup(FourCCNamespace::AudioFormatID, FourCC::new(b"avc1")), None);
+        assert_eq!(lookup(FourCCNamespace::AudioFormatID, kAudioFormatLinearPCM).unwrap().name, "kAudioFormatLinearPCM");
+        // The same code can name entries in several namespaces
+        assert!(lookup_all(kCMTextFormatType_3GText).count() >= 2);
+    }
+
+    #[test]
+    fn describe_formats_known_and_unknown_codes() {
+        assert_eq!(describe(FourCCNamespace::PixelFormatType, kCVPixelFormatType_32ARGB), "0x00000020 (kCVPixelFormatType_32ARGB, 32 bit ARGB)");
+        assert_eq!(describe(FourCCNamespace::VideoCodecType, FourCC::new(b"zzzz")), "'zzzz'");
+    }
+}
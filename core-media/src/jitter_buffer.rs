@@ -0,0 +1,486 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use core_foundation::base::OSStatus;
+
+use crate::{
+    sample_buffer::{kCMSampleBufferError_SampleTimingInfoInvalid, CMSampleTimingInfo},
+    sync::{kCMClockError_InvalidParameter, CMClock, CMClockOrTimebase, CMTimebase},
+    time::{add_times, is_numeric, make_time_saturating, subtract_times, value_in_timescale, CMTime, CMTimeScale},
+};
+
+const NANOSECONDS_PER_SECOND: CMTimeScale = 1_000_000_000;
+
+#[inline]
+fn nanoseconds(time: &CMTime) -> Option<i128> {
+    is_numeric(time).then(|| value_in_timescale(time, NANOSECONDS_PER_SECOND))
+}
+
+#[inline]
+fn from_nanoseconds(nanoseconds: i128) -> CMTime {
+    make_time_saturating(nanoseconds, NANOSECONDS_PER_SECOND)
+}
+
+// Source of the current time for scheduling releases
+pub trait Clock {
+    fn get_time(&self) -> CMTime;
+}
+
+impl Clock for CMClock {
+    #[inline]
+    fn get_time(&self) -> CMTime {
+        CMClock::get_time(self)
+    }
+}
+
+impl Clock for CMTimebase {
+    #[inline]
+    fn get_time(&self) -> CMTime {
+        CMTimebase::get_time(self)
+    }
+}
+
+impl Clock for CMClockOrTimebase {
+    #[inline]
+    fn get_time(&self) -> CMTime {
+        CMClockOrTimebase::get_time(self)
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    #[inline]
+    fn get_time(&self) -> CMTime {
+        (**self).get_time()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    #[inline]
+    fn get_time(&self) -> CMTime {
+        (**self).get_time()
+    }
+}
+
+// A clock that only moves when told to
+#[derive(Debug)]
+pub struct ManualClock {
+    time: Mutex<CMTime>,
+}
+
+impl ManualClock {
+    #[inline]
+    pub fn new(time: CMTime) -> Self {
+        Self {
+            time: Mutex::new(time),
+        }
+    }
+
+    #[inline]
+    pub fn set_time(&self, time: CMTime) {
+        *self.time.lock().unwrap_or_else(PoisonError::into_inner) = time;
+    }
+
+    pub fn advance(&self, delta: CMTime) {
+        let mut time = self.time.lock().unwrap_or_else(PoisonError::into_inner);
+        if is_numeric(&time) && is_numeric(&delta) {
+            *time = add_times(&time, &delta);
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline]
+    fn get_time(&self) -> CMTime {
+        *self.time.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum JitterBufferInsertion {
+    Queued,
+    Reordered,
+    DroppedLate,
+    DroppedDuplicate,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct JitterBufferStatistics {
+    pub received: u64,
+    pub released: u64,
+    pub reordered: u64,
+    pub late_dropped: u64,
+    pub duplicates_dropped: u64,
+    pub gaps: u64,
+    pub gap_duration: CMTime,
+    pub max_depth: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct JitterBufferSample<T> {
+    pub item: T,
+    pub timing: CMSampleTimingInfo,
+    // Clock times
+    pub arrival_time: CMTime,
+    pub release_time: CMTime,
+}
+
+#[derive(Clone, Debug)]
+pub enum JitterBufferOutput<T> {
+    Sample(JitterBufferSample<T>),
+    // Media time not covered by the released samples
+    Gap { start: CMTime, duration: CMTime },
+}
+
+struct PendingSample<T> {
+    item: T,
+    timing: CMSampleTimingInfo,
+    arrival: i128,
+    release: i128,
+}
+
+struct ReleasedSample {
+    decode_time: CMTime,
+    key: i128,
+    duration: CMTime,
+}
+
+// Holds samples for the target latency and releases them in decode order
+pub struct JitterBuffer<T, C: Clock> {
+    clock: C,
+    target_latency: i128,
+    max_lateness: Option<i128>,
+    // Clock and media times of the first sample, in nanoseconds
+    anchor: Option<(i128, i128)>,
+    pending: BTreeMap<i128, PendingSample<T>>,
+    last_released: Option<ReleasedSample>,
+    max_received: Option<i128>,
+    statistics: JitterBufferStatistics,
+}
+
+impl<T, C: Clock> JitterBuffer<T, C> {
+    pub fn new(clock: C, target_latency: CMTime) -> Result<Self, OSStatus> {
+        let target_latency = nanoseconds(&target_latency).filter(|latency| *latency >= 0).ok_or(kCMSampleBufferError_SampleTimingInfoInvalid)?;
+        Ok(Self {
+            clock,
+            target_latency,
+            max_lateness: None,
+            anchor: None,
+            pending: BTreeMap::new(),
+            last_released: None,
+            max_received: None,
+            statistics: JitterBufferStatistics::default(),
+        })
+    }
+
+    #[inline]
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    #[inline]
+    pub fn target_latency(&self) -> CMTime {
+        from_nanoseconds(self.target_latency)
+    }
+
+    // Samples arriving more than this after their release time are dropped
+    pub fn set_max_lateness(&mut self, max_lateness: Option<CMTime>) -> Result<(), OSStatus> {
+        self.max_lateness = match max_lateness {
+            Some(max_lateness) => {
+                Some(nanoseconds(&max_lateness).filter(|lateness| *lateness >= 0).ok_or(kCMSampleBufferError_SampleTimingInfoInvalid)?)
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
+    #[inline]
+    pub fn statistics(&self) -> &JitterBufferStatistics {
+        &self.statistics
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    // None while the clock has no numeric time, e.g. a timebase that isn't running
+    #[inline]
+    fn now(&self) -> Option<i128> {
+        nanoseconds(&self.clock.get_time())
+    }
+
+    // Falls back to the presentation time for streams without decode times
+    fn decode_time(timing: &CMSampleTimingInfo) -> Option<CMTime> {
+        [timing.decodeTimeStamp, timing.presentationTimeStamp].iter().copied().find(is_numeric)
+    }
+
+    pub fn push(&mut self, item: T, timing: CMSampleTimingInfo) -> Result<JitterBufferInsertion, OSStatus> {
+        let decode_time = Self::decode_time(&timing).ok_or(kCMSampleBufferError_SampleTimingInfoInvalid)?;
+        let key = value_in_timescale(&decode_time, NANOSECONDS_PER_SECOND);
+        // Anchoring on a made up time would release everything at once later
+        let arrival = self.now().ok_or(kCMClockError_InvalidParameter)?;
+        self.statistics.received += 1;
+        let (anchor_clock, anchor_media) = *self.anchor.get_or_insert((arrival, key));
+        let release = anchor_clock + (key - anchor_media) + self.target_latency;
+
+        // A re-sent copy of the last released sample is a duplicate rather than late
+        let released = self.last_released.as_ref().is_some_and(|released| key == released.key);
+        if released || self.pending.contains_key(&key) {
+            self.statistics.duplicates_dropped += 1;
+            return Ok(JitterBufferInsertion::DroppedDuplicate);
+        }
+        let behind_playout = self.last_released.as_ref().is_some_and(|released| key < released.key);
+        let too_late = self.max_lateness.is_some_and(|max_lateness| arrival - release > max_lateness);
+        if behind_playout || too_late {
+            self.statistics.late_dropped += 1;
+            return Ok(JitterBufferInsertion::DroppedLate);
+        }
+        let reordered = self.max_received.is_some_and(|max_received| key < max_received);
+        self.max_received = Some(self.max_received.map_or(key, |max_received| max_received.max(key)));
+        self.pending.insert(
+            key,
+            PendingSample {
+                item,
+                timing,
+                arrival,
+                release,
+            },
+        );
+        self.statistics.max_depth = self.statistics.max_depth.max(self.pending.len());
+        if reordered {
+            self.statistics.reordered += 1;
+            Ok(JitterBufferInsertion::Reordered)
+        } else {
+            Ok(JitterBufferInsertion::Queued)
+        }
+    }
+
+    // Clock time at which the next sample is due
+    pub fn next_release_time(&self) -> Option<CMTime> {
+        self.pending.values().next().map(|sample| from_nanoseconds(sample.release))
+    }
+
+    // Nothing is due while the clock has no numeric time
+    pub fn pop_ready(&mut self) -> Vec<JitterBufferOutput<T>> {
+        let mut output = Vec::new();
+        let now = match self.now() {
+            Some(now) => now,
+            None => return output,
+        };
+        while self.pending.values().next().is_some_and(|sample| sample.release <= now) {
+            self.release_first(&mut output);
+        }
+        output
+    }
+
+    // Releases everything regardless of the clock, e.g. at end of stream
+    pub fn flush(&mut self) -> Vec<JitterBufferOutput<T>> {
+        let mut output = Vec::new();
+        while !self.pending.is_empty() {
+            self.release_first(&mut output);
+        }
+        output
+    }
+
+    // Drops pending samples and re-anchors on the next push, statistics are kept
+    pub fn reset(&mut self) {
+        self.anchor = None;
+        self.pending.clear();
+        self.last_released = None;
+        self.max_received = None;
+    }
+
+    fn release_first(&mut self, output: &mut Vec<JitterBufferOutput<T>>) {
+        let key = match self.pending.keys().next() {
+            Some(key) => *key,
+            None => return,
+        };
+        let sample = match self.pending.remove(&key) {
+            Some(sample) => sample,
+            None => return,
+        };
+        let decode_time = Self::decode_time(&sample.timing).unwrap_or_default();
+        if let Some(previous) = self.last_released.as_ref().filter(|previous| is_numeric(&previous.duration)) {
+            let expected = add_times(&previous.decode_time, &previous.duration);
+            let gap = subtract_times(&decode_time, &expected);
+            if gap.value > 0 {
+                self.statistics.gaps += 1;
+                self.statistics.gap_duration = if is_numeric(&self.statistics.gap_duration) {
+                    add_times(&self.statistics.gap_duration, &gap)
+                } else {
+                    gap
+                };
+                output.push(JitterBufferOutput::Gap {
+                    start: expected,
+                    duration: gap,
+                });
+            }
+        }
+        self.last_released = Some(ReleasedSample {
+            decode_time,
+            key,
+            duration: sample.timing.duration,
+        });
+        self.statistics.released += 1;
+        output.push(JitterBufferOutput::Sample(JitterBufferSample {
+            item: sample.item,
+            timing: sample.timing,
+            arrival_time: from_nanoseconds(sample.arrival),
+            release_time: from_nanoseconds(sample.release),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{compare_times, make_time};
+
+    fn timing(frame: i64) -> CMSampleTimingInfo {
+        CMSampleTimingInfo {
+            duration: make_time(1, 30),
+            presentationTimeStamp: make_time(frame, 30),
+            decodeTimeStamp: make_time(frame, 30),
+        }
+    }
+
+    fn frames(output: &[JitterBufferOutput<i64>]) -> Vec<i64> {
+        output
+            .iter()
+            .filter_map(|output| match output {
+                JitterBufferOutput::Sample(sample) => Some(sample.item),
+                JitterBufferOutput::Gap {
+                    ..
+                } => None,
+            })
+            .collect()
+    }
+
+    fn assert_time_eq(a: CMTime, b: CMTime) {
+        assert_eq!(compare_times(&a, &b), std::cmp::Ordering::Equal, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn releases_after_target_latency() {
+        let clock = ManualClock::new(make_time(10, 1));
+        let mut buffer = JitterBuffer::new(&clock, make_time(100, 1000)).unwrap();
+
+        assert_eq!(buffer.push(0, timing(0)), Ok(JitterBufferInsertion::Queued));
+        assert_eq!(buffer.push(1, timing(1)), Ok(JitterBufferInsertion::Queued));
+        assert_time_eq(buffer.next_release_time().unwrap(), make_time(10_100, 1000));
+        assert!(buffer.pop_ready().is_empty());
+
+        clock.advance(make_time(100, 1000));
+        let output = buffer.pop_ready();
+        assert_eq!(frames(&output), vec![0]);
+        if let JitterBufferOutput::Sample(sample) = &output[0] {
+            assert_time_eq(sample.arrival_time, make_time(10, 1));
+            assert_time_eq(sample.release_time, make_time(10_100, 1000));
+        }
+
+        // The second frame is due one frame duration later
+        clock.advance(make_time(1, 30));
+        assert_eq!(frames(&buffer.pop_ready()), vec![1]);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.statistics().released, 2);
+    }
+
+    #[test]
+    fn reorders_and_drops_duplicates() {
+        let clock = ManualClock::new(make_time(0, 1));
+        let mut buffer = JitterBuffer::new(&clock, make_time(1, 1)).unwrap();
+
+        assert_eq!(buffer.push(0, timing(0)), Ok(JitterBufferInsertion::Queued));
+        assert_eq!(buffer.push(2, timing(2)), Ok(JitterBufferInsertion::Queued));
+        assert_eq!(buffer.push(1, timing(1)), Ok(JitterBufferInsertion::Reordered));
+        assert_eq!(buffer.push(10, timing(2)), Ok(JitterBufferInsertion::DroppedDuplicate));
+        assert_eq!(buffer.len(), 3);
+
+        clock.advance(make_time(2, 1));
+        assert_eq!(frames(&buffer.pop_ready()), vec![0, 1, 2]);
+        let statistics = buffer.statistics();
+        assert_eq!((statistics.received, statistics.reordered, statistics.duplicates_dropped, statistics.max_depth), (4, 1, 1, 3));
+    }
+
+    #[test]
+    fn drops_late_samples() {
+        let clock = ManualClock::new(make_time(0, 1));
+        let mut buffer = JitterBuffer::new(&clock, make_time(100, 1000)).unwrap();
+        buffer.set_max_lateness(Some(make_time(50, 1000))).unwrap();
+
+        buffer.push(0, timing(0)).unwrap();
+        buffer.push(3, timing(3)).unwrap();
+        clock.advance(make_time(100, 1000));
+        assert_eq!(frames(&buffer.pop_ready()), vec![0]);
+
+        // Behind the last released sample
+        assert_eq!(buffer.push(-1, timing(-1)), Ok(JitterBufferInsertion::DroppedLate));
+        // Frame 1 was due at 133ms and arrives at 200ms
+        clock.set_time(make_time(200, 1000));
+        assert_eq!(buffer.push(1, timing(1)), Ok(JitterBufferInsertion::DroppedLate));
+        // Frame 2 was due at 166ms and is within the allowed lateness
+        assert_eq!(buffer.push(2, timing(2)), Ok(JitterBufferInsertion::Reordered));
+        assert_eq!(buffer.statistics().late_dropped, 2);
+        assert_eq!(frames(&buffer.pop_ready()), vec![2, 3]);
+
+        // A re-sent copy of a released sample is a duplicate
+        assert_eq!(buffer.push(30, timing(3)), Ok(JitterBufferInsertion::DroppedDuplicate));
+        let statistics = buffer.statistics();
+        assert_eq!((statistics.late_dropped, statistics.duplicates_dropped), (2, 1));
+    }
+
+    #[test]
+    fn waits_for_a_numeric_clock_time() {
+        let clock = ManualClock::new(CMTime::default());
+        let mut buffer = JitterBuffer::new(&clock, make_time(100, 1000)).unwrap();
+
+        assert_eq!(buffer.push(0, timing(0)), Err(kCMClockError_InvalidParameter));
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.statistics().received, 0);
+
+        clock.set_time(make_time(10, 1));
+        buffer.push(0, timing(0)).unwrap();
+        clock.set_time(CMTime::default());
+        assert!(buffer.pop_ready().is_empty());
+        assert_eq!(buffer.len(), 1);
+
+        // Releases stay anchored on the first numeric time
+        clock.set_time(make_time(10_100, 1000));
+        assert_eq!(frames(&buffer.pop_ready()), vec![0]);
+    }
+
+    #[test]
+    fn reports_gaps() {
+        let clock = ManualClock::new(make_time(0, 1));
+        assert_eq!(JitterBuffer::<i64, _>::new(&clock, CMTime::default()).err(), Some(kCMSampleBufferError_SampleTimingInfoInvalid));
+
+        let mut buffer = JitterBuffer::new(&clock, make_time(0, 1)).unwrap();
+        buffer.push(0, timing(0)).unwrap();
+        buffer.push(1, timing(1)).unwrap();
+        buffer.push(4, timing(4)).unwrap();
+        let output = buffer.flush();
+        assert_eq!(frames(&output), vec![0, 1, 4]);
+        let gaps: Vec<(CMTime, CMTime)> = output
+            .iter()
+            .filter_map(|output| match output {
+                JitterBufferOutput::Gap {
+                    start,
+                    duration,
+                } => Some((*start, *duration)),
+                JitterBufferOutput::Sample(_) => None,
+            })
+            .collect();
+        assert_eq!(gaps.len(), 1);
+        assert_time_eq(gaps[0].0, make_time(2, 30));
+        assert_time_eq(gaps[0].1, make_time(2, 30));
+        assert_eq!(buffer.statistics().gaps, 1);
+        assert_time_eq(buffer.statistics().gap_duration, make_time(2, 30));
+    }
+}
@@ -0,0 +1,518 @@
+use core_foundation::{
+    base::{CFType, OSStatus, TCFType},
+    data::CFData,
+    dictionary::{CFDictionary, CFMutableDictionary},
+    number::CFNumber,
+    string::CFString,
+};
+
+use crate::format_description::{
+    kCMFormatDescriptionError_InvalidParameter, kCMFormatDescriptionExtension_Depth, kCMFormatDescriptionExtension_FormatName,
+    kCMFormatDescriptionExtension_ICCProfile, kCMVideoCodecType_JPEG, kCMVideoCodecType_JPEG_OpenDML, CMVideoCodecType, CMVideoDimensions,
+    CMVideoFormatDescription,
+};
+
+pub const JPEG_MARKER_SOI: u8 = 0xD8;
+pub const JPEG_MARKER_EOI: u8 = 0xD9;
+pub const JPEG_MARKER_SOS: u8 = 0xDA;
+pub const JPEG_MARKER_DHT: u8 = 0xC4;
+pub const JPEG_MARKER_DAC: u8 = 0xCC;
+pub const JPEG_MARKER_JPG: u8 = 0xC8;
+pub const JPEG_MARKER_TEM: u8 = 0x01;
+pub const JPEG_MARKER_RST0: u8 = 0xD0;
+pub const JPEG_MARKER_RST7: u8 = 0xD7;
+pub const JPEG_MARKER_APP1: u8 = 0xE1;
+pub const JPEG_MARKER_APP2: u8 = 0xE2;
+
+const EXIF_IDENTIFIER: &[u8] = b"Exif\0\0";
+const ICC_PROFILE_IDENTIFIER: &[u8] = b"ICC_PROFILE\0";
+const EXIF_TAG_ORIENTATION: u16 = 0x0112;
+const EXIF_TYPE_SHORT: u16 = 3;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum JpegCodingProcess {
+    Baseline,
+    ExtendedSequential,
+    Progressive,
+    Lossless,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum JpegChromaSubsampling {
+    Grayscale,
+    Chroma444,
+    Chroma422,
+    Chroma420,
+    Chroma411,
+    Chroma440,
+    Other,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ExifOrientation {
+    Up            = 1,
+    UpMirrored    = 2,
+    Down          = 3,
+    DownMirrored  = 4,
+    LeftMirrored  = 5,
+    Right         = 6,
+    RightMirrored = 7,
+    Left          = 8,
+}
+
+impl ExifOrientation {
+    pub fn from_value(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(ExifOrientation::Up),
+            2 => Some(ExifOrientation::UpMirrored),
+            3 => Some(ExifOrientation::Down),
+            4 => Some(ExifOrientation::DownMirrored),
+            5 => Some(ExifOrientation::LeftMirrored),
+            6 => Some(ExifOrientation::Right),
+            7 => Some(ExifOrientation::RightMirrored),
+            8 => Some(ExifOrientation::Left),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn swaps_dimensions(&self) -> bool {
+        (*self as u16) >= 5
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct JpegComponent {
+    pub id: u8,
+    pub horizontal_sampling_factor: u8,
+    pub vertical_sampling_factor: u8,
+    pub quantization_table: u8,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JpegImage {
+    pub width: u16,
+    pub height: u16,
+    pub precision: u8,
+    pub coding_process: JpegCodingProcess,
+    pub arithmetic_coding: bool,
+    pub differential: bool,
+    pub components: Vec<JpegComponent>,
+    pub scan_count: usize,
+    pub orientation: Option<ExifOrientation>,
+    pub icc_profile: Option<Vec<u8>>,
+    pub length: usize,
+}
+
+#[derive(Default)]
+struct IccProfileChunks {
+    count: u8,
+    chunks: Vec<(u8, Vec<u8>)>,
+}
+
+impl IccProfileChunks {
+    fn add(&mut self, payload: &[u8]) {
+        if payload.len() < 2 || payload[0] == 0 || payload[1] == 0 {
+            return;
+        }
+        if self.count != 0 && self.count != payload[1] {
+            return;
+        }
+        self.count = payload[1];
+        self.chunks.push((payload[0], payload[2..].to_vec()));
+    }
+
+    fn assemble(mut self) -> Option<Vec<u8>> {
+        if self.chunks.is_empty() || self.chunks.len() != self.count as usize {
+            return None;
+        }
+        self.chunks.sort_by_key(|(sequence, _)| *sequence);
+        if self.chunks.iter().enumerate().any(|(i, (sequence, _))| *sequence as usize != i + 1) {
+            return None;
+        }
+        Some(self.chunks.into_iter().flat_map(|(_, data)| data).collect())
+    }
+}
+
+impl JpegImage {
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        if data.len() < 4 || data[0] != 0xFF || data[1] != JPEG_MARKER_SOI {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let mut image: Option<Self> = None;
+        let mut orientation = None;
+        let mut icc_profile = IccProfileChunks::default();
+        let mut scan_count = 0;
+        let mut offset = 2;
+        loop {
+            let (marker, marker_end) = next_marker(data, offset).ok_or(kCMFormatDescriptionError_InvalidParameter)?;
+            offset = marker_end;
+            match marker {
+                JPEG_MARKER_EOI => break,
+                JPEG_MARKER_SOI => return Err(kCMFormatDescriptionError_InvalidParameter),
+                JPEG_MARKER_TEM | JPEG_MARKER_RST0..=JPEG_MARKER_RST7 => continue,
+                _ => {}
+            }
+            let payload = segment_payload(data, offset).ok_or(kCMFormatDescriptionError_InvalidParameter)?;
+            offset += 2 + payload.len();
+            match marker {
+                0xC0..=0xCF if marker != JPEG_MARKER_DHT && marker != JPEG_MARKER_JPG && marker != JPEG_MARKER_DAC => {
+                    if image.is_some() {
+                        return Err(kCMFormatDescriptionError_InvalidParameter);
+                    }
+                    image = Some(Self::parse_frame_header(marker, payload)?);
+                }
+                JPEG_MARKER_SOS => {
+                    let image = image.as_ref().ok_or(kCMFormatDescriptionError_InvalidParameter)?;
+                    image.validate_scan_header(payload)?;
+                    scan_count += 1;
+                    offset = skip_entropy_coded_data(data, offset);
+                }
+                JPEG_MARKER_APP1 if payload.starts_with(EXIF_IDENTIFIER) => {
+                    orientation = orientation.or_else(|| parse_exif_orientation(&payload[EXIF_IDENTIFIER.len()..]));
+                }
+                JPEG_MARKER_APP2 if payload.starts_with(ICC_PROFILE_IDENTIFIER) => {
+                    icc_profile.add(&payload[ICC_PROFILE_IDENTIFIER.len()..]);
+                }
+                _ => {}
+            }
+        }
+        let mut image = image.ok_or(kCMFormatDescriptionError_InvalidParameter)?;
+        if scan_count == 0 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        image.scan_count = scan_count;
+        image.orientation = orientation;
+        image.icc_profile = icc_profile.assemble();
+        image.length = offset;
+        Ok(image)
+    }
+
+    fn parse_frame_header(marker: u8, payload: &[u8]) -> Result<Self, OSStatus> {
+        if payload.len() < 6 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let precision = payload[0];
+        let height = u16::from_be_bytes([payload[1], payload[2]]);
+        let width = u16::from_be_bytes([payload[3], payload[4]]);
+        let component_count = payload[5] as usize;
+        // A zero height relies on a DNL segment, which capture devices do not emit
+        if width == 0 || height == 0 || !(1..=4).contains(&component_count) || payload.len() < 6 + component_count * 3 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let components = payload[6..6 + component_count * 3]
+            .chunks_exact(3)
+            .map(|component| JpegComponent {
+                id: component[0],
+                horizontal_sampling_factor: component[1] >> 4,
+                vertical_sampling_factor: component[1] & 0xF,
+                quantization_table: component[2],
+            })
+            .collect::<Vec<_>>();
+        let sampling_factors_valid = components.iter().all(|component| {
+            (1..=4).contains(&component.horizontal_sampling_factor) &&
+                (1..=4).contains(&component.vertical_sampling_factor) &&
+                component.quantization_table <= 3
+        });
+        if !sampling_factors_valid {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let coding_process = match marker & 0x3 {
+            0 => JpegCodingProcess::Baseline,
+            1 => JpegCodingProcess::ExtendedSequential,
+            2 => JpegCodingProcess::Progressive,
+            _ => JpegCodingProcess::Lossless,
+        };
+        Ok(Self {
+            width,
+            height,
+            precision,
+            coding_process,
+            arithmetic_coding: marker >= 0xC9,
+            differential: marker & 0x4 != 0,
+            components,
+            scan_count: 0,
+            orientation: None,
+            icc_profile: None,
+            length: 0,
+        })
+    }
+
+    fn validate_scan_header(&self, payload: &[u8]) -> Result<(), OSStatus> {
+        let component_count = *payload.first().ok_or(kCMFormatDescriptionError_InvalidParameter)? as usize;
+        if !(1..=4).contains(&component_count) || payload.len() < 1 + component_count * 2 + 3 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let components_known =
+            payload[1..1 + component_count * 2].chunks_exact(2).all(|component| self.components.iter().any(|known| known.id == component[0]));
+        if !components_known {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_progressive(&self) -> bool {
+        self.coding_process == JpegCodingProcess::Progressive
+    }
+
+    #[inline]
+    pub fn is_baseline(&self) -> bool {
+        self.coding_process == JpegCodingProcess::Baseline
+    }
+
+    #[inline]
+    pub fn dimensions(&self) -> CMVideoDimensions {
+        CMVideoDimensions {
+            width: self.width as i32,
+            height: self.height as i32,
+        }
+    }
+
+    pub fn display_dimensions(&self) -> CMVideoDimensions {
+        match self.orientation {
+            Some(orientation) if orientation.swaps_dimensions() => CMVideoDimensions {
+                width: self.height as i32,
+                height: self.width as i32,
+            },
+            _ => self.dimensions(),
+        }
+    }
+
+    pub fn chroma_subsampling(&self) -> JpegChromaSubsampling {
+        if self.components.len() == 1 {
+            return JpegChromaSubsampling::Grayscale;
+        }
+        let luma = &self.components[0];
+        let chroma_matches = self.components[1..].iter().all(|component| {
+            component.horizontal_sampling_factor == self.components[1].horizontal_sampling_factor &&
+                component.vertical_sampling_factor == self.components[1].vertical_sampling_factor
+        });
+        if !chroma_matches || self.components[1].horizontal_sampling_factor != 1 || self.components[1].vertical_sampling_factor != 1 {
+            return JpegChromaSubsampling::Other;
+        }
+        match (luma.horizontal_sampling_factor, luma.vertical_sampling_factor) {
+            (1, 1) => JpegChromaSubsampling::Chroma444,
+            (2, 1) => JpegChromaSubsampling::Chroma422,
+            (2, 2) => JpegChromaSubsampling::Chroma420,
+            (4, 1) => JpegChromaSubsampling::Chroma411,
+            (1, 2) => JpegChromaSubsampling::Chroma440,
+            _ => JpegChromaSubsampling::Other,
+        }
+    }
+
+    pub fn extensions(&self, codec_type: CMVideoCodecType) -> CFDictionary<CFString, CFType> {
+        let format_name = if codec_type == kCMVideoCodecType_JPEG_OpenDML {
+            "OpenDML JPEG"
+        } else {
+            "Photo - JPEG"
+        };
+        // QuickTime uses depth 40 for 8-bit grayscale
+        let depth = if self.components.len() == 1 {
+            40
+        } else {
+            24
+        };
+        let mut extensions = CFMutableDictionary::<CFString, CFType>::new();
+        unsafe {
+            extensions.add(
+                &CFString::wrap_under_get_rule(kCMFormatDescriptionExtension_FormatName),
+                &CFString::from_static_string(format_name).as_CFType(),
+            );
+            extensions.add(&CFString::wrap_under_get_rule(kCMFormatDescriptionExtension_Depth), &CFNumber::from(depth).as_CFType());
+            if let Some(icc_profile) = self.icc_profile.as_ref() {
+                extensions
+                    .add(&CFString::wrap_under_get_rule(kCMFormatDescriptionExtension_ICCProfile), &CFData::from_buffer(icc_profile).as_CFType());
+            }
+        }
+        extensions.to_immutable()
+    }
+
+    pub fn to_format_description(&self, codec_type: CMVideoCodecType) -> Result<CMVideoFormatDescription, OSStatus> {
+        if codec_type != kCMVideoCodecType_JPEG && codec_type != kCMVideoCodecType_JPEG_OpenDML {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        CMVideoFormatDescription::new(codec_type, self.width as i32, self.height as i32, Some(&self.extensions(codec_type)))
+    }
+}
+
+fn next_marker(data: &[u8], mut offset: usize) -> Option<(u8, usize)> {
+    if *data.get(offset)? != 0xFF {
+        return None;
+    }
+    // Any number of 0xFF fill bytes may precede a marker
+    while *data.get(offset)? == 0xFF {
+        offset += 1;
+    }
+    let marker = *data.get(offset)?;
+    if marker == 0 {
+        return None;
+    }
+    Some((marker, offset + 1))
+}
+
+fn segment_payload(data: &[u8], offset: usize) -> Option<&[u8]> {
+    let length = u16::from_be_bytes([*data.get(offset)?, *data.get(offset + 1)?]) as usize;
+    if length < 2 {
+        return None;
+    }
+    data.get(offset + 2..offset + length)
+}
+
+fn skip_entropy_coded_data(data: &[u8], mut offset: usize) -> usize {
+    while offset + 1 < data.len() {
+        if data[offset] == 0xFF {
+            let next = data[offset + 1];
+            // Stuffed zero bytes and restart markers belong to the entropy-coded segment
+            if next != 0 && next != 0xFF && !(JPEG_MARKER_RST0..=JPEG_MARKER_RST7).contains(&next) {
+                return offset;
+            }
+        }
+        offset += 1;
+    }
+    data.len()
+}
+
+fn parse_exif_orientation(tiff: &[u8]) -> Option<ExifOrientation> {
+    let big_endian = match tiff.get(0..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let read_u16 = |offset: usize| -> Option<u16> {
+        let bytes = [*tiff.get(offset)?, *tiff.get(offset + 1)?];
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let read_u32 = |offset: usize| -> Option<u32> {
+        let bytes = [*tiff.get(offset)?, *tiff.get(offset + 1)?, *tiff.get(offset + 2)?, *tiff.get(offset + 3)?];
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+    if read_u16(2)? != 42 {
+        return None;
+    }
+    let ifd_offset = read_u32(4)? as usize;
+    let entry_count = read_u16(ifd_offset)? as usize;
+    (0..entry_count).find_map(|i| {
+        let entry = ifd_offset + 2 + i * 12;
+        if read_u16(entry)? != EXIF_TAG_ORIENTATION || read_u16(entry + 2)? != EXIF_TYPE_SHORT {
+            return None;
+        }
+        ExifOrientation::from_value(read_u16(entry + 8)?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xFF, marker];
+        data.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    // 16x8 baseline 4:2:0 frame with an EXIF orientation, ending before the EOI
+    // marker
+    fn baseline_jpeg_without_eoi() -> Vec<u8> {
+        let mut data = vec![0xFF, JPEG_MARKER_SOI];
+        let mut exif = EXIF_IDENTIFIER.to_vec();
+        exif.extend_from_slice(&[b'M', b'M', 0, 42, 0, 0, 0, 8, 0, 1, 0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&segment(JPEG_MARKER_APP1, &exif));
+        data.extend_from_slice(&segment(0xC0, &[8, 0, 8, 0, 16, 3, 1, 0x22, 0, 2, 0x11, 1, 3, 0x11, 1]));
+        data.extend_from_slice(&segment(JPEG_MARKER_SOS, &[3, 1, 0x00, 2, 0x11, 3, 0x11, 0, 63, 0]));
+        // Entropy-coded data with a stuffed byte and a restart marker
+        data.extend_from_slice(&[0x12, 0xFF, 0x00, 0x34, 0xFF, JPEG_MARKER_RST0, 0x56]);
+        data
+    }
+
+    fn baseline_jpeg() -> Vec<u8> {
+        let mut data = baseline_jpeg_without_eoi();
+        data.extend_from_slice(&[0xFF, JPEG_MARKER_EOI]);
+        data
+    }
+
+    #[test]
+    fn parse_baseline_frame() {
+        let mut data = baseline_jpeg();
+        let length = data.len();
+        data.extend_from_slice(&[0, 0, 0]);
+        let image = JpegImage::parse(&data).unwrap();
+        assert_eq!((image.width, image.height), (16, 8));
+        assert_eq!(image.precision, 8);
+        assert!(image.is_baseline());
+        assert!(!image.arithmetic_coding);
+        assert_eq!(image.chroma_subsampling(), JpegChromaSubsampling::Chroma420);
+        assert_eq!(image.scan_count, 1);
+        assert_eq!(image.orientation, Some(ExifOrientation::Right));
+        assert_eq!((image.display_dimensions().width, image.display_dimensions().height), (8, 16));
+        assert_eq!(image.icc_profile, None);
+        assert_eq!(image.length, length);
+    }
+
+    #[test]
+    fn truncated_frames_are_rejected() {
+        assert_eq!(JpegImage::parse(&baseline_jpeg_without_eoi()), Err(kCMFormatDescriptionError_InvalidParameter));
+
+        // Cut off inside the scan header
+        let data = baseline_jpeg();
+        let sos = data.windows(2).position(|marker| marker == [0xFF, JPEG_MARKER_SOS]).unwrap();
+        assert_eq!(JpegImage::parse(&data[..sos + 6]), Err(kCMFormatDescriptionError_InvalidParameter));
+        assert_eq!(JpegImage::parse(&data[..sos + 1]), Err(kCMFormatDescriptionError_InvalidParameter));
+    }
+
+    #[test]
+    fn corrupt_frames_are_rejected() {
+        let data = baseline_jpeg();
+        let sof = data.windows(2).position(|marker| marker == [0xFF, 0xC0]).unwrap();
+
+        // Marker lengths must cover their own length field
+        let mut corrupted = data.clone();
+        corrupted[sof + 2..sof + 4].copy_from_slice(&1u16.to_be_bytes());
+        assert_eq!(JpegImage::parse(&corrupted), Err(kCMFormatDescriptionError_InvalidParameter));
+
+        // A length running past the data
+        let mut corrupted = data.clone();
+        corrupted[sof + 2..sof + 4].copy_from_slice(&0xFFF0u16.to_be_bytes());
+        assert_eq!(JpegImage::parse(&corrupted), Err(kCMFormatDescriptionError_InvalidParameter));
+
+        // Scans referencing a component the frame header does not declare
+        let mut corrupted = data.clone();
+        let sos = data.windows(2).position(|marker| marker == [0xFF, JPEG_MARKER_SOS]).unwrap();
+        corrupted[sos + 5] = 9;
+        assert_eq!(JpegImage::parse(&corrupted), Err(kCMFormatDescriptionError_InvalidParameter));
+
+        // Missing start of image
+        assert_eq!(JpegImage::parse(&data[2..]), Err(kCMFormatDescriptionError_InvalidParameter));
+    }
+
+    #[test]
+    fn icc_profile_chunks_are_assembled_in_order() {
+        let data = baseline_jpeg();
+        let icc_chunk = |sequence: u8, payload: &[u8]| {
+            let mut chunk = ICC_PROFILE_IDENTIFIER.to_vec();
+            chunk.extend_from_slice(&[sequence, 2]);
+            chunk.extend_from_slice(payload);
+            segment(JPEG_MARKER_APP2, &chunk)
+        };
+        let mut with_profile = data[..2].to_vec();
+        with_profile.extend_from_slice(&icc_chunk(2, &[3, 4]));
+        with_profile.extend_from_slice(&icc_chunk(1, &[1, 2]));
+        with_profile.extend_from_slice(&data[2..]);
+        assert_eq!(JpegImage::parse(&with_profile).unwrap().icc_profile, Some(vec![1, 2, 3, 4]));
+
+        // An incomplete profile is dropped
+        let mut partial = data[..2].to_vec();
+        partial.extend_from_slice(&icc_chunk(1, &[1, 2]));
+        partial.extend_from_slice(&data[2..]);
+        assert_eq!(JpegImage::parse(&partial).unwrap().icc_profile, None);
+    }
+}
@@ -1,6 +1,8 @@
 #![allow(non_snake_case, non_camel_case_types, non_upper_case_globals, improper_ctypes)]
 
 extern crate block;
+#[cfg(feature = "bytes")]
+extern crate bytes;
 #[macro_use]
 extern crate cfg_if;
 extern crate core_audio_types;
@@ -8,9 +10,15 @@ extern crate core_audio_types;
 extern crate core_foundation;
 extern crate core_graphics2 as core_graphics;
 extern crate core_video;
+#[cfg(feature = "futures")]
+extern crate futures_core;
+#[cfg(feature = "futures")]
+extern crate futures_sink;
 extern crate libc;
 #[cfg(feature = "objc")]
 extern crate objc2;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 pub type OSType = u32;
 
@@ -18,17 +26,39 @@ pub type OSType = u32;
 #[cfg_attr(feature = "link", link(name = "CoreMedia", kind = "framework"))]
 extern "C" {}
 
+pub mod ac3;
+pub mod adts;
 pub mod attachment;
+pub mod audio_format_comparison;
 #[cfg(target_os = "ios")]
 pub mod audio_clock;
 pub mod audio_device_clock;
 pub mod base;
+mod bit_reader;
 pub mod block_buffer;
+pub mod block_buffer_io;
 pub mod buffer_queue;
+pub mod closed_caption;
+pub mod dolby_vision;
 pub mod format_description;
 pub mod format_description_bridge;
+pub mod four_cc_registry;
+pub mod jitter_buffer;
+pub mod jpeg;
+pub mod mpeg2_video;
+pub mod mpeg_audio;
+pub mod prores;
+#[cfg(feature = "futures")]
+pub mod queue_stream;
+pub mod sample_attachments;
 pub mod sample_buffer;
 pub mod sample_queue;
+pub mod sample_rate_conversion;
+pub mod sample_timing;
 pub mod sync;
+mod sync_frame;
+#[cfg(test)]
+mod test_support;
 pub mod time;
 pub mod time_range;
+pub mod video_geometry;
@@ -0,0 +1,675 @@
+use core_foundation::base::OSStatus;
+use core_video::color_code_point::{ColorPrimaries, MatrixCoefficients, TransferCharacteristics};
+
+use crate::{bit_reader::BitReader, format_description::*, video_geometry::PixelAspectRatio};
+
+pub const MPEG2_SEQUENCE_HEADER_CODE: u8 = 0xB3;
+pub const MPEG2_EXTENSION_START_CODE: u8 = 0xB5;
+pub const MPEG2_SEQUENCE_END_CODE: u8 = 0xB7;
+pub const MPEG2_PICTURE_START_CODE: u8 = 0x00;
+
+const SEQUENCE_EXTENSION_ID: u32 = 1;
+const SEQUENCE_DISPLAY_EXTENSION_ID: u32 = 2;
+
+// Sequence header bit rates of the XDCAM formats
+const XDCAM_VBR35_MAX_BIT_RATE: u64 = 35_000_000;
+const XDCAM_CBR50_BIT_RATE: u64 = 50_000_000;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MPEG2Profile {
+    High,
+    SpatiallyScalable,
+    SNRScalable,
+    Main,
+    Simple,
+    Profile422,
+    MultiView,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MPEG2Level {
+    High,
+    High1440,
+    Main,
+    Low,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MPEG2ChromaFormat {
+    Chroma420,
+    Chroma422,
+    Chroma444,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct MPEG2SequenceHeader {
+    pub horizontal_size_value: u16,
+    pub vertical_size_value: u16,
+    pub aspect_ratio_information: u8,
+    pub frame_rate_code: u8,
+    pub bit_rate_value: u32,
+    pub vbv_buffer_size_value: u16,
+    pub constrained_parameters_flag: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct MPEG2SequenceExtension {
+    pub profile_and_level_indication: u8,
+    pub progressive_sequence: bool,
+    pub chroma_format: u8,
+    pub horizontal_size_extension: u8,
+    pub vertical_size_extension: u8,
+    pub bit_rate_extension: u16,
+    pub vbv_buffer_size_extension: u8,
+    pub low_delay: bool,
+    pub frame_rate_extension_n: u8,
+    pub frame_rate_extension_d: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct MPEG2SequenceDisplayExtension {
+    pub video_format: u8,
+    pub colour_description: Option<(u8, u8, u8)>,
+    pub display_horizontal_size: u16,
+    pub display_vertical_size: u16,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct MPEG2VideoSequence {
+    pub header: MPEG2SequenceHeader,
+    pub extension: Option<MPEG2SequenceExtension>,
+    pub display_extension: Option<MPEG2SequenceDisplayExtension>,
+}
+
+fn start_codes(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut offsets = Vec::new();
+    let mut i = 0;
+    while i + 3 < data.len() {
+        if data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1 {
+            offsets.push(i + 3);
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+    let ends: Vec<usize> = offsets.iter().skip(1).map(|offset| offset - 3).chain(std::iter::once(data.len())).collect();
+    offsets.into_iter().zip(ends).map(move |(start, end)| (data[start], &data[start + 1..end]))
+}
+
+impl MPEG2SequenceHeader {
+    pub fn parse(payload: &[u8]) -> Result<Self, OSStatus> {
+        let mut reader = BitReader::new(payload);
+        let mut read = || -> Option<Self> {
+            let horizontal_size_value = reader.read_bits(12)? as u16;
+            let vertical_size_value = reader.read_bits(12)? as u16;
+            let aspect_ratio_information = reader.read_bits(4)? as u8;
+            let frame_rate_code = reader.read_bits(4)? as u8;
+            let bit_rate_value = reader.read_bits(18)?;
+            reader.skip_bits(1)?;
+            let vbv_buffer_size_value = reader.read_bits(10)? as u16;
+            let constrained_parameters_flag = reader.read_bit()?;
+            if reader.read_bit()? {
+                reader.skip_bits(64 * 8)?;
+            }
+            if reader.read_bit()? {
+                reader.skip_bits(64 * 8)?;
+            }
+            Some(Self {
+                horizontal_size_value,
+                vertical_size_value,
+                aspect_ratio_information,
+                frame_rate_code,
+                bit_rate_value,
+                vbv_buffer_size_value,
+                constrained_parameters_flag,
+            })
+        };
+        let header = read().ok_or(kCMFormatDescriptionError_InvalidParameter)?;
+        if header.horizontal_size_value == 0 || header.vertical_size_value == 0 || header.frame_rate_code == 0 || header.frame_rate_code > 8 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        Ok(header)
+    }
+}
+
+impl MPEG2SequenceExtension {
+    pub fn parse(payload: &[u8]) -> Result<Self, OSStatus> {
+        let mut reader = BitReader::new(payload);
+        let mut read = || -> Option<Self> {
+            if reader.read_bits(4)? != SEQUENCE_EXTENSION_ID {
+                return None;
+            }
+            let profile_and_level_indication = reader.read_bits(8)? as u8;
+            let progressive_sequence = reader.read_bit()?;
+            let chroma_format = reader.read_bits(2)? as u8;
+            let horizontal_size_extension = reader.read_bits(2)? as u8;
+            let vertical_size_extension = reader.read_bits(2)? as u8;
+            let bit_rate_extension = reader.read_bits(12)? as u16;
+            reader.skip_bits(1)?;
+            Some(Self {
+                profile_and_level_indication,
+                progressive_sequence,
+                chroma_format,
+                horizontal_size_extension,
+                vertical_size_extension,
+                bit_rate_extension,
+                vbv_buffer_size_extension: reader.read_bits(8)? as u8,
+                low_delay: reader.read_bit()?,
+                frame_rate_extension_n: reader.read_bits(2)? as u8,
+                frame_rate_extension_d: reader.read_bits(5)? as u8,
+            })
+        };
+        read().ok_or(kCMFormatDescriptionError_InvalidParameter)
+    }
+}
+
+impl MPEG2SequenceDisplayExtension {
+    pub fn parse(payload: &[u8]) -> Result<Self, OSStatus> {
+        let mut reader = BitReader::new(payload);
+        let mut read = || -> Option<Self> {
+            if reader.read_bits(4)? != SEQUENCE_DISPLAY_EXTENSION_ID {
+                return None;
+            }
+            let video_format = reader.read_bits(3)? as u8;
+            let colour_description = if reader.read_bit()? {
+                Some((reader.read_bits(8)? as u8, reader.read_bits(8)? as u8, reader.read_bits(8)? as u8))
+            } else {
+                None
+            };
+            let display_horizontal_size = reader.read_bits(14)? as u16;
+            reader.skip_bits(1)?;
+            Some(Self {
+                video_format,
+                colour_description,
+                display_horizontal_size,
+                display_vertical_size: reader.read_bits(14)? as u16,
+            })
+        };
+        read().ok_or(kCMFormatDescriptionError_InvalidParameter)
+    }
+}
+
+impl MPEG2VideoSequence {
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        let mut sequence: Option<Self> = None;
+        for (code, payload) in start_codes(data) {
+            match code {
+                MPEG2_SEQUENCE_HEADER_CODE if sequence.is_none() => {
+                    sequence = Some(Self {
+                        header: MPEG2SequenceHeader::parse(payload)?,
+                        ..Default::default()
+                    });
+                }
+                MPEG2_EXTENSION_START_CODE => {
+                    let sequence = match sequence.as_mut() {
+                        Some(sequence) => sequence,
+                        None => continue,
+                    };
+                    match payload.first().map(|byte| (byte >> 4) as u32) {
+                        Some(SEQUENCE_EXTENSION_ID) if sequence.extension.is_none() => {
+                            sequence.extension = Some(MPEG2SequenceExtension::parse(payload)?);
+                        }
+                        Some(SEQUENCE_DISPLAY_EXTENSION_ID) if sequence.display_extension.is_none() => {
+                            sequence.display_extension = Some(MPEG2SequenceDisplayExtension::parse(payload)?);
+                        }
+                        _ => {}
+                    }
+                }
+                MPEG2_PICTURE_START_CODE | MPEG2_SEQUENCE_END_CODE if sequence.is_some() => break,
+                _ => {}
+            }
+        }
+        sequence.ok_or(kCMFormatDescriptionError_InvalidParameter)
+    }
+
+    #[inline]
+    pub fn is_mpeg1(&self) -> bool {
+        self.extension.is_none()
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        let extension = self.extension.map_or(0, |extension| extension.horizontal_size_extension as u32);
+        (extension << 12) | self.header.horizontal_size_value as u32
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        let extension = self.extension.map_or(0, |extension| extension.vertical_size_extension as u32);
+        (extension << 12) | self.header.vertical_size_value as u32
+    }
+
+    #[inline]
+    pub fn dimensions(&self) -> CMVideoDimensions {
+        CMVideoDimensions {
+            width: self.width() as i32,
+            height: self.height() as i32,
+        }
+    }
+
+    pub fn frame_rate(&self) -> Option<(u32, u32)> {
+        let (numerator, denominator) = match self.header.frame_rate_code {
+            1 => (24000, 1001),
+            2 => (24, 1),
+            3 => (25, 1),
+            4 => (30000, 1001),
+            5 => (30, 1),
+            6 => (50, 1),
+            7 => (60000, 1001),
+            8 => (60, 1),
+            _ => return None,
+        };
+        match self.extension {
+            Some(extension) => {
+                Some((numerator * (extension.frame_rate_extension_n as u32 + 1), denominator * (extension.frame_rate_extension_d as u32 + 1)))
+            }
+            None => Some((numerator, denominator)),
+        }
+    }
+
+    #[inline]
+    pub fn bit_rate(&self) -> u64 {
+        let extension = self.extension.map_or(0, |extension| extension.bit_rate_extension as u64);
+        ((extension << 18) | self.header.bit_rate_value as u64) * 400
+    }
+
+    #[inline]
+    pub fn vbv_buffer_size(&self) -> u32 {
+        let extension = self.extension.map_or(0, |extension| extension.vbv_buffer_size_extension as u32);
+        ((extension << 10) | self.header.vbv_buffer_size_value as u32) * 16 * 1024
+    }
+
+    pub fn profile(&self) -> Option<MPEG2Profile> {
+        let indication = self.extension?.profile_and_level_indication;
+        if indication & 0x80 != 0 {
+            return match indication {
+                0x82 | 0x85 => Some(MPEG2Profile::Profile422),
+                0x8A | 0x8B | 0x8D | 0x8E => Some(MPEG2Profile::MultiView),
+                _ => None,
+            };
+        }
+        match (indication >> 4) & 0x7 {
+            1 => Some(MPEG2Profile::High),
+            2 => Some(MPEG2Profile::SpatiallyScalable),
+            3 => Some(MPEG2Profile::SNRScalable),
+            4 => Some(MPEG2Profile::Main),
+            5 => Some(MPEG2Profile::Simple),
+            _ => None,
+        }
+    }
+
+    pub fn level(&self) -> Option<MPEG2Level> {
+        let indication = self.extension?.profile_and_level_indication;
+        if indication & 0x80 != 0 {
+            return match indication {
+                0x82 | 0x8A => Some(MPEG2Level::High),
+                0x8B => Some(MPEG2Level::High1440),
+                0x85 | 0x8D => Some(MPEG2Level::Main),
+                0x8E => Some(MPEG2Level::Low),
+                _ => None,
+            };
+        }
+        match indication & 0xF {
+            4 => Some(MPEG2Level::High),
+            6 => Some(MPEG2Level::High1440),
+            8 => Some(MPEG2Level::Main),
+            10 => Some(MPEG2Level::Low),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn chroma_format(&self) -> Option<MPEG2ChromaFormat> {
+        match self.extension.map_or(1, |extension| extension.chroma_format) {
+            1 => Some(MPEG2ChromaFormat::Chroma420),
+            2 => Some(MPEG2ChromaFormat::Chroma422),
+            3 => Some(MPEG2ChromaFormat::Chroma444),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_progressive(&self) -> bool {
+        // MPEG-1 has no interlaced coding
+        match self.extension {
+            Some(extension) => extension.progressive_sequence,
+            None => true,
+        }
+    }
+
+    pub fn display_aspect_ratio(&self) -> Option<(u32, u32)> {
+        if self.is_mpeg1() {
+            let (horizontal_spacing, vertical_spacing) = mpeg1_pixel_aspect_ratio(self.header.aspect_ratio_information)?;
+            let width = self.width() as u64 * horizontal_spacing as u64;
+            let height = self.height() as u64 * vertical_spacing as u64;
+            if width == 0 || height == 0 {
+                return None;
+            }
+            let divisor = gcd(width, height);
+            return Some(((width / divisor) as u32, (height / divisor) as u32));
+        }
+        match self.header.aspect_ratio_information {
+            1 => Some((self.width(), self.height())),
+            2 => Some((4, 3)),
+            3 => Some((16, 9)),
+            4 => Some((221, 100)),
+            _ => None,
+        }
+    }
+
+    pub fn pixel_aspect_ratio(&self) -> Option<PixelAspectRatio> {
+        if self.is_mpeg1() {
+            let (horizontal_spacing, vertical_spacing) = mpeg1_pixel_aspect_ratio(self.header.aspect_ratio_information)?;
+            let divisor = gcd(horizontal_spacing as u64, vertical_spacing as u64) as u32;
+            return Some(PixelAspectRatio::new((horizontal_spacing / divisor) as i32, (vertical_spacing / divisor) as i32));
+        }
+        let (display_width, display_height) = self.display_aspect_ratio()?;
+        let (width, height) = match self.display_extension {
+            Some(display) if display.display_horizontal_size != 0 && display.display_vertical_size != 0 => {
+                (display.display_horizontal_size as u64, display.display_vertical_size as u64)
+            }
+            _ => (self.width() as u64, self.height() as u64),
+        };
+        let horizontal_spacing = display_width as u64 * height;
+        let vertical_spacing = display_height as u64 * width;
+        if horizontal_spacing == 0 || vertical_spacing == 0 {
+            return None;
+        }
+        let divisor = gcd(horizontal_spacing, vertical_spacing);
+        Some(PixelAspectRatio::new((horizontal_spacing / divisor) as i32, (vertical_spacing / divisor) as i32))
+    }
+
+    pub fn color_primaries(&self) -> Option<ColorPrimaries> {
+        ColorPrimaries::from_code_point(self.display_extension?.colour_description?.0 as i32)
+    }
+
+    pub fn transfer_characteristics(&self) -> Option<TransferCharacteristics> {
+        TransferCharacteristics::from_code_point(self.display_extension?.colour_description?.1 as i32)
+    }
+
+    pub fn matrix_coefficients(&self) -> Option<MatrixCoefficients> {
+        MatrixCoefficients::from_code_point(self.display_extension?.colour_description?.2 as i32)
+    }
+
+    /// Never returns `kCMMPEG2VideoProfile_XF`: XF streams carry the same
+    /// 4:2:2 sequence headers as XDCAM HD422, which is reported instead.
+    pub fn mpeg2_video_profile(&self) -> Option<i32> {
+        let (numerator, denominator) = self.frame_rate()?;
+        // NTSC rates share profiles with their integer counterparts
+        let rate = (numerator + denominator / 2) / denominator;
+        let progressive = self.is_progressive();
+        let profile = self.profile()?;
+        let level = self.level()?;
+        let chroma_format = self.chroma_format()?;
+        let bit_rate = self.bit_rate();
+        let profile = match (profile, level, chroma_format, self.width(), self.height(), progressive, rate, bit_rate) {
+            (MPEG2Profile::Main, MPEG2Level::High1440, MPEG2ChromaFormat::Chroma420, 1280, 720, true, 24, _) => kCMMPEG2VideoProfile_HDV_720p24,
+            (MPEG2Profile::Main, MPEG2Level::High1440, MPEG2ChromaFormat::Chroma420, 1280, 720, true, 25, _) => kCMMPEG2VideoProfile_HDV_720p25,
+            (MPEG2Profile::Main, MPEG2Level::High1440, MPEG2ChromaFormat::Chroma420, 1280, 720, true, 30, _) => kCMMPEG2VideoProfile_HDV_720p30,
+            (MPEG2Profile::Main, MPEG2Level::High1440, MPEG2ChromaFormat::Chroma420, 1280, 720, true, 50, _) => kCMMPEG2VideoProfile_HDV_720p50,
+            (MPEG2Profile::Main, MPEG2Level::High1440, MPEG2ChromaFormat::Chroma420, 1280, 720, true, 60, _) => kCMMPEG2VideoProfile_HDV_720p60,
+            (MPEG2Profile::Main, MPEG2Level::High1440, MPEG2ChromaFormat::Chroma420, 1440, 1080, false, 30, _) => kCMMPEG2VideoProfile_HDV_1080i60,
+            (MPEG2Profile::Main, MPEG2Level::High1440, MPEG2ChromaFormat::Chroma420, 1440, 1080, false, 25, _) => kCMMPEG2VideoProfile_HDV_1080i50,
+            (MPEG2Profile::Main, MPEG2Level::High1440, MPEG2ChromaFormat::Chroma420, 1440, 1080, true, 24, _) => kCMMPEG2VideoProfile_HDV_1080p24,
+            (MPEG2Profile::Main, MPEG2Level::High1440, MPEG2ChromaFormat::Chroma420, 1440, 1080, true, 25, _) => kCMMPEG2VideoProfile_HDV_1080p25,
+            (MPEG2Profile::Main, MPEG2Level::High1440, MPEG2ChromaFormat::Chroma420, 1440, 1080, true, 30, _) => kCMMPEG2VideoProfile_HDV_1080p30,
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1440, 1080, false, 30, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD_1080i60_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1440, 1080, false, 25, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD_1080i50_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1440, 1080, true, 24, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD_1080p24_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1440, 1080, true, 25, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD_1080p25_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1440, 1080, true, 30, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD_1080p30_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1280, 720, true, 24, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_EX_720p24_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1280, 720, true, 25, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_EX_720p25_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1280, 720, true, 30, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_EX_720p30_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1280, 720, true, 50, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_EX_720p50_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1280, 720, true, 60, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_EX_720p60_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1920, 1080, false, 30, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_EX_1080i60_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1920, 1080, false, 25, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_EX_1080i50_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1920, 1080, true, 24, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_EX_1080p24_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1920, 1080, true, 25, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_EX_1080p25_VBR35
+            }
+            (MPEG2Profile::Main, MPEG2Level::High, MPEG2ChromaFormat::Chroma420, 1920, 1080, true, 30, 0..=XDCAM_VBR35_MAX_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_EX_1080p30_VBR35
+            }
+            (MPEG2Profile::Profile422, MPEG2Level::High, MPEG2ChromaFormat::Chroma422, 1280, 720, true, 24, XDCAM_CBR50_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD422_720p24_CBR50
+            }
+            (MPEG2Profile::Profile422, MPEG2Level::High, MPEG2ChromaFormat::Chroma422, 1280, 720, true, 25, XDCAM_CBR50_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD422_720p25_CBR50
+            }
+            (MPEG2Profile::Profile422, MPEG2Level::High, MPEG2ChromaFormat::Chroma422, 1280, 720, true, 30, XDCAM_CBR50_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD422_720p30_CBR50
+            }
+            (MPEG2Profile::Profile422, MPEG2Level::High, MPEG2ChromaFormat::Chroma422, 1280, 720, true, 50, XDCAM_CBR50_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD422_720p50_CBR50
+            }
+            (MPEG2Profile::Profile422, MPEG2Level::High, MPEG2ChromaFormat::Chroma422, 1280, 720, true, 60, XDCAM_CBR50_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD422_720p60_CBR50
+            }
+            (MPEG2Profile::Profile422, MPEG2Level::High, MPEG2ChromaFormat::Chroma422, 1920, 1080, false, 30, XDCAM_CBR50_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD422_1080i60_CBR50
+            }
+            (MPEG2Profile::Profile422, MPEG2Level::High, MPEG2ChromaFormat::Chroma422, 1920, 1080, false, 25, XDCAM_CBR50_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD422_1080i50_CBR50
+            }
+            (MPEG2Profile::Profile422, MPEG2Level::High, MPEG2ChromaFormat::Chroma422, 1920, 1080, true, 24, XDCAM_CBR50_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD422_1080p24_CBR50
+            }
+            (MPEG2Profile::Profile422, MPEG2Level::High, MPEG2ChromaFormat::Chroma422, 1920, 1080, true, 25, XDCAM_CBR50_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD422_1080p25_CBR50
+            }
+            (MPEG2Profile::Profile422, MPEG2Level::High, MPEG2ChromaFormat::Chroma422, 1920, 1080, true, 30, XDCAM_CBR50_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD422_1080p30_CBR50
+            }
+            (
+                MPEG2Profile::Main,
+                MPEG2Level::High | MPEG2Level::High1440,
+                MPEG2ChromaFormat::Chroma420,
+                960,
+                540,
+                true,
+                _,
+                0..=XDCAM_VBR35_MAX_BIT_RATE,
+            ) => kCMMPEG2VideoProfile_XDCAM_HD_540p,
+            (MPEG2Profile::Profile422, MPEG2Level::High, MPEG2ChromaFormat::Chroma422, 960, 540, true, _, 0..=XDCAM_CBR50_BIT_RATE) => {
+                kCMMPEG2VideoProfile_XDCAM_HD422_540p
+            }
+            _ => return None,
+        };
+        Some(profile)
+    }
+}
+
+// MPEG-1 codes give the pel aspect ratio, the height of a pixel over its width,
+// in units of 1/10000
+fn mpeg1_pixel_aspect_ratio(aspect_ratio_information: u8) -> Option<(u32, u32)> {
+    let pel_aspect_ratio = match aspect_ratio_information {
+        1 => 10000,
+        2 => 6735,
+        3 => 7031,
+        4 => 7615,
+        5 => 8055,
+        6 => 8437,
+        7 => 8935,
+        8 => 9157,
+        9 => 9815,
+        10 => 10255,
+        11 => 10695,
+        12 => 10950,
+        13 => 11575,
+        14 => 12015,
+        _ => return None,
+    };
+    Some((10000, pel_aspect_ratio))
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(
+        width: u16,
+        height: u16,
+        profile_and_level_indication: u8,
+        chroma_format: u8,
+        progressive: bool,
+        bit_rate: u32,
+    ) -> MPEG2VideoSequence {
+        let bit_rate_value = bit_rate / 400;
+        MPEG2VideoSequence {
+            header: MPEG2SequenceHeader {
+                horizontal_size_value: width,
+                vertical_size_value: height,
+                aspect_ratio_information: 3,
+                // 25 Hz
+                frame_rate_code: 3,
+                bit_rate_value: bit_rate_value & 0x3FFFF,
+                ..Default::default()
+            },
+            extension: Some(MPEG2SequenceExtension {
+                profile_and_level_indication,
+                progressive_sequence: progressive,
+                chroma_format,
+                bit_rate_extension: (bit_rate_value >> 18) as u16,
+                ..Default::default()
+            }),
+            display_extension: None,
+        }
+    }
+
+    #[test]
+    fn xdcam_profiles_depend_on_bit_rate() {
+        let main_high = 0x44;
+        let profile_422_high = 0x82;
+        assert_eq!(sequence(1920, 1080, main_high, 1, false, 35_000_000).mpeg2_video_profile(), Some(kCMMPEG2VideoProfile_XDCAM_EX_1080i50_VBR35));
+        assert_eq!(sequence(1920, 1080, main_high, 1, false, 50_000_000).mpeg2_video_profile(), None);
+        assert_eq!(sequence(1440, 1080, main_high, 1, true, 25_000_000).mpeg2_video_profile(), Some(kCMMPEG2VideoProfile_XDCAM_HD_1080p25_VBR35));
+        assert_eq!(sequence(1440, 1080, main_high, 1, true, 80_000_000).mpeg2_video_profile(), None);
+        assert_eq!(
+            sequence(1920, 1080, profile_422_high, 2, false, 50_000_000).mpeg2_video_profile(),
+            Some(kCMMPEG2VideoProfile_XDCAM_HD422_1080i50_CBR50)
+        );
+        assert_eq!(sequence(1920, 1080, profile_422_high, 2, false, 35_000_000).mpeg2_video_profile(), None);
+        assert_eq!(sequence(960, 540, main_high, 1, true, 35_000_000).mpeg2_video_profile(), Some(kCMMPEG2VideoProfile_XDCAM_HD_540p));
+        assert_eq!(sequence(960, 540, profile_422_high, 2, true, 50_000_000).mpeg2_video_profile(), Some(kCMMPEG2VideoProfile_XDCAM_HD422_540p));
+        // HDV is not limited by the XDCAM rates
+        assert_eq!(sequence(1440, 1080, 0x46, 1, false, 25_000_000).mpeg2_video_profile(), Some(kCMMPEG2VideoProfile_HDV_1080i50));
+    }
+    // Video CD sequence header: 352x240, CCIR 601 525-line pels, 29.97 Hz, 1150
+    // kbit/s
+    const MPEG1_SEQUENCE: [u8; 20] = [
+        0x00, 0x00, 0x01, 0xB3, 0x16, 0x00, 0xF0, 0xC4, 0x02, 0xCE, 0xE0, 0xA4, // sequence header
+        0x00, 0x00, 0x01, 0xB8, 0x00, 0x08, 0x00, 0x00, // group of pictures
+    ];
+
+    // XDCAM HD422 1080i50: 4:2:2 profile at high level, 50 Mbit/s, BT.709
+    const XDCAM_HD422_SEQUENCE: [u8; 38] = [
+        0x00, 0x00, 0x01, 0xB3, 0x78, 0x04, 0x38, 0x33, 0x7A, 0x12, 0x32, 0x00, // sequence header
+        0x00, 0x00, 0x01, 0xB5, 0x18, 0x24, 0x00, 0x01, 0x00, 0x00, // sequence extension
+        0x00, 0x00, 0x01, 0xB5, 0x23, 0x01, 0x01, 0x01, 0x1E, 0x02, 0x21, 0xC0, // sequence display extension
+        0x00, 0x00, 0x01, 0x00, // picture
+    ];
+
+    // Main profile at main level 720x576 4:3, 25 Hz, 8 Mbit/s
+    const MPEG2_SD_SEQUENCE: [u8; 22] = [
+        0x00, 0x00, 0x01, 0xB3, 0x2D, 0x02, 0x40, 0x23, 0x13, 0x88, 0x23, 0x80, // sequence header
+        0x00, 0x00, 0x01, 0xB5, 0x14, 0x82, 0x00, 0x01, 0x00, 0x00, // sequence extension
+    ];
+
+    #[test]
+    fn parse_mpeg1_sequence() {
+        let sequence = MPEG2VideoSequence::parse(&MPEG1_SEQUENCE).unwrap();
+        assert!(sequence.is_mpeg1());
+        assert!(sequence.header.constrained_parameters_flag);
+        assert_eq!((sequence.width(), sequence.height()), (352, 240));
+        assert_eq!(sequence.frame_rate(), Some((30000, 1001)));
+        assert_eq!(sequence.bit_rate(), 1_150_000);
+        assert_eq!(sequence.vbv_buffer_size(), 20 * 16 * 1024);
+        assert!(sequence.is_progressive());
+        assert_eq!(sequence.chroma_format(), Some(MPEG2ChromaFormat::Chroma420));
+        assert_eq!((sequence.profile(), sequence.level(), sequence.mpeg2_video_profile()), (None, None, None));
+        // Code 12 is a pel aspect ratio of 1.0950, not an MPEG-2 display aspect ratio
+        assert_eq!(sequence.pixel_aspect_ratio(), Some(PixelAspectRatio::new(200, 219)));
+        assert_eq!(sequence.display_aspect_ratio(), Some((880, 657)));
+    }
+
+    #[test]
+    fn parse_xdcam_hd422_sequence() {
+        let sequence = MPEG2VideoSequence::parse(&XDCAM_HD422_SEQUENCE).unwrap();
+        assert!(!sequence.is_mpeg1());
+        assert_eq!(
+            sequence.dimensions(),
+            CMVideoDimensions {
+                width: 1920,
+                height: 1080,
+            }
+        );
+        assert_eq!(sequence.frame_rate(), Some((25, 1)));
+        assert_eq!(sequence.bit_rate(), XDCAM_CBR50_BIT_RATE);
+        assert!(!sequence.is_progressive());
+        assert_eq!(sequence.profile(), Some(MPEG2Profile::Profile422));
+        assert_eq!(sequence.level(), Some(MPEG2Level::High));
+        assert_eq!(sequence.chroma_format(), Some(MPEG2ChromaFormat::Chroma422));
+        assert_eq!(sequence.mpeg2_video_profile(), Some(kCMMPEG2VideoProfile_XDCAM_HD422_1080i50_CBR50));
+        assert_eq!(sequence.display_aspect_ratio(), Some((16, 9)));
+        assert_eq!(sequence.pixel_aspect_ratio(), Some(PixelAspectRatio::square()));
+        assert_eq!(sequence.display_extension.map(|display| (display.video_format, display.colour_description)), Some((1, Some((1, 1, 1)))));
+        assert_eq!(sequence.color_primaries(), ColorPrimaries::from_code_point(1));
+        assert_eq!(sequence.matrix_coefficients(), MatrixCoefficients::from_code_point(1));
+    }
+
+    #[test]
+    fn parse_mpeg2_sd_sequence() {
+        let sequence = MPEG2VideoSequence::parse(&MPEG2_SD_SEQUENCE).unwrap();
+        assert_eq!((sequence.width(), sequence.height()), (720, 576));
+        assert_eq!(sequence.bit_rate(), 8_000_000);
+        assert_eq!((sequence.profile(), sequence.level()), (Some(MPEG2Profile::Main), Some(MPEG2Level::Main)));
+        assert_eq!(sequence.display_aspect_ratio(), Some((4, 3)));
+        assert_eq!(sequence.pixel_aspect_ratio(), Some(PixelAspectRatio::new(16, 15)));
+        assert_eq!(sequence.mpeg2_video_profile(), None);
+    }
+
+    #[test]
+    fn reject_invalid_sequences() {
+        assert_eq!(MPEG2VideoSequence::parse(&MPEG1_SEQUENCE[12..]), Err(kCMFormatDescriptionError_InvalidParameter));
+        assert_eq!(MPEG2VideoSequence::parse(&MPEG1_SEQUENCE[..8]), Err(kCMFormatDescriptionError_InvalidParameter));
+        // Frame rate code 0 is forbidden
+        let mut data = MPEG1_SEQUENCE;
+        data[7] = 0xC0;
+        assert_eq!(MPEG2VideoSequence::parse(&data), Err(kCMFormatDescriptionError_InvalidParameter));
+    }
+}
@@ -0,0 +1,653 @@
+use core_audio_types::{
+    base_types::{
+        kAudioChannelLayoutTag_Mono, kAudioChannelLayoutTag_Stereo, kAudioFormatMPEGLayer1, kAudioFormatMPEGLayer2, kAudioFormatMPEGLayer3,
+        AudioFormatID, AudioStreamBasicDescription, AudioStreamPacketDescription,
+    },
+    channel_layout::ChannelLayout,
+};
+use core_foundation::{
+    base::{OSStatus, TCFType},
+    string::CFString,
+};
+
+use crate::{
+    attachment::{kCMAttachmentMode_ShouldNotPropagate, CMAttachmentBearerSubClass},
+    format_description::{kCMFormatDescriptionError_InvalidParameter, CMAudioFormatDescription},
+    sample_buffer::{kCMSampleBufferAttachmentKey_TrimDurationAtEnd, kCMSampleBufferAttachmentKey_TrimDurationAtStart, CMSampleBuffer},
+    sync_frame::{self, SyncFrameIter, SyncHeader},
+    time::{CMTime, CMTimeScale},
+};
+
+pub const MPEG_AUDIO_HEADER_SIZE: usize = 4;
+pub const ID3V2_HEADER_SIZE: usize = 10;
+// Decoder delay of the reference MP3 synthesis filter, LAME delays exclude it
+pub const MP3_DECODER_DELAY: u32 = 529;
+
+const XING_FLAG_FRAMES: u32 = 0x1;
+const XING_FLAG_BYTES: u32 = 0x2;
+const XING_FLAG_TOC: u32 = 0x4;
+const XING_FLAG_QUALITY: u32 = 0x8;
+const XING_TOC_SIZE: usize = 100;
+const LAME_ENCODER_VERSION_SIZE: usize = 9;
+const LAME_DELAY_OFFSET: usize = 21;
+const VBRI_OFFSET: usize = MPEG_AUDIO_HEADER_SIZE + 32;
+
+const BIT_RATES_V1_L1: [u32; 15] = [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448];
+const BIT_RATES_V1_L2: [u32; 15] = [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384];
+const BIT_RATES_V1_L3: [u32; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+const BIT_RATES_V2_L1: [u32; 15] = [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256];
+const BIT_RATES_V2_L23: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+const SAMPLE_RATES_V1: [u32; 3] = [44100, 48000, 32000];
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MpegAudioVersion {
+    Mpeg1,
+    Mpeg2,
+    Mpeg25,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MpegAudioLayer {
+    Layer1,
+    Layer2,
+    Layer3,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MpegAudioChannelMode {
+    Stereo,
+    JointStereo,
+    DualChannel,
+    Mono,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MpegAudioHeader {
+    pub version: MpegAudioVersion,
+    pub layer: MpegAudioLayer,
+    pub protection_absent: bool,
+    pub bit_rate: u32,
+    pub sample_rate: u32,
+    pub padding: bool,
+    pub private_bit: bool,
+    pub channel_mode: MpegAudioChannelMode,
+    pub mode_extension: u8,
+    pub copyright: bool,
+    pub original: bool,
+    pub emphasis: u8,
+    pub frame_length: usize,
+}
+
+impl MpegAudioHeader {
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        let invalid = kCMFormatDescriptionError_InvalidParameter;
+        if data.len() < MPEG_AUDIO_HEADER_SIZE || data[0] != 0xFF || data[1] & 0xE0 != 0xE0 {
+            return Err(invalid);
+        }
+        let version = match (data[1] >> 3) & 0x3 {
+            0 => MpegAudioVersion::Mpeg25,
+            2 => MpegAudioVersion::Mpeg2,
+            3 => MpegAudioVersion::Mpeg1,
+            _ => return Err(invalid),
+        };
+        let layer = match (data[1] >> 1) & 0x3 {
+            1 => MpegAudioLayer::Layer3,
+            2 => MpegAudioLayer::Layer2,
+            3 => MpegAudioLayer::Layer1,
+            _ => return Err(invalid),
+        };
+        let bit_rate_index = (data[2] >> 4) as usize;
+        let sample_rate_index = ((data[2] >> 2) & 0x3) as usize;
+        // Free format streams have no frame length in the header
+        if bit_rate_index == 0 || bit_rate_index == 15 || sample_rate_index == 3 {
+            return Err(invalid);
+        }
+        let bit_rates = match (version, layer) {
+            (MpegAudioVersion::Mpeg1, MpegAudioLayer::Layer1) => &BIT_RATES_V1_L1,
+            (MpegAudioVersion::Mpeg1, MpegAudioLayer::Layer2) => &BIT_RATES_V1_L2,
+            (MpegAudioVersion::Mpeg1, MpegAudioLayer::Layer3) => &BIT_RATES_V1_L3,
+            (_, MpegAudioLayer::Layer1) => &BIT_RATES_V2_L1,
+            _ => &BIT_RATES_V2_L23,
+        };
+        let bit_rate = bit_rates[bit_rate_index] * 1000;
+        let sample_rate = match version {
+            MpegAudioVersion::Mpeg1 => SAMPLE_RATES_V1[sample_rate_index],
+            MpegAudioVersion::Mpeg2 => SAMPLE_RATES_V1[sample_rate_index] / 2,
+            MpegAudioVersion::Mpeg25 => SAMPLE_RATES_V1[sample_rate_index] / 4,
+        };
+        let padding = data[2] & 0x2 != 0;
+        let channel_mode = match data[3] >> 6 {
+            0 => MpegAudioChannelMode::Stereo,
+            1 => MpegAudioChannelMode::JointStereo,
+            2 => MpegAudioChannelMode::DualChannel,
+            _ => MpegAudioChannelMode::Mono,
+        };
+        let frame_length = match (version, layer) {
+            (_, MpegAudioLayer::Layer1) => (12 * bit_rate / sample_rate + padding as u32) * 4,
+            (MpegAudioVersion::Mpeg1, _) | (_, MpegAudioLayer::Layer2) => 144 * bit_rate / sample_rate + padding as u32,
+            _ => 72 * bit_rate / sample_rate + padding as u32,
+        } as usize;
+        Ok(Self {
+            version,
+            layer,
+            protection_absent: data[1] & 0x1 != 0,
+            bit_rate,
+            sample_rate,
+            padding,
+            private_bit: data[2] & 0x1 != 0,
+            channel_mode,
+            mode_extension: (data[3] >> 4) & 0x3,
+            copyright: data[3] & 0x8 != 0,
+            original: data[3] & 0x4 != 0,
+            emphasis: data[3] & 0x3,
+            frame_length,
+        })
+    }
+
+    #[inline]
+    pub fn channel_count(&self) -> u32 {
+        if self.channel_mode == MpegAudioChannelMode::Mono {
+            1
+        } else {
+            2
+        }
+    }
+
+    #[inline]
+    pub fn frames_per_packet(&self) -> u32 {
+        match (self.version, self.layer) {
+            (_, MpegAudioLayer::Layer1) => 384,
+            (MpegAudioVersion::Mpeg1, _) | (_, MpegAudioLayer::Layer2) => 1152,
+            _ => 576,
+        }
+    }
+
+    #[inline]
+    pub fn format_id(&self) -> AudioFormatID {
+        match self.layer {
+            MpegAudioLayer::Layer1 => kAudioFormatMPEGLayer1,
+            MpegAudioLayer::Layer2 => kAudioFormatMPEGLayer2,
+            MpegAudioLayer::Layer3 => kAudioFormatMPEGLayer3,
+        }
+    }
+
+    // Bytes between the header and the main data of a layer 3 frame
+    #[inline]
+    pub fn side_information_size(&self) -> usize {
+        let size = match (self.version, self.channel_mode) {
+            (MpegAudioVersion::Mpeg1, MpegAudioChannelMode::Mono) => 17,
+            (MpegAudioVersion::Mpeg1, _) => 32,
+            (_, MpegAudioChannelMode::Mono) => 9,
+            _ => 17,
+        };
+        if self.protection_absent {
+            size
+        } else {
+            size + 2
+        }
+    }
+
+    // Bit rate and padding may change from frame to frame
+    #[inline]
+    pub fn is_same_stream(&self, other: &MpegAudioHeader) -> bool {
+        self.version == other.version &&
+            self.layer == other.layer &&
+            self.sample_rate == other.sample_rate &&
+            self.channel_count() == other.channel_count()
+    }
+
+    pub fn stream_basic_description(&self) -> AudioStreamBasicDescription {
+        AudioStreamBasicDescription {
+            mSampleRate: self.sample_rate as f64,
+            mFormatID: self.format_id(),
+            mFormatFlags: 0,
+            mBytesPerPacket: 0,
+            mFramesPerPacket: self.frames_per_packet(),
+            mBytesPerFrame: 0,
+            mChannelsPerFrame: self.channel_count(),
+            mBitsPerChannel: 0,
+            mReserved: 0,
+        }
+    }
+
+    pub fn channel_layout(&self) -> ChannelLayout {
+        ChannelLayout::Tag(if self.channel_count() == 1 {
+            kAudioChannelLayoutTag_Mono
+        } else {
+            kAudioChannelLayoutTag_Stereo
+        })
+    }
+}
+
+// Size of a leading ID3v2 tag, footer included
+pub fn id3v2_tag_size(data: &[u8]) -> Option<usize> {
+    if data.len() < ID3V2_HEADER_SIZE || &data[..3] != b"ID3" || data[3] == 0xFF || data[4] == 0xFF {
+        return None;
+    }
+    if data[6..10].iter().any(|byte| byte & 0x80 != 0) {
+        return None;
+    }
+    let size = data[6..10].iter().fold(0usize, |size, byte| (size << 7) | *byte as usize);
+    let footer = if data[5] & 0x10 != 0 {
+        ID3V2_HEADER_SIZE
+    } else {
+        0
+    };
+    Some(ID3V2_HEADER_SIZE + size + footer)
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MpegAudioInfoTagKind {
+    Xing,
+    Info,
+    Vbri,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MpegAudioInfoTag {
+    pub kind: MpegAudioInfoTagKind,
+    pub frame_count: Option<u32>,
+    pub byte_count: Option<u32>,
+    pub toc: Option<Vec<u8>>,
+    pub quality: Option<u32>,
+    pub encoder: Option<String>,
+    pub encoder_delay: Option<u32>,
+    pub encoder_padding: Option<u32>,
+}
+
+fn read_u16_be(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32_be(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+impl MpegAudioInfoTag {
+    // Looks for a Xing, Info or VBRI header in the first frame of a stream
+    pub fn parse(header: &MpegAudioHeader, frame: &[u8]) -> Option<Self> {
+        if header.layer != MpegAudioLayer::Layer3 {
+            return None;
+        }
+        Self::parse_xing(frame, MPEG_AUDIO_HEADER_SIZE + header.side_information_size()).or_else(|| Self::parse_vbri(frame))
+    }
+
+    fn parse_xing(frame: &[u8], offset: usize) -> Option<Self> {
+        let kind = match frame.get(offset..offset + 4)? {
+            b"Xing" => MpegAudioInfoTagKind::Xing,
+            b"Info" => MpegAudioInfoTagKind::Info,
+            _ => return None,
+        };
+        let flags = read_u32_be(frame, offset + 4)?;
+        let mut position = offset + 8;
+        let mut tag = Self {
+            kind,
+            frame_count: None,
+            byte_count: None,
+            toc: None,
+            quality: None,
+            encoder: None,
+            encoder_delay: None,
+            encoder_padding: None,
+        };
+        if flags & XING_FLAG_FRAMES != 0 {
+            tag.frame_count = Some(read_u32_be(frame, position)?);
+            position += 4;
+        }
+        if flags & XING_FLAG_BYTES != 0 {
+            tag.byte_count = Some(read_u32_be(frame, position)?);
+            position += 4;
+        }
+        if flags & XING_FLAG_TOC != 0 {
+            tag.toc = Some(frame.get(position..position + XING_TOC_SIZE)?.to_vec());
+            position += XING_TOC_SIZE;
+        }
+        if flags & XING_FLAG_QUALITY != 0 {
+            tag.quality = Some(read_u32_be(frame, position)?);
+            position += 4;
+        }
+        // LAME extension, delay and padding are two 12 bit values
+        if let Some(encoder) = frame.get(position..position + LAME_ENCODER_VERSION_SIZE) {
+            if encoder.iter().all(|byte| byte.is_ascii_graphic() || *byte == b' ' || *byte == 0) && encoder[0].is_ascii_alphabetic() {
+                tag.encoder = Some(String::from_utf8_lossy(encoder).trim_end_matches(['\0', ' ']).to_string());
+                if let Some(bytes) = frame.get(position + LAME_DELAY_OFFSET..position + LAME_DELAY_OFFSET + 3) {
+                    tag.encoder_delay = Some(((bytes[0] as u32) << 4) | (bytes[1] as u32 >> 4));
+                    tag.encoder_padding = Some(((bytes[1] as u32 & 0xF) << 8) | bytes[2] as u32);
+                }
+            }
+        }
+        Some(tag)
+    }
+
+    fn parse_vbri(frame: &[u8]) -> Option<Self> {
+        if frame.get(VBRI_OFFSET..VBRI_OFFSET + 4)? != b"VBRI" {
+            return None;
+        }
+        Some(Self {
+            kind: MpegAudioInfoTagKind::Vbri,
+            frame_count: Some(read_u32_be(frame, VBRI_OFFSET + 14)?),
+            byte_count: Some(read_u32_be(frame, VBRI_OFFSET + 10)?),
+            toc: None,
+            quality: Some(read_u16_be(frame, VBRI_OFFSET + 8)? as u32),
+            encoder: None,
+            encoder_delay: Some(read_u16_be(frame, VBRI_OFFSET + 6)? as u32),
+            encoder_padding: None,
+        })
+    }
+
+    // Follows the LAME convention for layer 3 priming
+    pub fn gapless_info(&self, header: &MpegAudioHeader) -> MpegAudioGaplessInfo {
+        let decoder_delay = if header.layer == MpegAudioLayer::Layer3 {
+            MP3_DECODER_DELAY
+        } else {
+            0
+        };
+        let encoder_delay = self.encoder_delay.unwrap_or(0);
+        let encoder_padding = self.encoder_padding.unwrap_or(0);
+        MpegAudioGaplessInfo {
+            priming_frames: if self.encoder_delay.is_some() {
+                encoder_delay + decoder_delay
+            } else {
+                0
+            },
+            remainder_frames: encoder_padding.saturating_sub(decoder_delay),
+            total_frames: self.frame_count.map(|count| count as u64 * header.frames_per_packet() as u64),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct MpegAudioGaplessInfo {
+    pub priming_frames: u32,
+    pub remainder_frames: u32,
+    pub total_frames: Option<u64>,
+}
+
+impl MpegAudioGaplessInfo {
+    // The end trim is only known with the stream length
+    pub fn trim_frames(&self, first_frame: u64, frame_count: u64) -> (u64, u64) {
+        let end_frame = first_frame + frame_count;
+        let start = (self.priming_frames as u64).saturating_sub(first_frame).min(frame_count);
+        let end = self.total_frames.map_or(0, |total| {
+            let remainder_start = total.saturating_sub(self.remainder_frames as u64);
+            end_frame.saturating_sub(remainder_start.max(first_frame)).min(frame_count - start)
+        });
+        (start, end)
+    }
+
+    pub fn trim_durations(&self, first_frame: u64, frame_count: u64, sample_rate: u32) -> (CMTime, CMTime) {
+        let (start, end) = self.trim_frames(first_frame, frame_count);
+        (CMTime::make(start as i64, sample_rate as CMTimeScale), CMTime::make(end as i64, sample_rate as CMTimeScale))
+    }
+}
+
+pub fn set_trim_durations(sample_buffer: &CMSampleBuffer, trim_at_start: CMTime, trim_at_end: CMTime) {
+    let bearer = sample_buffer.as_CMAttachmentBearer();
+    let trims =
+        unsafe { [(kCMSampleBufferAttachmentKey_TrimDurationAtStart, trim_at_start), (kCMSampleBufferAttachmentKey_TrimDurationAtEnd, trim_at_end)] };
+    for (key, duration) in trims {
+        let key = unsafe { CFString::wrap_under_get_rule(key) };
+        if duration.value == 0 {
+            bearer.remove_attachment(&key);
+        } else if let Some(dictionary) = duration.copy_as_dictionary() {
+            bearer.set_attachment(&key, &dictionary.as_CFType(), kCMAttachmentMode_ShouldNotPropagate);
+        }
+    }
+}
+
+impl SyncHeader for MpegAudioHeader {
+    const HEADER_SIZE: usize = MPEG_AUDIO_HEADER_SIZE;
+
+    #[inline]
+    fn parse_header(data: &[u8]) -> Option<Self> {
+        Self::parse(data).ok()
+    }
+
+    #[inline]
+    fn frame_length(&self) -> usize {
+        self.frame_length
+    }
+
+    // The next header has to describe the same stream, unless a tag follows
+    fn is_boundary(&self, next: &[u8]) -> bool {
+        match next.get(..MPEG_AUDIO_HEADER_SIZE) {
+            Some(next_header) if &next_header[..3] != b"TAG" && &next_header[..3] != b"ID3" => {
+                MpegAudioHeader::parse(next_header).is_ok_and(|next_header| next_header.is_same_stream(self))
+            }
+            _ => true,
+        }
+    }
+
+    #[inline]
+    fn is_continued_by(&self, next: &Self) -> bool {
+        self.is_same_stream(next)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MpegAudioFrame<'a> {
+    pub header: MpegAudioHeader,
+    pub data: &'a [u8],
+    pub offset: usize,
+}
+
+// Starts after a leading ID3v2 tag
+pub struct MpegAudioFrameIter<'a>(SyncFrameIter<'a, MpegAudioHeader>);
+
+impl<'a> MpegAudioFrameIter<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self(SyncFrameIter::new(data, id3v2_tag_size(data).unwrap_or(0)))
+    }
+
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.0.position()
+    }
+}
+
+impl<'a> Iterator for MpegAudioFrameIter<'a> {
+    type Item = MpegAudioFrame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|frame| MpegAudioFrame {
+            header: frame.header,
+            data: frame.data,
+            offset: frame.offset,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MpegAudioPacketList {
+    pub header: MpegAudioHeader,
+    pub data: Vec<u8>,
+    pub packet_descriptions: Vec<AudioStreamPacketDescription>,
+    pub info_tag: Option<MpegAudioInfoTag>,
+    pub consumed: usize,
+}
+
+impl MpegAudioPacketList {
+    // Frames up to a configuration change, without the info tag frame, resume from
+    // `consumed`
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        let mut frames = MpegAudioFrameIter::new(data);
+        let first = frames.next().ok_or(kCMFormatDescriptionError_InvalidParameter)?;
+        let info_tag = MpegAudioInfoTag::parse(&first.header, first.data);
+        let mut list = Self {
+            header: first.header,
+            data: Vec::new(),
+            packet_descriptions: Vec::new(),
+            info_tag,
+            consumed: first.offset + first.data.len(),
+        };
+        // The info tag frame is not part of the encoded audio
+        let mut frame = if list.info_tag.is_some() {
+            frames.next()
+        } else {
+            Some(first)
+        };
+        while let Some(current) = frame {
+            if !current.header.is_same_stream(&list.header) {
+                break;
+            }
+            list.packet_descriptions.push(AudioStreamPacketDescription {
+                mStartOffset: list.data.len() as i64,
+                mVariableFramesInPacket: 0,
+                mDataByteSize: current.data.len() as u32,
+            });
+            list.data.extend_from_slice(current.data);
+            list.consumed = current.offset + current.data.len();
+            frame = frames.next();
+        }
+        Ok(list)
+    }
+
+    #[inline]
+    pub fn packet_count(&self) -> usize {
+        self.packet_descriptions.len()
+    }
+
+    #[inline]
+    pub fn frame_count(&self) -> u64 {
+        self.packet_descriptions.len() as u64 * self.header.frames_per_packet() as u64
+    }
+
+    #[inline]
+    pub fn stream_basic_description(&self) -> AudioStreamBasicDescription {
+        self.header.stream_basic_description()
+    }
+
+    #[inline]
+    pub fn gapless_info(&self) -> MpegAudioGaplessInfo {
+        self.info_tag.as_ref().map(|tag| tag.gapless_info(&self.header)).unwrap_or_default()
+    }
+
+    pub fn new_format_description(&self) -> Result<CMAudioFormatDescription, OSStatus> {
+        CMAudioFormatDescription::new_with_channel_layout(&self.stream_basic_description(), Some(&self.header.channel_layout()), &[], None)
+    }
+
+    pub fn new_sample_buffer(
+        &self,
+        format_description: &CMAudioFormatDescription,
+        presentation_time_stamp: CMTime,
+    ) -> Result<CMSampleBuffer, OSStatus> {
+        sync_frame::new_sample_buffer(&self.data, &self.packet_descriptions, format_description, presentation_time_stamp)
+    }
+
+    // `first_frame` is the stream position of the first packet
+    pub fn new_trimmed_sample_buffer(
+        &self,
+        format_description: &CMAudioFormatDescription,
+        presentation_time_stamp: CMTime,
+        gapless_info: &MpegAudioGaplessInfo,
+        first_frame: u64,
+    ) -> Result<CMSampleBuffer, OSStatus> {
+        let sample_buffer = self.new_sample_buffer(format_description, presentation_time_stamp)?;
+        let (trim_at_start, trim_at_end) = gapless_info.trim_durations(first_frame, self.frame_count(), self.header.sample_rate);
+        set_trim_durations(&sample_buffer, trim_at_start, trim_at_end);
+        Ok(sample_buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // MPEG-1 layer 3, 32 kbit/s, 48 kHz, mono
+    const MONO_HEADER: [u8; 4] = [0xFF, 0xFB, 0x14, 0xC0];
+    const MONO_FRAME_LENGTH: usize = 96;
+    const MONO_SIDE_INFORMATION_SIZE: usize = 17;
+
+    fn frame(fill: u8) -> Vec<u8> {
+        let mut frame = MONO_HEADER.to_vec();
+        frame.resize(MONO_FRAME_LENGTH, fill);
+        frame
+    }
+
+    fn xing_frame() -> Vec<u8> {
+        let mut frame = frame(0);
+        let offset = MPEG_AUDIO_HEADER_SIZE + MONO_SIDE_INFORMATION_SIZE;
+        frame[offset..offset + 4].copy_from_slice(b"Xing");
+        frame[offset + 4..offset + 8].copy_from_slice(&(XING_FLAG_FRAMES | XING_FLAG_BYTES | XING_FLAG_QUALITY).to_be_bytes());
+        frame[offset + 8..offset + 12].copy_from_slice(&100u32.to_be_bytes());
+        frame[offset + 12..offset + 16].copy_from_slice(&9600u32.to_be_bytes());
+        frame[offset + 16..offset + 20].copy_from_slice(&78u32.to_be_bytes());
+        frame[offset + 20..offset + 29].copy_from_slice(b"LAME3.100");
+        // Delay 576, padding 1000
+        frame[offset + 20 + LAME_DELAY_OFFSET..offset + 23 + LAME_DELAY_OFFSET].copy_from_slice(&[0x24, 0x03, 0xE8]);
+        frame
+    }
+
+    #[test]
+    fn header_frame_length() {
+        let header = MpegAudioHeader::parse(&MONO_HEADER).unwrap();
+        assert_eq!(header.version, MpegAudioVersion::Mpeg1);
+        assert_eq!(header.layer, MpegAudioLayer::Layer3);
+        assert_eq!(header.sample_rate, 48000);
+        assert_eq!(header.bit_rate, 32000);
+        assert_eq!(header.channel_count(), 1);
+        assert_eq!(header.frame_length, MONO_FRAME_LENGTH);
+        assert_eq!(header.side_information_size(), MONO_SIDE_INFORMATION_SIZE);
+        assert_eq!(header.frames_per_packet(), 1152);
+    }
+
+    #[test]
+    fn xing_lame_gapless_info() {
+        let frame = xing_frame();
+        let header = MpegAudioHeader::parse(&frame).unwrap();
+        let tag = MpegAudioInfoTag::parse(&header, &frame).unwrap();
+        assert_eq!(tag.kind, MpegAudioInfoTagKind::Xing);
+        assert_eq!(tag.frame_count, Some(100));
+        assert_eq!(tag.byte_count, Some(9600));
+        assert_eq!(tag.quality, Some(78));
+        assert_eq!(tag.toc, None);
+        assert_eq!(tag.encoder.as_deref(), Some("LAME3.100"));
+        assert_eq!(tag.encoder_delay, Some(576));
+        assert_eq!(tag.encoder_padding, Some(1000));
+        let info = tag.gapless_info(&header);
+        assert_eq!(info.priming_frames, 576 + MP3_DECODER_DELAY);
+        assert_eq!(info.remainder_frames, 1000 - MP3_DECODER_DELAY);
+        assert_eq!(info.total_frames, Some(115200));
+        assert_eq!(info.trim_frames(0, 1152), (1105, 0));
+        assert_eq!(info.trim_frames(115200 - 1152, 1152), (0, 471));
+    }
+
+    #[test]
+    fn vbri_gapless_info() {
+        let mut frame = frame(0);
+        frame[VBRI_OFFSET..VBRI_OFFSET + 4].copy_from_slice(b"VBRI");
+        frame[VBRI_OFFSET + 4..VBRI_OFFSET + 18].copy_from_slice(&[0, 1, 0x02, 0x40, 0, 75, 0, 0, 0x25, 0x80, 0, 0, 0, 100]);
+        let header = MpegAudioHeader::parse(&frame).unwrap();
+        let tag = MpegAudioInfoTag::parse(&header, &frame).unwrap();
+        assert_eq!(tag.kind, MpegAudioInfoTagKind::Vbri);
+        assert_eq!(tag.frame_count, Some(100));
+        assert_eq!(tag.byte_count, Some(9600));
+        assert_eq!(tag.quality, Some(75));
+        assert_eq!(tag.encoder_delay, Some(576));
+        let info = tag.gapless_info(&header);
+        assert_eq!(info.priming_frames, 576 + MP3_DECODER_DELAY);
+        assert_eq!(info.remainder_frames, 0);
+    }
+
+    #[test]
+    fn packet_list_skips_tags_and_info_frame() {
+        let mut data = b"ID3\x04\x00\x00\x00\x00\x00\x00".to_vec();
+        data.extend_from_slice(&xing_frame());
+        data.extend_from_slice(&frame(1));
+        data.extend_from_slice(&frame(2));
+        let end = data.len();
+        data.extend_from_slice(b"APETAGEX\xD0\x07\x00\x00");
+        let list = MpegAudioPacketList::parse(&data).unwrap();
+        assert_eq!(list.info_tag.as_ref().map(|tag| tag.kind), Some(MpegAudioInfoTagKind::Xing));
+        assert_eq!(list.packet_count(), 2);
+        assert_eq!(list.packet_descriptions[1].mStartOffset, MONO_FRAME_LENGTH as i64);
+        assert_eq!(list.data[MONO_FRAME_LENGTH + 4], 2);
+        assert_eq!(list.consumed, end);
+
+        // A lone frame needs a header of the same stream after it
+        let mut stray = frame(1);
+        stray.extend_from_slice(&[0; 8]);
+        assert!(MpegAudioFrameIter::new(&stray).next().is_none());
+    }
+}
@@ -0,0 +1,319 @@
+use core_audio_types::four_cc::FourCC;
+use core_foundation::{
+    base::{OSStatus, TCFType},
+    number::CFNumber,
+    string::{CFString, CFStringRef},
+};
+use core_video::color_code_point::{ColorPrimaries, MatrixCoefficients, TransferCharacteristics};
+
+use crate::{
+    format_description::{
+        kCMFormatDescriptionError_InvalidParameter, kCMFormatDescriptionExtension_ColorPrimaries, kCMFormatDescriptionExtension_FieldCount,
+        kCMFormatDescriptionExtension_FieldDetail, kCMFormatDescriptionExtension_TransferFunction, kCMFormatDescriptionExtension_YCbCrMatrix,
+        kCMFormatDescriptionFieldDetail_TemporalBottomFirst, kCMFormatDescriptionFieldDetail_TemporalTopFirst, kCMVideoCodecType_AppleProRes422,
+        kCMVideoCodecType_AppleProRes422HQ, kCMVideoCodecType_AppleProRes422LT, kCMVideoCodecType_AppleProRes422Proxy,
+        kCMVideoCodecType_AppleProRes4444, kCMVideoCodecType_AppleProRes4444XQ, CMVideoCodecType, CMVideoDimensions, CMVideoFormatDescription,
+        TCMFormatDescription,
+    },
+    OSType,
+};
+
+pub const kProResFrameIdentifier: OSType = FourCC::new(b"icpf").as_u32();
+
+const FRAME_CONTAINER_HEADER_SIZE: usize = 8;
+const MIN_FRAME_HEADER_SIZE: usize = 20;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ProResInterlaceMode {
+    Progressive,
+    TopFieldFirst,
+    BottomFieldFirst,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ProResChromaFormat {
+    Chroma422,
+    Chroma444,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ProResAlphaChannelType {
+    None,
+    Alpha8Bit,
+    Alpha16Bit,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ProResFrameHeader {
+    pub frame_size: u32,
+    pub header_size: u16,
+    pub version: u16,
+    pub creator: OSType,
+    pub width: u16,
+    pub height: u16,
+    pub chroma_format: ProResChromaFormat,
+    pub interlace_mode: ProResInterlaceMode,
+    pub aspect_ratio_information: u8,
+    pub frame_rate_code: u8,
+    pub color_primaries: u8,
+    pub transfer_characteristics: u8,
+    pub matrix_coefficients: u8,
+    pub alpha_channel_type: ProResAlphaChannelType,
+    pub custom_luma_quantization_matrix: bool,
+    pub custom_chroma_quantization_matrix: bool,
+}
+
+impl ProResFrameHeader {
+    pub fn parse(data: &[u8]) -> Result<Self, OSStatus> {
+        if data.len() < FRAME_CONTAINER_HEADER_SIZE + MIN_FRAME_HEADER_SIZE {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let frame_size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        if (frame_size as usize) < FRAME_CONTAINER_HEADER_SIZE + MIN_FRAME_HEADER_SIZE || frame_size as usize > data.len() {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let identifier = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        if identifier != kProResFrameIdentifier {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let header = &data[FRAME_CONTAINER_HEADER_SIZE..frame_size as usize];
+        let header_size = u16::from_be_bytes([header[0], header[1]]);
+        let version = u16::from_be_bytes([header[2], header[3]]);
+        if (header_size as usize) < MIN_FRAME_HEADER_SIZE || header_size as usize > header.len() || version > 1 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let chroma_format = match header[12] >> 6 {
+            2 => ProResChromaFormat::Chroma422,
+            3 => ProResChromaFormat::Chroma444,
+            _ => return Err(kCMFormatDescriptionError_InvalidParameter),
+        };
+        let interlace_mode = match (header[12] >> 2) & 0x3 {
+            0 => ProResInterlaceMode::Progressive,
+            1 => ProResInterlaceMode::TopFieldFirst,
+            2 => ProResInterlaceMode::BottomFieldFirst,
+            _ => return Err(kCMFormatDescriptionError_InvalidParameter),
+        };
+        let alpha_channel_type = match header[17] & 0xF {
+            0 => ProResAlphaChannelType::None,
+            1 => ProResAlphaChannelType::Alpha8Bit,
+            2 => ProResAlphaChannelType::Alpha16Bit,
+            _ => return Err(kCMFormatDescriptionError_InvalidParameter),
+        };
+        let frame_header = Self {
+            frame_size,
+            header_size,
+            version,
+            creator: u32::from_be_bytes([header[4], header[5], header[6], header[7]]),
+            width: u16::from_be_bytes([header[8], header[9]]),
+            height: u16::from_be_bytes([header[10], header[11]]),
+            chroma_format,
+            interlace_mode,
+            aspect_ratio_information: header[13] >> 4,
+            frame_rate_code: header[13] & 0xF,
+            color_primaries: header[14],
+            transfer_characteristics: header[15],
+            matrix_coefficients: header[16],
+            alpha_channel_type,
+            custom_luma_quantization_matrix: header[19] & 0x2 != 0,
+            custom_chroma_quantization_matrix: header[19] & 0x1 != 0,
+        };
+        if frame_header.width == 0 || frame_header.height == 0 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        Ok(frame_header)
+    }
+
+    #[inline]
+    pub fn dimensions(&self) -> CMVideoDimensions {
+        CMVideoDimensions {
+            width: self.width as i32,
+            height: self.height as i32,
+        }
+    }
+
+    #[inline]
+    pub fn is_interlaced(&self) -> bool {
+        self.interlace_mode != ProResInterlaceMode::Progressive
+    }
+
+    #[inline]
+    pub fn has_alpha(&self) -> bool {
+        self.alpha_channel_type != ProResAlphaChannelType::None
+    }
+
+    pub fn frame_rate(&self) -> Option<(u32, u32)> {
+        match self.frame_rate_code {
+            1 => Some((24000, 1001)),
+            2 => Some((24, 1)),
+            3 => Some((25, 1)),
+            4 => Some((30000, 1001)),
+            5 => Some((30, 1)),
+            6 => Some((50, 1)),
+            7 => Some((60000, 1001)),
+            8 => Some((60, 1)),
+            9 => Some((100, 1)),
+            10 => Some((120000, 1001)),
+            11 => Some((120, 1)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn color_primaries(&self) -> Option<ColorPrimaries> {
+        ColorPrimaries::from_code_point(self.color_primaries as i32)
+    }
+
+    #[inline]
+    pub fn transfer_characteristics(&self) -> Option<TransferCharacteristics> {
+        TransferCharacteristics::from_code_point(self.transfer_characteristics as i32)
+    }
+
+    #[inline]
+    pub fn matrix_coefficients(&self) -> Option<MatrixCoefficients> {
+        MatrixCoefficients::from_code_point(self.matrix_coefficients as i32)
+    }
+
+    pub fn compatible_codec_types(&self) -> &'static [CMVideoCodecType] {
+        match self.chroma_format {
+            ProResChromaFormat::Chroma444 => &[kCMVideoCodecType_AppleProRes4444, kCMVideoCodecType_AppleProRes4444XQ],
+            // 4:2:2 variants cannot carry an alpha channel
+            ProResChromaFormat::Chroma422 if self.has_alpha() => &[],
+            ProResChromaFormat::Chroma422 => &[
+                kCMVideoCodecType_AppleProRes422HQ,
+                kCMVideoCodecType_AppleProRes422,
+                kCMVideoCodecType_AppleProRes422LT,
+                kCMVideoCodecType_AppleProRes422Proxy,
+            ],
+        }
+    }
+
+    // Rough estimate only: the header does not name the variant, so guess it from
+    // bits per pixel and prefer a codec type from the container when there is one
+    pub fn estimated_codec_type(&self) -> Option<CMVideoCodecType> {
+        let pixels = self.width as u64 * self.height as u64;
+        if pixels == 0 || self.frame_size == 0 {
+            return None;
+        }
+        let bits_per_pixel = (self.frame_size as u64 * 8) as f64 / pixels as f64;
+        let codec_type = match self.chroma_format {
+            ProResChromaFormat::Chroma444 if bits_per_pixel >= 6.6 => kCMVideoCodecType_AppleProRes4444XQ,
+            ProResChromaFormat::Chroma444 => kCMVideoCodecType_AppleProRes4444,
+            ProResChromaFormat::Chroma422 if self.has_alpha() => return None,
+            ProResChromaFormat::Chroma422 if bits_per_pixel >= 2.95 => kCMVideoCodecType_AppleProRes422HQ,
+            ProResChromaFormat::Chroma422 if bits_per_pixel >= 2.0 => kCMVideoCodecType_AppleProRes422,
+            ProResChromaFormat::Chroma422 if bits_per_pixel >= 1.2 => kCMVideoCodecType_AppleProRes422LT,
+            ProResChromaFormat::Chroma422 => kCMVideoCodecType_AppleProRes422Proxy,
+        };
+        Some(codec_type)
+    }
+
+    pub fn validate_codec_type(&self, codec_type: CMVideoCodecType) -> Result<(), OSStatus> {
+        if self.compatible_codec_types().contains(&codec_type) {
+            Ok(())
+        } else {
+            Err(kCMFormatDescriptionError_InvalidParameter)
+        }
+    }
+
+    pub fn validate_format_description(&self, desc: &CMVideoFormatDescription) -> Result<(), OSStatus> {
+        self.validate_codec_type(desc.get_codec_type())?;
+        let dimensions = desc.get_dimensions();
+        if dimensions.width != self.width as i32 || dimensions.height != self.height as i32 {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        let format_description = desc.as_buffer();
+        let extension = |key: CFStringRef| format_description.get_extension(&unsafe { CFString::wrap_under_get_rule(key) });
+        let string_extension = |key: CFStringRef| extension(key).and_then(|value| value.downcast::<CFString>());
+
+        let field_count = unsafe { extension(kCMFormatDescriptionExtension_FieldCount) }
+            .and_then(|value| value.downcast::<CFNumber>())
+            .and_then(|value| value.to_i32());
+        if let Some(field_count) = field_count {
+            if (field_count == 2) != self.is_interlaced() {
+                return Err(kCMFormatDescriptionError_InvalidParameter);
+            }
+        }
+        if let Some(field_detail) = unsafe { string_extension(kCMFormatDescriptionExtension_FieldDetail) } {
+            let expected = match self.interlace_mode {
+                ProResInterlaceMode::TopFieldFirst => Some(unsafe { kCMFormatDescriptionFieldDetail_TemporalTopFirst }),
+                ProResInterlaceMode::BottomFieldFirst => Some(unsafe { kCMFormatDescriptionFieldDetail_TemporalBottomFirst }),
+                ProResInterlaceMode::Progressive => None,
+            };
+            if let Some(expected) = expected {
+                if field_detail != unsafe { CFString::wrap_under_get_rule(expected) } {
+                    return Err(kCMFormatDescriptionError_InvalidParameter);
+                }
+            }
+        }
+
+        let color_primaries = unsafe { string_extension(kCMFormatDescriptionExtension_ColorPrimaries) }.map(|value| value.to_string());
+        let transfer_function = unsafe { string_extension(kCMFormatDescriptionExtension_TransferFunction) }.map(|value| value.to_string());
+        let ycbcr_matrix = unsafe { string_extension(kCMFormatDescriptionExtension_YCbCrMatrix) }.map(|value| value.to_string());
+        self.validate_color_extensions(color_primaries.as_deref(), transfer_function.as_deref(), ycbcr_matrix.as_deref())
+    }
+
+    // Several code points share one extension string, so compare strings rather
+    // than the code point read back from the extension. Unspecified code points in
+    // the frame header do not conflict with the format description
+    pub fn validate_color_extensions(
+        &self,
+        color_primaries: Option<&str>,
+        transfer_function: Option<&str>,
+        ycbcr_matrix: Option<&str>,
+    ) -> Result<(), OSStatus> {
+        let conflicts =
+            |expected: Option<&'static str>, value: Option<&str>| matches!((expected, value), (Some(expected), Some(value)) if expected != value);
+        if conflicts(self.color_primaries().and_then(|primaries| primaries.string_value()), color_primaries) ||
+            conflicts(self.transfer_characteristics().and_then(|transfer| transfer.string_value()), transfer_function) ||
+            conflicts(self.matrix_coefficients().and_then(|matrix| matrix.string_value()), ycbcr_matrix)
+        {
+            return Err(kCMFormatDescriptionError_InvalidParameter);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(frame_size: u32, data_len: usize) -> Vec<u8> {
+        let mut data = vec![0; data_len];
+        data[0..4].copy_from_slice(&frame_size.to_be_bytes());
+        data[4..8].copy_from_slice(b"icpf");
+        data[8..10].copy_from_slice(&(MIN_FRAME_HEADER_SIZE as u16).to_be_bytes());
+        data[12..16].copy_from_slice(b"apl0");
+        data[16..18].copy_from_slice(&1920u16.to_be_bytes());
+        data[18..20].copy_from_slice(&1080u16.to_be_bytes());
+        data[20] = 0x80;
+        data
+    }
+
+    #[test]
+    fn parse_checks_frame_size() {
+        let header = ProResFrameHeader::parse(&frame(64, 64)).unwrap();
+        assert_eq!(header.frame_size, 64);
+        assert_eq!(header.creator, FourCC::new(b"apl0").as_u32());
+        assert_eq!((header.width, header.height), (1920, 1080));
+        assert_eq!(header.chroma_format, ProResChromaFormat::Chroma422);
+        assert!(ProResFrameHeader::parse(&frame(64, 100)).is_ok());
+        assert_eq!(ProResFrameHeader::parse(&frame(65, 64)), Err(kCMFormatDescriptionError_InvalidParameter));
+        assert_eq!(ProResFrameHeader::parse(&frame(27, 64)), Err(kCMFormatDescriptionError_InvalidParameter));
+    }
+
+    #[test]
+    fn color_extensions_compare_shared_strings() {
+        let mut data = frame(64, 64);
+        // SMPTE 240M primaries, SMPTE 170M transfer and matrix
+        data[22..25].copy_from_slice(&[7, 6, 6]);
+        let header = ProResFrameHeader::parse(&data).unwrap();
+        assert_eq!(header.validate_color_extensions(Some("SMPTE_C"), Some("ITU_R_709_2"), Some("ITU_R_601_4")), Ok(()));
+        assert_eq!(header.validate_color_extensions(None, None, None), Ok(()));
+        assert_eq!(header.validate_color_extensions(Some("ITU_R_709_2"), None, None), Err(kCMFormatDescriptionError_InvalidParameter));
+        assert_eq!(header.validate_color_extensions(None, None, Some("ITU_R_709_2")), Err(kCMFormatDescriptionError_InvalidParameter));
+
+        // Unspecified code points accept any extension
+        let header = ProResFrameHeader::parse(&frame(64, 64)).unwrap();
+        assert_eq!(header.validate_color_extensions(Some("SMPTE_C"), Some("Linear"), Some("ITU_R_2020")), Ok(()));
+    }
+}
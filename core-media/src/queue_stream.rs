@@ -0,0 +1,659 @@
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use core_foundation::base::OSStatus;
+use futures_core::Stream;
+use futures_sink::Sink;
+use libc::c_void;
+
+use crate::{
+    buffer_queue::{
+        kCMBufferQueueError_EnqueueAfterEndOfData, kCMBufferQueueTrigger_WhenBufferCountBecomesGreaterThan,
+        kCMBufferQueueTrigger_WhenBufferCountBecomesLessThan, kCMBufferQueueTrigger_WhenDataBecomesReady, kCMBufferQueueTrigger_WhenEndOfDataReached,
+        kCMBufferQueueTrigger_WhenReset, BufferQueue, BufferQueueTriggerToken, BufferTiming, CMBuffer, CMBufferQueue, CMBufferQueueTriggerCondition,
+        CMBufferQueueTriggerToken,
+    },
+    sample_queue::{kCMSimpleQueueError_QueueIsFull, CMSimpleQueue},
+    time::CMTime,
+};
+
+#[derive(Debug, Default)]
+pub struct QueueWaker {
+    waker: Mutex<Option<Waker>>,
+}
+
+impl QueueWaker {
+    pub fn register(&self, waker: &Waker) {
+        let mut slot = self.waker.lock().unwrap_or_else(PoisonError::into_inner);
+        if !slot.as_ref().is_some_and(|registered| registered.will_wake(waker)) {
+            *slot = Some(waker.clone());
+        }
+    }
+
+    pub fn wake(&self) {
+        if let Some(waker) = self.waker.lock().unwrap_or_else(PoisonError::into_inner).take() {
+            waker.wake();
+        }
+    }
+}
+
+// Queues whose triggers can wake async readers and writers
+pub trait TriggeredQueue {
+    type Buffer;
+    type TriggerToken: Copy;
+
+    fn enqueue_buffer(&self, buffer: Self::Buffer) -> Result<(), OSStatus>;
+    fn dequeue_ready_buffer(&self) -> Option<Self::Buffer>;
+    fn buffer_count(&self) -> usize;
+    fn is_at_end_of_data(&self) -> bool;
+    fn mark_end_of_data(&self) -> Result<(), OSStatus>;
+    fn install_wake_trigger(
+        &self,
+        condition: CMBufferQueueTriggerCondition,
+        threshold: usize,
+        waker: Arc<QueueWaker>,
+    ) -> Result<Self::TriggerToken, OSStatus>;
+    fn remove_wake_trigger(&self, token: Self::TriggerToken);
+}
+
+#[inline]
+fn is_count_condition(condition: CMBufferQueueTriggerCondition) -> bool {
+    condition == kCMBufferQueueTrigger_WhenBufferCountBecomesLessThan || condition == kCMBufferQueueTrigger_WhenBufferCountBecomesGreaterThan
+}
+
+impl TriggeredQueue for CMBufferQueue {
+    type Buffer = CMBuffer;
+    type TriggerToken = CMBufferQueueTriggerToken;
+
+    #[inline]
+    fn enqueue_buffer(&self, buffer: CMBuffer) -> Result<(), OSStatus> {
+        self.enqueue(&buffer)
+    }
+
+    #[inline]
+    fn dequeue_ready_buffer(&self) -> Option<CMBuffer> {
+        self.dequeue_if_data_ready_and_retain()
+    }
+
+    #[inline]
+    fn buffer_count(&self) -> usize {
+        self.get_buffer_count() as usize
+    }
+
+    #[inline]
+    fn is_at_end_of_data(&self) -> bool {
+        CMBufferQueue::is_at_end_of_data(self)
+    }
+
+    #[inline]
+    fn mark_end_of_data(&self) -> Result<(), OSStatus> {
+        CMBufferQueue::mark_end_of_data(self)
+    }
+
+    fn install_wake_trigger(
+        &self,
+        condition: CMBufferQueueTriggerCondition,
+        threshold: usize,
+        waker: Arc<QueueWaker>,
+    ) -> Result<CMBufferQueueTriggerToken, OSStatus> {
+        let closure = move |_| waker.wake();
+        if is_count_condition(condition) {
+            self.install_trigger_with_integer_threshold_closure(condition, threshold as _, Some(closure))
+        } else {
+            self.install_trigger_closure(condition, CMTime::default(), Some(closure))
+        }
+    }
+
+    #[inline]
+    fn remove_wake_trigger(&self, token: CMBufferQueueTriggerToken) {
+        let _ = self.remove_trigger(token);
+    }
+}
+
+impl<T: BufferTiming> TriggeredQueue for BufferQueue<T> {
+    type Buffer = T;
+    type TriggerToken = BufferQueueTriggerToken;
+
+    #[inline]
+    fn enqueue_buffer(&self, buffer: T) -> Result<(), OSStatus> {
+        self.enqueue(buffer)
+    }
+
+    #[inline]
+    fn dequeue_ready_buffer(&self) -> Option<T> {
+        self.dequeue_if_data_ready().ok().flatten()
+    }
+
+    #[inline]
+    fn buffer_count(&self) -> usize {
+        self.get_buffer_count()
+    }
+
+    #[inline]
+    fn is_at_end_of_data(&self) -> bool {
+        BufferQueue::is_at_end_of_data(self)
+    }
+
+    #[inline]
+    fn mark_end_of_data(&self) -> Result<(), OSStatus> {
+        BufferQueue::mark_end_of_data(self)
+    }
+
+    fn install_wake_trigger(
+        &self,
+        condition: CMBufferQueueTriggerCondition,
+        threshold: usize,
+        waker: Arc<QueueWaker>,
+    ) -> Result<BufferQueueTriggerToken, OSStatus> {
+        let closure = move |_| waker.wake();
+        if is_count_condition(condition) {
+            self.install_trigger_with_integer_threshold_closure(condition, threshold, Some(closure))
+        } else {
+            self.install_trigger_closure(condition, CMTime::default(), Some(closure))
+        }
+    }
+
+    #[inline]
+    fn remove_wake_trigger(&self, token: BufferQueueTriggerToken) {
+        let _ = self.remove_trigger(token);
+    }
+}
+
+fn install_wake_triggers<Q: TriggeredQueue>(
+    queue: &Q,
+    conditions: &[(CMBufferQueueTriggerCondition, usize)],
+    waker: &Arc<QueueWaker>,
+) -> Result<Vec<Q::TriggerToken>, OSStatus> {
+    let mut tokens = Vec::with_capacity(conditions.len());
+    for (condition, threshold) in conditions {
+        match queue.install_wake_trigger(*condition, *threshold, waker.clone()) {
+            Ok(token) => tokens.push(token),
+            Err(status) => {
+                tokens.into_iter().for_each(|token| queue.remove_wake_trigger(token));
+                return Err(status);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// Ends once the queue reaches end of data
+pub struct BufferQueueStream<Q: TriggeredQueue> {
+    queue: Arc<Q>,
+    waker: Arc<QueueWaker>,
+    tokens: Vec<Q::TriggerToken>,
+}
+
+impl<Q: TriggeredQueue> BufferQueueStream<Q> {
+    pub fn new(queue: Arc<Q>) -> Result<Self, OSStatus> {
+        let waker = Arc::new(QueueWaker::default());
+        let tokens = install_wake_triggers(
+            &*queue,
+            &[
+                (kCMBufferQueueTrigger_WhenDataBecomesReady, 0),
+                (kCMBufferQueueTrigger_WhenBufferCountBecomesGreaterThan, 0),
+                (kCMBufferQueueTrigger_WhenEndOfDataReached, 0),
+            ],
+            &waker,
+        )?;
+        Ok(Self {
+            queue,
+            waker,
+            tokens,
+        })
+    }
+
+    #[inline]
+    pub fn queue(&self) -> &Arc<Q> {
+        &self.queue
+    }
+}
+
+impl<Q: TriggeredQueue> Stream for BufferQueueStream<Q> {
+    type Item = Q::Buffer;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Q::Buffer>> {
+        if let Some(buffer) = self.queue.dequeue_ready_buffer() {
+            return Poll::Ready(Some(buffer));
+        }
+        if self.queue.is_at_end_of_data() {
+            return Poll::Ready(None);
+        }
+        self.waker.register(cx.waker());
+        // Checks again in case a trigger fired before the waker was registered
+        if let Some(buffer) = self.queue.dequeue_ready_buffer() {
+            return Poll::Ready(Some(buffer));
+        }
+        if self.queue.is_at_end_of_data() {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    }
+}
+
+impl<Q: TriggeredQueue> Drop for BufferQueueStream<Q> {
+    fn drop(&mut self) {
+        let queue = &self.queue;
+        self.tokens.drain(..).for_each(|token| queue.remove_wake_trigger(token));
+    }
+}
+
+// Waits while the queue holds the high water mark or more buffers
+pub struct BufferQueueSink<Q: TriggeredQueue> {
+    queue: Arc<Q>,
+    high_water_mark: usize,
+    waker: Arc<QueueWaker>,
+    tokens: Vec<Q::TriggerToken>,
+}
+
+impl<Q: TriggeredQueue> BufferQueueSink<Q> {
+    pub fn new(queue: Arc<Q>, high_water_mark: usize) -> Result<Self, OSStatus> {
+        let high_water_mark = high_water_mark.max(1);
+        let waker = Arc::new(QueueWaker::default());
+        let tokens = install_wake_triggers(
+            &*queue,
+            &[(kCMBufferQueueTrigger_WhenBufferCountBecomesLessThan, high_water_mark), (kCMBufferQueueTrigger_WhenReset, 0)],
+            &waker,
+        )?;
+        Ok(Self {
+            queue,
+            high_water_mark,
+            waker,
+            tokens,
+        })
+    }
+
+    #[inline]
+    pub fn queue(&self) -> &Arc<Q> {
+        &self.queue
+    }
+
+    #[inline]
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+}
+
+impl<Q: TriggeredQueue> Sink<Q::Buffer> for BufferQueueSink<Q> {
+    type Error = OSStatus;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), OSStatus>> {
+        if self.queue.buffer_count() < self.high_water_mark {
+            return Poll::Ready(Ok(()));
+        }
+        self.waker.register(cx.waker());
+        if self.queue.buffer_count() < self.high_water_mark {
+            return Poll::Ready(Ok(()));
+        }
+        Poll::Pending
+    }
+
+    fn start_send(self: Pin<&mut Self>, buffer: Q::Buffer) -> Result<(), OSStatus> {
+        self.queue.enqueue_buffer(buffer)
+    }
+
+    // Buffers are enqueued synchronously, so there is nothing to flush
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), OSStatus>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), OSStatus>> {
+        Poll::Ready(self.queue.mark_end_of_data())
+    }
+}
+
+impl<Q: TriggeredQueue> Drop for BufferQueueSink<Q> {
+    fn drop(&mut self) {
+        let queue = &self.queue;
+        self.tokens.drain(..).for_each(|token| queue.remove_wake_trigger(token));
+    }
+}
+
+pub fn buffer_queue_channel<Q: TriggeredQueue>(
+    queue: Arc<Q>,
+    high_water_mark: usize,
+) -> Result<(BufferQueueSink<Q>, BufferQueueStream<Q>), OSStatus> {
+    Ok((BufferQueueSink::new(queue.clone(), high_water_mark)?, BufferQueueStream::new(queue)?))
+}
+
+// Fixed capacity FIFOs without triggers, such as CMSimpleQueue
+pub trait ElementQueue {
+    type Element;
+
+    fn enqueue_element(&self, element: Self::Element) -> Result<(), OSStatus>;
+    fn dequeue_element(&self) -> Option<Self::Element>;
+    fn element_count(&self) -> usize;
+    fn element_capacity(&self) -> usize;
+}
+
+impl ElementQueue for CMSimpleQueue {
+    type Element = *const c_void;
+
+    #[inline]
+    fn enqueue_element(&self, element: *const c_void) -> Result<(), OSStatus> {
+        self.enqueue(element)
+    }
+
+    #[inline]
+    fn dequeue_element(&self) -> Option<*const c_void> {
+        let element = self.dequeue();
+        (!element.is_null()).then_some(element)
+    }
+
+    #[inline]
+    fn element_count(&self) -> usize {
+        self.get_count() as usize
+    }
+
+    #[inline]
+    fn element_capacity(&self) -> usize {
+        self.get_capacity() as usize
+    }
+}
+
+// CMSimpleQueue has no end of data, so a simple queue sink reports this once
+// its stream is gone or it was closed. It takes the last code of the block
+// CoreMedia reserves for simple queue errors, which CoreMedia itself leaves
+// unused
+pub const SIMPLE_QUEUE_CHANNEL_CLOSED: OSStatus = -12779;
+
+// The queue has no triggers, so the two halves wake each other
+struct SimpleQueueShared<Q: ElementQueue> {
+    queue: Q,
+    closed: AtomicBool,
+    reader: QueueWaker,
+    writer: QueueWaker,
+}
+
+// CMSimpleQueue is safe with one enqueuing and one dequeuing thread
+unsafe impl Send for SimpleQueueShared<CMSimpleQueue> {}
+unsafe impl Sync for SimpleQueueShared<CMSimpleQueue> {}
+
+pub struct SimpleQueueStream<Q: ElementQueue = CMSimpleQueue> {
+    shared: Arc<SimpleQueueShared<Q>>,
+}
+
+pub struct SimpleQueueSink<Q: ElementQueue = CMSimpleQueue> {
+    shared: Arc<SimpleQueueShared<Q>>,
+}
+
+pub fn simple_queue_channel<Q: ElementQueue>(queue: Q) -> (SimpleQueueSink<Q>, SimpleQueueStream<Q>) {
+    let shared = Arc::new(SimpleQueueShared {
+        queue,
+        closed: AtomicBool::new(false),
+        reader: QueueWaker::default(),
+        writer: QueueWaker::default(),
+    });
+    (
+        SimpleQueueSink {
+            shared: shared.clone(),
+        },
+        SimpleQueueStream {
+            shared,
+        },
+    )
+}
+
+impl<Q: ElementQueue> SimpleQueueShared<Q> {
+    fn dequeue(&self) -> Option<Q::Element> {
+        let element = self.queue.dequeue_element()?;
+        self.writer.wake();
+        Some(element)
+    }
+
+    #[inline]
+    fn is_full(&self) -> bool {
+        self.queue.element_count() >= self.queue.element_capacity()
+    }
+
+    #[inline]
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.reader.wake();
+    }
+}
+
+impl<Q: ElementQueue> Stream for SimpleQueueStream<Q> {
+    type Item = Q::Element;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Q::Element>> {
+        if let Some(element) = self.shared.dequeue() {
+            return Poll::Ready(Some(element));
+        }
+        self.shared.reader.register(cx.waker());
+        if let Some(element) = self.shared.dequeue() {
+            return Poll::Ready(Some(element));
+        }
+        // The sink closes only after its last element was enqueued
+        if self.shared.is_closed() {
+            return Poll::Ready(self.shared.dequeue());
+        }
+        Poll::Pending
+    }
+}
+
+impl<Q: ElementQueue> Drop for SimpleQueueStream<Q> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.shared.writer.wake();
+    }
+}
+
+impl<Q: ElementQueue> Sink<Q::Element> for SimpleQueueSink<Q> {
+    type Error = OSStatus;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), OSStatus>> {
+        if self.shared.is_closed() {
+            return Poll::Ready(Err(SIMPLE_QUEUE_CHANNEL_CLOSED));
+        }
+        if !self.shared.is_full() {
+            return Poll::Ready(Ok(()));
+        }
+        self.shared.writer.register(cx.waker());
+        if self.shared.is_closed() {
+            return Poll::Ready(Err(SIMPLE_QUEUE_CHANNEL_CLOSED));
+        }
+        if !self.shared.is_full() {
+            return Poll::Ready(Ok(()));
+        }
+        Poll::Pending
+    }
+
+    fn start_send(self: Pin<&mut Self>, element: Q::Element) -> Result<(), OSStatus> {
+        if self.shared.is_closed() {
+            return Err(SIMPLE_QUEUE_CHANNEL_CLOSED);
+        }
+        if self.shared.is_full() {
+            return Err(kCMSimpleQueueError_QueueIsFull);
+        }
+        self.shared.queue.enqueue_element(element)?;
+        self.shared.reader.wake();
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), OSStatus>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), OSStatus>> {
+        self.shared.close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<Q: ElementQueue> Drop for SimpleQueueSink<Q> {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, sync::atomic::AtomicUsize, task::Wake};
+
+    use super::*;
+    use crate::{buffer_queue::BufferQueueOrder, test_support::MockBuffer};
+
+    struct MockQueue {
+        elements: Mutex<VecDeque<usize>>,
+        capacity: usize,
+    }
+
+    impl ElementQueue for MockQueue {
+        type Element = usize;
+
+        fn enqueue_element(&self, element: usize) -> Result<(), OSStatus> {
+            self.elements.lock().unwrap().push_back(element);
+            Ok(())
+        }
+
+        fn dequeue_element(&self) -> Option<usize> {
+            self.elements.lock().unwrap().pop_front()
+        }
+
+        fn element_count(&self) -> usize {
+            self.elements.lock().unwrap().len()
+        }
+
+        fn element_capacity(&self) -> usize {
+            self.capacity
+        }
+    }
+
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counting_waker() -> (Arc<CountingWaker>, Waker) {
+        let counter = Arc::new(CountingWaker::default());
+        (counter.clone(), Waker::from(counter))
+    }
+
+    fn wakes(counter: &CountingWaker) -> usize {
+        counter.0.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn stream_becomes_ready_on_enqueue() {
+        let queue = Arc::new(BufferQueue::new(0, BufferQueueOrder::Unsorted));
+        let (mut sink, mut stream) = buffer_queue_channel(queue, 4).unwrap();
+        let (counter, waker) = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Ready(Ok(())));
+        Pin::new(&mut sink).start_send(MockBuffer::new(0)).unwrap();
+        assert!(wakes(&counter) > 0);
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(MockBuffer::new(0))));
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+    }
+
+    #[test]
+    fn sink_waits_at_high_water_mark() {
+        let queue = Arc::new(BufferQueue::new(0, BufferQueueOrder::Unsorted));
+        let (mut sink, mut stream) = buffer_queue_channel(queue.clone(), 2).unwrap();
+        let (counter, waker) = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        for value in 0..2 {
+            assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Ready(Ok(())));
+            Pin::new(&mut sink).start_send(MockBuffer::new(value)).unwrap();
+        }
+        assert_eq!(queue.get_buffer_count(), 2);
+        assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Pending);
+
+        let before = wakes(&counter);
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(MockBuffer::new(0))));
+        assert!(wakes(&counter) > before);
+        assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn close_ends_stream() {
+        let queue = Arc::new(BufferQueue::new(0, BufferQueueOrder::Unsorted));
+        let (mut sink, mut stream) = buffer_queue_channel(queue, 4).unwrap();
+        let (counter, waker) = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        Pin::new(&mut sink).start_send(MockBuffer::new(0)).unwrap();
+        assert_eq!(Pin::new(&mut sink).poll_close(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(MockBuffer::new(0))));
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+        assert_eq!(Pin::new(&mut sink).start_send(MockBuffer::new(1)), Err(kCMBufferQueueError_EnqueueAfterEndOfData));
+
+        let queue = Arc::new(BufferQueue::<MockBuffer>::new(0, BufferQueueOrder::Unsorted));
+        let (mut sink, mut stream) = buffer_queue_channel(queue, 4).unwrap();
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+        let before = wakes(&counter);
+        assert_eq!(Pin::new(&mut sink).poll_close(&mut cx), Poll::Ready(Ok(())));
+        assert!(wakes(&counter) > before);
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn simple_queue_close_and_drop() {
+        let queue = MockQueue {
+            elements: Mutex::new(VecDeque::new()),
+            capacity: 2,
+        };
+        let (mut sink, mut stream) = simple_queue_channel(queue);
+        let (counter, waker) = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+        for element in 0..2 {
+            assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Ready(Ok(())));
+            Pin::new(&mut sink).start_send(element).unwrap();
+        }
+        assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Pending);
+        assert_eq!(Pin::new(&mut sink).start_send(2), Err(kCMSimpleQueueError_QueueIsFull));
+
+        let before = wakes(&counter);
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(0)));
+        assert!(wakes(&counter) > before);
+        assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Ready(Ok(())));
+
+        // Elements sent before closing are still delivered
+        drop(sink);
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(1)));
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+
+        let queue = MockQueue {
+            elements: Mutex::new(VecDeque::new()),
+            capacity: 1,
+        };
+        let (mut sink, stream) = simple_queue_channel(queue);
+        Pin::new(&mut sink).start_send(0).unwrap();
+        assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Pending);
+        let before = wakes(&counter);
+        drop(stream);
+        assert!(wakes(&counter) > before);
+        assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Ready(Err(SIMPLE_QUEUE_CHANNEL_CLOSED)));
+
+        // Nothing is queued for a stream that is gone
+        let queue = MockQueue {
+            elements: Mutex::new(VecDeque::new()),
+            capacity: 2,
+        };
+        let (mut sink, stream) = simple_queue_channel(queue);
+        Pin::new(&mut sink).start_send(0).unwrap();
+        drop(stream);
+        assert_eq!(Pin::new(&mut sink).start_send(1), Err(SIMPLE_QUEUE_CHANNEL_CLOSED));
+        assert_eq!(sink.shared.queue.element_count(), 1);
+    }
+}
@@ -0,0 +1,505 @@
+use std::{convert::TryInto, ptr::null_mut};
+
+use core_foundation::{
+    base::{CFType, OSStatus, TCFType},
+    boolean::CFBoolean,
+    data::CFData,
+    dictionary::{CFDictionary, CFMutableDictionary, CFMutableDictionaryRef},
+    number::CFNumber,
+    string::{CFString, CFStringRef},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attachment::{kCMAttachmentMode_ShouldNotPropagate, CMAttachmentBearerRef, CMAttachmentBearerSubClass, CMGetAttachment},
+    sample_buffer::{
+        kCMHEVCTemporalLevelInfoKey_ConstraintIndicatorFlags, kCMHEVCTemporalLevelInfoKey_LevelIndex,
+        kCMHEVCTemporalLevelInfoKey_ProfileCompatibilityFlags, kCMHEVCTemporalLevelInfoKey_ProfileIndex, kCMHEVCTemporalLevelInfoKey_ProfileSpace,
+        kCMHEVCTemporalLevelInfoKey_TemporalLevel, kCMHEVCTemporalLevelInfoKey_TierFlag,
+        kCMSampleAttachmentKey_AudioIndependentSampleDecoderRefreshCount, kCMSampleAttachmentKey_DependsOnOthers,
+        kCMSampleAttachmentKey_DisplayImmediately, kCMSampleAttachmentKey_DoNotDisplay, kCMSampleAttachmentKey_EarlierDisplayTimesAllowed,
+        kCMSampleAttachmentKey_HEVCStepwiseTemporalSubLayerAccess, kCMSampleAttachmentKey_HEVCSyncSampleNALUnitType,
+        kCMSampleAttachmentKey_HEVCTemporalLevelInfo, kCMSampleAttachmentKey_HEVCTemporalSubLayerAccess, kCMSampleAttachmentKey_HasRedundantCoding,
+        kCMSampleAttachmentKey_IsDependedOnByOthers, kCMSampleAttachmentKey_NotSync, kCMSampleAttachmentKey_PartialSync,
+        kCMSampleBufferAttachmentKey_DrainAfterDecoding, kCMSampleBufferAttachmentKey_ResetDecoderBeforeDecoding,
+        kCMSampleBufferError_InvalidSampleData, kCMSampleBufferError_SampleIndexOutOfRange, CMSampleBuffer,
+    },
+};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct HEVCTemporalLevelInfo {
+    pub temporal_level: i32,
+    pub profile_space: i32,
+    pub tier_flag: bool,
+    pub profile_index: i32,
+    pub profile_compatibility_flags: [u8; 4],
+    pub constraint_indicator_flags: [u8; 6],
+    pub level_index: i32,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SampleAttachments {
+    pub not_sync: bool,
+    pub partial_sync: bool,
+    pub has_redundant_coding: Option<bool>,
+    pub is_depended_on_by_others: Option<bool>,
+    pub depends_on_others: Option<bool>,
+    pub earlier_display_times_allowed: Option<bool>,
+    pub display_immediately: bool,
+    pub do_not_display: bool,
+    pub hevc_temporal_level_info: Option<HEVCTemporalLevelInfo>,
+    pub hevc_temporal_sub_layer_access: bool,
+    pub hevc_stepwise_temporal_sub_layer_access: bool,
+    pub hevc_sync_sample_nal_unit_type: Option<i32>,
+    pub audio_independent_sample_decoder_refresh_count: Option<i32>,
+}
+
+impl HEVCTemporalLevelInfo {
+    pub fn from_dictionary(dictionary: &CFDictionary<CFString, CFType>) -> Option<Self> {
+        unsafe {
+            Some(Self {
+                temporal_level: find_i32(dictionary, kCMHEVCTemporalLevelInfoKey_TemporalLevel)?,
+                profile_space: find_i32(dictionary, kCMHEVCTemporalLevelInfoKey_ProfileSpace).unwrap_or_default(),
+                tier_flag: find_bool(dictionary, kCMHEVCTemporalLevelInfoKey_TierFlag).unwrap_or_default(),
+                profile_index: find_i32(dictionary, kCMHEVCTemporalLevelInfoKey_ProfileIndex).unwrap_or_default(),
+                profile_compatibility_flags: find_bytes(dictionary, kCMHEVCTemporalLevelInfoKey_ProfileCompatibilityFlags).unwrap_or_default(),
+                constraint_indicator_flags: find_bytes(dictionary, kCMHEVCTemporalLevelInfoKey_ConstraintIndicatorFlags).unwrap_or_default(),
+                level_index: find_i32(dictionary, kCMHEVCTemporalLevelInfoKey_LevelIndex).unwrap_or_default(),
+            })
+        }
+    }
+
+    pub fn to_dictionary(&self) -> CFDictionary<CFString, CFType> {
+        let mut dictionary = CFMutableDictionary::<CFString, CFType>::new();
+        unsafe {
+            dictionary.set(key(kCMHEVCTemporalLevelInfoKey_TemporalLevel), CFNumber::from(self.temporal_level).as_CFType());
+            dictionary.set(key(kCMHEVCTemporalLevelInfoKey_ProfileSpace), CFNumber::from(self.profile_space).as_CFType());
+            dictionary.set(key(kCMHEVCTemporalLevelInfoKey_TierFlag), CFBoolean::from(self.tier_flag).as_CFType());
+            dictionary.set(key(kCMHEVCTemporalLevelInfoKey_ProfileIndex), CFNumber::from(self.profile_index).as_CFType());
+            dictionary
+                .set(key(kCMHEVCTemporalLevelInfoKey_ProfileCompatibilityFlags), CFData::from_buffer(&self.profile_compatibility_flags).as_CFType());
+            dictionary
+                .set(key(kCMHEVCTemporalLevelInfoKey_ConstraintIndicatorFlags), CFData::from_buffer(&self.constraint_indicator_flags).as_CFType());
+            dictionary.set(key(kCMHEVCTemporalLevelInfoKey_LevelIndex), CFNumber::from(self.level_index).as_CFType());
+        }
+        dictionary.to_immutable()
+    }
+}
+
+impl SampleAttachments {
+    #[inline]
+    pub fn is_sync(&self) -> bool {
+        !self.not_sync
+    }
+
+    #[inline]
+    pub fn is_keyframe(&self) -> bool {
+        !self.not_sync && !self.partial_sync
+    }
+
+    #[inline]
+    pub fn is_droppable(&self) -> bool {
+        self.is_depended_on_by_others == Some(false)
+    }
+
+    #[inline]
+    pub fn is_displayable(&self) -> bool {
+        !self.do_not_display
+    }
+
+    pub fn from_dictionary(dictionary: &CFDictionary<CFString, CFType>) -> Self {
+        Self::from_entries(AttachmentKey::ALL.iter().filter_map(|&attachment_key| {
+            let value = dictionary.find(unsafe { key(attachment_key.key_ref()) })?;
+            let value = if let Some(value) = value.downcast::<CFBoolean>() {
+                AttachmentValue::Boolean(value.into())
+            } else if let Some(value) = value.downcast::<CFNumber>() {
+                AttachmentValue::Integer(value.to_i32()?)
+            } else {
+                let value = value.downcast::<CFDictionary>()?;
+                let info = unsafe { CFDictionary::wrap_under_get_rule(value.as_concrete_TypeRef()) };
+                AttachmentValue::TemporalLevelInfo(HEVCTemporalLevelInfo::from_dictionary(&info)?)
+            };
+            Some((attachment_key, value))
+        }))
+    }
+
+    pub fn to_dictionary(&self) -> CFDictionary<CFString, CFType> {
+        let mut dictionary = CFMutableDictionary::<CFString, CFType>::new();
+        self.write_to_dictionary(&mut dictionary);
+        dictionary.to_immutable()
+    }
+
+    pub fn write_to_dictionary(&self, dictionary: &mut CFMutableDictionary<CFString, CFType>) {
+        let entries = self.entries();
+        for &attachment_key in AttachmentKey::ALL.iter() {
+            let value = entries.iter().find(|(entry_key, _)| *entry_key == attachment_key).map(|(_, value)| match value {
+                AttachmentValue::Boolean(value) => CFBoolean::from(*value).as_CFType(),
+                AttachmentValue::Integer(value) => CFNumber::from(*value).as_CFType(),
+                AttachmentValue::TemporalLevelInfo(info) => info.to_dictionary().as_CFType(),
+            });
+            unsafe { set_optional(dictionary, attachment_key.key_ref(), value) };
+        }
+    }
+
+    // The attachments present in a sample's dictionary. Flags that default to false
+    // are only present when set, as CoreMedia writes them
+    fn entries(&self) -> Vec<(AttachmentKey, AttachmentValue)> {
+        let flags = [
+            (AttachmentKey::NotSync, self.not_sync),
+            (AttachmentKey::PartialSync, self.partial_sync),
+            (AttachmentKey::DisplayImmediately, self.display_immediately),
+            (AttachmentKey::DoNotDisplay, self.do_not_display),
+            (AttachmentKey::HEVCTemporalSubLayerAccess, self.hevc_temporal_sub_layer_access),
+            (AttachmentKey::HEVCStepwiseTemporalSubLayerAccess, self.hevc_stepwise_temporal_sub_layer_access),
+        ];
+        let optional_flags = [
+            (AttachmentKey::HasRedundantCoding, self.has_redundant_coding),
+            (AttachmentKey::IsDependedOnByOthers, self.is_depended_on_by_others),
+            (AttachmentKey::DependsOnOthers, self.depends_on_others),
+            (AttachmentKey::EarlierDisplayTimesAllowed, self.earlier_display_times_allowed),
+        ];
+        let integers = [
+            (AttachmentKey::HEVCSyncSampleNALUnitType, self.hevc_sync_sample_nal_unit_type),
+            (AttachmentKey::AudioIndependentSampleDecoderRefreshCount, self.audio_independent_sample_decoder_refresh_count),
+        ];
+        let mut entries: Vec<_> = flags.iter().filter(|(_, value)| *value).map(|&(key, value)| (key, AttachmentValue::Boolean(value))).collect();
+        entries.extend(optional_flags.iter().filter_map(|&(key, value)| Some((key, AttachmentValue::Boolean(value?)))));
+        entries.extend(self.hevc_temporal_level_info.map(|info| (AttachmentKey::HEVCTemporalLevelInfo, AttachmentValue::TemporalLevelInfo(info))));
+        entries.extend(integers.iter().filter_map(|&(key, value)| Some((key, AttachmentValue::Integer(value?)))));
+        entries
+    }
+
+    // Values of the wrong type are ignored, as a missing attachment would be
+    fn from_entries<I>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (AttachmentKey, AttachmentValue)>,
+    {
+        let mut attachments = Self::default();
+        for entry in entries {
+            match entry {
+                (AttachmentKey::NotSync, AttachmentValue::Boolean(value)) => attachments.not_sync = value,
+                (AttachmentKey::PartialSync, AttachmentValue::Boolean(value)) => attachments.partial_sync = value,
+                (AttachmentKey::HasRedundantCoding, AttachmentValue::Boolean(value)) => attachments.has_redundant_coding = Some(value),
+                (AttachmentKey::IsDependedOnByOthers, AttachmentValue::Boolean(value)) => attachments.is_depended_on_by_others = Some(value),
+                (AttachmentKey::DependsOnOthers, AttachmentValue::Boolean(value)) => attachments.depends_on_others = Some(value),
+                (AttachmentKey::EarlierDisplayTimesAllowed, AttachmentValue::Boolean(value)) => {
+                    attachments.earlier_display_times_allowed = Some(value)
+                }
+                (AttachmentKey::DisplayImmediately, AttachmentValue::Boolean(value)) => attachments.display_immediately = value,
+                (AttachmentKey::DoNotDisplay, AttachmentValue::Boolean(value)) => attachments.do_not_display = value,
+                (AttachmentKey::HEVCTemporalLevelInfo, AttachmentValue::TemporalLevelInfo(info)) => attachments.hevc_temporal_level_info = Some(info),
+                (AttachmentKey::HEVCTemporalSubLayerAccess, AttachmentValue::Boolean(value)) => attachments.hevc_temporal_sub_layer_access = value,
+                (AttachmentKey::HEVCStepwiseTemporalSubLayerAccess, AttachmentValue::Boolean(value)) => {
+                    attachments.hevc_stepwise_temporal_sub_layer_access = value
+                }
+                (AttachmentKey::HEVCSyncSampleNALUnitType, AttachmentValue::Integer(value)) => {
+                    attachments.hevc_sync_sample_nal_unit_type = Some(value)
+                }
+                (AttachmentKey::AudioIndependentSampleDecoderRefreshCount, AttachmentValue::Integer(value)) => {
+                    attachments.audio_independent_sample_decoder_refresh_count = Some(value)
+                }
+                _ => {}
+            }
+        }
+        attachments
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum AttachmentKey {
+    NotSync,
+    PartialSync,
+    HasRedundantCoding,
+    IsDependedOnByOthers,
+    DependsOnOthers,
+    EarlierDisplayTimesAllowed,
+    DisplayImmediately,
+    DoNotDisplay,
+    HEVCTemporalLevelInfo,
+    HEVCTemporalSubLayerAccess,
+    HEVCStepwiseTemporalSubLayerAccess,
+    HEVCSyncSampleNALUnitType,
+    AudioIndependentSampleDecoderRefreshCount,
+}
+
+impl AttachmentKey {
+    const ALL: [AttachmentKey; 13] = [
+        AttachmentKey::NotSync,
+        AttachmentKey::PartialSync,
+        AttachmentKey::HasRedundantCoding,
+        AttachmentKey::IsDependedOnByOthers,
+        AttachmentKey::DependsOnOthers,
+        AttachmentKey::EarlierDisplayTimesAllowed,
+        AttachmentKey::DisplayImmediately,
+        AttachmentKey::DoNotDisplay,
+        AttachmentKey::HEVCTemporalLevelInfo,
+        AttachmentKey::HEVCTemporalSubLayerAccess,
+        AttachmentKey::HEVCStepwiseTemporalSubLayerAccess,
+        AttachmentKey::HEVCSyncSampleNALUnitType,
+        AttachmentKey::AudioIndependentSampleDecoderRefreshCount,
+    ];
+
+    unsafe fn key_ref(self) -> CFStringRef {
+        match self {
+            AttachmentKey::NotSync => kCMSampleAttachmentKey_NotSync,
+            AttachmentKey::PartialSync => kCMSampleAttachmentKey_PartialSync,
+            AttachmentKey::HasRedundantCoding => kCMSampleAttachmentKey_HasRedundantCoding,
+            AttachmentKey::IsDependedOnByOthers => kCMSampleAttachmentKey_IsDependedOnByOthers,
+            AttachmentKey::DependsOnOthers => kCMSampleAttachmentKey_DependsOnOthers,
+            AttachmentKey::EarlierDisplayTimesAllowed => kCMSampleAttachmentKey_EarlierDisplayTimesAllowed,
+            AttachmentKey::DisplayImmediately => kCMSampleAttachmentKey_DisplayImmediately,
+            AttachmentKey::DoNotDisplay => kCMSampleAttachmentKey_DoNotDisplay,
+            AttachmentKey::HEVCTemporalLevelInfo => kCMSampleAttachmentKey_HEVCTemporalLevelInfo,
+            AttachmentKey::HEVCTemporalSubLayerAccess => kCMSampleAttachmentKey_HEVCTemporalSubLayerAccess,
+            AttachmentKey::HEVCStepwiseTemporalSubLayerAccess => kCMSampleAttachmentKey_HEVCStepwiseTemporalSubLayerAccess,
+            AttachmentKey::HEVCSyncSampleNALUnitType => kCMSampleAttachmentKey_HEVCSyncSampleNALUnitType,
+            AttachmentKey::AudioIndependentSampleDecoderRefreshCount => kCMSampleAttachmentKey_AudioIndependentSampleDecoderRefreshCount,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum AttachmentValue {
+    Boolean(bool),
+    Integer(i32),
+    TemporalLevelInfo(HEVCTemporalLevelInfo),
+}
+
+impl CMSampleBuffer {
+    pub fn get_sample_attachments(&self) -> Vec<SampleAttachments> {
+        self.get_sample_attachments_array(false)
+            .map(|attachments| attachments.iter().map(|attachment| SampleAttachments::from_dictionary(&attachment)).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn get_sample_attachments_at(&self, sample_index: usize) -> SampleAttachments {
+        self.get_sample_attachments_array(false)
+            .and_then(|attachments| attachments.get(sample_index as isize).map(|attachment| SampleAttachments::from_dictionary(&attachment)))
+            .unwrap_or_default()
+    }
+
+    pub fn set_sample_attachments_at(&self, sample_index: usize, attachments: &SampleAttachments) -> Result<(), OSStatus> {
+        let array = self.get_sample_attachments_array(true).ok_or(kCMSampleBufferError_InvalidSampleData)?;
+        let attachment = array.get(sample_index as isize).ok_or(kCMSampleBufferError_SampleIndexOutOfRange)?;
+        // The dictionaries returned with create_if_necessary are mutable and owned by
+        // the sample buffer
+        let mut dictionary: CFMutableDictionary<CFString, CFType> =
+            unsafe { CFMutableDictionary::wrap_under_get_rule(attachment.as_concrete_TypeRef() as CFMutableDictionaryRef) };
+        attachments.write_to_dictionary(&mut dictionary);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_keyframe(&self) -> bool {
+        self.get_sample_attachments_at(0).is_keyframe()
+    }
+
+    #[inline]
+    pub fn is_droppable(&self) -> bool {
+        self.get_sample_attachments_at(0).is_droppable()
+    }
+
+    #[inline]
+    pub fn is_displayable(&self) -> bool {
+        self.get_sample_attachments_at(0).is_displayable()
+    }
+
+    // Decoder control flags are attached to the sample buffer rather than to its
+    // samples
+    #[inline]
+    pub fn get_reset_decoder_before_decoding(&self) -> bool {
+        unsafe { self.get_buffer_flag(kCMSampleBufferAttachmentKey_ResetDecoderBeforeDecoding) }
+    }
+
+    #[inline]
+    pub fn set_reset_decoder_before_decoding(&self, reset: bool) {
+        unsafe { self.set_buffer_flag(kCMSampleBufferAttachmentKey_ResetDecoderBeforeDecoding, reset) }
+    }
+
+    #[inline]
+    pub fn get_drain_after_decoding(&self) -> bool {
+        unsafe { self.get_buffer_flag(kCMSampleBufferAttachmentKey_DrainAfterDecoding) }
+    }
+
+    #[inline]
+    pub fn set_drain_after_decoding(&self, drain: bool) {
+        unsafe { self.set_buffer_flag(kCMSampleBufferAttachmentKey_DrainAfterDecoding, drain) }
+    }
+
+    unsafe fn get_buffer_flag(&self, key_ref: CFStringRef) -> bool {
+        let value = CMGetAttachment(self.as_concrete_TypeRef() as CMAttachmentBearerRef, key_ref, null_mut());
+        if value.is_null() {
+            return false;
+        }
+        CFType::wrap_under_get_rule(value).downcast::<CFBoolean>().is_some_and(bool::from)
+    }
+
+    unsafe fn set_buffer_flag(&self, key_ref: CFStringRef, value: bool) {
+        let bearer = self.as_CMAttachmentBearer();
+        if value {
+            bearer.set_attachment(&key(key_ref), &CFBoolean::true_value().as_CFType(), kCMAttachmentMode_ShouldNotPropagate);
+        } else {
+            bearer.remove_attachment(&key(key_ref));
+        }
+    }
+}
+
+#[inline]
+unsafe fn key(key: CFStringRef) -> CFString {
+    CFString::wrap_under_get_rule(key)
+}
+
+unsafe fn find_bool(dictionary: &CFDictionary<CFString, CFType>, key_ref: CFStringRef) -> Option<bool> {
+    dictionary.find(key(key_ref)).and_then(|value| value.downcast::<CFBoolean>()).map(bool::from)
+}
+
+unsafe fn find_i32(dictionary: &CFDictionary<CFString, CFType>, key_ref: CFStringRef) -> Option<i32> {
+    dictionary.find(key(key_ref)).and_then(|value| value.downcast::<CFNumber>()).and_then(|value| value.to_i32())
+}
+
+unsafe fn find_bytes<const N: usize>(dictionary: &CFDictionary<CFString, CFType>, key_ref: CFStringRef) -> Option<[u8; N]> {
+    dictionary.find(key(key_ref)).and_then(|value| value.downcast::<CFData>()).and_then(|value| value.bytes().try_into().ok())
+}
+
+unsafe fn set_optional(dictionary: &mut CFMutableDictionary<CFString, CFType>, key_ref: CFStringRef, value: Option<CFType>) {
+    match value {
+        Some(value) => dictionary.set(key(key_ref), value),
+        None => dictionary.remove(key(key_ref)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_classification() {
+        let idr = SampleAttachments {
+            depends_on_others: Some(false),
+            ..Default::default()
+        };
+        assert!(idr.is_sync() && idr.is_keyframe() && !idr.is_droppable() && idr.is_displayable());
+
+        let open_gop_i = SampleAttachments {
+            partial_sync: true,
+            ..Default::default()
+        };
+        assert!(open_gop_i.is_sync() && !open_gop_i.is_keyframe());
+
+        let b_frame = SampleAttachments {
+            not_sync: true,
+            is_depended_on_by_others: Some(false),
+            depends_on_others: Some(true),
+            ..Default::default()
+        };
+        assert!(!b_frame.is_sync() && !b_frame.is_keyframe() && b_frame.is_droppable());
+
+        let hidden = SampleAttachments {
+            not_sync: true,
+            do_not_display: true,
+            ..Default::default()
+        };
+        assert!(!hidden.is_displayable() && !hidden.is_droppable());
+    }
+
+    #[test]
+    fn attachment_entries() {
+        assert!(SampleAttachments::default().entries().is_empty());
+
+        let b_frame = SampleAttachments {
+            not_sync: true,
+            is_depended_on_by_others: Some(false),
+            depends_on_others: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            b_frame.entries(),
+            [
+                (AttachmentKey::NotSync, AttachmentValue::Boolean(true)),
+                (AttachmentKey::IsDependedOnByOthers, AttachmentValue::Boolean(false)),
+                (AttachmentKey::DependsOnOthers, AttachmentValue::Boolean(true)),
+            ]
+        );
+
+        let attachments = SampleAttachments {
+            not_sync: true,
+            partial_sync: true,
+            has_redundant_coding: Some(false),
+            is_depended_on_by_others: Some(true),
+            depends_on_others: Some(false),
+            earlier_display_times_allowed: Some(true),
+            display_immediately: true,
+            do_not_display: true,
+            hevc_temporal_level_info: Some(HEVCTemporalLevelInfo {
+                temporal_level: 2,
+                tier_flag: true,
+                profile_compatibility_flags: [0x60, 0, 0, 0],
+                level_index: 123,
+                ..Default::default()
+            }),
+            hevc_temporal_sub_layer_access: true,
+            hevc_stepwise_temporal_sub_layer_access: true,
+            hevc_sync_sample_nal_unit_type: Some(19),
+            audio_independent_sample_decoder_refresh_count: Some(1),
+        };
+        let entries = attachments.entries();
+        assert_eq!(entries.len(), AttachmentKey::ALL.len());
+        assert!(AttachmentKey::ALL.iter().all(|attachment_key| entries.iter().any(|(entry_key, _)| entry_key == attachment_key)));
+        assert_eq!(SampleAttachments::from_entries(entries), attachments);
+
+        let mismatched = [
+            (AttachmentKey::NotSync, AttachmentValue::Integer(1)),
+            (AttachmentKey::HEVCSyncSampleNALUnitType, AttachmentValue::Boolean(true)),
+            (AttachmentKey::DoNotDisplay, AttachmentValue::Boolean(true)),
+        ];
+        assert_eq!(
+            SampleAttachments::from_entries(mismatched),
+            SampleAttachments {
+                do_not_display: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let attachments = SampleAttachments {
+            partial_sync: true,
+            depends_on_others: Some(false),
+            hevc_temporal_level_info: Some(HEVCTemporalLevelInfo {
+                temporal_level: 1,
+                constraint_indicator_flags: [0x90, 0, 0, 0, 0, 0],
+                ..Default::default()
+            }),
+            audio_independent_sample_decoder_refresh_count: Some(2),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&attachments).unwrap();
+        assert_eq!(serde_json::from_str::<SampleAttachments>(&json).unwrap(), attachments);
+        let json = serde_json::to_string(&SampleAttachments::default()).unwrap();
+        assert_eq!(serde_json::from_str::<SampleAttachments>(&json).unwrap(), SampleAttachments::default());
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    #[test]
+    fn dictionary_round_trip() {
+        let attachments = SampleAttachments {
+            not_sync: true,
+            is_depended_on_by_others: Some(false),
+            earlier_display_times_allowed: Some(true),
+            hevc_temporal_level_info: Some(HEVCTemporalLevelInfo {
+                temporal_level: 2,
+                profile_compatibility_flags: [0x60, 0, 0, 0],
+                ..Default::default()
+            }),
+            hevc_sync_sample_nal_unit_type: Some(19),
+            ..Default::default()
+        };
+        assert_eq!(SampleAttachments::from_dictionary(&attachments.to_dictionary()), attachments);
+        assert_eq!(SampleAttachments::from_dictionary(&SampleAttachments::default().to_dictionary()), SampleAttachments::default());
+    }
+}
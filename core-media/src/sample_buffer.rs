@@ -1,7 +1,10 @@
 use std::ptr::{null, null_mut};
 
 use block::{Block, ConcreteBlock};
-use core_audio_types::base_types::{AudioBufferList, AudioStreamPacketDescription};
+use core_audio_types::{
+    audio_buffer_list::AudioBufferListBuf,
+    base_types::{AudioBufferList, AudioStreamPacketDescription},
+};
 use core_foundation::{
     array::{CFArray, CFArrayRef},
     base::{kCFAllocatorDefault, Boolean, CFAllocatorRef, CFRange, CFType, CFTypeID, OSStatus, TCFType},
@@ -14,6 +17,7 @@ use libc::{c_void, size_t};
 use objc2::encode::{Encoding, RefEncode};
 
 use crate::{
+    attachment::CMAttachmentBearerSubClass,
     base::CMItemCount,
     block_buffer::{CMBlockBuffer, CMBlockBufferRef},
     format_description::{CMFormatDescription, CMFormatDescriptionRef, CMVideoFormatDescription, CMVideoFormatDescriptionRef},
@@ -341,6 +345,8 @@ declare_TCFType! {
 impl_TCFType!(CMSampleBuffer, CMSampleBufferRef, CMSampleBufferGetTypeID);
 impl_CFTypeDescription!(CMSampleBuffer);
 
+impl CMAttachmentBearerSubClass for CMSampleBuffer {}
+
 impl CMSampleBuffer {
     #[inline]
     pub unsafe fn new(
@@ -721,6 +727,34 @@ impl CMSampleBuffer {
         }
     }
 
+    #[inline]
+    pub fn set_data_buffer_from_audio_buffer_list_buf(&self, flags: u32, buffer_list: &AudioBufferListBuf) -> Result<(), OSStatus> {
+        let status = unsafe {
+            CMSampleBufferSetDataBufferFromAudioBufferList(
+                self.as_concrete_TypeRef(),
+                kCFAllocatorDefault,
+                kCFAllocatorDefault,
+                flags,
+                buffer_list.as_ptr(),
+            )
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status)
+        }
+    }
+
+    #[inline]
+    pub fn copy_pcm_data_into_audio_buffer_list(&self, frame_offset: i32, num_frames: i32, buffer_list: &mut AudioBufferListBuf) -> Result<(), OSStatus> {
+        let status = unsafe { CMSampleBufferCopyPCMDataIntoAudioBufferList(self.as_concrete_TypeRef(), frame_offset, num_frames, buffer_list.as_mut_ptr()) };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(status)
+        }
+    }
+
     #[inline]
     pub fn set_data_ready(&self) -> Result<(), OSStatus> {
         let status = unsafe { CMSampleBufferSetDataReady(self.as_concrete_TypeRef()) };
@@ -0,0 +1,88 @@
+use core_audio_types::sample_rate_converter::SampleRateConverter;
+
+use crate::time::{is_numeric, make_time, make_time_saturating, value_in_timescale, CMTime, CMTimeScale};
+
+const FALLBACK_TIMESCALE: CMTimeScale = 1_000_000_000;
+
+pub trait SampleRateConverterTiming {
+    fn output_timescale(&self) -> CMTimeScale;
+    fn output_presentation_time(&self, input_presentation_time: CMTime) -> CMTime;
+    fn output_duration(&self, frame_count: usize) -> CMTime;
+}
+
+impl SampleRateConverterTiming for SampleRateConverter {
+    fn output_timescale(&self) -> CMTimeScale {
+        let sample_rate = self.output_sample_rate();
+        if sample_rate.fract() == 0.0 && sample_rate <= CMTimeScale::MAX as f64 {
+            sample_rate as CMTimeScale
+        } else {
+            FALLBACK_TIMESCALE
+        }
+    }
+
+    // Same mapping as output_time_stamp, applied to presentation times
+    fn output_presentation_time(&self, input_presentation_time: CMTime) -> CMTime {
+        if !is_numeric(&input_presentation_time) {
+            return input_presentation_time;
+        }
+        let timescale = self.output_timescale();
+        let offset = self.output_time_offset() * timescale as f64 / self.output_sample_rate();
+        let mut time = make_time_saturating(value_in_timescale(&input_presentation_time, timescale) + offset.round() as i128, timescale);
+        time.epoch = input_presentation_time.epoch;
+        time
+    }
+
+    fn output_duration(&self, frame_count: usize) -> CMTime {
+        let timescale = self.output_timescale();
+        make_time((frame_count as f64 * timescale as f64 / self.output_sample_rate()).round() as i64, timescale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core_audio_types::{
+        base_types::{AudioStreamBasicDescription, FillOutASBDForLPCM},
+        sample_rate_converter::SampleRateConverterQuality,
+    };
+
+    use super::*;
+
+    fn converter(input_sample_rate: f64, output_sample_rate: f64) -> SampleRateConverter {
+        let format = |sample_rate| {
+            let mut asbd = AudioStreamBasicDescription::default();
+            FillOutASBDForLPCM(&mut asbd, sample_rate, 1, 32, 32, true, false, false);
+            asbd
+        };
+        SampleRateConverter::new(&format(input_sample_rate), &format(output_sample_rate), SampleRateConverterQuality::default()).unwrap()
+    }
+
+    #[test]
+    fn output_presentation_times_advance_by_output_frames() {
+        let mut converter = converter(44100.0, 48000.0);
+        assert_eq!(converter.output_timescale(), 48000);
+        // Input presentation times start one second in
+        let mut input_frames = 44100;
+        let mut expected = converter.output_presentation_time(make_time(input_frames, 44100));
+        assert_eq!(expected, make_time(48000, 48000));
+        let mut output = vec![0; 4 * 2048];
+        for chunk_frames in [1024, 441, 7, 1500, 1024] {
+            let frames = converter.process(&[&vec![0; 4 * chunk_frames]], &mut [&mut output]).unwrap();
+            input_frames += chunk_frames as i64;
+            let duration = converter.output_duration(frames);
+            assert_eq!(duration, make_time(frames as i64, 48000));
+            expected.value += duration.value;
+            assert_eq!(converter.output_presentation_time(make_time(input_frames, 44100)), expected);
+        }
+        assert_eq!(expected.value - 48000, converter.output_frame_count() as i64);
+
+        // Non-numeric times pass through unchanged
+        assert_eq!(converter.output_presentation_time(CMTime::default()), CMTime::default());
+    }
+
+    #[test]
+    fn fractional_rates_use_the_fallback_timescale() {
+        let converter = converter(48000.0, 48000.0 / 1.001);
+        assert_eq!(converter.output_timescale(), FALLBACK_TIMESCALE);
+        assert_eq!(converter.output_duration(48), make_time(1_001_000, FALLBACK_TIMESCALE));
+    }
+}
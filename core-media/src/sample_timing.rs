@@ -0,0 +1,339 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use core_foundation::base::OSStatus;
+
+use crate::{
+    sample_buffer::{kCMSampleBufferError_SampleTimingInfoInvalid, CMSampleTimingInfo},
+    time::{convert_timescale, is_numeric, make_time, CMTimeScale},
+};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SampleTimingIssue {
+    MissingPresentationTime { index: usize },
+    MissingDecodeTime { index: usize },
+    MissingDuration { index: usize },
+    NonMonotonicDecodeTime { index: usize },
+    DuplicatePresentationTime { index: usize, first_index: usize },
+}
+
+impl Display for SampleTimingIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SampleTimingIssue::MissingPresentationTime {
+                index,
+            } => write!(f, "sample {} has no presentation time", index),
+            SampleTimingIssue::MissingDecodeTime {
+                index,
+            } => write!(f, "sample {} has no decode time", index),
+            SampleTimingIssue::MissingDuration {
+                index,
+            } => write!(f, "sample {} has no duration", index),
+            SampleTimingIssue::NonMonotonicDecodeTime {
+                index,
+            } => write!(f, "decode time of sample {} does not increase", index),
+            SampleTimingIssue::DuplicatePresentationTime {
+                index,
+                first_index,
+            } => write!(f, "sample {} has the same presentation time as sample {}", index, first_index),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct CompositionOffsetEntry {
+    pub sample_count: u32,
+    pub sample_offset: i64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SampleTimingAnalysis {
+    pub timescale: CMTimeScale,
+    // Decode order, missing values are synthesized
+    pub presentation_times: Vec<i64>,
+    pub decode_times: Vec<i64>,
+    pub durations: Vec<i64>,
+    pub reorder_depth: usize,
+    pub composition_offsets: Vec<CompositionOffsetEntry>,
+    pub min_composition_offset: i64,
+    pub max_composition_offset: i64,
+    pub issues: Vec<SampleTimingIssue>,
+}
+
+impl SampleTimingAnalysis {
+    // Uses the timescale of the first numeric time
+    pub fn analyze(timing_infos: &[CMSampleTimingInfo]) -> Result<Self, OSStatus> {
+        let timescale = timing_infos
+            .iter()
+            .flat_map(|info| [info.presentationTimeStamp, info.decodeTimeStamp, info.duration])
+            .find(is_numeric)
+            .map(|time| time.timescale)
+            .ok_or(kCMSampleBufferError_SampleTimingInfoInvalid)?;
+        Self::analyze_with_timescale(timing_infos, timescale)
+    }
+
+    pub fn analyze_with_timescale(timing_infos: &[CMSampleTimingInfo], timescale: CMTimeScale) -> Result<Self, OSStatus> {
+        if timescale <= 0 {
+            return Err(kCMSampleBufferError_SampleTimingInfoInvalid);
+        }
+        let mut analysis = Self {
+            timescale,
+            ..Default::default()
+        };
+        let count = timing_infos.len();
+        if count == 0 {
+            return Ok(analysis);
+        }
+        let mut presentation_times: Vec<Option<i64>> =
+            timing_infos.iter().map(|info| convert_timescale(&info.presentationTimeStamp, timescale)).collect();
+        let decode_times: Vec<Option<i64>> = timing_infos.iter().map(|info| convert_timescale(&info.decodeTimeStamp, timescale)).collect();
+        let durations: Vec<Option<i64>> = timing_infos.iter().map(|info| convert_timescale(&info.duration, timescale)).collect();
+        if presentation_times.iter().all(Option::is_none) && decode_times.iter().all(Option::is_none) {
+            return Err(kCMSampleBufferError_SampleTimingInfoInvalid);
+        }
+        // Samples without any decode time are in presentation order already
+        let has_decode_times = decode_times.iter().any(Option::is_some);
+        for index in 0..count {
+            if presentation_times[index].is_none() {
+                analysis.issues.push(SampleTimingIssue::MissingPresentationTime {
+                    index,
+                });
+            }
+            if has_decode_times && decode_times[index].is_none() {
+                analysis.issues.push(SampleTimingIssue::MissingDecodeTime {
+                    index,
+                });
+            }
+            if durations[index].is_none() {
+                analysis.issues.push(SampleTimingIssue::MissingDuration {
+                    index,
+                });
+            }
+        }
+        // Fall back to the decode time, then to the end of the previous sample
+        for index in 0..count {
+            if presentation_times[index].is_some() {
+                continue;
+            }
+            presentation_times[index] = decode_times[index].or_else(|| {
+                let previous = index.checked_sub(1)?;
+                Some(presentation_times[previous]?.saturating_add(durations[previous].unwrap_or(0)))
+            });
+        }
+        let first_known = presentation_times.iter().flatten().next().copied().unwrap_or(0);
+        let presentation_times: Vec<i64> = presentation_times.into_iter().map(|time| time.unwrap_or(first_known)).collect();
+
+        let mut presentation_order: Vec<usize> = (0..count).collect();
+        presentation_order.sort_by_key(|index| (presentation_times[*index], *index));
+        for pair in presentation_order.windows(2) {
+            if presentation_times[pair[0]] == presentation_times[pair[1]] {
+                analysis.issues.push(SampleTimingIssue::DuplicatePresentationTime {
+                    index: pair[1],
+                    first_index: pair[0],
+                });
+            }
+        }
+
+        // Presentation durations come from the next sample in presentation order
+        let mut synthesized_durations = vec![0; count];
+        for (rank, index) in presentation_order.iter().enumerate() {
+            synthesized_durations[*index] = match presentation_order.get(rank + 1) {
+                Some(next) => presentation_times[*next].saturating_sub(presentation_times[*index]),
+                None => rank.checked_sub(1).map_or(0, |previous| synthesized_durations[presentation_order[previous]]),
+            };
+        }
+        let durations: Vec<i64> =
+            durations.iter().zip(synthesized_durations).map(|(duration, synthesized)| duration.unwrap_or(synthesized)).collect();
+
+        // Sorted presentation times, shifted so that no sample is late. The shift
+        // can exceed the i64 range when timestamps sit at both extremes
+        let shift = (0..count)
+            .map(|index| presentation_times[presentation_order[index]] as i128 - presentation_times[index] as i128)
+            .max()
+            .unwrap_or(0)
+            .max(0);
+        let decode_times: Vec<i64> = decode_times
+            .iter()
+            .enumerate()
+            .map(|(index, time)| time.unwrap_or_else(|| saturate(presentation_times[presentation_order[index]] as i128 - shift)))
+            .collect();
+        for index in 1..count {
+            if decode_times[index] <= decode_times[index - 1] {
+                analysis.issues.push(SampleTimingIssue::NonMonotonicDecodeTime {
+                    index,
+                });
+            }
+        }
+
+        analysis.reorder_depth = reorder_depth(&presentation_times);
+        for index in 0..count {
+            let offset = presentation_times[index].saturating_sub(decode_times[index]);
+            match analysis.composition_offsets.last_mut() {
+                Some(entry) if entry.sample_offset == offset && entry.sample_count < u32::MAX => entry.sample_count += 1,
+                _ => analysis.composition_offsets.push(CompositionOffsetEntry {
+                    sample_count: 1,
+                    sample_offset: offset,
+                }),
+            }
+        }
+        analysis.min_composition_offset = analysis.composition_offsets.iter().map(|entry| entry.sample_offset).min().unwrap_or(0);
+        analysis.max_composition_offset = analysis.composition_offsets.iter().map(|entry| entry.sample_offset).max().unwrap_or(0);
+        analysis.presentation_times = presentation_times;
+        analysis.decode_times = decode_times;
+        analysis.durations = durations;
+        Ok(analysis)
+    }
+
+    #[inline]
+    pub fn sample_count(&self) -> usize {
+        self.presentation_times.len()
+    }
+
+    #[inline]
+    pub fn has_reordering(&self) -> bool {
+        self.reorder_depth > 0
+    }
+
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    // The ctts table needs version 1 for negative offsets
+    #[inline]
+    pub fn has_negative_composition_offsets(&self) -> bool {
+        self.min_composition_offset < 0
+    }
+
+    // compositionToDTSShift of the cslg box
+    #[inline]
+    pub fn composition_to_decode_shift(&self) -> i64 {
+        self.min_composition_offset.saturating_neg().max(0)
+    }
+
+    pub fn timing_infos(&self) -> Vec<CMSampleTimingInfo> {
+        (0..self.sample_count())
+            .map(|index| CMSampleTimingInfo {
+                duration: make_time(self.durations[index], self.timescale),
+                presentationTimeStamp: make_time(self.presentation_times[index], self.timescale),
+                decodeTimeStamp: make_time(self.decode_times[index], self.timescale),
+            })
+            .collect()
+    }
+}
+
+#[inline]
+fn saturate(value: i128) -> i64 {
+    value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+// Largest number of samples decoded before a sample but presented after it
+fn reorder_depth(presentation_times: &[i64]) -> usize {
+    let mut sorted: Vec<i64> = presentation_times.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    // Fenwick tree over presentation ranks
+    let mut tree = vec![0usize; sorted.len() + 1];
+    let mut depth = 0;
+    for (decoded, time) in presentation_times.iter().enumerate() {
+        let rank = sorted.binary_search(time).unwrap_or(0) + 1;
+        let mut not_later = 0;
+        let mut position = rank;
+        while position > 0 {
+            not_later += tree[position];
+            position &= position - 1;
+        }
+        depth = depth.max(decoded - not_later);
+        let mut position = rank;
+        while position < tree.len() {
+            tree[position] += 1;
+            position += position & position.wrapping_neg();
+        }
+    }
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::CMTime;
+
+    const TIMESCALE: CMTimeScale = 30000;
+    const FRAME: i64 = 1001;
+
+    // Decode order presentation times in frames, without decode times
+    fn timing_infos(frames: &[i64]) -> Vec<CMSampleTimingInfo> {
+        frames
+            .iter()
+            .map(|frame| CMSampleTimingInfo {
+                duration: make_time(FRAME, TIMESCALE),
+                presentationTimeStamp: make_time(frame * FRAME, TIMESCALE),
+                decodeTimeStamp: CMTime::default(),
+            })
+            .collect()
+    }
+
+    fn assert_decode_times_precede_presentation(analysis: &SampleTimingAnalysis) {
+        assert!(analysis.decode_times.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", analysis.decode_times);
+        assert!(analysis.decode_times.iter().zip(&analysis.presentation_times).all(|(decode, presentation)| decode <= presentation));
+    }
+
+    // Expands the ctts entries over the decode times
+    fn ctts_presentation_times(analysis: &SampleTimingAnalysis) -> Vec<i64> {
+        let offsets = analysis.composition_offsets.iter().flat_map(|entry| (0..entry.sample_count).map(move |_| entry.sample_offset));
+        analysis.decode_times.iter().zip(offsets).map(|(decode, offset)| decode + offset).collect()
+    }
+
+    #[test]
+    fn ipbb_gop() {
+        let analysis = SampleTimingAnalysis::analyze(&timing_infos(&[0, 3, 1, 2, 6, 4, 5])).unwrap();
+        assert_eq!(analysis.timescale, TIMESCALE);
+        assert_eq!(analysis.reorder_depth, 1);
+        assert!(analysis.is_valid());
+        assert_decode_times_precede_presentation(&analysis);
+        assert_eq!(analysis.decode_times, [-1, 0, 1, 2, 3, 4, 5].map(|frame| frame * FRAME));
+        assert_eq!(analysis.composition_to_decode_shift(), 0);
+        assert_eq!(ctts_presentation_times(&analysis), analysis.presentation_times);
+    }
+
+    #[test]
+    fn b_pyramid_gop() {
+        let frames = [0, 4, 2, 1, 3, 8, 6, 5, 7];
+        let analysis = SampleTimingAnalysis::analyze(&timing_infos(&frames)).unwrap();
+        assert_eq!(analysis.reorder_depth, 2);
+        assert!(analysis.is_valid());
+        assert_decode_times_precede_presentation(&analysis);
+        assert_eq!(analysis.presentation_times, frames.map(|frame| frame * FRAME));
+        assert_eq!(ctts_presentation_times(&analysis), analysis.presentation_times);
+        assert_eq!(analysis.composition_offsets.iter().map(|entry| entry.sample_count).sum::<u32>(), frames.len() as u32);
+    }
+
+    #[test]
+    fn explicit_decode_times_round_trip() {
+        let mut infos = timing_infos(&[0, 3, 1, 2]);
+        for (index, info) in infos.iter_mut().enumerate() {
+            info.decodeTimeStamp = make_time(index as i64 * FRAME, TIMESCALE);
+        }
+        let analysis = SampleTimingAnalysis::analyze(&infos).unwrap();
+        assert_eq!(analysis.min_composition_offset, -FRAME);
+        assert!(analysis.has_negative_composition_offsets());
+        assert_eq!(analysis.composition_to_decode_shift(), FRAME);
+        assert_eq!(ctts_presentation_times(&analysis), analysis.presentation_times);
+        assert_eq!(analysis.timing_infos(), infos);
+    }
+
+    #[test]
+    fn extreme_timestamps_do_not_overflow() {
+        let infos = [i64::MAX, i64::MIN + 1, 0]
+            .iter()
+            .map(|time| CMSampleTimingInfo {
+                duration: CMTime::default(),
+                presentationTimeStamp: make_time(*time, TIMESCALE),
+                decodeTimeStamp: CMTime::default(),
+            })
+            .collect::<Vec<_>>();
+        let analysis = SampleTimingAnalysis::analyze(&infos).unwrap();
+        assert_eq!(analysis.reorder_depth, 1);
+        assert!(analysis.decode_times.iter().zip(&analysis.presentation_times).all(|(decode, presentation)| decode <= presentation));
+        assert!(analysis.durations.iter().all(|duration| *duration >= 0));
+    }
+}
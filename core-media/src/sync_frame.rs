@@ -0,0 +1,216 @@
+use std::marker::PhantomData;
+
+use core_audio_types::base_types::AudioStreamPacketDescription;
+use core_foundation::base::OSStatus;
+
+use crate::{
+    block_buffer::{CMBlockBuffer, TBlockBuffer},
+    format_description::{CMAudioFormatDescription, TCMFormatDescription},
+    sample_buffer::CMSampleBuffer,
+    time::CMTime,
+};
+
+// The header of a self delimiting frame in an elementary audio stream
+pub(crate) trait SyncHeader: Sized {
+    // Bytes needed to parse a header
+    const HEADER_SIZE: usize;
+
+    fn parse_header(data: &[u8]) -> Option<Self>;
+
+    fn frame_length(&self) -> usize;
+
+    // Whether the data a frame ends at confirms it, `next` may be empty or short
+    fn is_boundary(&self, next: &[u8]) -> bool;
+
+    // Whether the next header continues the same chain of frames
+    fn is_continued_by(&self, next: &Self) -> bool;
+}
+
+pub(crate) struct SyncFrame<'a, H> {
+    pub header: H,
+    pub data: &'a [u8],
+    pub offset: usize,
+}
+
+pub(crate) struct SyncFrameIter<'a, H> {
+    data: &'a [u8],
+    position: usize,
+    synced: bool,
+    header: PhantomData<H>,
+}
+
+impl<'a, H: SyncHeader> SyncFrameIter<'a, H> {
+    #[inline]
+    pub fn new(data: &'a [u8], position: usize) -> Self {
+        Self {
+            data,
+            position,
+            synced: false,
+            header: PhantomData,
+        }
+    }
+
+    // Offset of the first byte that has not been returned as part of a frame
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'a, H: SyncHeader> Iterator for SyncFrameIter<'a, H> {
+    type Item = SyncFrame<'a, H>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut offset = self.position;
+        while offset + H::HEADER_SIZE <= self.data.len() {
+            if let Some(header) = H::parse_header(&self.data[offset..]) {
+                let next = offset + header.frame_length();
+                // Partial trailing frame
+                if next > self.data.len() {
+                    break;
+                }
+                // A header the previous frame ends at is already checked, so the last
+                // frame of a chain may be followed by any trailing data, while any other
+                // frame needs a boundary after it to reject stray sync patterns
+                let chained = self.synced && offset == self.position;
+                if chained || header.is_boundary(&self.data[next..]) {
+                    self.position = next;
+                    self.synced = H::parse_header(&self.data[next..]).is_some_and(|next| header.is_continued_by(&next));
+                    return Some(SyncFrame {
+                        header,
+                        data: &self.data[offset..next],
+                        offset,
+                    });
+                }
+            }
+            offset += 1;
+        }
+        None
+    }
+}
+
+pub(crate) fn new_sample_buffer(
+    data: &[u8],
+    packet_descriptions: &[AudioStreamPacketDescription],
+    format_description: &CMAudioFormatDescription,
+    presentation_time_stamp: CMTime,
+) -> Result<CMSampleBuffer, OSStatus> {
+    let mut block_buffer = CMBlockBuffer::new_empty(0, 0)?;
+    block_buffer.append_data_bytes(data)?;
+    CMSampleBuffer::new_audio_sample_buffer_ready_with_packet_descriptions(
+        &block_buffer,
+        &format_description.as_buffer(),
+        packet_descriptions.len() as _,
+        presentation_time_stamp,
+        Some(packet_descriptions),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNC: u8 = 0xA5;
+
+    // Sync byte, stream id and frame length
+    #[derive(Debug, PartialEq)]
+    struct TestHeader {
+        stream: u8,
+        length: usize,
+    }
+
+    impl SyncHeader for TestHeader {
+        const HEADER_SIZE: usize = 3;
+
+        fn parse_header(data: &[u8]) -> Option<Self> {
+            match data {
+                [SYNC, stream, length, ..] if *length as usize >= Self::HEADER_SIZE => Some(Self {
+                    stream: *stream,
+                    length: *length as usize,
+                }),
+                _ => None,
+            }
+        }
+
+        fn frame_length(&self) -> usize {
+            self.length
+        }
+
+        fn is_boundary(&self, next: &[u8]) -> bool {
+            next.is_empty() || next[0] == SYNC
+        }
+
+        fn is_continued_by(&self, next: &Self) -> bool {
+            next.stream == self.stream
+        }
+    }
+
+    fn frame(stream: u8, length: u8) -> Vec<u8> {
+        let mut frame = vec![SYNC, stream, length];
+        frame.resize(length as usize, 0);
+        frame
+    }
+
+    fn offsets(data: &[u8], position: usize) -> Vec<usize> {
+        SyncFrameIter::<TestHeader>::new(data, position).map(|frame| frame.offset).collect()
+    }
+
+    #[test]
+    fn frames_are_found_after_leading_data() {
+        let mut data = vec![0, SYNC];
+        data.extend_from_slice(&frame(1, 4));
+        data.extend_from_slice(&frame(1, 5));
+        data.extend_from_slice(&frame(2, 4));
+        let mut frames = SyncFrameIter::<TestHeader>::new(&data, 0);
+        let first = frames.next().unwrap();
+        assert_eq!(
+            (first.header, first.data, first.offset),
+            (
+                TestHeader {
+                    stream: 1,
+                    length: 4
+                },
+                &data[2..6],
+                2
+            )
+        );
+        assert_eq!(frames.position(), 6);
+        assert_eq!(frames.map(|frame| frame.offset).collect::<Vec<_>>(), [6, 11]);
+
+        // Partial trailing frames are left for the next call
+        let end = data.len();
+        data.extend_from_slice(&frame(2, 8)[..5]);
+        let mut frames = SyncFrameIter::<TestHeader>::new(&data, 0);
+        assert_eq!(frames.by_ref().count(), 3);
+        assert_eq!(frames.position(), end);
+        assert_eq!(offsets(&data, 6), [6, 11]);
+    }
+
+    #[test]
+    fn stray_sync_patterns_are_skipped() {
+        // The first pattern claims a frame that doesn't end at a boundary
+        let mut data = vec![SYNC, 1, 6];
+        data.extend_from_slice(&frame(1, 4));
+        data.extend_from_slice(&frame(1, 4));
+        assert_eq!(offsets(&data, 0), [3, 7]);
+    }
+
+    #[test]
+    fn last_frame_of_a_chain_may_precede_trailing_data() {
+        let mut data = frame(1, 4);
+        data.extend_from_slice(&frame(1, 4));
+        data.extend_from_slice(b"TAG0000000");
+        assert_eq!(offsets(&data, 0), [0, 4]);
+
+        // A frame of another stream doesn't vouch for the one before it
+        let mut data = frame(1, 4);
+        data.extend_from_slice(&frame(2, 4));
+        data.extend_from_slice(b"TAG0000000");
+        assert_eq!(offsets(&data, 0), [0]);
+
+        // A lone frame before trailing data is still treated as a stray sync pattern
+        let mut data = frame(1, 4);
+        data.extend_from_slice(b"TAG0000000");
+        assert!(offsets(&data, 0).is_empty());
+    }
+}
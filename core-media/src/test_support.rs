@@ -0,0 +1,49 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::{
+    buffer_queue::BufferTiming,
+    time::{make_time, CMTime},
+};
+
+// One frame at 30 fps per buffer, shared by the queue and stream tests
+#[derive(Clone, Debug)]
+pub(crate) struct MockBuffer {
+    pub(crate) time: i64,
+    pub(crate) ready: Arc<AtomicBool>,
+}
+
+impl MockBuffer {
+    pub(crate) fn new(time: i64) -> Self {
+        Self {
+            time,
+            ready: Arc::new(AtomicBool::new(true)),
+        }
+    }
+}
+
+impl PartialEq for MockBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.time == other.time
+    }
+}
+
+impl BufferTiming for MockBuffer {
+    fn get_decode_time_stamp(&self) -> CMTime {
+        make_time(self.time, 30)
+    }
+
+    fn get_presentation_time_stamp(&self) -> CMTime {
+        make_time(self.time, 30)
+    }
+
+    fn get_duration(&self) -> CMTime {
+        make_time(1, 30)
+    }
+
+    fn is_data_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+}
@@ -1,3 +1,5 @@
+use std::{cmp::Ordering, convert::TryFrom};
+
 use core_foundation::{
     base::{kCFAllocatorDefault, CFAllocatorRef, TCFType},
     dictionary::{CFDictionary, CFDictionaryRef},
@@ -184,3 +186,146 @@ impl CMTime {
         unsafe { CMTimeShow(*self) }
     }
 }
+
+// Native arithmetic for code that must not call into CoreMedia, following its
+// rules
+
+#[inline]
+pub(crate) fn is_numeric(time: &CMTime) -> bool {
+    time.flags & (kCMTimeFlags_Valid | kCMTimeFlags_ImpliedValueFlagsMask) == kCMTimeFlags_Valid && time.timescale > 0
+}
+
+#[inline]
+pub(crate) fn make_time(value: CMTimeValue, timescale: CMTimeScale) -> CMTime {
+    CMTime {
+        value,
+        timescale,
+        flags: kCMTimeFlags_Valid,
+        epoch: 0,
+    }
+}
+
+#[inline]
+fn make_time_with_flags(flags: CMTimeFlags) -> CMTime {
+    CMTime {
+        value: 0,
+        timescale: 0,
+        flags: kCMTimeFlags_Valid | flags,
+        epoch: 0,
+    }
+}
+
+// Overflowing values become infinite like CMTimeAdd results
+pub(crate) fn make_time_saturating(value: i128, timescale: CMTimeScale) -> CMTime {
+    match CMTimeValue::try_from(value) {
+        Ok(value) => make_time(value, timescale),
+        Err(_) if value > 0 => make_time_with_flags(kCMTimeFlags_PositiveInfinity),
+        Err(_) => make_time_with_flags(kCMTimeFlags_NegativeInfinity),
+    }
+}
+
+// Rounds half away from zero like kCMTimeRoundingMethod_Default, the time must
+// be numeric
+pub(crate) fn value_in_timescale(time: &CMTime, timescale: CMTimeScale) -> i128 {
+    if time.timescale == timescale {
+        return time.value as i128;
+    }
+    let numerator = time.value as i128 * timescale as i128;
+    let denominator = time.timescale as i128;
+    if numerator >= 0 {
+        (numerator + denominator / 2) / denominator
+    } else {
+        (numerator - denominator / 2) / denominator
+    }
+}
+
+#[inline]
+pub(crate) fn convert_timescale(time: &CMTime, timescale: CMTimeScale) -> Option<CMTimeValue> {
+    if !is_numeric(time) {
+        return None;
+    }
+    CMTimeValue::try_from(value_in_timescale(time, timescale)).ok()
+}
+
+// Least common multiple of the timescales, or the larger one on overflow
+pub(crate) fn common_timescale(a: CMTimeScale, b: CMTimeScale) -> CMTimeScale {
+    let (mut x, mut y) = (a as i64, b as i64);
+    while y != 0 {
+        let remainder = x % y;
+        x = y;
+        y = remainder;
+    }
+    CMTimeScale::try_from(a as i64 / x * b as i64).unwrap_or_else(|_| a.max(b))
+}
+
+// Negative infinity, numeric, indefinite, positive infinity, then invalid like
+// CMTimeCompare
+fn compare_rank(time: &CMTime) -> u8 {
+    if time.flags & kCMTimeFlags_Valid == 0 {
+        4
+    } else if time.flags & kCMTimeFlags_PositiveInfinity != 0 {
+        3
+    } else if time.flags & kCMTimeFlags_Indefinite != 0 {
+        2
+    } else if time.flags & kCMTimeFlags_NegativeInfinity != 0 {
+        0
+    } else if time.timescale > 0 {
+        1
+    } else {
+        4
+    }
+}
+
+pub(crate) fn compare_times(a: &CMTime, b: &CMTime) -> Ordering {
+    match (compare_rank(a), compare_rank(b)) {
+        (1, 1) => a.epoch.cmp(&b.epoch).then_with(|| (a.value as i128 * b.timescale as i128).cmp(&(b.value as i128 * a.timescale as i128))),
+        (a, b) => a.cmp(&b),
+    }
+}
+
+// Only numeric times are added, anything else gives an invalid time
+pub(crate) fn add_times(a: &CMTime, b: &CMTime) -> CMTime {
+    if !is_numeric(a) || !is_numeric(b) {
+        return CMTime::default();
+    }
+    let timescale = common_timescale(a.timescale, b.timescale);
+    make_time_saturating(value_in_timescale(a, timescale) + value_in_timescale(b, timescale), timescale)
+}
+
+pub(crate) fn subtract_times(a: &CMTime, b: &CMTime) -> CMTime {
+    if !is_numeric(a) || !is_numeric(b) {
+        return CMTime::default();
+    }
+    let timescale = common_timescale(a.timescale, b.timescale);
+    make_time_saturating(value_in_timescale(a, timescale) - value_in_timescale(b, timescale), timescale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_times_orders_like_core_media() {
+        let negative_infinity = make_time_with_flags(kCMTimeFlags_NegativeInfinity);
+        let positive_infinity = make_time_with_flags(kCMTimeFlags_PositiveInfinity);
+        let indefinite = make_time_with_flags(kCMTimeFlags_Indefinite);
+        let times = [negative_infinity, make_time(-1, 2), make_time(1, 3), make_time(1, 2), indefinite, positive_infinity, CMTime::default()];
+        for (i, a) in times.iter().enumerate() {
+            for (j, b) in times.iter().enumerate() {
+                assert_eq!(compare_times(a, b), i.cmp(&j), "{:?} {:?}", a, b);
+            }
+        }
+        assert_eq!(compare_times(&make_time(1, 2), &make_time(15, 30)), Ordering::Equal);
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(add_times(&make_time(1, 2), &make_time(1, 3)), make_time(5, 6));
+        assert_eq!(subtract_times(&make_time(1, 2), &make_time(1, 3)), make_time(1, 6));
+        assert_eq!(add_times(&make_time(1, 2), &CMTime::default()), CMTime::default());
+        assert_eq!(add_times(&make_time(i64::MAX, 1), &make_time(1, 1)), make_time_with_flags(kCMTimeFlags_PositiveInfinity));
+        assert_eq!(convert_timescale(&make_time(1, 3), 1000), Some(333));
+        assert_eq!(convert_timescale(&make_time(-1, 2000), 1000), Some(-1));
+        assert_eq!(convert_timescale(&make_time_with_flags(kCMTimeFlags_Indefinite), 1000), None);
+    }
+}
@@ -0,0 +1,488 @@
+use std::convert::TryFrom;
+
+use core_foundation::{
+    array::CFArray,
+    base::{CFType, CFTypeRef, TCFType},
+    dictionary::CFDictionary,
+    number::CFNumber,
+    string::{CFString, CFStringRef},
+};
+use core_graphics::{
+    base::CGFloat,
+    geometry::{CGRect, CGSize},
+};
+use libc::c_void;
+
+use crate::format_description::{
+    kCMFormatDescriptionExtension_CleanAperture, kCMFormatDescriptionExtension_PixelAspectRatio, kCMFormatDescriptionKey_CleanApertureHeight,
+    kCMFormatDescriptionKey_CleanApertureHeightRational, kCMFormatDescriptionKey_CleanApertureHorizontalOffset,
+    kCMFormatDescriptionKey_CleanApertureHorizontalOffsetRational, kCMFormatDescriptionKey_CleanApertureVerticalOffset,
+    kCMFormatDescriptionKey_CleanApertureVerticalOffsetRational, kCMFormatDescriptionKey_CleanApertureWidth,
+    kCMFormatDescriptionKey_CleanApertureWidthRational, kCMFormatDescriptionKey_PixelAspectRatioHorizontalSpacing,
+    kCMFormatDescriptionKey_PixelAspectRatioVerticalSpacing, CMVideoDimensions, CMVideoFormatDescription, TCMFormatDescription,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rational {
+    pub numerator: i32,
+    pub denominator: i32,
+}
+
+impl Rational {
+    #[inline]
+    pub const fn new(numerator: i32, denominator: i32) -> Self {
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+
+    #[inline]
+    pub const fn from_integer(value: i32) -> Self {
+        Self::new(value, 1)
+    }
+
+    pub fn from_float(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        // Crop rectangles produce half-pixel offsets, try small denominators first
+        for denominator in [1, 2, 4, 8, 16] {
+            let numerator = value * denominator as f64;
+            if numerator.fract() == 0.0 && numerator.abs() <= i32::MAX as f64 {
+                return Some(Self::new(numerator as i32, denominator).reduced());
+            }
+        }
+        let (mut p0, mut q0, mut p1, mut q1) = (0i64, 1i64, 1i64, 0i64);
+        let mut x = value;
+        loop {
+            let a = x.floor();
+            let (p2, q2) = (a as i64 * p1 + p0, a as i64 * q1 + q0);
+            if p2.abs() > i32::MAX as i64 || q2 > i32::MAX as i64 {
+                break;
+            }
+            (p0, q0, p1, q1) = (p1, q1, p2, q2);
+            let frac = x - a;
+            if frac.abs() < 1e-12 || (p1 as f64 / q1 as f64 - value).abs() < 1e-12 {
+                break;
+            }
+            x = 1.0 / frac;
+        }
+        if q1 == 0 {
+            None
+        } else {
+            Some(Self::new(p1 as i32, q1 as i32))
+        }
+    }
+
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.denominator != 0
+    }
+
+    pub fn reduced(&self) -> Self {
+        if !self.is_valid() {
+            return *self;
+        }
+        // Normalise in i64 so negating i32::MIN cannot overflow
+        let divisor = gcd(self.numerator.unsigned_abs(), self.denominator.unsigned_abs()).max(1) as i64;
+        let sign = if self.denominator < 0 {
+            -1
+        } else {
+            1
+        };
+        let numerator = sign * self.numerator as i64 / divisor;
+        let denominator = sign * self.denominator as i64 / divisor;
+        match (i32::try_from(numerator), i32::try_from(denominator)) {
+            (Ok(numerator), Ok(denominator)) => Self::new(numerator, denominator),
+            _ => *self,
+        }
+    }
+
+    #[inline]
+    pub fn to_float(&self) -> CGFloat {
+        self.numerator as CGFloat / self.denominator as CGFloat
+    }
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// Apertures are reported in whole pixels, halves round away from zero
+#[inline]
+fn round_pixels(value: CGFloat) -> CGFloat {
+    value.round()
+}
+
+// Origin and size of the part of [start, end) inside the encoded extent
+#[inline]
+fn clip_span(start: CGFloat, end: CGFloat, extent: i32) -> (CGFloat, CGFloat) {
+    let extent = extent.max(0) as CGFloat;
+    let start = start.max(0.0).min(extent);
+    let end = end.max(0.0).min(extent);
+    (start, (end - start).max(0.0))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CleanAperture {
+    pub width: Rational,
+    pub height: Rational,
+    pub horizontal_offset: Rational,
+    pub vertical_offset: Rational,
+}
+
+impl CleanAperture {
+    #[inline]
+    pub fn new(width: Rational, height: Rational, horizontal_offset: Rational, vertical_offset: Rational) -> Self {
+        Self {
+            width,
+            height,
+            horizontal_offset,
+            vertical_offset,
+        }
+    }
+
+    #[inline]
+    pub fn centered(width: i32, height: i32) -> Self {
+        Self::new(Rational::from_integer(width), Rational::from_integer(height), Rational::from_integer(0), Rational::from_integer(0))
+    }
+
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.width.is_valid() &&
+            self.height.is_valid() &&
+            self.horizontal_offset.is_valid() &&
+            self.vertical_offset.is_valid() &&
+            self.width.to_float() > 0.0 &&
+            self.height.to_float() > 0.0
+    }
+
+    pub fn rect(&self, dimensions: CMVideoDimensions, origin_is_at_top_left: bool) -> CGRect {
+        let width = self.width.to_float();
+        let height = self.height.to_float();
+        let x = (dimensions.width as CGFloat - width) / 2.0 + self.horizontal_offset.to_float();
+        let y = if origin_is_at_top_left {
+            (dimensions.height as CGFloat - height) / 2.0 + self.vertical_offset.to_float()
+        } else {
+            (dimensions.height as CGFloat - height) / 2.0 - self.vertical_offset.to_float()
+        };
+        CGRect::new(x, y, width, height)
+    }
+
+    // The rectangle is clipped to the encoded dimensions first
+    pub fn from_rect(rect: &CGRect, dimensions: CMVideoDimensions, origin_is_at_top_left: bool) -> Option<Self> {
+        let (x, width) = clip_span(rect.origin.x, rect.origin.x + rect.size.width, dimensions.width);
+        let (y, height) = clip_span(rect.origin.y, rect.origin.y + rect.size.height, dimensions.height);
+        let horizontal_offset = x + width / 2.0 - dimensions.width as CGFloat / 2.0;
+        let vertical_offset = y + height / 2.0 - dimensions.height as CGFloat / 2.0;
+        let width = Rational::from_float(width)?;
+        let height = Rational::from_float(height)?;
+        let vertical_offset = if origin_is_at_top_left {
+            vertical_offset
+        } else {
+            -vertical_offset
+        };
+        let aperture = Self::new(width, height, Rational::from_float(horizontal_offset)?, Rational::from_float(vertical_offset)?);
+        if aperture.is_valid() {
+            Some(aperture)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PixelAspectRatio {
+    pub horizontal_spacing: i32,
+    pub vertical_spacing: i32,
+}
+
+impl Default for PixelAspectRatio {
+    #[inline]
+    fn default() -> Self {
+        Self::square()
+    }
+}
+
+impl PixelAspectRatio {
+    #[inline]
+    pub const fn new(horizontal_spacing: i32, vertical_spacing: i32) -> Self {
+        Self {
+            horizontal_spacing,
+            vertical_spacing,
+        }
+    }
+
+    #[inline]
+    pub const fn square() -> Self {
+        Self::new(1, 1)
+    }
+
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.horizontal_spacing > 0 && self.vertical_spacing > 0
+    }
+
+    #[inline]
+    pub fn is_square(&self) -> bool {
+        self.horizontal_spacing == self.vertical_spacing
+    }
+
+    #[inline]
+    pub fn apply(&self, size: CGSize) -> CGSize {
+        if !self.is_valid() || self.is_square() {
+            return size;
+        }
+        CGSize::new(size.width * self.horizontal_spacing as CGFloat / self.vertical_spacing as CGFloat, size.height)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ApertureMode {
+    CleanAperture,
+    ProductionAperture,
+    EncodedPixels,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VideoGeometry {
+    pub dimensions: CMVideoDimensions,
+    pub clean_aperture: Option<CleanAperture>,
+    pub pixel_aspect_ratio: Option<PixelAspectRatio>,
+}
+
+impl VideoGeometry {
+    #[inline]
+    pub fn new(dimensions: CMVideoDimensions) -> Self {
+        Self {
+            dimensions,
+            clean_aperture: None,
+            pixel_aspect_ratio: None,
+        }
+    }
+
+    #[inline]
+    pub fn with_clean_aperture(mut self, clean_aperture: CleanAperture) -> Self {
+        self.clean_aperture = Some(clean_aperture);
+        self
+    }
+
+    #[inline]
+    pub fn with_pixel_aspect_ratio(mut self, pixel_aspect_ratio: PixelAspectRatio) -> Self {
+        self.pixel_aspect_ratio = Some(pixel_aspect_ratio);
+        self
+    }
+
+    pub fn from_format_description(desc: &CMVideoFormatDescription) -> Self {
+        let mut geometry = Self::new(desc.get_dimensions());
+        if let Some(extensions) = desc.as_buffer().get_extensions() {
+            geometry.clean_aperture =
+                unsafe { find_dictionary(&extensions, kCMFormatDescriptionExtension_CleanAperture) }.and_then(|dict| parse_clean_aperture(&dict));
+            geometry.pixel_aspect_ratio = unsafe { find_dictionary(&extensions, kCMFormatDescriptionExtension_PixelAspectRatio) }
+                .and_then(|dict| parse_pixel_aspect_ratio(&dict));
+        }
+        geometry
+    }
+
+    #[inline]
+    fn valid_clean_aperture(&self) -> Option<&CleanAperture> {
+        self.clean_aperture.as_ref().filter(|aperture| aperture.is_valid())
+    }
+
+    #[inline]
+    fn valid_pixel_aspect_ratio(&self) -> Option<&PixelAspectRatio> {
+        self.pixel_aspect_ratio.as_ref().filter(|ratio| ratio.is_valid())
+    }
+
+    #[inline]
+    pub fn encoded_size(&self) -> CGSize {
+        CGSize::new(self.dimensions.width as CGFloat, self.dimensions.height as CGFloat)
+    }
+
+    #[inline]
+    pub fn clean_aperture_rect(&self, origin_is_at_top_left: bool) -> CGRect {
+        if let Some(aperture) = self.valid_clean_aperture() {
+            // Round the edges rather than the size so the aperture cannot grow past them
+            let rect = aperture.rect(self.dimensions, origin_is_at_top_left);
+            let (x, width) = clip_span(round_pixels(rect.origin.x), round_pixels(rect.origin.x + rect.size.width), self.dimensions.width);
+            let (y, height) = clip_span(round_pixels(rect.origin.y), round_pixels(rect.origin.y + rect.size.height), self.dimensions.height);
+            if width > 0.0 && height > 0.0 {
+                return CGRect::new(x, y, width, height);
+            }
+        }
+        CGRect::new(0.0, 0.0, self.dimensions.width as CGFloat, self.dimensions.height as CGFloat)
+    }
+
+    pub fn presentation_size(&self, use_pixel_aspect_ratio: bool, use_clean_aperture: bool) -> CGSize {
+        let size = if use_clean_aperture {
+            self.clean_aperture_rect(true).size
+        } else {
+            self.encoded_size()
+        };
+        match self.valid_pixel_aspect_ratio() {
+            Some(ratio) if use_pixel_aspect_ratio => {
+                let size = ratio.apply(size);
+                CGSize::new(round_pixels(size.width), round_pixels(size.height))
+            }
+            _ => size,
+        }
+    }
+
+    #[inline]
+    pub fn aperture_size(&self, mode: ApertureMode) -> CGSize {
+        match mode {
+            ApertureMode::CleanAperture => self.presentation_size(true, true),
+            ApertureMode::ProductionAperture => self.presentation_size(true, false),
+            ApertureMode::EncodedPixels => self.presentation_size(false, false),
+        }
+    }
+
+    #[inline]
+    pub fn crop_rect(&self) -> CGRect {
+        self.clean_aperture_rect(true)
+    }
+
+    #[inline]
+    pub fn set_crop_rect(&mut self, rect: &CGRect) -> bool {
+        match CleanAperture::from_rect(rect, self.dimensions, true) {
+            Some(aperture) => {
+                self.clean_aperture = Some(aperture);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+unsafe fn find_value(dict: &CFDictionary, key: CFStringRef) -> Option<CFType> {
+    dict.find(key as *const c_void).map(|value| CFType::wrap_under_get_rule(*value as CFTypeRef))
+}
+
+unsafe fn find_dictionary(extensions: &CFDictionary<CFString, CFType>, key: CFStringRef) -> Option<CFDictionary> {
+    extensions.find(CFString::wrap_under_get_rule(key)).and_then(|value| value.downcast::<CFDictionary>())
+}
+
+unsafe fn find_rational(dict: &CFDictionary, key: CFStringRef, rational_key: CFStringRef) -> Option<Rational> {
+    if let Some(array) = find_value(dict, rational_key).and_then(|value| value.downcast::<CFArray>()) {
+        if array.len() == 2 {
+            let numerator = CFType::wrap_under_get_rule(*array.get(0)? as CFTypeRef).downcast::<CFNumber>()?.to_i32()?;
+            let denominator = CFType::wrap_under_get_rule(*array.get(1)? as CFTypeRef).downcast::<CFNumber>()?.to_i32()?;
+            return Some(Rational::new(numerator, denominator));
+        }
+    }
+    Rational::from_float(find_value(dict, key)?.downcast::<CFNumber>()?.to_f64()?)
+}
+
+fn parse_clean_aperture(dict: &CFDictionary) -> Option<CleanAperture> {
+    unsafe {
+        Some(CleanAperture::new(
+            find_rational(dict, kCMFormatDescriptionKey_CleanApertureWidth, kCMFormatDescriptionKey_CleanApertureWidthRational)?,
+            find_rational(dict, kCMFormatDescriptionKey_CleanApertureHeight, kCMFormatDescriptionKey_CleanApertureHeightRational)?,
+            find_rational(dict, kCMFormatDescriptionKey_CleanApertureHorizontalOffset, kCMFormatDescriptionKey_CleanApertureHorizontalOffsetRational)
+                .unwrap_or(Rational::from_integer(0)),
+            find_rational(dict, kCMFormatDescriptionKey_CleanApertureVerticalOffset, kCMFormatDescriptionKey_CleanApertureVerticalOffsetRational)
+                .unwrap_or(Rational::from_integer(0)),
+        ))
+    }
+}
+
+fn parse_pixel_aspect_ratio(dict: &CFDictionary) -> Option<PixelAspectRatio> {
+    unsafe {
+        let horizontal_spacing = find_value(dict, kCMFormatDescriptionKey_PixelAspectRatioHorizontalSpacing)?.downcast::<CFNumber>()?.to_i32()?;
+        let vertical_spacing = find_value(dict, kCMFormatDescriptionKey_PixelAspectRatioVerticalSpacing)?.downcast::<CFNumber>()?.to_i32()?;
+        Some(PixelAspectRatio::new(horizontal_spacing, vertical_spacing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dimensions(width: i32, height: i32) -> CMVideoDimensions {
+        CMVideoDimensions {
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn reduced_handles_extremes() {
+        assert_eq!(Rational::new(4, -8).reduced(), Rational::new(-1, 2));
+        assert_eq!(Rational::new(i32::MIN, 2).reduced(), Rational::new(i32::MIN / 2, 1));
+        assert_eq!(Rational::new(i32::MIN, -1).reduced(), Rational::new(i32::MIN, -1));
+        assert_eq!(Rational::new(i32::MIN, -2).reduced(), Rational::new(1 << 30, 1));
+    }
+
+    #[test]
+    fn clean_aperture_rounds_to_whole_pixels() {
+        let geometry = VideoGeometry::new(dimensions(1920, 1080)).with_clean_aperture(CleanAperture::centered(1919, 1079));
+        let rect = geometry.clean_aperture_rect(true);
+        assert_eq!((rect.origin.x, rect.origin.y, rect.size.width, rect.size.height), (1.0, 1.0, 1919.0, 1079.0));
+        let aperture = CleanAperture::new(Rational::new(2877, 2), Rational::from_integer(1080), Rational::new(-1, 3), Rational::new(1, 3));
+        let rect = VideoGeometry::new(dimensions(1440, 1080)).with_clean_aperture(aperture).clean_aperture_rect(false);
+        assert_eq!((rect.origin.x, rect.origin.y, rect.size.width, rect.size.height), (0.0, 0.0, 1439.0, 1080.0));
+    }
+
+    #[test]
+    fn presentation_size_rounds_pixel_aspect_ratio() {
+        let geometry = VideoGeometry::new(dimensions(720, 480)).with_pixel_aspect_ratio(PixelAspectRatio::new(10, 11));
+        let size = geometry.presentation_size(true, false);
+        assert_eq!((size.width, size.height), (655.0, 480.0));
+        let size = geometry.with_clean_aperture(CleanAperture::centered(704, 480)).aperture_size(ApertureMode::CleanAperture);
+        assert_eq!((size.width, size.height), (640.0, 480.0));
+        let size = geometry.aperture_size(ApertureMode::EncodedPixels);
+        assert_eq!((size.width, size.height), (720.0, 480.0));
+    }
+
+    #[test]
+    fn crop_rect_round_trips() {
+        let mut geometry = VideoGeometry::new(dimensions(1920, 1080));
+        let rect = CGRect::new(8.0, 4.0, 1900.0, 1070.0);
+        assert!(geometry.set_crop_rect(&rect));
+        assert_eq!(
+            geometry.clean_aperture,
+            Some(CleanAperture::new(
+                Rational::from_integer(1900),
+                Rational::from_integer(1070),
+                Rational::from_integer(-2),
+                Rational::from_integer(-1)
+            ))
+        );
+        assert_eq!(geometry.crop_rect(), rect);
+
+        // Half pixel offsets survive the rational conversion in both origins
+        let rect = CGRect::new(10.5, 20.0, 700.0, 440.5);
+        for origin_is_at_top_left in [true, false] {
+            let aperture = CleanAperture::from_rect(&rect, dimensions(720, 480), origin_is_at_top_left).unwrap();
+            assert_eq!(aperture.rect(dimensions(720, 480), origin_is_at_top_left), rect);
+        }
+        let rect = VideoGeometry::new(dimensions(720, 480))
+            .with_clean_aperture(CleanAperture::from_rect(&rect, dimensions(720, 480), true).unwrap())
+            .crop_rect();
+        assert_eq!((rect.origin.x, rect.origin.y, rect.size.width, rect.size.height), (11.0, 20.0, 700.0, 441.0));
+    }
+
+    #[test]
+    fn apertures_are_clamped_to_dimensions() {
+        let mut geometry = VideoGeometry::new(dimensions(1280, 720));
+        assert!(geometry.set_crop_rect(&CGRect::new(-10.0, 700.0, 100.0, 40.0)));
+        assert_eq!(geometry.crop_rect(), CGRect::new(0.0, 700.0, 90.0, 20.0));
+        assert!(!geometry.set_crop_rect(&CGRect::new(1300.0, 0.0, 100.0, 100.0)));
+
+        // Rounding both edges up cannot push the aperture past the encoded width
+        let aperture = CleanAperture::new(Rational::new(2559, 2), Rational::from_integer(720), Rational::new(1, 4), Rational::from_integer(0));
+        let rect = VideoGeometry::new(dimensions(1280, 720)).with_clean_aperture(aperture).crop_rect();
+        assert_eq!((rect.origin.x, rect.size.width), (1.0, 1279.0));
+        assert!(rect.origin.x + rect.size.width <= 1280.0);
+
+        // An aperture entirely outside the picture falls back to the encoded pixels
+        let aperture =
+            CleanAperture::new(Rational::from_integer(100), Rational::from_integer(100), Rational::from_integer(2000), Rational::from_integer(0));
+        let rect = VideoGeometry::new(dimensions(1280, 720)).with_clean_aperture(aperture).crop_rect();
+        assert_eq!(rect, CGRect::new(0.0, 0.0, 1280.0, 720.0));
+    }
+}
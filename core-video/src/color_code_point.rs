@@ -0,0 +1,479 @@
+use std::convert::TryFrom;
+
+use core_foundation::string::CFString;
+
+use crate::image_buffer::{CVImageBufferColorPrimaries, CVImageBufferTransferFunction, CVImageBufferYCbCrMatrix};
+
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ColorPrimaries {
+    #[doc(alias = "kCVImageBufferColorPrimaries_ITU_R_709_2")]
+    BT709       = 1,
+    Unspecified = 2,
+    BT470M      = 4,
+    #[doc(alias = "kCVImageBufferColorPrimaries_EBU_3213")]
+    BT470BG     = 5,
+    #[doc(alias = "kCVImageBufferColorPrimaries_SMPTE_C")]
+    SMPTE170M   = 6,
+    SMPTE240M   = 7,
+    Film        = 8,
+    #[doc(alias = "kCVImageBufferColorPrimaries_ITU_R_2020")]
+    BT2020      = 9,
+    SMPTE428    = 10,
+    #[doc(alias = "kCVImageBufferColorPrimaries_DCI_P3")]
+    SMPTE431    = 11,
+    #[doc(alias = "kCVImageBufferColorPrimaries_P3_D65")]
+    SMPTE432    = 12,
+    #[doc(alias = "kCVImageBufferColorPrimaries_P22")]
+    EBU3213     = 22,
+}
+
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TransferCharacteristics {
+    #[doc(alias = "kCVImageBufferTransferFunction_ITU_R_709_2")]
+    BT709        = 1,
+    Unspecified  = 2,
+    Gamma22      = 4,
+    Gamma28      = 5,
+    SMPTE170M    = 6,
+    #[doc(alias = "kCVImageBufferTransferFunction_SMPTE_240M_1995")]
+    SMPTE240M    = 7,
+    #[doc(alias = "kCVImageBufferTransferFunction_Linear")]
+    Linear       = 8,
+    Log100       = 9,
+    Log316       = 10,
+    IEC61966_2_4 = 11,
+    BT1361       = 12,
+    #[doc(alias = "kCVImageBufferTransferFunction_sRGB")]
+    IEC61966_2_1 = 13,
+    #[doc(alias = "kCVImageBufferTransferFunction_ITU_R_2020")]
+    BT2020_10    = 14,
+    BT2020_12    = 15,
+    #[doc(alias = "kCVImageBufferTransferFunction_SMPTE_ST_2084_PQ")]
+    SMPTE2084    = 16,
+    #[doc(alias = "kCVImageBufferTransferFunction_SMPTE_ST_428_1")]
+    SMPTE428     = 17,
+    #[doc(alias = "kCVImageBufferTransferFunction_ITU_R_2100_HLG")]
+    ARIB_STD_B67 = 18,
+}
+
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MatrixCoefficients {
+    Identity         = 0,
+    #[doc(alias = "kCVImageBufferYCbCrMatrix_ITU_R_709_2")]
+    BT709            = 1,
+    Unspecified      = 2,
+    FCC              = 4,
+    #[doc(alias = "kCVImageBufferYCbCrMatrix_ITU_R_601_4")]
+    BT470BG          = 5,
+    SMPTE170M        = 6,
+    #[doc(alias = "kCVImageBufferYCbCrMatrix_SMPTE_240M_1995")]
+    SMPTE240M        = 7,
+    YCgCo            = 8,
+    #[doc(alias = "kCVImageBufferYCbCrMatrix_ITU_R_2020")]
+    BT2020_NCL       = 9,
+    BT2020_CL        = 10,
+    SMPTE2085        = 11,
+    ChromaDerivedNCL = 12,
+    ChromaDerivedCL  = 13,
+    ICtCp            = 14,
+}
+
+impl ColorPrimaries {
+    pub fn from_code_point(code_point: i32) -> Option<Self> {
+        match code_point {
+            1 => Some(ColorPrimaries::BT709),
+            2 => Some(ColorPrimaries::Unspecified),
+            4 => Some(ColorPrimaries::BT470M),
+            5 => Some(ColorPrimaries::BT470BG),
+            6 => Some(ColorPrimaries::SMPTE170M),
+            7 => Some(ColorPrimaries::SMPTE240M),
+            8 => Some(ColorPrimaries::Film),
+            9 => Some(ColorPrimaries::BT2020),
+            10 => Some(ColorPrimaries::SMPTE428),
+            11 => Some(ColorPrimaries::SMPTE431),
+            12 => Some(ColorPrimaries::SMPTE432),
+            22 => Some(ColorPrimaries::EBU3213),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn code_point(&self) -> i32 {
+        *self as i32
+    }
+
+    /// Returns the canonical code point for a string. `SMPTE240M` also maps to
+    /// `SMPTE_C`, so it reads back as `SMPTE170M`.
+    pub fn from_string_value(value: &str) -> Option<Self> {
+        match value {
+            "ITU_R_709_2" => Some(ColorPrimaries::BT709),
+            "EBU_3213" => Some(ColorPrimaries::BT470BG),
+            "SMPTE_C" => Some(ColorPrimaries::SMPTE170M),
+            "ITU_R_2020" => Some(ColorPrimaries::BT2020),
+            "DCI_P3" => Some(ColorPrimaries::SMPTE431),
+            "P3_D65" => Some(ColorPrimaries::SMPTE432),
+            "P22" => Some(ColorPrimaries::EBU3213),
+            _ => None,
+        }
+    }
+
+    pub fn string_value(&self) -> Option<&'static str> {
+        match self {
+            ColorPrimaries::BT709 => Some("ITU_R_709_2"),
+            ColorPrimaries::BT470BG => Some("EBU_3213"),
+            ColorPrimaries::SMPTE170M | ColorPrimaries::SMPTE240M => Some("SMPTE_C"),
+            ColorPrimaries::BT2020 => Some("ITU_R_2020"),
+            ColorPrimaries::SMPTE431 => Some("DCI_P3"),
+            ColorPrimaries::SMPTE432 => Some("P3_D65"),
+            ColorPrimaries::EBU3213 => Some("P22"),
+            _ => None,
+        }
+    }
+
+    pub fn av_video_string_value(&self) -> Option<&'static str> {
+        match self {
+            ColorPrimaries::BT709 | ColorPrimaries::BT470BG | ColorPrimaries::SMPTE170M | ColorPrimaries::BT2020 | ColorPrimaries::SMPTE432 => {
+                self.string_value()
+            }
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn from_cf_string(value: &CFString) -> Option<Self> {
+        Self::from_string_value(&value.to_string())
+    }
+
+    #[inline]
+    pub fn to_cf_string(&self) -> Option<CFString> {
+        self.string_value().map(CFString::from_static_string)
+    }
+}
+
+impl TransferCharacteristics {
+    pub fn from_code_point(code_point: i32) -> Option<Self> {
+        match code_point {
+            1 => Some(TransferCharacteristics::BT709),
+            2 => Some(TransferCharacteristics::Unspecified),
+            4 => Some(TransferCharacteristics::Gamma22),
+            5 => Some(TransferCharacteristics::Gamma28),
+            6 => Some(TransferCharacteristics::SMPTE170M),
+            7 => Some(TransferCharacteristics::SMPTE240M),
+            8 => Some(TransferCharacteristics::Linear),
+            9 => Some(TransferCharacteristics::Log100),
+            10 => Some(TransferCharacteristics::Log316),
+            11 => Some(TransferCharacteristics::IEC61966_2_4),
+            12 => Some(TransferCharacteristics::BT1361),
+            13 => Some(TransferCharacteristics::IEC61966_2_1),
+            14 => Some(TransferCharacteristics::BT2020_10),
+            15 => Some(TransferCharacteristics::BT2020_12),
+            16 => Some(TransferCharacteristics::SMPTE2084),
+            17 => Some(TransferCharacteristics::SMPTE428),
+            18 => Some(TransferCharacteristics::ARIB_STD_B67),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn code_point(&self) -> i32 {
+        *self as i32
+    }
+
+    /// Returns the canonical code point for a string. `SMPTE170M` shares
+    /// `ITU_R_709_2` with `BT709` and `BT2020_12` shares `ITU_R_2020` with
+    /// `BT2020_10`, so they read back as the latter.
+    pub fn from_string_value(value: &str) -> Option<Self> {
+        match value {
+            "ITU_R_709_2" => Some(TransferCharacteristics::BT709),
+            "SMPTE_240M_1995" => Some(TransferCharacteristics::SMPTE240M),
+            "Linear" => Some(TransferCharacteristics::Linear),
+            "IEC_sRGB" => Some(TransferCharacteristics::IEC61966_2_1),
+            "ITU_R_2020" => Some(TransferCharacteristics::BT2020_10),
+            "SMPTE_ST_2084_PQ" => Some(TransferCharacteristics::SMPTE2084),
+            "SMPTE_ST_428_1" => Some(TransferCharacteristics::SMPTE428),
+            "ITU_R_2100_HLG" => Some(TransferCharacteristics::ARIB_STD_B67),
+            _ => None,
+        }
+    }
+
+    pub fn string_value(&self) -> Option<&'static str> {
+        match self {
+            TransferCharacteristics::BT709 | TransferCharacteristics::SMPTE170M => Some("ITU_R_709_2"),
+            TransferCharacteristics::SMPTE240M => Some("SMPTE_240M_1995"),
+            TransferCharacteristics::Linear => Some("Linear"),
+            TransferCharacteristics::IEC61966_2_1 => Some("IEC_sRGB"),
+            TransferCharacteristics::BT2020_10 | TransferCharacteristics::BT2020_12 => Some("ITU_R_2020"),
+            TransferCharacteristics::SMPTE2084 => Some("SMPTE_ST_2084_PQ"),
+            TransferCharacteristics::SMPTE428 => Some("SMPTE_ST_428_1"),
+            TransferCharacteristics::ARIB_STD_B67 => Some("ITU_R_2100_HLG"),
+            _ => None,
+        }
+    }
+
+    pub fn av_video_string_value(&self) -> Option<&'static str> {
+        match self {
+            TransferCharacteristics::BT709 |
+            TransferCharacteristics::SMPTE170M |
+            TransferCharacteristics::SMPTE240M |
+            TransferCharacteristics::SMPTE2084 |
+            TransferCharacteristics::ARIB_STD_B67 |
+            TransferCharacteristics::Linear => self.string_value(),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn from_cf_string(value: &CFString) -> Option<Self> {
+        Self::from_string_value(&value.to_string())
+    }
+
+    #[inline]
+    pub fn to_cf_string(&self) -> Option<CFString> {
+        self.string_value().map(CFString::from_static_string)
+    }
+}
+
+impl MatrixCoefficients {
+    pub fn from_code_point(code_point: i32) -> Option<Self> {
+        match code_point {
+            0 => Some(MatrixCoefficients::Identity),
+            1 => Some(MatrixCoefficients::BT709),
+            2 => Some(MatrixCoefficients::Unspecified),
+            4 => Some(MatrixCoefficients::FCC),
+            5 => Some(MatrixCoefficients::BT470BG),
+            6 => Some(MatrixCoefficients::SMPTE170M),
+            7 => Some(MatrixCoefficients::SMPTE240M),
+            8 => Some(MatrixCoefficients::YCgCo),
+            9 => Some(MatrixCoefficients::BT2020_NCL),
+            10 => Some(MatrixCoefficients::BT2020_CL),
+            11 => Some(MatrixCoefficients::SMPTE2085),
+            12 => Some(MatrixCoefficients::ChromaDerivedNCL),
+            13 => Some(MatrixCoefficients::ChromaDerivedCL),
+            14 => Some(MatrixCoefficients::ICtCp),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn code_point(&self) -> i32 {
+        *self as i32
+    }
+
+    /// Returns the canonical code point for a string. `SMPTE170M` uses the same
+    /// coefficients as `BT470BG` and reads back as it from `ITU_R_601_4`.
+    pub fn from_string_value(value: &str) -> Option<Self> {
+        match value {
+            "ITU_R_709_2" => Some(MatrixCoefficients::BT709),
+            "ITU_R_601_4" => Some(MatrixCoefficients::BT470BG),
+            "SMPTE_240M_1995" => Some(MatrixCoefficients::SMPTE240M),
+            "ITU_R_2020" => Some(MatrixCoefficients::BT2020_NCL),
+            _ => None,
+        }
+    }
+
+    pub fn string_value(&self) -> Option<&'static str> {
+        match self {
+            MatrixCoefficients::BT709 => Some("ITU_R_709_2"),
+            MatrixCoefficients::BT470BG | MatrixCoefficients::SMPTE170M => Some("ITU_R_601_4"),
+            MatrixCoefficients::SMPTE240M => Some("SMPTE_240M_1995"),
+            MatrixCoefficients::BT2020_NCL => Some("ITU_R_2020"),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn av_video_string_value(&self) -> Option<&'static str> {
+        self.string_value()
+    }
+
+    #[inline]
+    pub fn from_cf_string(value: &CFString) -> Option<Self> {
+        Self::from_string_value(&value.to_string())
+    }
+
+    #[inline]
+    pub fn to_cf_string(&self) -> Option<CFString> {
+        self.string_value().map(CFString::from_static_string)
+    }
+}
+
+impl From<ColorPrimaries> for i32 {
+    fn from(color_primaries: ColorPrimaries) -> i32 {
+        color_primaries as i32
+    }
+}
+
+impl TryFrom<i32> for ColorPrimaries {
+    type Error = i32;
+
+    fn try_from(code_point: i32) -> Result<Self, Self::Error> {
+        Self::from_code_point(code_point).ok_or(code_point)
+    }
+}
+
+impl From<TransferCharacteristics> for i32 {
+    fn from(transfer_characteristics: TransferCharacteristics) -> i32 {
+        transfer_characteristics as i32
+    }
+}
+
+impl TryFrom<i32> for TransferCharacteristics {
+    type Error = i32;
+
+    fn try_from(code_point: i32) -> Result<Self, Self::Error> {
+        Self::from_code_point(code_point).ok_or(code_point)
+    }
+}
+
+impl From<MatrixCoefficients> for i32 {
+    fn from(matrix_coefficients: MatrixCoefficients) -> i32 {
+        matrix_coefficients as i32
+    }
+}
+
+impl TryFrom<i32> for MatrixCoefficients {
+    type Error = i32;
+
+    fn try_from(code_point: i32) -> Result<Self, Self::Error> {
+        Self::from_code_point(code_point).ok_or(code_point)
+    }
+}
+
+impl From<CVImageBufferColorPrimaries> for ColorPrimaries {
+    fn from(color_primaries: CVImageBufferColorPrimaries) -> ColorPrimaries {
+        match color_primaries {
+            CVImageBufferColorPrimaries::ITU_R_709_2 => ColorPrimaries::BT709,
+            CVImageBufferColorPrimaries::EBU_3213 => ColorPrimaries::BT470BG,
+            CVImageBufferColorPrimaries::SMPTE_C => ColorPrimaries::SMPTE170M,
+            CVImageBufferColorPrimaries::P22 => ColorPrimaries::EBU3213,
+            CVImageBufferColorPrimaries::DCI_P3 => ColorPrimaries::SMPTE431,
+            CVImageBufferColorPrimaries::P3_D65 => ColorPrimaries::SMPTE432,
+            CVImageBufferColorPrimaries::ITU_R_2020 => ColorPrimaries::BT2020,
+        }
+    }
+}
+
+impl TryFrom<ColorPrimaries> for CVImageBufferColorPrimaries {
+    type Error = ColorPrimaries;
+
+    fn try_from(color_primaries: ColorPrimaries) -> Result<Self, Self::Error> {
+        match color_primaries {
+            ColorPrimaries::BT709 => Ok(CVImageBufferColorPrimaries::ITU_R_709_2),
+            ColorPrimaries::BT470BG => Ok(CVImageBufferColorPrimaries::EBU_3213),
+            ColorPrimaries::SMPTE170M | ColorPrimaries::SMPTE240M => Ok(CVImageBufferColorPrimaries::SMPTE_C),
+            ColorPrimaries::EBU3213 => Ok(CVImageBufferColorPrimaries::P22),
+            ColorPrimaries::SMPTE431 => Ok(CVImageBufferColorPrimaries::DCI_P3),
+            ColorPrimaries::SMPTE432 => Ok(CVImageBufferColorPrimaries::P3_D65),
+            ColorPrimaries::BT2020 => Ok(CVImageBufferColorPrimaries::ITU_R_2020),
+            _ => Err(color_primaries),
+        }
+    }
+}
+
+impl TryFrom<CVImageBufferTransferFunction> for TransferCharacteristics {
+    type Error = CVImageBufferTransferFunction;
+
+    fn try_from(transfer_function: CVImageBufferTransferFunction) -> Result<Self, Self::Error> {
+        match transfer_function {
+            CVImageBufferTransferFunction::ITU_R_709_2 => Ok(TransferCharacteristics::BT709),
+            CVImageBufferTransferFunction::SMPTE_240M_1995 => Ok(TransferCharacteristics::SMPTE240M),
+            CVImageBufferTransferFunction::sRGB => Ok(TransferCharacteristics::IEC61966_2_1),
+            CVImageBufferTransferFunction::ITU_R_2020 => Ok(TransferCharacteristics::BT2020_10),
+            CVImageBufferTransferFunction::SMPTE_ST_428_1 => Ok(TransferCharacteristics::SMPTE428),
+            CVImageBufferTransferFunction::SMPTE_ST_2084_PQ => Ok(TransferCharacteristics::SMPTE2084),
+            CVImageBufferTransferFunction::ITU_R_2100_HLG => Ok(TransferCharacteristics::ARIB_STD_B67),
+            CVImageBufferTransferFunction::Linear => Ok(TransferCharacteristics::Linear),
+            CVImageBufferTransferFunction::UseGamma => Err(transfer_function),
+        }
+    }
+}
+
+impl TryFrom<TransferCharacteristics> for CVImageBufferTransferFunction {
+    type Error = TransferCharacteristics;
+
+    fn try_from(transfer_characteristics: TransferCharacteristics) -> Result<Self, Self::Error> {
+        match transfer_characteristics {
+            TransferCharacteristics::BT709 | TransferCharacteristics::SMPTE170M => Ok(CVImageBufferTransferFunction::ITU_R_709_2),
+            TransferCharacteristics::SMPTE240M => Ok(CVImageBufferTransferFunction::SMPTE_240M_1995),
+            TransferCharacteristics::IEC61966_2_1 => Ok(CVImageBufferTransferFunction::sRGB),
+            TransferCharacteristics::BT2020_10 | TransferCharacteristics::BT2020_12 => Ok(CVImageBufferTransferFunction::ITU_R_2020),
+            TransferCharacteristics::SMPTE428 => Ok(CVImageBufferTransferFunction::SMPTE_ST_428_1),
+            TransferCharacteristics::SMPTE2084 => Ok(CVImageBufferTransferFunction::SMPTE_ST_2084_PQ),
+            TransferCharacteristics::ARIB_STD_B67 => Ok(CVImageBufferTransferFunction::ITU_R_2100_HLG),
+            TransferCharacteristics::Linear => Ok(CVImageBufferTransferFunction::Linear),
+            _ => Err(transfer_characteristics),
+        }
+    }
+}
+
+impl TryFrom<CVImageBufferYCbCrMatrix> for MatrixCoefficients {
+    type Error = CVImageBufferYCbCrMatrix;
+
+    fn try_from(ycbcr_matrix: CVImageBufferYCbCrMatrix) -> Result<Self, Self::Error> {
+        match ycbcr_matrix {
+            CVImageBufferYCbCrMatrix::ITU_R_709_2 => Ok(MatrixCoefficients::BT709),
+            CVImageBufferYCbCrMatrix::ITU_R_601_4 => Ok(MatrixCoefficients::BT470BG),
+            CVImageBufferYCbCrMatrix::SMPTE_240M_1995 => Ok(MatrixCoefficients::SMPTE240M),
+            CVImageBufferYCbCrMatrix::ITU_R_2020 => Ok(MatrixCoefficients::BT2020_NCL),
+            CVImageBufferYCbCrMatrix::DCI_P3 | CVImageBufferYCbCrMatrix::P3_D65 => Err(ycbcr_matrix),
+        }
+    }
+}
+
+impl TryFrom<MatrixCoefficients> for CVImageBufferYCbCrMatrix {
+    type Error = MatrixCoefficients;
+
+    fn try_from(matrix_coefficients: MatrixCoefficients) -> Result<Self, Self::Error> {
+        match matrix_coefficients {
+            MatrixCoefficients::BT709 => Ok(CVImageBufferYCbCrMatrix::ITU_R_709_2),
+            MatrixCoefficients::BT470BG | MatrixCoefficients::SMPTE170M => Ok(CVImageBufferYCbCrMatrix::ITU_R_601_4),
+            MatrixCoefficients::SMPTE240M => Ok(CVImageBufferYCbCrMatrix::SMPTE_240M_1995),
+            MatrixCoefficients::BT2020_NCL => Ok(CVImageBufferYCbCrMatrix::ITU_R_2020),
+            _ => Err(matrix_coefficients),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_values_round_trip_to_canonical_code_points() {
+        for code_point in 0..32 {
+            if let Some(color_primaries) = ColorPrimaries::from_code_point(code_point) {
+                if let Some(value) = color_primaries.string_value() {
+                    assert_eq!(ColorPrimaries::from_string_value(value).unwrap().string_value(), Some(value));
+                }
+            }
+            if let Some(transfer_characteristics) = TransferCharacteristics::from_code_point(code_point) {
+                if let Some(value) = transfer_characteristics.string_value() {
+                    assert_eq!(TransferCharacteristics::from_string_value(value).unwrap().string_value(), Some(value));
+                }
+            }
+            if let Some(matrix_coefficients) = MatrixCoefficients::from_code_point(code_point) {
+                if let Some(value) = matrix_coefficients.string_value() {
+                    assert_eq!(MatrixCoefficients::from_string_value(value).unwrap().string_value(), Some(value));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shared_string_values_read_back_as_canonical_code_points() {
+        assert_eq!(ColorPrimaries::SMPTE240M.string_value(), Some("SMPTE_C"));
+        assert_eq!(ColorPrimaries::from_string_value("SMPTE_C"), Some(ColorPrimaries::SMPTE170M));
+        assert_eq!(ColorPrimaries::SMPTE240M.av_video_string_value(), None);
+
+        assert_eq!(TransferCharacteristics::SMPTE170M.string_value(), Some("ITU_R_709_2"));
+        assert_eq!(TransferCharacteristics::from_string_value("ITU_R_709_2"), Some(TransferCharacteristics::BT709));
+        assert_eq!(TransferCharacteristics::BT2020_12.string_value(), Some("ITU_R_2020"));
+        assert_eq!(TransferCharacteristics::from_string_value("ITU_R_2020"), Some(TransferCharacteristics::BT2020_10));
+
+        assert_eq!(MatrixCoefficients::SMPTE170M.string_value(), Some("ITU_R_601_4"));
+        assert_eq!(MatrixCoefficients::from_string_value("ITU_R_601_4"), Some(MatrixCoefficients::BT470BG));
+    }
+}
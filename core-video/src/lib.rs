@@ -1,9 +1,11 @@
 #![allow(non_snake_case, non_camel_case_types, non_upper_case_globals, improper_ctypes)]
 
 extern crate block;
+extern crate core_audio_types;
 #[macro_use]
 extern crate core_foundation;
 extern crate core_graphics2 as core_graphics;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
 extern crate io_surface;
 extern crate libc;
 #[cfg(feature = "metal")]
@@ -25,6 +27,7 @@ pub type OSType = u32;
 
 pub mod base;
 pub mod buffer;
+pub mod color_code_point;
 #[cfg(all(target_os = "macos", feature = "display-link"))]
 pub mod display_link;
 pub mod host_time;
@@ -46,6 +49,7 @@ pub mod opengl_texture;
 #[cfg(target_os = "macos")]
 pub mod opengl_texture_cache;
 pub mod pixel_buffer;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
 pub mod pixel_buffer_io_surface;
 pub mod pixel_buffer_pool;
 pub mod pixel_format_description;
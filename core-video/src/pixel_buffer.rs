@@ -1,5 +1,6 @@
 use std::ptr::{null, null_mut};
 
+use core_audio_types::four_cc::FourCC;
 use core_foundation::{
     array::CFArrayRef,
     base::{kCFAllocatorDefault, Boolean, CFAllocatorRef, CFType, CFTypeID, TCFType},
@@ -18,11 +19,6 @@ use crate::{
 
 pub type CVPixelBufferRef = CVImageBufferRef;
 
-#[inline]
-const fn fourcc(code: &[u8; 4]) -> u32 {
-    ((code[0] as u32) << 24) | ((code[1] as u32) << 16) | ((code[2] as u32) << 8) | ((code[3] as u32) << 0)
-}
-
 pub type CVPixelBufferLockFlags = u64;
 
 pub const kCVPixelFormatType_1Monochrome: OSType = 0x00000001; /* 1 bit indexed */
@@ -34,92 +30,92 @@ pub const kCVPixelFormatType_2IndexedGray_WhiteIsZero: OSType = 0x00000022; /* 2
 pub const kCVPixelFormatType_4IndexedGray_WhiteIsZero: OSType = 0x00000024; /* 4 bit indexed gray, white is zero */
 pub const kCVPixelFormatType_8IndexedGray_WhiteIsZero: OSType = 0x00000028; /* 8 bit indexed gray, white is zero */
 pub const kCVPixelFormatType_16BE555: OSType = 0x00000010; /* 16 bit BE RGB 555 */
-pub const kCVPixelFormatType_16LE555: OSType = fourcc(b"L555"); /* 16 bit LE RGB 555 */
-pub const kCVPixelFormatType_16LE5551: OSType = fourcc(b"5551"); /* 16 bit LE RGB 5551 */
-pub const kCVPixelFormatType_16BE565: OSType = fourcc(b"B565"); /* 16 bit BE RGB 565 */
-pub const kCVPixelFormatType_16LE565: OSType = fourcc(b"L565"); /* 16 bit LE RGB 565 */
+pub const kCVPixelFormatType_16LE555: OSType = FourCC::new(b"L555").as_u32(); /* 16 bit LE RGB 555 */
+pub const kCVPixelFormatType_16LE5551: OSType = FourCC::new(b"5551").as_u32(); /* 16 bit LE RGB 5551 */
+pub const kCVPixelFormatType_16BE565: OSType = FourCC::new(b"B565").as_u32(); /* 16 bit BE RGB 565 */
+pub const kCVPixelFormatType_16LE565: OSType = FourCC::new(b"L565").as_u32(); /* 16 bit LE RGB 565 */
 pub const kCVPixelFormatType_24RGB: OSType = 0x00000018; /* 24 bit RGB */
-pub const kCVPixelFormatType_24BGR: OSType = fourcc(b"24BG"); /* 24 bit BGR */
+pub const kCVPixelFormatType_24BGR: OSType = FourCC::new(b"24BG").as_u32(); /* 24 bit BGR */
 pub const kCVPixelFormatType_32ARGB: OSType = 0x00000020; /* 32 bit ARGB */
-pub const kCVPixelFormatType_32BGRA: OSType = fourcc(b"BGRA"); /* 32 bit BGRA */
-pub const kCVPixelFormatType_32ABGR: OSType = fourcc(b"ABGR"); /* 32 bit ABGR */
-pub const kCVPixelFormatType_32RGBA: OSType = fourcc(b"RGBA"); /* 32 bit RGBA */
-pub const kCVPixelFormatType_64ARGB: OSType = fourcc(b"b64a"); /* 64 bit ARGB, 16-bit big-endian samples */
-pub const kCVPixelFormatType_48RGB: OSType = fourcc(b"b48r"); /* 48 bit RGB, 16-bit big-endian samples */
-pub const kCVPixelFormatType_32AlphaGray: OSType = fourcc(b"b32a"); /* 32 bit AlphaGray, 16-bit big-endian samples, black is zero */
-pub const kCVPixelFormatType_16Gray: OSType = fourcc(b"b16g"); /* 16 bit Grayscale, 16-bit big-endian samples, black is zero */
-pub const kCVPixelFormatType_30RGB: OSType = fourcc(b"R10k"); /* 30 bit RGB, 10-bit big-endian samples, 2 unused padding bits (at least significant end). */
-pub const kCVPixelFormatType_422YpCbCr8: OSType = fourcc(b"2vuy"); /* Component Y'CbCr 8-bit 4:2:2, ordered Cb Y'0 Cr Y'1 */
-pub const kCVPixelFormatType_4444YpCbCrA8: OSType = fourcc(b"v408"); /* Component Y'CbCrA 8-bit 4:4:4:4, ordered Cb Y' Cr A */
-pub const kCVPixelFormatType_4444YpCbCrA8R: OSType = fourcc(b"r408"); /* Component Y'CbCrA 8-bit 4:4:4:4, rendering format. full range alpha, zero biased YUV, ordered A Y' Cb Cr */
-pub const kCVPixelFormatType_4444AYpCbCr8: OSType = fourcc(b"y408"); /* Component Y'CbCrA 8-bit 4:4:4:4, ordered A Y' Cb Cr, full range alpha, video range Y'CbCr. */
-pub const kCVPixelFormatType_4444AYpCbCr16: OSType = fourcc(b"y416"); /* Component Y'CbCrA 16-bit 4:4:4:4, ordered A Y' Cb Cr, full range alpha, video range Y'CbCr, 16-bit little-endian samples. */
-pub const kCVPixelFormatType_4444AYpCbCrFloat: OSType = fourcc(b"r4fl"); /* Component AY'CbCr single precision floating-point 4:4:4:4 */
-pub const kCVPixelFormatType_444YpCbCr8: OSType = fourcc(b"v308"); /* Component Y'CbCr 8-bit 4:4:4 */
-pub const kCVPixelFormatType_422YpCbCr16: OSType = fourcc(b"v216"); /* Component Y'CbCr 10,12,14,16-bit 4:2:2 */
-pub const kCVPixelFormatType_422YpCbCr10: OSType = fourcc(b"v210"); /* Component Y'CbCr 10-bit 4:2:2 */
-pub const kCVPixelFormatType_444YpCbCr10: OSType = fourcc(b"v410"); /* Component Y'CbCr 10-bit 4:4:4 */
-pub const kCVPixelFormatType_420YpCbCr8Planar: OSType = fourcc(b"y420"); /* Planar Component Y'CbCr 8-bit 4:2:0.  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrPlanar struct */
-pub const kCVPixelFormatType_420YpCbCr8PlanarFullRange: OSType = fourcc(b"f420"); /* Planar Component Y'CbCr 8-bit 4:2:0, full range.  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrPlanar struct */
-pub const kCVPixelFormatType_422YpCbCr_4A_8BiPlanar: OSType = fourcc(b"a2vy"); /* First plane: Video-range Component Y'CbCr 8-bit 4:2:2, ordered Cb Y'0 Cr Y'1; second plane: alpha 8-bit 0-255 */
-pub const kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange: OSType = fourcc(b"420v"); /* Bi-Planar Component Y'CbCr 8-bit 4:2:0, video-range (luma=[16,235] chroma=[16,240]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
-pub const kCVPixelFormatType_420YpCbCr8BiPlanarFullRange: OSType = fourcc(b"420f"); /* Bi-Planar Component Y'CbCr 8-bit 4:2:0, full-range (luma=[0,255] chroma=[1,255]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
-pub const kCVPixelFormatType_422YpCbCr8BiPlanarVideoRange: OSType = fourcc(b"422v"); /* Bi-Planar Component Y'CbCr 8-bit 4:2:2, video-range (luma=[16,235] chroma=[16,240]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
-pub const kCVPixelFormatType_422YpCbCr8BiPlanarFullRange: OSType = fourcc(b"422f"); /* Bi-Planar Component Y'CbCr 8-bit 4:2:2, full-range (luma=[0,255] chroma=[1,255]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
-pub const kCVPixelFormatType_444YpCbCr8BiPlanarVideoRange: OSType = fourcc(b"444v"); /* Bi-Planar Component Y'CbCr 8-bit 4:4:4, video-range (luma=[16,235] chroma=[16,240]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
-pub const kCVPixelFormatType_444YpCbCr8BiPlanarFullRange: OSType = fourcc(b"444f"); /* Bi-Planar Component Y'CbCr 8-bit 4:4:4, full-range (luma=[0,255] chroma=[1,255]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
-pub const kCVPixelFormatType_422YpCbCr8_yuvs: OSType = fourcc(b"yuvs"); /* Component Y'CbCr 8-bit 4:2:2, ordered Y'0 Cb Y'1 Cr */
-pub const kCVPixelFormatType_422YpCbCr8FullRange: OSType = fourcc(b"yuvf"); /* Component Y'CbCr 8-bit 4:2:2, full range, ordered Y'0 Cb Y'1 Cr */
-pub const kCVPixelFormatType_OneComponent8: OSType = fourcc(b"L008"); /* 8 bit one component, black is zero */
-pub const kCVPixelFormatType_TwoComponent8: OSType = fourcc(b"2C08"); /* 8 bit two component, black is zero */
-pub const kCVPixelFormatType_30RGBLEPackedWideGamut: OSType = fourcc(b"w30r"); /* little-endian RGB101010, 2 MSB are zero, wide-gamut (384-895) */
-pub const kCVPixelFormatType_ARGB2101010LEPacked: OSType = fourcc(b"l10r"); /* little-endian ARGB2101010 full-range ARGB */
-pub const kCVPixelFormatType_40ARGBLEWideGamut: OSType = fourcc(b"w40a"); /* little-endian ARGB10101010, each 10 bits in the MSBs of 16bits, wide-gamut (384-895, including alpha) */
-pub const kCVPixelFormatType_40ARGBLEWideGamutPremultiplied: OSType = fourcc(b"w40m"); /* little-endian ARGB10101010, each 10 bits in the MSBs of 16bits, wide-gamut (384-895, including alpha). Alpha premultiplied */
-pub const kCVPixelFormatType_OneComponent10: OSType = fourcc(b"L010"); /* 10 bit little-endian one component, stored as 10 MSBs of 16 bits, black is zero */
-pub const kCVPixelFormatType_OneComponent12: OSType = fourcc(b"L012"); /* 12 bit little-endian one component, stored as 12 MSBs of 16 bits, black is zero */
-pub const kCVPixelFormatType_OneComponent16: OSType = fourcc(b"L016"); /* 16 bit little-endian one component, black is zero */
-pub const kCVPixelFormatType_TwoComponent16: OSType = fourcc(b"2C16"); /* 16 bit little-endian two component, black is zero */
-pub const kCVPixelFormatType_OneComponent16Half: OSType = fourcc(b"L00h"); /* 16 bit one component IEEE half-precision float, 16-bit little-endian samples */
-pub const kCVPixelFormatType_OneComponent32Float: OSType = fourcc(b"L00f"); /* 32 bit one component IEEE float, 32-bit little-endian samples */
-pub const kCVPixelFormatType_TwoComponent16Half: OSType = fourcc(b"2C0h"); /* 16 bit two component IEEE half-precision float, 16-bit little-endian samples */
-pub const kCVPixelFormatType_TwoComponent32Float: OSType = fourcc(b"2C0f"); /* 32 bit two component IEEE float, 32-bit little-endian samples */
-pub const kCVPixelFormatType_64RGBAHalf: OSType = fourcc(b"RGhA"); /* 64 bit RGBA IEEE half-precision float, 16-bit little-endian samples */
-pub const kCVPixelFormatType_128RGBAFloat: OSType = fourcc(b"RGfA"); /* 128 bit RGBA IEEE float, 32-bit little-endian samples */
-pub const kCVPixelFormatType_14Bayer_GRBG: OSType = fourcc(b"grb4"); /* Bayer 14-bit Little-Endian, packed in 16-bits, ordered G R G R... alternating with B G B G... */
-pub const kCVPixelFormatType_14Bayer_RGGB: OSType = fourcc(b"rgg4"); /* Bayer 14-bit Little-Endian, packed in 16-bits, ordered R G R G... alternating with G B G B... */
-pub const kCVPixelFormatType_14Bayer_BGGR: OSType = fourcc(b"bgg4"); /* Bayer 14-bit Little-Endian, packed in 16-bits, ordered B G B G... alternating with G R G R... */
-pub const kCVPixelFormatType_14Bayer_GBRG: OSType = fourcc(b"gbr4"); /* Bayer 14-bit Little-Endian, packed in 16-bits, ordered G B G B... alternating with R G R G... */
-pub const kCVPixelFormatType_DisparityFloat16: OSType = fourcc(b"hdis"); /* IEEE754-2008 binary16 (half float), describing the normalized shift when comparing two images. Units are 1/meters: ( pixelShift / (pixelFocalLength * baselineInMeters) ) */
-pub const kCVPixelFormatType_DisparityFloat32: OSType = fourcc(b"fdis"); /* IEEE754-2008 binary32 float, describing the normalized shift when comparing two images. Units are 1/meters: ( pixelShift / (pixelFocalLength * baselineInMeters) ) */
-pub const kCVPixelFormatType_DepthFloat16: OSType = fourcc(b"hdep"); /* IEEE754-2008 binary16 (half float), describing the depth (distance to an object) in meters */
-pub const kCVPixelFormatType_DepthFloat32: OSType = fourcc(b"fdep"); /* IEEE754-2008 binary32 float, describing the depth (distance to an object) in meters */
-pub const kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange: OSType = fourcc(b"x420"); /* 2 plane YCbCr10 4:2:0, each 10 bits in the MSBs of 16bits, video-range (luma=[64,940] chroma=[64,960]) */
-pub const kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange: OSType = fourcc(b"x422"); /* 2 plane YCbCr10 4:2:2, each 10 bits in the MSBs of 16bits, video-range (luma=[64,940] chroma=[64,960]) */
-pub const kCVPixelFormatType_444YpCbCr10BiPlanarVideoRange: OSType = fourcc(b"x444"); /* 2 plane YCbCr10 4:4:4, each 10 bits in the MSBs of 16bits, video-range (luma=[64,940] chroma=[64,960]) */
-pub const kCVPixelFormatType_420YpCbCr10BiPlanarFullRange: OSType = fourcc(b"xf20"); /* 2 plane YCbCr10 4:2:0, each 10 bits in the MSBs of 16bits, full-range (Y range 0-1023) */
-pub const kCVPixelFormatType_422YpCbCr10BiPlanarFullRange: OSType = fourcc(b"xf22"); /* 2 plane YCbCr10 4:2:2, each 10 bits in the MSBs of 16bits, full-range (Y range 0-1023) */
-pub const kCVPixelFormatType_444YpCbCr10BiPlanarFullRange: OSType = fourcc(b"xf44"); /* 2 plane YCbCr10 4:4:4, each 10 bits in the MSBs of 16bits, full-range (Y range 0-1023) */
-pub const kCVPixelFormatType_420YpCbCr8VideoRange_8A_TriPlanar: OSType = fourcc(b"v0a8"); /* first and second planes as per 420YpCbCr8BiPlanarVideoRange (420v), alpha 8 bits in third plane full-range.  No CVPlanarPixelBufferInfo struct. */
-pub const kCVPixelFormatType_16VersatileBayer: OSType = fourcc(b"bp16"); /* Single plane Bayer 16-bit little-endian sensor element ("sensel") samples from full-size decoding of ProRes RAW images; Bayer pattern (sensel ordering) and other raw conversion information is described via buffer attachments */
-pub const kCVPixelFormatType_64RGBA_DownscaledProResRAW: OSType = fourcc(b"bp64"); /* Single plane 64-bit RGBA (16-bit little-endian samples) from downscaled decoding of ProRes RAW images; components--which may not be co-sited with one another--are sensel values and require raw conversion, information for which is described via buffer attachments */
-pub const kCVPixelFormatType_422YpCbCr16BiPlanarVideoRange: OSType = fourcc(b"sv22"); /* 2 plane YCbCr16 4:2:2, video-range (luma=[4096,60160] chroma=[4096,61440]) */
-pub const kCVPixelFormatType_444YpCbCr16BiPlanarVideoRange: OSType = fourcc(b"sv44"); /* 2 plane YCbCr16 4:4:4, video-range (luma=[4096,60160] chroma=[4096,61440]) */
-pub const kCVPixelFormatType_444YpCbCr16VideoRange_16A_TriPlanar: OSType = fourcc(b"s4as"); /* 3 plane video-range YCbCr16 4:4:4 with 16-bit full-range alpha (luma=[4096,60160] chroma=[4096,61440] alpha=[0,65535]).  No CVPlanarPixelBufferInfo struct. */
-
-pub const kCVPixelFormatType_Lossless_32BGRA: OSType = fourcc(b"&BGA"); /* Lossless-compressed form of kCVPixelFormatType_32BGRA. */
+pub const kCVPixelFormatType_32BGRA: OSType = FourCC::new(b"BGRA").as_u32(); /* 32 bit BGRA */
+pub const kCVPixelFormatType_32ABGR: OSType = FourCC::new(b"ABGR").as_u32(); /* 32 bit ABGR */
+pub const kCVPixelFormatType_32RGBA: OSType = FourCC::new(b"RGBA").as_u32(); /* 32 bit RGBA */
+pub const kCVPixelFormatType_64ARGB: OSType = FourCC::new(b"b64a").as_u32(); /* 64 bit ARGB, 16-bit big-endian samples */
+pub const kCVPixelFormatType_48RGB: OSType = FourCC::new(b"b48r").as_u32(); /* 48 bit RGB, 16-bit big-endian samples */
+pub const kCVPixelFormatType_32AlphaGray: OSType = FourCC::new(b"b32a").as_u32(); /* 32 bit AlphaGray, 16-bit big-endian samples, black is zero */
+pub const kCVPixelFormatType_16Gray: OSType = FourCC::new(b"b16g").as_u32(); /* 16 bit Grayscale, 16-bit big-endian samples, black is zero */
+pub const kCVPixelFormatType_30RGB: OSType = FourCC::new(b"R10k").as_u32(); /* 30 bit RGB, 10-bit big-endian samples, 2 unused padding bits (at least significant end). */
+pub const kCVPixelFormatType_422YpCbCr8: OSType = FourCC::new(b"2vuy").as_u32(); /* Component Y'CbCr 8-bit 4:2:2, ordered Cb Y'0 Cr Y'1 */
+pub const kCVPixelFormatType_4444YpCbCrA8: OSType = FourCC::new(b"v408").as_u32(); /* Component Y'CbCrA 8-bit 4:4:4:4, ordered Cb Y' Cr A */
+pub const kCVPixelFormatType_4444YpCbCrA8R: OSType = FourCC::new(b"r408").as_u32(); /* Component Y'CbCrA 8-bit 4:4:4:4, rendering format. full range alpha, zero biased YUV, ordered A Y' Cb Cr */
+pub const kCVPixelFormatType_4444AYpCbCr8: OSType = FourCC::new(b"y408").as_u32(); /* Component Y'CbCrA 8-bit 4:4:4:4, ordered A Y' Cb Cr, full range alpha, video range Y'CbCr. */
+pub const kCVPixelFormatType_4444AYpCbCr16: OSType = FourCC::new(b"y416").as_u32(); /* Component Y'CbCrA 16-bit 4:4:4:4, ordered A Y' Cb Cr, full range alpha, video range Y'CbCr, 16-bit little-endian samples. */
+pub const kCVPixelFormatType_4444AYpCbCrFloat: OSType = FourCC::new(b"r4fl").as_u32(); /* Component AY'CbCr single precision floating-point 4:4:4:4 */
+pub const kCVPixelFormatType_444YpCbCr8: OSType = FourCC::new(b"v308").as_u32(); /* Component Y'CbCr 8-bit 4:4:4 */
+pub const kCVPixelFormatType_422YpCbCr16: OSType = FourCC::new(b"v216").as_u32(); /* Component Y'CbCr 10,12,14,16-bit 4:2:2 */
+pub const kCVPixelFormatType_422YpCbCr10: OSType = FourCC::new(b"v210").as_u32(); /* Component Y'CbCr 10-bit 4:2:2 */
+pub const kCVPixelFormatType_444YpCbCr10: OSType = FourCC::new(b"v410").as_u32(); /* Component Y'CbCr 10-bit 4:4:4 */
+pub const kCVPixelFormatType_420YpCbCr8Planar: OSType = FourCC::new(b"y420").as_u32(); /* Planar Component Y'CbCr 8-bit 4:2:0.  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrPlanar struct */
+pub const kCVPixelFormatType_420YpCbCr8PlanarFullRange: OSType = FourCC::new(b"f420").as_u32(); /* Planar Component Y'CbCr 8-bit 4:2:0, full range.  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrPlanar struct */
+pub const kCVPixelFormatType_422YpCbCr_4A_8BiPlanar: OSType = FourCC::new(b"a2vy").as_u32(); /* First plane: Video-range Component Y'CbCr 8-bit 4:2:2, ordered Cb Y'0 Cr Y'1; second plane: alpha 8-bit 0-255 */
+pub const kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange: OSType = FourCC::new(b"420v").as_u32(); /* Bi-Planar Component Y'CbCr 8-bit 4:2:0, video-range (luma=[16,235] chroma=[16,240]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
+pub const kCVPixelFormatType_420YpCbCr8BiPlanarFullRange: OSType = FourCC::new(b"420f").as_u32(); /* Bi-Planar Component Y'CbCr 8-bit 4:2:0, full-range (luma=[0,255] chroma=[1,255]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
+pub const kCVPixelFormatType_422YpCbCr8BiPlanarVideoRange: OSType = FourCC::new(b"422v").as_u32(); /* Bi-Planar Component Y'CbCr 8-bit 4:2:2, video-range (luma=[16,235] chroma=[16,240]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
+pub const kCVPixelFormatType_422YpCbCr8BiPlanarFullRange: OSType = FourCC::new(b"422f").as_u32(); /* Bi-Planar Component Y'CbCr 8-bit 4:2:2, full-range (luma=[0,255] chroma=[1,255]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
+pub const kCVPixelFormatType_444YpCbCr8BiPlanarVideoRange: OSType = FourCC::new(b"444v").as_u32(); /* Bi-Planar Component Y'CbCr 8-bit 4:4:4, video-range (luma=[16,235] chroma=[16,240]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
+pub const kCVPixelFormatType_444YpCbCr8BiPlanarFullRange: OSType = FourCC::new(b"444f").as_u32(); /* Bi-Planar Component Y'CbCr 8-bit 4:4:4, full-range (luma=[0,255] chroma=[1,255]).  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrBiPlanar struct */
+pub const kCVPixelFormatType_422YpCbCr8_yuvs: OSType = FourCC::new(b"yuvs").as_u32(); /* Component Y'CbCr 8-bit 4:2:2, ordered Y'0 Cb Y'1 Cr */
+pub const kCVPixelFormatType_422YpCbCr8FullRange: OSType = FourCC::new(b"yuvf").as_u32(); /* Component Y'CbCr 8-bit 4:2:2, full range, ordered Y'0 Cb Y'1 Cr */
+pub const kCVPixelFormatType_OneComponent8: OSType = FourCC::new(b"L008").as_u32(); /* 8 bit one component, black is zero */
+pub const kCVPixelFormatType_TwoComponent8: OSType = FourCC::new(b"2C08").as_u32(); /* 8 bit two component, black is zero */
+pub const kCVPixelFormatType_30RGBLEPackedWideGamut: OSType = FourCC::new(b"w30r").as_u32(); /* little-endian RGB101010, 2 MSB are zero, wide-gamut (384-895) */
+pub const kCVPixelFormatType_ARGB2101010LEPacked: OSType = FourCC::new(b"l10r").as_u32(); /* little-endian ARGB2101010 full-range ARGB */
+pub const kCVPixelFormatType_40ARGBLEWideGamut: OSType = FourCC::new(b"w40a").as_u32(); /* little-endian ARGB10101010, each 10 bits in the MSBs of 16bits, wide-gamut (384-895, including alpha) */
+pub const kCVPixelFormatType_40ARGBLEWideGamutPremultiplied: OSType = FourCC::new(b"w40m").as_u32(); /* little-endian ARGB10101010, each 10 bits in the MSBs of 16bits, wide-gamut (384-895, including alpha). Alpha premultiplied */
+pub const kCVPixelFormatType_OneComponent10: OSType = FourCC::new(b"L010").as_u32(); /* 10 bit little-endian one component, stored as 10 MSBs of 16 bits, black is zero */
+pub const kCVPixelFormatType_OneComponent12: OSType = FourCC::new(b"L012").as_u32(); /* 12 bit little-endian one component, stored as 12 MSBs of 16 bits, black is zero */
+pub const kCVPixelFormatType_OneComponent16: OSType = FourCC::new(b"L016").as_u32(); /* 16 bit little-endian one component, black is zero */
+pub const kCVPixelFormatType_TwoComponent16: OSType = FourCC::new(b"2C16").as_u32(); /* 16 bit little-endian two component, black is zero */
+pub const kCVPixelFormatType_OneComponent16Half: OSType = FourCC::new(b"L00h").as_u32(); /* 16 bit one component IEEE half-precision float, 16-bit little-endian samples */
+pub const kCVPixelFormatType_OneComponent32Float: OSType = FourCC::new(b"L00f").as_u32(); /* 32 bit one component IEEE float, 32-bit little-endian samples */
+pub const kCVPixelFormatType_TwoComponent16Half: OSType = FourCC::new(b"2C0h").as_u32(); /* 16 bit two component IEEE half-precision float, 16-bit little-endian samples */
+pub const kCVPixelFormatType_TwoComponent32Float: OSType = FourCC::new(b"2C0f").as_u32(); /* 32 bit two component IEEE float, 32-bit little-endian samples */
+pub const kCVPixelFormatType_64RGBAHalf: OSType = FourCC::new(b"RGhA").as_u32(); /* 64 bit RGBA IEEE half-precision float, 16-bit little-endian samples */
+pub const kCVPixelFormatType_128RGBAFloat: OSType = FourCC::new(b"RGfA").as_u32(); /* 128 bit RGBA IEEE float, 32-bit little-endian samples */
+pub const kCVPixelFormatType_14Bayer_GRBG: OSType = FourCC::new(b"grb4").as_u32(); /* Bayer 14-bit Little-Endian, packed in 16-bits, ordered G R G R... alternating with B G B G... */
+pub const kCVPixelFormatType_14Bayer_RGGB: OSType = FourCC::new(b"rgg4").as_u32(); /* Bayer 14-bit Little-Endian, packed in 16-bits, ordered R G R G... alternating with G B G B... */
+pub const kCVPixelFormatType_14Bayer_BGGR: OSType = FourCC::new(b"bgg4").as_u32(); /* Bayer 14-bit Little-Endian, packed in 16-bits, ordered B G B G... alternating with G R G R... */
+pub const kCVPixelFormatType_14Bayer_GBRG: OSType = FourCC::new(b"gbr4").as_u32(); /* Bayer 14-bit Little-Endian, packed in 16-bits, ordered G B G B... alternating with R G R G... */
+pub const kCVPixelFormatType_DisparityFloat16: OSType = FourCC::new(b"hdis").as_u32(); /* IEEE754-2008 binary16 (half float), describing the normalized shift when comparing two images. Units are 1/meters: ( pixelShift / (pixelFocalLength * baselineInMeters) ) */
+pub const kCVPixelFormatType_DisparityFloat32: OSType = FourCC::new(b"fdis").as_u32(); /* IEEE754-2008 binary32 float, describing the normalized shift when comparing two images. Units are 1/meters: ( pixelShift / (pixelFocalLength * baselineInMeters) ) */
+pub const kCVPixelFormatType_DepthFloat16: OSType = FourCC::new(b"hdep").as_u32(); /* IEEE754-2008 binary16 (half float), describing the depth (distance to an object) in meters */
+pub const kCVPixelFormatType_DepthFloat32: OSType = FourCC::new(b"fdep").as_u32(); /* IEEE754-2008 binary32 float, describing the depth (distance to an object) in meters */
+pub const kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange: OSType = FourCC::new(b"x420").as_u32(); /* 2 plane YCbCr10 4:2:0, each 10 bits in the MSBs of 16bits, video-range (luma=[64,940] chroma=[64,960]) */
+pub const kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange: OSType = FourCC::new(b"x422").as_u32(); /* 2 plane YCbCr10 4:2:2, each 10 bits in the MSBs of 16bits, video-range (luma=[64,940] chroma=[64,960]) */
+pub const kCVPixelFormatType_444YpCbCr10BiPlanarVideoRange: OSType = FourCC::new(b"x444").as_u32(); /* 2 plane YCbCr10 4:4:4, each 10 bits in the MSBs of 16bits, video-range (luma=[64,940] chroma=[64,960]) */
+pub const kCVPixelFormatType_420YpCbCr10BiPlanarFullRange: OSType = FourCC::new(b"xf20").as_u32(); /* 2 plane YCbCr10 4:2:0, each 10 bits in the MSBs of 16bits, full-range (Y range 0-1023) */
+pub const kCVPixelFormatType_422YpCbCr10BiPlanarFullRange: OSType = FourCC::new(b"xf22").as_u32(); /* 2 plane YCbCr10 4:2:2, each 10 bits in the MSBs of 16bits, full-range (Y range 0-1023) */
+pub const kCVPixelFormatType_444YpCbCr10BiPlanarFullRange: OSType = FourCC::new(b"xf44").as_u32(); /* 2 plane YCbCr10 4:4:4, each 10 bits in the MSBs of 16bits, full-range (Y range 0-1023) */
+pub const kCVPixelFormatType_420YpCbCr8VideoRange_8A_TriPlanar: OSType = FourCC::new(b"v0a8").as_u32(); /* first and second planes as per 420YpCbCr8BiPlanarVideoRange (420v), alpha 8 bits in third plane full-range.  No CVPlanarPixelBufferInfo struct. */
+pub const kCVPixelFormatType_16VersatileBayer: OSType = FourCC::new(b"bp16").as_u32(); /* Single plane Bayer 16-bit little-endian sensor element ("sensel") samples from full-size decoding of ProRes RAW images; Bayer pattern (sensel ordering) and other raw conversion information is described via buffer attachments */
+pub const kCVPixelFormatType_64RGBA_DownscaledProResRAW: OSType = FourCC::new(b"bp64").as_u32(); /* Single plane 64-bit RGBA (16-bit little-endian samples) from downscaled decoding of ProRes RAW images; components--which may not be co-sited with one another--are sensel values and require raw conversion, information for which is described via buffer attachments */
+pub const kCVPixelFormatType_422YpCbCr16BiPlanarVideoRange: OSType = FourCC::new(b"sv22").as_u32(); /* 2 plane YCbCr16 4:2:2, video-range (luma=[4096,60160] chroma=[4096,61440]) */
+pub const kCVPixelFormatType_444YpCbCr16BiPlanarVideoRange: OSType = FourCC::new(b"sv44").as_u32(); /* 2 plane YCbCr16 4:4:4, video-range (luma=[4096,60160] chroma=[4096,61440]) */
+pub const kCVPixelFormatType_444YpCbCr16VideoRange_16A_TriPlanar: OSType = FourCC::new(b"s4as").as_u32(); /* 3 plane video-range YCbCr16 4:4:4 with 16-bit full-range alpha (luma=[4096,60160] chroma=[4096,61440] alpha=[0,65535]).  No CVPlanarPixelBufferInfo struct. */
+
+pub const kCVPixelFormatType_Lossless_32BGRA: OSType = FourCC::new(b"&BGA").as_u32(); /* Lossless-compressed form of kCVPixelFormatType_32BGRA. */
 
 // Lossless-compressed Bi-planar YCbCr pixel format types
-pub const kCVPixelFormatType_Lossless_420YpCbCr8BiPlanarVideoRange: OSType = fourcc(b"&8v0"); /* Lossless-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. */
-pub const kCVPixelFormatType_Lossless_420YpCbCr8BiPlanarFullRange: OSType = fourcc(b"&8f0"); /* Lossless-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarFullRange.  No CVPlanarPixelBufferInfo struct. */
-pub const kCVPixelFormatType_Lossless_420YpCbCr10PackedBiPlanarVideoRange: OSType = fourcc(b"&xv0"); /* Lossless-compressed-packed form of kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. Format is compressed-packed with no padding bits between pixels. */
-pub const kCVPixelFormatType_Lossless_422YpCbCr10PackedBiPlanarVideoRange: OSType = fourcc(b"&xv2"); /* Lossless-compressed form of kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. Format is compressed-packed with no padding bits between pixels. */
-
-pub const kCVPixelFormatType_Lossy_32BGRA: OSType = fourcc(b"-BGA"); /* Lossy-compressed form of kCVPixelFormatType_32BGRA. No CVPlanarPixelBufferInfo struct.  */
-pub const kCVPixelFormatType_Lossy_420YpCbCr8BiPlanarVideoRange: OSType = fourcc(b"-8v0"); /* Lossy-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. */
-pub const kCVPixelFormatType_Lossy_420YpCbCr8BiPlanarFullRange: OSType = fourcc(b"-8f0"); /* Lossy-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarFullRange.  No CVPlanarPixelBufferInfo struct. */
-pub const kCVPixelFormatType_Lossy_420YpCbCr10PackedBiPlanarVideoRange: OSType = fourcc(b"-xv0"); /* Lossy-compressed form of kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. Format is compressed-packed with no padding bits between pixels. */
-pub const kCVPixelFormatType_Lossy_422YpCbCr10PackedBiPlanarVideoRange: OSType = fourcc(b"-xv2"); /* Lossy-compressed form of kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. Format is compressed-packed with no padding bits between pixels. */
+pub const kCVPixelFormatType_Lossless_420YpCbCr8BiPlanarVideoRange: OSType = FourCC::new(b"&8v0").as_u32(); /* Lossless-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. */
+pub const kCVPixelFormatType_Lossless_420YpCbCr8BiPlanarFullRange: OSType = FourCC::new(b"&8f0").as_u32(); /* Lossless-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarFullRange.  No CVPlanarPixelBufferInfo struct. */
+pub const kCVPixelFormatType_Lossless_420YpCbCr10PackedBiPlanarVideoRange: OSType = FourCC::new(b"&xv0").as_u32(); /* Lossless-compressed-packed form of kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. Format is compressed-packed with no padding bits between pixels. */
+pub const kCVPixelFormatType_Lossless_422YpCbCr10PackedBiPlanarVideoRange: OSType = FourCC::new(b"&xv2").as_u32(); /* Lossless-compressed form of kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. Format is compressed-packed with no padding bits between pixels. */
+
+pub const kCVPixelFormatType_Lossy_32BGRA: OSType = FourCC::new(b"-BGA").as_u32(); /* Lossy-compressed form of kCVPixelFormatType_32BGRA. No CVPlanarPixelBufferInfo struct.  */
+pub const kCVPixelFormatType_Lossy_420YpCbCr8BiPlanarVideoRange: OSType = FourCC::new(b"-8v0").as_u32(); /* Lossy-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. */
+pub const kCVPixelFormatType_Lossy_420YpCbCr8BiPlanarFullRange: OSType = FourCC::new(b"-8f0").as_u32(); /* Lossy-compressed form of kCVPixelFormatType_420YpCbCr8BiPlanarFullRange.  No CVPlanarPixelBufferInfo struct. */
+pub const kCVPixelFormatType_Lossy_420YpCbCr10PackedBiPlanarVideoRange: OSType = FourCC::new(b"-xv0").as_u32(); /* Lossy-compressed form of kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. Format is compressed-packed with no padding bits between pixels. */
+pub const kCVPixelFormatType_Lossy_422YpCbCr10PackedBiPlanarVideoRange: OSType = FourCC::new(b"-xv2").as_u32(); /* Lossy-compressed form of kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange.  No CVPlanarPixelBufferInfo struct. Format is compressed-packed with no padding bits between pixels. */
 
 pub const kCVPixelBufferLock_ReadOnly: CVPixelBufferLockFlags = 0x00000001;
 